@@ -0,0 +1,78 @@
+//! Guest fixture for the conformance tests.
+//!
+//! The behavior is selected by the `x-test` request header, so one module covers
+//! every case of `tests/abi.rs`.
+
+use http_wasm_guest::{
+    Guest,
+    host::{
+        Request, Response, admin,
+        feature::{self, Feature},
+        log, random, time,
+    },
+    register,
+};
+
+struct Fixture {}
+
+impl Guest for Fixture {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        let case = request.header.get(b"x-test").map(|v| v.to_string()).unwrap_or_default();
+        match case.as_str() {
+            "echo" => {
+                let echo = format!("{} {} {} {}", request.method(), request.uri(), request.version(), request.source_addr());
+                request.header.set(b"x-echo", echo.as_bytes());
+                request.header.set(b"x-config", &admin::config());
+            }
+            "rewrite" => {
+                request.set_method(b"POST");
+                request.set_uri(b"/rewritten?q=1");
+                request.header.set(b"x-set", b"one");
+                request.header.add(b"x-add", b"two");
+                request.header.remove(b"x-remove");
+                let body = request.body.read();
+                request.body.write(format!("{}|{}", body, body.len()).as_bytes());
+            }
+            "headers" => {
+                let names = request.header.names().iter().map(|n| n.to_string().to_ascii_lowercase()).collect::<Vec<_>>();
+                request.header.set(b"x-names", names.join(",").as_bytes());
+                let values = request.header.values(b"x-multi").iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                request.header.set(b"x-values", values.join(",").as_bytes());
+                let large = request.header.get(b"x-large").map(|v| v.len()).unwrap_or_default();
+                request.header.set(b"x-large-len", large.to_string().as_bytes());
+            }
+            "stop" => {
+                response.set_status(403);
+                response.header.set(b"content-type", b"text/plain");
+                response.body.write(b"denied");
+                return (false, 0);
+            }
+            "runtime" => {
+                request.header.set(b"x-now", time::unix_secs().to_string().as_bytes());
+                let nonce = random::bytes::<16>();
+                request.header.set(b"x-random", if nonce.is_ok_and(|nonce| nonce != [0; 16]) { b"ok" } else { b"zero" });
+            }
+            _ => {}
+        }
+        (true, 42)
+    }
+
+    fn features(&self) -> Feature {
+        feature::BufferRequest | feature::BufferResponse
+    }
+
+    fn handle_response(&self, req_ctx: i32, request: &Request, response: &Response, is_error: bool) {
+        response.header.set(b"x-ctx", req_ctx.to_string().as_bytes());
+        response.header.set(b"x-error", is_error.to_string().as_bytes());
+        if request.header.get(b"x-test").is_some_and(|v| v == "rewrite") {
+            response.set_status(201);
+            let body = response.body.read();
+            response.body.write(body.to_string().to_uppercase().as_bytes());
+        }
+    }
+}
+
+fn main() {
+    log::write(0, b"conformance fixture registered");
+    register(Fixture {});
+}
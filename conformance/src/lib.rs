@@ -0,0 +1,420 @@
+//! A reference http-wasm host for testing guests built with `http-wasm-guest`.
+//!
+//! [`Host`] implements the `http_handler` imports of the
+//! [http-wasm handler ABI](https://http-wasm.io/http-handler-abi/) on top of wasmtime,
+//! following the behavior of the Go reference host: the module is started once, then
+//! `handle_request` and, if the request continues, `handle_response` are called per
+//! [`Exchange`]. Tests can thereby verify the handler layer of the crate against a real
+//! runtime instead of the mock used by the unit tests.
+//!
+//! ```no_run
+//! use http_wasm_guest_conformance::{Exchange, Host, build_example};
+//!
+//! let wasm = build_example("header").unwrap();
+//! let mut plugin = Host::new(&wasm).unwrap().start().unwrap();
+//! let outcome = plugin.handle(Exchange::default()).unwrap();
+//! assert_eq!(outcome.exchange.request.header("X-Custom-Header"), Some("FooBar"));
+//! ```
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, bail};
+use wasmtime::{Caller, Engine, Extern, Instance, Linker, Module, Store};
+use wasmtime_wasi::{I32Exit, WasiCtxBuilder, p1::WasiP1Ctx};
+
+const KIND_REQUEST: i32 = 0;
+const KIND_RESPONSE: i32 = 1;
+
+/// Headers and body of a request or response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Message {
+    /// Header names and values in order; names are compared case-insensitively.
+    pub headers: Vec<(String, String)>,
+    /// The body.
+    pub body: Vec<u8>,
+}
+
+impl Message {
+    /// The first value of a header.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_values(name).into_iter().next()
+    }
+
+    /// All values of a header.
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers.iter().filter(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str()).collect()
+    }
+
+    /// Add a header value, builder style.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in &self.headers {
+            if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                names.push(name);
+            }
+        }
+        names
+    }
+}
+
+/// The state of one request/response cycle as seen and changed by the guest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    /// Request method.
+    pub method: String,
+    /// Request target.
+    pub uri: String,
+    /// Protocol version, e.g. `HTTP/1.1`.
+    pub version: String,
+    /// Client address.
+    pub source_addr: String,
+    /// Request headers and body.
+    pub request: Message,
+    /// Response status; the upstream status once `handle_response` runs.
+    pub status: u32,
+    /// Response headers and body; the upstream response once `handle_response` runs.
+    pub response: Message,
+}
+
+impl Default for Exchange {
+    fn default() -> Self {
+        Self {
+            method: "GET".into(),
+            uri: "/".into(),
+            version: "HTTP/1.1".into(),
+            source_addr: "192.0.2.1:1234".into(),
+            request: Message::default(),
+            status: 200,
+            response: Message::default(),
+        }
+    }
+}
+
+/// Result of [`Plugin::handle`].
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// The exchange after both handlers ran.
+    pub exchange: Exchange,
+    /// Whether `handle_request` let the request continue to the upstream.
+    pub next: bool,
+    /// The context value `handle_request` passed to `handle_response`.
+    pub ctx: i32,
+}
+
+/// A compiled guest module with the host configuration.
+pub struct Host {
+    engine: Engine,
+    module: Module,
+    config: Vec<u8>,
+    log_level: i32,
+}
+
+impl Host {
+    /// Compile the guest at `wasm`.
+    pub fn new(wasm: &Path) -> Result<Self> {
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, wasm).with_context(|| format!("compiling {}", wasm.display()))?;
+        Ok(Self { engine, module, config: Vec::new(), log_level: 0 })
+    }
+
+    /// Set the configuration returned by `get_config`.
+    pub fn with_config(mut self, config: &[u8]) -> Self {
+        self.config = config.to_vec();
+        self
+    }
+
+    /// Set the lowest enabled log level: debug=−1, info=0, warn=1, error=2.
+    pub fn with_log_level(mut self, level: i32) -> Self {
+        self.log_level = level;
+        self
+    }
+
+    /// Instantiate the module and run its `_start` function, which registers the guest.
+    pub fn start(&self) -> Result<Plugin> {
+        let mut linker = Linker::new(&self.engine);
+        wasmtime_wasi::p1::add_to_linker_sync(&mut linker, |state: &mut State| &mut state.wasi)?;
+        add_handler_imports(&mut linker)?;
+        let state = State {
+            wasi: WasiCtxBuilder::new().build_p1(),
+            config: self.config.clone(),
+            log_level: self.log_level,
+            features: 0,
+            logs: Vec::new(),
+            exchange: Exchange::default(),
+            read: [0; 2],
+            written: [false; 2],
+        };
+        let mut store = Store::new(&self.engine, state);
+        let instance = linker.instantiate(&mut store, &self.module)?;
+        if let Ok(start) = instance.get_typed_func::<(), ()>(&mut store, "_start") {
+            match start.call(&mut store, ()) {
+                Ok(()) => {}
+                Err(err) if err.downcast_ref::<I32Exit>().is_some_and(|exit| exit.0 == 0) => {}
+                Err(err) => return Err(err.context("running _start")),
+            }
+        }
+        Ok(Plugin { store, instance })
+    }
+}
+
+/// A started guest instance; state kept by the guest persists across exchanges.
+pub struct Plugin {
+    store: Store<State>,
+    instance: Instance,
+}
+
+impl Plugin {
+    /// Run `handle_request` and, if the request continues, `handle_response`.
+    ///
+    /// The response of `exchange` stands in for the upstream response.
+    pub fn handle(&mut self, exchange: Exchange) -> Result<Outcome> {
+        let state = self.store.data_mut();
+        state.exchange = Exchange { status: 200, response: Message::default(), ..exchange.clone() };
+        state.read = [0; 2];
+        state.written = [false; 2];
+        let handle_request = self.instance.get_typed_func::<(), i64>(&mut self.store, "handle_request")?;
+        let result = handle_request.call(&mut self.store, ())?;
+        let (next, ctx) = (result & 1 == 1, (result >> 32) as i32);
+        if next {
+            let state = self.store.data_mut();
+            state.exchange.status = exchange.status;
+            state.exchange.response = exchange.response;
+            state.read[KIND_RESPONSE as usize] = 0;
+            state.written[KIND_RESPONSE as usize] = false;
+            let handle_response = self.instance.get_typed_func::<(i32, i32), ()>(&mut self.store, "handle_response")?;
+            handle_response.call(&mut self.store, (ctx, 0))?;
+        }
+        Ok(Outcome { exchange: self.store.data().exchange.clone(), next, ctx })
+    }
+
+    /// Features enabled by the guest.
+    pub fn features(&self) -> u32 {
+        self.store.data().features
+    }
+
+    /// Messages logged by the guest with their levels.
+    pub fn logs(&self) -> &[(i32, String)] {
+        &self.store.data().logs
+    }
+}
+
+struct State {
+    wasi: WasiP1Ctx,
+    config: Vec<u8>,
+    log_level: i32,
+    features: u32,
+    logs: Vec<(i32, String)>,
+    exchange: Exchange,
+    /// Body bytes already read per kind.
+    read: [usize; 2],
+    /// Whether the body was written in the current phase per kind; the first write
+    /// replaces the body.
+    written: [bool; 2],
+}
+
+impl State {
+    fn message(&mut self, kind: i32) -> Result<&mut Message> {
+        match kind {
+            KIND_REQUEST => Ok(&mut self.exchange.request),
+            KIND_RESPONSE => Ok(&mut self.exchange.response),
+            _ => bail!("unsupported header kind {kind}"),
+        }
+    }
+}
+
+fn memory(caller: &mut Caller<'_, State>) -> Result<wasmtime::Memory> {
+    match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => Ok(memory),
+        _ => bail!("guest exports no memory"),
+    }
+}
+
+fn read(caller: &mut Caller<'_, State>, ptr: i32, len: i32) -> Result<Vec<u8>> {
+    let mut data = vec![0; len as u32 as usize];
+    memory(caller)?.read(&caller, ptr as u32 as usize, &mut data)?;
+    Ok(data)
+}
+
+fn read_string(caller: &mut Caller<'_, State>, ptr: i32, len: i32) -> Result<String> {
+    Ok(String::from_utf8_lossy(&read(caller, ptr, len)?).into_owned())
+}
+
+/// Write `data` if it fits into `limit` and return its length, as the ABI requires.
+fn write(caller: &mut Caller<'_, State>, ptr: i32, limit: i32, data: &[u8]) -> Result<i32> {
+    if data.len() <= limit as u32 as usize {
+        memory(caller)?.write(&mut *caller, ptr as u32 as usize, data)?;
+    }
+    Ok(data.len() as i32)
+}
+
+/// Write NUL-terminated values and return `count << 32 | len`.
+fn write_values<'a>(caller: &mut Caller<'_, State>, ptr: i32, limit: i32, values: impl Iterator<Item = &'a str>) -> Result<i64> {
+    let mut data = Vec::new();
+    let mut count = 0i64;
+    for value in values {
+        data.extend_from_slice(value.as_bytes());
+        data.push(0);
+        count += 1;
+    }
+    let len = write(caller, ptr, limit, &data)?;
+    Ok(count << 32 | i64::from(len))
+}
+
+fn add_handler_imports(linker: &mut Linker<State>) -> Result<()> {
+    const M: &str = "http_handler";
+    linker.func_wrap(M, "log", |mut caller: Caller<'_, State>, level: i32, ptr: i32, len: i32| {
+        let message = read_string(&mut caller, ptr, len)?;
+        caller.data_mut().logs.push((level, message));
+        Ok(())
+    })?;
+    linker.func_wrap(M, "log_enabled", |caller: Caller<'_, State>, level: i32| i32::from(level >= caller.data().log_level))?;
+    linker.func_wrap(M, "get_config", |mut caller: Caller<'_, State>, ptr: i32, limit: i32| {
+        let config = caller.data().config.clone();
+        write(&mut caller, ptr, limit, &config)
+    })?;
+    linker.func_wrap(M, "enable_features", |mut caller: Caller<'_, State>, features: i32| {
+        let state = caller.data_mut();
+        state.features |= features as u32;
+        state.features as i32
+    })?;
+    linker.func_wrap(M, "get_method", |mut caller: Caller<'_, State>, ptr: i32, limit: i32| {
+        let method = caller.data().exchange.method.clone();
+        write(&mut caller, ptr, limit, method.as_bytes())
+    })?;
+    linker.func_wrap(M, "set_method", |mut caller: Caller<'_, State>, ptr: i32, len: i32| {
+        caller.data_mut().exchange.method = read_string(&mut caller, ptr, len)?;
+        Ok(())
+    })?;
+    linker.func_wrap(M, "get_uri", |mut caller: Caller<'_, State>, ptr: i32, limit: i32| {
+        let uri = caller.data().exchange.uri.clone();
+        write(&mut caller, ptr, limit, uri.as_bytes())
+    })?;
+    linker.func_wrap(M, "set_uri", |mut caller: Caller<'_, State>, ptr: i32, len: i32| {
+        caller.data_mut().exchange.uri = read_string(&mut caller, ptr, len)?;
+        Ok(())
+    })?;
+    linker.func_wrap(M, "get_protocol_version", |mut caller: Caller<'_, State>, ptr: i32, limit: i32| {
+        let version = caller.data().exchange.version.clone();
+        write(&mut caller, ptr, limit, version.as_bytes())
+    })?;
+    linker.func_wrap(M, "get_source_addr", |mut caller: Caller<'_, State>, ptr: i32, limit: i32| {
+        let addr = caller.data().exchange.source_addr.clone();
+        write(&mut caller, ptr, limit, addr.as_bytes())
+    })?;
+    linker.func_wrap(M, "get_header_names", |mut caller: Caller<'_, State>, kind: i32, ptr: i32, limit: i32| {
+        let message = caller.data_mut().message(kind)?.clone();
+        write_values(&mut caller, ptr, limit, message.names().into_iter())
+    })?;
+    linker.func_wrap(
+        M,
+        "get_header_values",
+        |mut caller: Caller<'_, State>, kind: i32, name: i32, name_len: i32, ptr: i32, limit: i32| {
+            let name = read_string(&mut caller, name, name_len)?;
+            let message = caller.data_mut().message(kind)?.clone();
+            write_values(&mut caller, ptr, limit, message.header_values(&name).into_iter())
+        },
+    )?;
+    linker.func_wrap(
+        M,
+        "set_header_value",
+        |mut caller: Caller<'_, State>, kind: i32, name: i32, name_len: i32, value: i32, value_len: i32| {
+            let name = read_string(&mut caller, name, name_len)?;
+            let value = read_string(&mut caller, value, value_len)?;
+            let message = caller.data_mut().message(kind)?;
+            match message.headers.iter().position(|(n, _)| n.eq_ignore_ascii_case(&name)) {
+                Some(i) => {
+                    message.headers[i].1 = value;
+                    let mut j = i + 1;
+                    while j < message.headers.len() {
+                        if message.headers[j].0.eq_ignore_ascii_case(&name) {
+                            message.headers.remove(j);
+                        } else {
+                            j += 1;
+                        }
+                    }
+                }
+                None => message.headers.push((name, value)),
+            }
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        M,
+        "add_header_value",
+        |mut caller: Caller<'_, State>, kind: i32, name: i32, name_len: i32, value: i32, value_len: i32| {
+            let name = read_string(&mut caller, name, name_len)?;
+            let value = read_string(&mut caller, value, value_len)?;
+            caller.data_mut().message(kind)?.headers.push((name, value));
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(M, "remove_header", |mut caller: Caller<'_, State>, kind: i32, name: i32, name_len: i32| {
+        let name = read_string(&mut caller, name, name_len)?;
+        caller.data_mut().message(kind)?.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(&name));
+        Ok(())
+    })?;
+    linker.func_wrap(M, "read_body", |mut caller: Caller<'_, State>, kind: i32, ptr: i32, limit: i32| {
+        let state = caller.data_mut();
+        let offset = state.read[kind as usize & 1];
+        let body = &state.message(kind)?.body;
+        let chunk = body.get(offset..).unwrap_or_default();
+        let chunk = chunk.get(..chunk.len().min(limit as u32 as usize)).unwrap_or_default().to_vec();
+        let eof = offset + chunk.len() >= body.len();
+        state.read[kind as usize & 1] += chunk.len();
+        memory(&mut caller)?.write(&mut caller, ptr as u32 as usize, &chunk)?;
+        Ok(i64::from(eof) << 32 | chunk.len() as i64)
+    })?;
+    linker.func_wrap(M, "write_body", |mut caller: Caller<'_, State>, kind: i32, ptr: i32, len: i32| {
+        let data = read(&mut caller, ptr, len)?;
+        let state = caller.data_mut();
+        let first = !std::mem::replace(&mut state.written[kind as usize & 1], true);
+        let body = &mut state.message(kind)?.body;
+        if first {
+            body.clear();
+        }
+        body.extend_from_slice(&data);
+        Ok(())
+    })?;
+    linker.func_wrap(M, "get_status_code", |caller: Caller<'_, State>| caller.data().exchange.status as i32)?;
+    linker.func_wrap(M, "set_status_code", |mut caller: Caller<'_, State>, status: i32| {
+        caller.data_mut().exchange.status = status as u32;
+    })?;
+    Ok(())
+}
+
+/// Build an example of the `http-wasm-guest` crate for `wasm32-wasip1` and return the
+/// path of the module.
+pub fn build_example(name: &str) -> Result<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    build(&root.join("Cargo.toml"), &["--example", name], &format!("examples/{name}.wasm"))
+}
+
+/// Build the guest fixture in `conformance/guest` for `wasm32-wasip1` and return the
+/// path of the module.
+pub fn build_fixture() -> Result<PathBuf> {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("guest/Cargo.toml");
+    build(&manifest, &[], "conformance-guest.wasm")
+}
+
+fn build(manifest: &Path, args: &[&str], artifact: &str) -> Result<PathBuf> {
+    let target_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/guests");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--quiet", "--target", "wasm32-wasip1", "--manifest-path"])
+        .arg(manifest)
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .args(args)
+        .status()
+        .context("running cargo")?;
+    if !status.success() {
+        bail!("building {} failed: {status}", manifest.display());
+    }
+    Ok(target_dir.join("wasm32-wasip1/debug").join(artifact))
+}
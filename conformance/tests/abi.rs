@@ -0,0 +1,129 @@
+//! Runs the examples and the guest fixture under the reference host.
+
+use std::sync::OnceLock;
+
+use http_wasm_guest_conformance::{Exchange, Host, Message, Plugin, build_example, build_fixture};
+
+fn fixture() -> Plugin {
+    static WASM: OnceLock<std::path::PathBuf> = OnceLock::new();
+    let wasm = WASM.get_or_init(|| build_fixture().unwrap());
+    Host::new(wasm).unwrap().with_config(b"{\"key\":1}").start().unwrap()
+}
+
+fn case(name: &str) -> Exchange {
+    Exchange { request: Message::default().with_header("X-Test", name), ..Exchange::default() }
+}
+
+#[test]
+fn header_example_adds_header() {
+    let mut plugin = Host::new(&build_example("header").unwrap()).unwrap().start().unwrap();
+    assert_eq!(plugin.logs(), [(0, "Registering plugin to add custom header".to_string())]);
+    let outcome = plugin.handle(Exchange::default()).unwrap();
+    assert!(outcome.next);
+    assert_eq!(outcome.exchange.request.header_values("x-custom-header"), ["FooBar"]);
+}
+
+#[test]
+fn info_example_logs_request() {
+    let mut plugin = Host::new(&build_example("info").unwrap()).unwrap().start().unwrap();
+    assert_eq!(plugin.features(), 3);
+    let exchange = Exchange {
+        request: Message { headers: vec![("Accept".into(), "*/*".into())], body: b"hello".to_vec() },
+        ..Exchange::default()
+    };
+    plugin.handle(exchange).unwrap();
+    let logs = plugin.logs().iter().map(|(_, message)| message.as_str()).collect::<Vec<_>>();
+    assert_eq!(logs, ["Request: GET HTTP/1.1 / 192.0.2.1:1234", "Header: Accept [*/*]", "Body: hello", "Status: 200"]);
+}
+
+#[test]
+fn info_example_skips_disabled_levels() {
+    let mut plugin = Host::new(&build_example("info").unwrap()).unwrap().with_log_level(1).start().unwrap();
+    plugin.handle(Exchange::default()).unwrap();
+    assert!(plugin.logs().is_empty());
+}
+
+#[test]
+fn fixture_enables_features() {
+    let plugin = fixture();
+    assert_eq!(plugin.features(), 3);
+    assert_eq!(plugin.logs(), [(0, "conformance fixture registered".to_string())]);
+}
+
+#[test]
+fn fixture_reads_request_and_config() {
+    let exchange = Exchange { method: "PUT".into(), uri: "/a?b=c".into(), version: "HTTP/2.0".into(), ..case("echo") };
+    let outcome = fixture().handle(exchange).unwrap();
+    let request = &outcome.exchange.request;
+    assert_eq!(request.header("x-echo"), Some("PUT /a?b=c HTTP/2.0 192.0.2.1:1234"));
+    assert_eq!(request.header("x-config"), Some("{\"key\":1}"));
+}
+
+#[test]
+fn fixture_rewrites_request_and_response() {
+    let mut exchange = case("rewrite");
+    exchange.request = exchange.request.with_header("x-set", "zero").with_header("X-Set", "zero").with_header("x-remove", "gone");
+    exchange.request.body = b"payload".to_vec();
+    exchange.response.body = b"upstream".to_vec();
+    let outcome = fixture().handle(exchange).unwrap();
+    assert!(outcome.next);
+    assert_eq!(outcome.ctx, 42);
+    let exchange = outcome.exchange;
+    assert_eq!(exchange.method, "POST");
+    assert_eq!(exchange.uri, "/rewritten?q=1");
+    assert_eq!(exchange.request.header_values("x-set"), ["one"]);
+    assert_eq!(exchange.request.header_values("x-add"), ["two"]);
+    assert!(exchange.request.header_values("x-remove").is_empty());
+    assert_eq!(exchange.request.body, b"payload|7");
+    assert_eq!(exchange.status, 201);
+    assert_eq!(exchange.response.body, b"UPSTREAM");
+    assert_eq!(exchange.response.header("x-ctx"), Some("42"));
+    assert_eq!(exchange.response.header("x-error"), Some("false"));
+}
+
+#[test]
+fn fixture_reads_headers_beyond_buffer() {
+    let large = "v".repeat(5000);
+    let mut exchange = case("headers");
+    exchange.request = exchange.request.with_header("X-Multi", "a").with_header("x-multi", "b").with_header("x-large", &large);
+    let request = fixture().handle(exchange).unwrap().exchange.request;
+    assert_eq!(request.header("x-names"), Some("x-test,x-multi,x-large"));
+    assert_eq!(request.header("x-values"), Some("a,b"));
+    assert_eq!(request.header("x-large-len"), Some("5000"));
+}
+
+#[test]
+fn fixture_reads_body_beyond_buffer() {
+    let mut exchange = case("rewrite");
+    exchange.request.body = vec![b'x'; 10_000];
+    let request = fixture().handle(exchange).unwrap().exchange.request;
+    assert_eq!(request.body.len(), 10_006);
+    assert!(request.body.ends_with(b"x|10000"));
+}
+
+#[test]
+fn fixture_stops_request() {
+    let outcome = fixture().handle(case("stop")).unwrap();
+    assert!(!outcome.next);
+    assert_eq!(outcome.exchange.status, 403);
+    assert_eq!(outcome.exchange.response.header("content-type"), Some("text/plain"));
+    assert_eq!(outcome.exchange.response.body, b"denied");
+    assert!(outcome.exchange.response.header("x-ctx").is_none());
+}
+
+#[test]
+fn fixture_uses_wasi_clock_and_random() {
+    let request = fixture().handle(case("runtime")).unwrap().exchange.request;
+    let now: u64 = request.header("x-now").unwrap().parse().unwrap();
+    assert!(now > 1_600_000_000);
+    assert_eq!(request.header("x-random"), Some("ok"));
+}
+
+#[test]
+fn fixture_keeps_state_across_exchanges() {
+    let mut plugin = fixture();
+    for _ in 0..3 {
+        let outcome = plugin.handle(case("rewrite")).unwrap();
+        assert_eq!(outcome.exchange.request.header_values("x-add"), ["two"]);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::host::Request;
+
+/// Request headers identifying a client, see [`CacheKey::build_shared`].
+const CREDENTIAL_HEADERS: [&str; 2] = ["authorization", "cookie"];
+
+/// Builder for canonical cache keys.
+///
+/// The key consists of the method, scheme, host, normalized path and query, and the
+/// values of the configured `Vary` headers, so that requests for the same resource
+/// map to the same key:
+///
+/// - scheme and host are lowercased and default ports (`:80`, `:443`) removed,
+/// - dot segments and repeated slashes are removed from the path, percent-encodings
+///   of unreserved characters are decoded and the hex digits of the others uppercased,
+/// - query parameters are sorted, optionally restricted to the significant ones.
+///
+/// Origin-form request targets (`/path?query`) take the host from the `Host` header
+/// and the scheme from `X-Forwarded-Proto`, defaulting to `http`.
+///
+/// ```
+/// use http_wasm_guest::cache::CacheKey;
+///
+/// let key = CacheKey::new().query_params(&["page", "q"]).vary("Accept-Encoding");
+/// assert_eq!(
+///     key.build_from("GET", "https://Example.com:443/a//b/../c?utm=x&q=1&page=2", |name| {
+///         (name == "accept-encoding").then(|| "gzip".to_string())
+///     }),
+///     "GET https://example.com/a/c?page=2&q=1\naccept-encoding: gzip"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CacheKey {
+    query: Query,
+    vary: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+enum Query {
+    #[default]
+    All,
+    Only(Vec<String>),
+}
+
+impl CacheKey {
+    /// Create a builder including all query parameters and no headers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include only the given query parameters; others (e.g. tracking parameters)
+    /// do not change the key. An empty list ignores the query entirely.
+    pub fn query_params(mut self, names: &[&str]) -> Self {
+        self.query = Query::Only(names.iter().map(|n| n.to_string()).collect());
+        self
+    }
+
+    /// Include the value of the request header `name`, as listed in `Vary`.
+    pub fn vary(mut self, name: &str) -> Self {
+        self.vary.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Build the key for `request`.
+    pub fn build(&self, request: &Request) -> String {
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        self.build_from(&lossy(&request.method()), &lossy(&request.uri()), |name| {
+            request.header.get(name.as_bytes()).map(|v| lossy(&v))
+        })
+    }
+
+    /// Build the key for `request` if a response to it may be shared with other clients.
+    ///
+    /// Returns `None` for requests carrying `Authorization` or `Cookie` unless the key
+    /// includes that header through [`vary`](Self::vary); such requests must neither
+    /// be served from nor stored in a shared cache.
+    pub fn build_shared(&self, request: &Request) -> Option<String> {
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        self.build_shared_from(&lossy(&request.method()), &lossy(&request.uri()), |name| {
+            request.header.get(name.as_bytes()).map(|v| lossy(&v))
+        })
+    }
+
+    /// Like [`build_shared`](Self::build_shared), from request parts.
+    pub fn build_shared_from(&self, method: &str, uri: &str, header: impl Fn(&str) -> Option<String>) -> Option<String> {
+        let private = CREDENTIAL_HEADERS.iter().any(|name| header(name).is_some() && !self.vary.iter().any(|v| v == name));
+        (!private).then(|| self.build_from(method, uri, header))
+    }
+
+    /// Build the key from request parts; `header` looks up request headers by their
+    /// lowercase name.
+    pub fn build_from(&self, method: &str, uri: &str, header: impl Fn(&str) -> Option<String>) -> String {
+        let (scheme, authority, target) = match uri.split_once("://") {
+            Some((scheme, rest)) => {
+                let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+                let (authority, target) = rest.split_at_checked(end).unwrap_or((rest, ""));
+                (scheme.to_ascii_lowercase(), authority.to_string(), target)
+            }
+            None => (
+                header("x-forwarded-proto").map_or_else(|| "http".into(), |p| p.trim().to_ascii_lowercase()),
+                header("host").unwrap_or_default(),
+                uri,
+            ),
+        };
+        let target = target.split('#').next().unwrap_or_default();
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+        let mut key = format!("{} {scheme}://{}{}", method.to_ascii_uppercase(), host(&authority, &scheme), normalize_path(path));
+        let query = self.normalize_query(query);
+        if !query.is_empty() {
+            key.push('?');
+            key.push_str(&query);
+        }
+        for name in &self.vary {
+            key.push('\n');
+            key.push_str(name);
+            key.push_str(": ");
+            key.push_str(header(name).as_deref().unwrap_or_default().trim());
+        }
+        key
+    }
+
+    fn normalize_query(&self, query: &str) -> String {
+        let mut params: Vec<(String, &str)> = query
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let name = p.split('=').next().unwrap_or_default();
+                (normalize_encoding(name), p)
+            })
+            .filter(|(name, _)| match &self.query {
+                Query::All => true,
+                Query::Only(names) => names.iter().any(|n| n == name),
+            })
+            .collect();
+        params.sort();
+        params.iter().map(|(_, p)| normalize_encoding(p)).collect::<Vec<_>>().join("&")
+    }
+}
+
+/// Lowercase host without userinfo and default port.
+fn host(authority: &str, scheme: &str) -> String {
+    let host = authority.rsplit('@').next().unwrap_or_default().to_ascii_lowercase();
+    match (scheme, host.rsplit_once(':')) {
+        ("http", Some((name, "80"))) | ("https", Some((name, "443"))) => name.to_string(),
+        _ => host,
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    let mut segments: Vec<String> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(normalize_encoding(s)),
+        }
+    }
+    let trailing = path.len() > 1 && (path.ends_with('/') || path.ends_with("/.") || path.ends_with("/.."));
+    let mut out = format!("/{}", segments.join("/"));
+    if trailing && !segments.is_empty() {
+        out.push('/');
+    }
+    out
+}
+
+/// Decode percent-encoded unreserved characters and uppercase the hex digits of
+/// the remaining percent-encodings.
+fn normalize_encoding(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while let Some(&b) = bytes.get(i) {
+        let hex = bytes.get(i + 1..i + 3).filter(|_| b == b'%').filter(|h| h.iter().all(u8::is_ascii_hexdigit));
+        match hex.and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok().map(|d| (h, d))) {
+            Some((_, decoded)) if decoded.is_ascii_alphanumeric() || matches!(decoded, b'-' | b'.' | b'_' | b'~') => {
+                out.push(decoded);
+                i += 3;
+            }
+            Some((hex, _)) => {
+                out.push(b'%');
+                out.extend_from_slice(hex.to_ascii_uppercase().as_bytes());
+                i += 3;
+            }
+            None => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    // only ASCII is decoded, so the input's UTF-8 stays intact
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(key: &CacheKey, uri: &str) -> String {
+        key.build_from("get", uri, |name| match name {
+            "host" => Some("Example.COM:80".into()),
+            "accept-encoding" => Some(" br ".into()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn origin_form_uses_host_header() {
+        assert_eq!(build(&CacheKey::new(), "/a?b=1"), "GET http://example.com/a?b=1");
+        assert_eq!(build(&CacheKey::new(), ""), "GET http://example.com/");
+    }
+
+    #[test]
+    fn absolute_form() {
+        assert_eq!(build(&CacheKey::new(), "HTTPS://u@Host:8443"), "GET https://host:8443/");
+        assert_eq!(build(&CacheKey::new(), "https://h/p#frag"), "GET https://h/p");
+    }
+
+    #[test]
+    fn path_normalization() {
+        assert_eq!(normalize_path("/a/./b/../c/"), "/a/c/");
+        assert_eq!(normalize_path("//a///b"), "/a/b");
+        assert_eq!(normalize_path("/../.."), "/");
+        assert_eq!(normalize_path("/%7euser/%2f%zz%+1/ä"), "/~user/%2F%zz%+1/ä");
+    }
+
+    #[test]
+    fn query_sorted_and_filtered() {
+        assert_eq!(build(&CacheKey::new(), "/?b=2&a=1&&a=0"), "GET http://example.com/?a=0&a=1&b=2");
+        assert_eq!(build(&CacheKey::new().query_params(&["a"]), "/?b=2&a=1"), "GET http://example.com/?a=1");
+        assert_eq!(build(&CacheKey::new().query_params(&[]), "/?b=2"), "GET http://example.com/");
+        assert_eq!(build(&CacheKey::new().query_params(&["~a"]), "/?%7Ea=%41"), "GET http://example.com/?~a=A");
+    }
+
+    #[test]
+    fn vary_headers() {
+        let key = CacheKey::new().vary("Accept-Encoding").vary("Accept-Language");
+        assert_eq!(build(&key, "/"), "GET http://example.com/\naccept-encoding: br\naccept-language: ");
+    }
+
+    #[test]
+    fn from_request() {
+        // The mock request is "GET https://test"
+        assert_eq!(CacheKey::new().build(&Request::new()), "GET https://test/");
+    }
+
+    #[test]
+    fn shared_key_excludes_credentials() {
+        let header = |name: &str| (name == "cookie").then(|| "session=1".to_string());
+        assert_eq!(CacheKey::new().build_shared_from("GET", "https://a/", header), None);
+        let key = CacheKey::new().vary("Cookie");
+        assert_eq!(key.build_shared_from("GET", "https://a/", header), Some("GET https://a/\ncookie: session=1".into()));
+        assert!(CacheKey::new().build_shared_from("GET", "https://a/", |_| None).is_some());
+    }
+}
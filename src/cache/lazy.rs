@@ -0,0 +1,138 @@
+use std::{cell::RefCell, fmt, rc::Rc, time::Duration};
+
+use super::{monotonic_millis, ttl_millis};
+
+/// A value computed on first use and kept for the lifetime of the guest instance, or
+/// until its optional time to live has passed.
+///
+/// Suited for expensive derivations from the configuration such as compiled patterns
+/// or parsed key sets. The initializer runs outside of any borrow, so it may use other
+/// cached values.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use http_wasm_guest::{cache::Lazy, host::admin};
+///
+/// struct Plugin {
+///     blocked: Lazy<Vec<String>>,
+/// }
+///
+/// let plugin = Plugin {
+///     blocked: Lazy::with_ttl(|| admin::config().to_string().lines().map(str::to_string).collect(), Duration::from_secs(60)),
+/// };
+/// let blocked = plugin.blocked.get();
+/// ```
+pub struct Lazy<T, F = fn() -> T> {
+    init: F,
+    ttl: Option<Duration>,
+    value: RefCell<Option<(Rc<T>, u64)>>,
+}
+
+impl<T, F: Fn() -> T> Lazy<T, F> {
+    /// Create a lazy value computed by `init`.
+    pub const fn new(init: F) -> Self {
+        Self { init, ttl: None, value: RefCell::new(None) }
+    }
+
+    /// Create a lazy value computed by `init` that is recomputed on the first access
+    /// after `ttl` has passed.
+    pub const fn with_ttl(init: F, ttl: Duration) -> Self {
+        Self { init, ttl: Some(ttl), value: RefCell::new(None) }
+    }
+
+    /// Return the value, computing it if it is missing or expired.
+    pub fn get(&self) -> Rc<T> {
+        self.get_at(monotonic_millis())
+    }
+
+    /// Return the value if it has been computed and has not expired.
+    pub fn peek(&self) -> Option<Rc<T>> {
+        self.peek_at(monotonic_millis())
+    }
+
+    /// Drop the value so the next access computes it again.
+    pub fn reset(&self) {
+        self.value.borrow_mut().take();
+    }
+
+    fn get_at(&self, now_ms: u64) -> Rc<T> {
+        if let Some(value) = self.peek_at(now_ms) {
+            return value;
+        }
+        let value = Rc::new((self.init)());
+        let expires_ms = self.ttl.map_or(u64::MAX, |ttl| now_ms.saturating_add(ttl_millis(ttl)));
+        *self.value.borrow_mut() = Some((value.clone(), expires_ms));
+        value
+    }
+
+    fn peek_at(&self, now_ms: u64) -> Option<Rc<T>> {
+        match &*self.value.borrow() {
+            Some((value, expires_ms)) if now_ms < *expires_ms => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for Lazy<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.value.try_borrow().ok().and_then(|value| value.as_ref().map(|(value, _)| value.clone()));
+        f.debug_struct("Lazy").field("value", &value).field("ttl", &self.ttl).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn computes_once() {
+        let calls = Cell::new(0);
+        let lazy = Lazy::new(|| {
+            calls.set(calls.get() + 1);
+            calls.get()
+        });
+        assert_eq!(lazy.peek_at(0), None);
+        assert_eq!(*lazy.get_at(0), 1);
+        assert_eq!(*lazy.get_at(u64::MAX - 1), 1);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn recomputes_after_ttl() {
+        let calls = Cell::new(0);
+        let lazy = Lazy::with_ttl(
+            || {
+                calls.set(calls.get() + 1);
+                calls.get()
+            },
+            Duration::from_secs(1),
+        );
+        assert_eq!(*lazy.get_at(0), 1);
+        assert_eq!(*lazy.get_at(999), 1);
+        assert_eq!(lazy.peek_at(1_000), None);
+        assert_eq!(*lazy.get_at(1_000), 2);
+    }
+
+    #[test]
+    fn reset_recomputes() {
+        let calls = Cell::new(0);
+        let lazy = Lazy::new(|| {
+            calls.set(calls.get() + 1);
+            calls.get()
+        });
+        lazy.get_at(0);
+        lazy.reset();
+        assert_eq!(*lazy.get_at(0), 2);
+    }
+
+    #[test]
+    fn init_may_use_other_lazy_values() {
+        let inner = Lazy::new(|| 2);
+        let outer = Lazy::new(|| *inner.get() * 3);
+        assert_eq!(*outer.get(), 6);
+        assert_eq!(format!("{inner:?}"), "Lazy { value: Some(2), ttl: None }");
+    }
+}
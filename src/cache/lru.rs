@@ -0,0 +1,124 @@
+use std::{borrow::Borrow, collections::HashMap, hash::Hash};
+
+struct Slot<V> {
+    value: V,
+    size: usize,
+    expires_ms: u64,
+    used: u64,
+}
+
+/// Values with an expiry time and a size, bounded in number and total size.
+///
+/// The bookkeeping shared by [`Memo`](super::Memo) and [`MicroCache`](super::MicroCache):
+/// expired values make room first, then the least recently used ones. Callers pass the
+/// time in milliseconds of [`monotonic_millis`](super::monotonic_millis).
+pub(super) struct Lru<K, V> {
+    slots: HashMap<K, Slot<V>>,
+    max_entries: usize,
+    max_size: usize,
+    size: usize,
+    clock: u64,
+}
+
+impl<K, V> Lru<K, V> {
+    /// Create an empty map holding at most `max_entries` values of `max_size` in total.
+    pub(super) fn new(max_entries: usize, max_size: usize) -> Self {
+        Self { slots: HashMap::new(), max_entries, max_size, size: 0, clock: 0 }
+    }
+
+    /// Number of values, including expired ones not evicted yet.
+    pub(super) fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Total size of all values.
+    #[cfg(test)]
+    pub(super) fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<K: Eq + Hash, V> Lru<K, V> {
+    /// Return the value for `key` if it has not expired at `now_ms`, marking it as
+    /// recently used; an expired value is removed.
+    pub(super) fn get<Q>(&mut self, key: &Q, now_ms: u64) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if self.slots.get(key)?.expires_ms <= now_ms {
+            self.remove(key);
+            return None;
+        }
+        self.clock += 1;
+        let slot = self.slots.get_mut(key)?;
+        slot.used = self.clock;
+        Some(&slot.value)
+    }
+
+    /// Store `value` under `key`, replacing an earlier value and evicting others until it
+    /// fits; returns `false` if it can never fit and was not stored.
+    pub(super) fn insert(&mut self, key: K, value: V, size: usize, expires_ms: u64, now_ms: u64) -> bool {
+        if self.max_entries == 0 || size > self.max_size {
+            return false;
+        }
+        self.remove(&key);
+        if !self.fits(size) {
+            self.retain(|slot| slot.expires_ms > now_ms);
+        }
+        while !self.fits(size) {
+            let Some(oldest) = self.slots.values().map(|slot| slot.used).min() else {
+                break;
+            };
+            self.retain(|slot| slot.used != oldest);
+        }
+        self.clock += 1;
+        self.size += size;
+        self.slots.insert(key, Slot { value, size, expires_ms, used: self.clock });
+        true
+    }
+
+    /// Remove and return the value for `key`.
+    pub(super) fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let slot = self.slots.remove(key)?;
+        self.size -= slot.size;
+        Some(slot.value)
+    }
+
+    /// Remove all values.
+    pub(super) fn clear(&mut self) {
+        self.slots.clear();
+        self.size = 0;
+    }
+
+    fn fits(&self, size: usize) -> bool {
+        self.slots.len() < self.max_entries && self.size.saturating_add(size) <= self.max_size
+    }
+
+    fn retain(&mut self, keep: impl Fn(&Slot<V>) -> bool) {
+        self.slots.retain(|_, slot| keep(slot));
+        self.size = self.slots.values().map(|slot| slot.size).sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_values_make_room_before_used_ones() {
+        let mut lru = Lru::new(4, 10);
+        lru.insert("old", 1, 4, 100, 0);
+        lru.insert("used", 2, 4, u64::MAX, 0);
+        assert!(lru.insert("new", 3, 4, u64::MAX, 100));
+        assert_eq!((lru.len(), lru.size()), (2, 8));
+        assert_eq!(lru.get("used", 100), Some(&2));
+        assert!(!lru.insert("huge", 4, 11, u64::MAX, 100));
+        lru.clear();
+        assert_eq!((lru.len(), lru.size()), (0, 0));
+    }
+}
@@ -0,0 +1,198 @@
+use std::{borrow::Borrow, cell::RefCell, fmt, hash::Hash, rc::Rc, time::Duration};
+
+use super::{lru::Lru, monotonic_millis, ttl_millis};
+
+/// Bounded memoization of a derivation per key, with optional time to live.
+///
+/// Keeps at most `max_entries` results in the guest instance and evicts the least
+/// recently used one first, which suits lookups such as geo data per client address or
+/// key sets per issuer. Computations run outside of any borrow.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use http_wasm_guest::{
+///     Guest,
+///     cache::Memo,
+///     host::{Request, Response},
+/// };
+///
+/// fn country(ip: &[u8]) -> String {
+///     // an expensive lookup
+///     String::from_utf8_lossy(ip).into_owned()
+/// }
+///
+/// struct Plugin {
+///     countries: Memo<Vec<u8>, String>,
+/// }
+///
+/// impl Guest for Plugin {
+///     fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+///         let ip = request.source_addr().to_vec();
+///         let country = self.countries.get_or_insert_with(ip.clone(), || country(&ip));
+///         request.header.set(b"X-Country", country.as_bytes());
+///         (true, 0)
+///     }
+/// }
+///
+/// let plugin = Plugin { countries: Memo::new(1024).ttl(Duration::from_secs(300)) };
+/// ```
+pub struct Memo<K, V> {
+    max_entries: usize,
+    ttl: Option<Duration>,
+    entries: RefCell<Lru<K, Rc<V>>>,
+}
+
+impl<K: Eq + Hash, V> Memo<K, V> {
+    /// Create an empty memo holding at most `max_entries` values.
+    pub fn new(max_entries: usize) -> Self {
+        Self { max_entries, ttl: None, entries: RefCell::new(Lru::new(max_entries, usize::MAX)) }
+    }
+
+    /// Expire values `ttl` after they were stored.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Return the fresh value for `key`, marking it as recently used.
+    pub fn get<Q>(&self, key: &Q) -> Option<Rc<V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_at(key, monotonic_millis())
+    }
+
+    /// Return the fresh value for `key`, computing and storing it with `f` if needed.
+    pub fn get_or_insert_with(&self, key: K, f: impl FnOnce() -> V) -> Rc<V> {
+        let now_ms = monotonic_millis();
+        match self.get_at(&key, now_ms) {
+            Some(value) => value,
+            None => self.insert_at(key, f(), now_ms),
+        }
+    }
+
+    /// Store `value` under `key`, replacing an earlier value.
+    pub fn insert(&self, key: K, value: V) -> Rc<V> {
+        self.insert_at(key, value, monotonic_millis())
+    }
+
+    /// Remove and return the value for `key`.
+    pub fn remove<Q>(&self, key: &Q) -> Option<Rc<V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.borrow_mut().remove(key)
+    }
+
+    /// Remove all values.
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    /// Number of stored values, including expired ones not evicted yet.
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    /// Whether the memo holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get_at<Q>(&self, key: &Q, now_ms: u64) -> Option<Rc<V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.borrow_mut().get(key, now_ms).cloned()
+    }
+
+    fn insert_at(&self, key: K, value: V, now_ms: u64) -> Rc<V> {
+        let value = Rc::new(value);
+        let expires_ms = self.ttl.map_or(u64::MAX, |ttl| now_ms.saturating_add(ttl_millis(ttl)));
+        self.entries.borrow_mut().insert(key, value.clone(), 0, expires_ms, now_ms);
+        value
+    }
+}
+
+impl<K, V> fmt::Debug for Memo<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let len = self.entries.try_borrow().map(|entries| entries.len()).ok();
+        f.debug_struct("Memo").field("len", &len).field("max_entries", &self.max_entries).field("ttl", &self.ttl).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn computes_once_per_key() {
+        let memo = Memo::new(4);
+        let calls = Cell::new(0);
+        let compute = |n: u32| {
+            calls.set(calls.get() + 1);
+            n * 2
+        };
+        assert_eq!(*memo.get_or_insert_with(1, || compute(1)), 2);
+        assert_eq!(*memo.get_or_insert_with(1, || compute(1)), 2);
+        assert_eq!(*memo.get_or_insert_with(2, || compute(2)), 4);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(memo.len(), 2);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let memo = Memo::new(2);
+        memo.insert_at("a", 1, 0);
+        memo.insert_at("b", 2, 0);
+        assert!(memo.get_at("a", 0).is_some());
+        memo.insert_at("c", 3, 0);
+        assert!(memo.get_at("b", 0).is_none());
+        assert_eq!(memo.get_at("a", 0).as_deref(), Some(&1));
+        assert_eq!(memo.get_at("c", 0).as_deref(), Some(&3));
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let memo = Memo::new(2).ttl(Duration::from_secs(1));
+        memo.insert_at("a".to_string(), 1, 0);
+        assert!(memo.get_at("a", 999).is_some());
+        assert!(memo.get_at("a", 1_000).is_none());
+        assert!(memo.is_empty());
+    }
+
+    #[test]
+    fn expired_entries_make_room_first() {
+        let memo = Memo::new(2).ttl(Duration::from_secs(1));
+        memo.insert_at("old", 1, 0);
+        memo.insert_at("fresh", 2, 900);
+        memo.get_at("old", 500);
+        memo.insert_at("new", 3, 1_000);
+        assert!(memo.get_at("fresh", 1_000).is_some());
+        assert!(memo.get_at("new", 1_000).is_some());
+    }
+
+    #[test]
+    fn zero_capacity_stores_nothing() {
+        let memo = Memo::new(0);
+        assert_eq!(*memo.insert("a", 1), 1);
+        assert!(memo.is_empty());
+    }
+
+    #[test]
+    fn remove_and_clear() {
+        let memo = Memo::new(4);
+        memo.insert(String::from("a"), 1);
+        memo.insert(String::from("b"), 2);
+        assert_eq!(memo.remove("a").as_deref(), Some(&1));
+        memo.clear();
+        assert!(memo.is_empty());
+        assert_eq!(format!("{memo:?}"), "Memo { len: Some(0), max_entries: 4, ttl: None }");
+    }
+}
@@ -0,0 +1,285 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use super::{lru::Lru, monotonic_millis, ttl_millis};
+use crate::host::Response;
+
+/// Response headers that are not stored: they describe a single connection or
+/// client, or are recomputed when the entry is served.
+const SKIPPED_HEADERS: [&[u8]; 5] = [b"Connection", b"Content-Length", b"Keep-Alive", b"Set-Cookie", b"Transfer-Encoding"];
+
+/// Configuration for the [`MicroCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MicroCacheConfig {
+    /// Maximum number of entries; the least recently used entry is evicted first.
+    pub max_entries: usize,
+    /// Maximum total size of all entries in bytes (status line excluded).
+    pub max_bytes: usize,
+    /// Maximum size of a single entry; larger responses are not stored.
+    pub max_entry_bytes: usize,
+    /// Time to live of entries stored with [`insert`](MicroCache::insert).
+    pub ttl: Duration,
+}
+
+impl Default for MicroCacheConfig {
+    fn default() -> Self {
+        Self { max_entries: 256, max_bytes: 1024 * 1024, max_entry_bytes: 64 * 1024, ttl: Duration::from_secs(5) }
+    }
+}
+
+/// A stored response.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CachedResponse {
+    /// Status code.
+    pub status: i32,
+    /// Header names and values, in order.
+    pub headers: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Body.
+    pub body: Vec<u8>,
+}
+
+impl CachedResponse {
+    /// Capture status, headers and body of `response`.
+    ///
+    /// `Set-Cookie` and connection-specific headers are left out. Reading the body in
+    /// `handle_response` requires `feature::BufferResponse`.
+    pub fn from_response(response: &Response) -> Self {
+        let headers = response
+            .header
+            .entries_iter()
+            .filter(|(name, _)| !SKIPPED_HEADERS.iter().any(|skipped| name.eq_ignore_ascii_case(skipped)))
+            .flat_map(|(name, values)| values.into_iter().map(move |value| (name.to_vec(), value.to_vec())))
+            .collect();
+        Self { status: response.status(), headers, body: response.body.read().to_vec() }
+    }
+
+    /// Write status, headers and body to `response`.
+    pub fn write_to(&self, response: &Response) {
+        response.set_status(self.status);
+        for (name, _) in &self.headers {
+            response.header.remove(name);
+        }
+        for (name, value) in &self.headers {
+            response.header.add(name, value);
+        }
+        response.header.set(b"Content-Length", self.body.len().to_string().as_bytes());
+        response.body.write(&self.body);
+    }
+
+    /// Returns `false` if `Cache-Control` marks the response as `private`, `no-store` or
+    /// `no-cache`, so it must not be served to other clients from a shared cache.
+    pub fn is_shareable(&self) -> bool {
+        !self.headers.iter().filter(|(name, _)| name.eq_ignore_ascii_case(b"Cache-Control")).any(|(_, value)| {
+            value.split(|b| *b == b',').any(|directive| {
+                let name = directive.split(|b| *b == b'=').next().unwrap_or_default().trim_ascii();
+                [&b"private"[..], b"no-store", b"no-cache"].iter().any(|d| name.eq_ignore_ascii_case(d))
+            })
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.body.len() + self.headers.iter().map(|(name, value)| name.len() + value.len()).sum::<usize>()
+    }
+}
+
+/// In-memory LRU response cache with per-entry time to live.
+///
+/// Serves small, hot responses directly from `handle_request` without a round trip to
+/// the upstream. Memory is bounded by the number of entries and their total size.
+/// The cache lives in the guest instance, so it is not shared between instances or
+/// replicas. Build keys with [`CacheKey::build_shared`](super::CacheKey::build_shared),
+/// which yields no key for requests with credentials the key does not include, so one
+/// client's response is never served to another. Responses marked `private`,
+/// `no-store` or `no-cache` are not stored.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     Guest,
+///     cache::{CacheKey, MicroCache, MicroCacheConfig},
+///     host::{Request, Response, admin, feature},
+/// };
+///
+/// struct Plugin {
+///     cache: MicroCache,
+///     key: CacheKey,
+/// }
+///
+/// impl Guest for Plugin {
+///     fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+///         let Some(key) = self.key.build_shared(request) else {
+///             return (true, 0);
+///         };
+///         if self.cache.serve(&key, response) {
+///             return (false, 0);
+///         }
+///         admin::enable(feature::BufferResponse);
+///         (true, 0)
+///     }
+///
+///     fn handle_response(&self, _req_ctx: i32, request: &Request, response: &Response, is_error: bool) {
+///         if let Some(key) = self.key.build_shared(request).filter(|_| !is_error && response.status() == 200) {
+///             self.cache.store(&key, response);
+///         }
+///     }
+/// }
+///
+/// let plugin = Plugin { cache: MicroCache::new(MicroCacheConfig::default()), key: CacheKey::new() };
+/// ```
+pub struct MicroCache {
+    config: MicroCacheConfig,
+    entries: RefCell<Lru<String, Rc<CachedResponse>>>,
+}
+
+impl MicroCache {
+    /// Create an empty cache with the given configuration.
+    pub fn new(config: MicroCacheConfig) -> Self {
+        let entries = RefCell::new(Lru::new(config.max_entries, config.max_bytes));
+        Self { config, entries }
+    }
+
+    /// Return the fresh entry for `key`, marking it as recently used.
+    pub fn get(&self, key: &str) -> Option<Rc<CachedResponse>> {
+        self.get_at(key, monotonic_millis())
+    }
+
+    /// Store `response` under `key` with the configured time to live.
+    ///
+    /// Returns `false` if the entry exceeds `max_entry_bytes` or is not
+    /// [shareable](CachedResponse::is_shareable) and was not stored.
+    pub fn insert(&self, key: &str, response: CachedResponse) -> bool {
+        self.insert_with_ttl(key, response, self.config.ttl)
+    }
+
+    /// Store `response` under `key` with the given time to live.
+    pub fn insert_with_ttl(&self, key: &str, response: CachedResponse, ttl: Duration) -> bool {
+        self.insert_at(key, response, ttl, monotonic_millis())
+    }
+
+    /// Remove the entry for `key`.
+    pub fn remove(&self, key: &str) {
+        self.entries.borrow_mut().remove(key);
+    }
+
+    /// Number of stored entries, including expired ones not evicted yet.
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    /// Whether the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Answer with the fresh entry for `key`, if any; returns `true` if it was written
+    /// to `response`.
+    pub fn serve(&self, key: &str, response: &Response) -> bool {
+        match self.get(key) {
+            Some(cached) => {
+                cached.write_to(response);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Capture `response` and store it under `key`, see [`CachedResponse::from_response`].
+    pub fn store(&self, key: &str, response: &Response) -> bool {
+        self.insert(key, CachedResponse::from_response(response))
+    }
+
+    fn get_at(&self, key: &str, now_ms: u64) -> Option<Rc<CachedResponse>> {
+        self.entries.borrow_mut().get(key, now_ms).cloned()
+    }
+
+    fn insert_at(&self, key: &str, response: CachedResponse, ttl: Duration, now_ms: u64) -> bool {
+        let size = response.size();
+        if size > self.config.max_entry_bytes || !response.is_shareable() {
+            return false;
+        }
+        let expires_ms = now_ms.saturating_add(ttl_millis(ttl));
+        self.entries.borrow_mut().insert(key.to_string(), Rc::new(response), size, expires_ms, now_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_entries: usize, max_bytes: usize) -> MicroCache {
+        MicroCache::new(MicroCacheConfig { max_entries, max_bytes, max_entry_bytes: max_bytes, ttl: Duration::from_secs(1) })
+    }
+
+    fn body(len: usize) -> CachedResponse {
+        CachedResponse { status: 200, headers: Vec::new(), body: vec![b'x'; len] }
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let cache = cache(4, 100);
+        assert!(cache.insert_at("a", body(1), Duration::from_millis(500), 1000));
+        assert!(cache.get_at("a", 1499).is_some());
+        assert!(cache.get_at("a", 1500).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = cache(2, 100);
+        let ttl = Duration::from_secs(10);
+        cache.insert_at("a", body(1), ttl, 0);
+        cache.insert_at("b", body(1), ttl, 0);
+        cache.get_at("a", 1);
+        cache.insert_at("c", body(1), ttl, 2);
+        assert!(cache.get_at("a", 3).is_some());
+        assert!(cache.get_at("b", 3).is_none());
+        assert!(cache.get_at("c", 3).is_some());
+    }
+
+    #[test]
+    fn bounded_by_bytes() {
+        let cache = cache(10, 10);
+        let ttl = Duration::from_secs(10);
+        assert!(cache.insert_at("a", body(6), ttl, 0));
+        assert!(cache.insert_at("b", body(4), ttl, 0));
+        assert!(cache.insert_at("c", body(5), ttl, 0));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_at("a", 0).is_none());
+        assert!(!cache.insert_at("d", body(11), ttl, 0));
+    }
+
+    #[test]
+    fn replace_and_remove_keep_size() {
+        let cache = cache(10, 10);
+        let ttl = Duration::from_secs(10);
+        cache.insert_at("a", body(8), ttl, 0);
+        cache.insert_at("a", body(9), ttl, 0);
+        assert_eq!(cache.entries.borrow().size(), 9);
+        cache.remove("a");
+        assert_eq!(cache.entries.borrow().size(), 0);
+    }
+
+    #[test]
+    fn refuses_private_responses() {
+        let cache = cache(4, 100);
+        let ttl = Duration::from_secs(10);
+        for value in ["private", "max-age=60, no-store", "No-Cache", "private=\"Set-Cookie\""] {
+            let response = CachedResponse { headers: vec![(b"cache-control".to_vec(), value.as_bytes().to_vec())], ..body(1) };
+            assert!(!response.is_shareable(), "{value}");
+            assert!(!cache.insert_at("a", response, ttl, 0), "{value}");
+        }
+        let public = CachedResponse { headers: vec![(b"Cache-Control".to_vec(), b"public, max-age=60".to_vec())], ..body(1) };
+        assert!(cache.insert_at("a", public, ttl, 0));
+    }
+
+    #[test]
+    fn capture_and_serve() {
+        // The mock response is "200" with the mock body and headers
+        let response = Response::new();
+        let cached = CachedResponse::from_response(&response);
+        assert_eq!(cached.status, 200);
+        assert_eq!(cached.body, b"<html><body>test</body>");
+        let cache = MicroCache::new(MicroCacheConfig::default());
+        assert!(!cache.serve("k", &response));
+        assert!(cache.store("k", &response));
+        assert!(cache.serve("k", &response));
+    }
+}
@@ -0,0 +1,24 @@
+//! Building blocks for caching plugins.
+mod key;
+mod lazy;
+mod lru;
+mod memo;
+mod micro;
+
+use std::time::Duration;
+
+pub use key::CacheKey;
+pub use lazy::Lazy;
+pub use memo::Memo;
+pub use micro::{CachedResponse, MicroCache, MicroCacheConfig};
+
+use crate::host::time;
+
+/// Milliseconds of the monotonic clock, which time-to-live checks use.
+fn monotonic_millis() -> u64 {
+    time::monotonic_nanos() / 1_000_000
+}
+
+fn ttl_millis(ttl: Duration) -> u64 {
+    u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX)
+}
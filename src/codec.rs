@@ -0,0 +1,205 @@
+//! Byte-to-text encodings shared by the header, body and TLS helpers.
+use std::{
+    error::Error,
+    fmt::{Display, Write},
+};
+
+const BASE64_STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Error returned when decoding text into bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input contains a character outside of the alphabet at this byte offset.
+    InvalidByte(usize),
+    /// The input length is not possible for the encoding.
+    InvalidLength,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::InvalidByte(position) => write!(f, "invalid character at byte {position}"),
+            DecodeError::InvalidLength => write!(f, "invalid input length"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Encode base64 with the standard or URL-safe alphabet of RFC 4648.
+pub(crate) fn base64_encode(input: &[u8], url_safe: bool, pad: bool) -> String {
+    let alphabet = if url_safe { BASE64_URL_SAFE } else { BASE64_STANDARD };
+    let char_at = |index: u32| char::from(alphabet.get((index & 0x3F) as usize).copied().unwrap_or(b'='));
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(char_at(n >> (18 - 6 * i)));
+        }
+        if pad {
+            out.extend(std::iter::repeat_n('=', 3 - chunk.len()));
+        }
+    }
+    out
+}
+
+/// Decode base64 with the standard or URL-safe alphabet; padding is optional.
+pub(crate) fn base64_decode(input: &[u8], url_safe: bool) -> Result<Vec<u8>, DecodeError> {
+    let data = input.strip_suffix(b"==").or_else(|| input.strip_suffix(b"=")).unwrap_or(input);
+    let mut out = Vec::with_capacity(data.len() / 4 * 3 + 2);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for (i, &b) in data.iter().enumerate() {
+        let v = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' if !url_safe => 62,
+            b'/' if !url_safe => 63,
+            b'-' if url_safe => 62,
+            b'_' if url_safe => 63,
+            _ => return Err(DecodeError::InvalidByte(i)),
+        };
+        acc = (acc << 6) | u32::from(v);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if data.len() % 4 == 1 || (data.len() != input.len() && input.len() % 4 != 0) {
+        return Err(DecodeError::InvalidLength);
+    }
+    Ok(out)
+}
+
+/// Encode lowercase hex.
+pub(crate) fn hex_encode(input: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let digit = |nibble: u8| char::from(DIGITS.get(usize::from(nibble & 0x0F)).copied().unwrap_or(b'0'));
+    input.iter().flat_map(|b| [digit(b >> 4), digit(*b)]).collect()
+}
+
+/// Decode hex digits of either case.
+pub(crate) fn hex_decode(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let digit = |i: usize| input.get(i).and_then(|b| hex_digit(*b)).ok_or(DecodeError::InvalidByte(i));
+    let out = (0..input.len() / 2).map(|i| Ok(digit(2 * i)? << 4 | digit(2 * i + 1)?)).collect::<Result<_, _>>()?;
+    match input.len() % 2 {
+        0 => Ok(out),
+        _ => Err(digit(input.len() - 1).err().unwrap_or(DecodeError::InvalidLength)),
+    }
+}
+
+fn hex_digit(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Encode all bytes except the unreserved characters of RFC 3986 (and `/` if
+/// `keep_slash`) as `%XX` with uppercase hex digits.
+pub(crate) fn percent_encode(input: &[u8], keep_slash: bool) -> String {
+    let mut out = String::with_capacity(input.len());
+    for &b in input {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(char::from(b)),
+            b'/' if keep_slash => out.push('/'),
+            _ => {
+                let _ = write!(out, "%{b:02X}");
+            }
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes, optionally treating `+` as a space.
+///
+/// Invalid escapes are copied unchanged.
+pub(crate) fn percent_decode(input: &[u8], plus_as_space: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'%' => match (input.get(i + 1).and_then(|b| hex_digit(*b)), input.get(i + 2).and_then(|b| hex_digit(*b))) {
+                (Some(hi), Some(lo)) => {
+                    out.push(hi << 4 | lo);
+                    i += 3;
+                    continue;
+                }
+                _ => out.push(b'%'),
+            },
+            b'+' if plus_as_space => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Append `value` escaped as JSON string content (without quotes).
+pub(crate) fn escape_json(value: &str, out: &mut String) {
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_rfc4648_vectors() {
+        let vectors = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE=")];
+        for (plain, encoded) in vectors {
+            assert_eq!(base64_encode(plain.as_bytes(), false, true), encoded);
+            assert_eq!(base64_decode(encoded.as_bytes(), false).unwrap(), plain.as_bytes());
+            assert_eq!(base64_decode(encoded.trim_end_matches('=').as_bytes(), false).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn base64_url_safe() {
+        assert_eq!(base64_encode(b"\xFB\xFF\xBF", true, false), "-_-_");
+        assert_eq!(base64_encode(b"\xFB\xFF", false, false), "+/8");
+        assert_eq!(base64_decode(b"-_-_", true).unwrap(), b"\xFB\xFF\xBF");
+        assert_eq!(base64_decode(b"-_-_", false), Err(DecodeError::InvalidByte(0)));
+        assert_eq!(base64_decode(b"+/8=", true), Err(DecodeError::InvalidByte(0)));
+    }
+
+    #[test]
+    fn base64_invalid_input() {
+        assert_eq!(base64_decode(b"Zm9vY", false), Err(DecodeError::InvalidLength));
+        assert_eq!(base64_decode(b"Zm8==", false), Err(DecodeError::InvalidLength));
+        assert_eq!(base64_decode(b"Zm=v", false), Err(DecodeError::InvalidByte(2)));
+        assert_eq!(base64_decode(b"Zm9 v", false), Err(DecodeError::InvalidByte(3)));
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        assert_eq!(hex_encode(b"\x00\x7F\xAB\xff"), "007fabff");
+        assert_eq!(hex_decode(b"007fABff").unwrap(), b"\x00\x7F\xAB\xff");
+        assert_eq!(hex_decode(b"").unwrap(), b"");
+        assert_eq!(hex_decode(b"0g"), Err(DecodeError::InvalidByte(1)));
+        assert_eq!(hex_decode(b"abc"), Err(DecodeError::InvalidLength));
+        assert_eq!(hex_decode(b"abx"), Err(DecodeError::InvalidByte(2)));
+    }
+
+    #[test]
+    fn percent_encode_unreserved() {
+        assert_eq!(percent_encode(b"a b/c~d_e.f-g+\xC3\xA4", true), "a%20b/c~d_e.f-g%2B%C3%A4");
+        assert_eq!(percent_encode(b"a/b", false), "a%2Fb");
+    }
+
+    #[test]
+    fn percent_decode_escapes() {
+        assert_eq!(percent_decode(b"a%20b+c%2x%", true), b"a b c%2x%");
+        assert_eq!(percent_decode(b"a+b", false), b"a+b");
+    }
+}
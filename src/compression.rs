@@ -0,0 +1,339 @@
+//! Content codings for request and response bodies.
+//!
+//! Requires the `compression` feature, which provides `gzip` and `deflate`; the
+//! `brotli` feature adds `br` and the `zstd` feature adds `zstd`. The codecs work on complete buffers, which
+//! matches how the host hands out bodies, and every decoder is bounded by an output
+//! limit to protect the plugin from decompression bombs.
+//!
+//! ```no_run
+//! use http_wasm_guest::host::Request;
+//!
+//! fn inspect(request: &Request) {
+//!     match request.body.read_decompressed() {
+//!         Ok(body) => { /* scan the plain body */ }
+//!         Err(_) => { /* reject the request */ }
+//!     }
+//! }
+//! ```
+use std::{error::Error, fmt::Display};
+
+use miniz_oxide::{
+    deflate,
+    inflate::{self, TINFLStatus},
+};
+
+use crate::headers::Encoding;
+
+/// Default maximum size of decoded bodies (16 MiB).
+pub const DEFAULT_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Errors returned when decoding a body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompressionError {
+    /// The body uses a content coding this build cannot decode.
+    Unsupported(String),
+    /// The body is not valid for its content coding.
+    Malformed,
+    /// The decoded body exceeds the configured limit.
+    TooLarge,
+}
+
+impl Display for CompressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompressionError::Unsupported(coding) => write!(f, "unsupported content coding: {coding}"),
+            CompressionError::Malformed => write!(f, "malformed compressed data"),
+            CompressionError::TooLarge => write!(f, "decompressed data exceeds limit"),
+        }
+    }
+}
+
+impl Error for CompressionError {}
+
+/// Decode `data` compressed with `encoding`, producing at most `limit` bytes.
+///
+/// `deflate` accepts the zlib format required by HTTP as well as raw deflate data
+/// sent by some clients.
+pub fn decode(encoding: Encoding, data: &[u8], limit: usize) -> Result<Vec<u8>, CompressionError> {
+    match encoding {
+        Encoding::Identity => {
+            if data.len() > limit {
+                return Err(CompressionError::TooLarge);
+            }
+            Ok(data.to_vec())
+        }
+        Encoding::Gzip => gunzip(data, limit),
+        Encoding::Deflate if is_zlib(data) => inflate::decompress_to_vec_zlib_with_limit(data, limit).map_err(inflate_error),
+        Encoding::Deflate => inflate::decompress_to_vec_with_limit(data, limit).map_err(inflate_error),
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli => brotli_decode(data, limit),
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => zstd_decode(data, limit),
+        // reachable unless all codec features are enabled
+        #[allow(unreachable_patterns)]
+        other => Err(CompressionError::Unsupported(other.to_string())),
+    }
+}
+
+/// Encode `data` with `encoding` at the given compression level.
+///
+/// The level is clamped to the range of the codec: `0..=9` for `gzip` and `deflate`,
+/// `0..=11` for `br`. The pure-Rust `zstd` encoder only distinguishes between `0`
+/// (stored) and its fastest mode.
+pub fn encode(encoding: Encoding, data: &[u8], level: u8) -> Result<Vec<u8>, CompressionError> {
+    match encoding {
+        Encoding::Identity => Ok(data.to_vec()),
+        Encoding::Gzip => Ok(gzip(data, level.min(9))),
+        Encoding::Deflate => Ok(deflate::compress_to_vec_zlib(data, level.min(9))),
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli => brotli_encode(data, level.min(11)),
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => Ok(zstd_encode(data, level)),
+        // reachable unless all codec features are enabled
+        #[allow(unreachable_patterns)]
+        other => Err(CompressionError::Unsupported(other.to_string())),
+    }
+}
+
+/// Decode a body according to a `Content-Encoding` header value.
+///
+/// The codings are listed in the order they were applied, so they are removed in
+/// reverse order.
+pub fn decode_content(content_encoding: &[u8], data: &[u8], limit: usize) -> Result<Vec<u8>, CompressionError> {
+    let mut codings = Vec::new();
+    for coding in content_encoding.split(|b| *b == b',').map(<[u8]>::trim_ascii).filter(|c| !c.is_empty()) {
+        let encoding =
+            Encoding::parse(coding).ok_or_else(|| CompressionError::Unsupported(String::from_utf8_lossy(coding).into_owned()))?;
+        codings.push(encoding);
+    }
+    let mut decoded = data.to_vec();
+    for encoding in codings.into_iter().rev() {
+        decoded = decode(encoding, &decoded, limit)?;
+    }
+    Ok(decoded)
+}
+
+/// Returns `true` if `data` starts with a valid zlib header (RFC 1950).
+fn is_zlib(data: &[u8]) -> bool {
+    match data {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    }
+}
+
+fn inflate_error(error: inflate::DecompressError) -> CompressionError {
+    match error.status {
+        TINFLStatus::HasMoreOutput => CompressionError::TooLarge,
+        _ => CompressionError::Malformed,
+    }
+}
+
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+/// Encode `data` as a single-member gzip stream without file name or timestamp.
+fn gzip(data: &[u8], level: u8) -> Vec<u8> {
+    let deflated = deflate::compress_to_vec(data, level);
+    let mut out = Vec::with_capacity(deflated.len() + 18);
+    out.extend_from_slice(&GZIP_MAGIC);
+    // flags, mtime, extra flags, OS "unknown"
+    out.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0xff]);
+    out.extend_from_slice(&deflated);
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+/// Decode a single-member gzip stream (RFC 1952) and verify its CRC and size.
+fn gunzip(data: &[u8], limit: usize) -> Result<Vec<u8>, CompressionError> {
+    let (header, mut rest) = data.split_at_checked(10).ok_or(CompressionError::Malformed)?;
+    if header.get(..3) != Some(&GZIP_MAGIC[..]) {
+        return Err(CompressionError::Malformed);
+    }
+    let flags = header.get(3).copied().unwrap_or_default();
+    if flags & FEXTRA != 0 {
+        let len = match rest {
+            [lo, hi, ..] => u16::from_le_bytes([*lo, *hi]) as usize,
+            _ => return Err(CompressionError::Malformed),
+        };
+        rest = rest.get(2 + len..).ok_or(CompressionError::Malformed)?;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let end = rest.iter().position(|b| *b == 0).ok_or(CompressionError::Malformed)?;
+            rest = rest.get(end + 1..).ok_or(CompressionError::Malformed)?;
+        }
+    }
+    if flags & FHCRC != 0 {
+        rest = rest.get(2..).ok_or(CompressionError::Malformed)?;
+    }
+    let trailer = rest.len().checked_sub(8).and_then(|at| rest.get(at..)).ok_or(CompressionError::Malformed)?;
+    let decoded = inflate::decompress_to_vec_with_limit(rest, limit).map_err(inflate_error)?;
+    let (crc, size) = trailer.split_at_checked(4).ok_or(CompressionError::Malformed)?;
+    let crc = u32::from_le_bytes(crc.try_into().map_err(|_| CompressionError::Malformed)?);
+    let size = u32::from_le_bytes(size.try_into().map_err(|_| CompressionError::Malformed)?);
+    if crc != crc32(&decoded) || size != decoded.len() as u32 {
+        return Err(CompressionError::Malformed);
+    }
+    Ok(decoded)
+}
+
+/// Encode `data` with brotli, using a 4 MiB window to bound memory use.
+#[cfg(feature = "brotli")]
+fn brotli_encode(data: &[u8], quality: u8) -> Result<Vec<u8>, CompressionError> {
+    use std::io::Write;
+
+    let mut writer = brotli::CompressorWriter::new(Vec::new(), 4096, quality as u32, 22);
+    writer.write_all(data).map_err(|_| CompressionError::Malformed)?;
+    Ok(writer.into_inner())
+}
+
+#[cfg(feature = "brotli")]
+fn brotli_decode(data: &[u8], limit: usize) -> Result<Vec<u8>, CompressionError> {
+    read_limited(brotli::Decompressor::new(data, 4096), limit)
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_encode(data: &[u8], level: u8) -> Vec<u8> {
+    use ruzstd::encoding::{CompressionLevel, compress_to_vec};
+
+    compress_to_vec(data, if level == 0 { CompressionLevel::Uncompressed } else { CompressionLevel::Fastest })
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_decode(data: &[u8], limit: usize) -> Result<Vec<u8>, CompressionError> {
+    let decoder = ruzstd::decoding::StreamingDecoder::new(data).map_err(|_| CompressionError::Malformed)?;
+    read_limited(decoder, limit)
+}
+
+/// Read a decoder to the end, failing once more than `limit` bytes are produced.
+#[cfg(any(feature = "brotli", feature = "zstd"))]
+fn read_limited(reader: impl std::io::Read, limit: usize) -> Result<Vec<u8>, CompressionError> {
+    use std::io::Read;
+
+    let mut out = Vec::new();
+    reader.take(limit as u64 + 1).read_to_end(&mut out).map_err(|_| CompressionError::Malformed)?;
+    if out.len() > limit {
+        return Err(CompressionError::TooLarge);
+    }
+    Ok(out)
+}
+
+/// CRC-32 (IEEE 802.3) as used by gzip.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: &[u8] = b"hello hello hello hello";
+    const GZIP: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01,
+        0xe3, 0x51, 0x3d, 0x8d, 0x17, 0x00, 0x00, 0x00,
+    ];
+    // with FNAME "a.txt"
+    const GZIP_NAMED: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0x61, 0x2e, 0x74, 0x78, 0x74, 0x00, 0xcb, 0x48, 0xcd, 0xc9,
+        0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01, 0xe3, 0x51, 0x3d, 0x8d, 0x17, 0x00, 0x00, 0x00,
+    ];
+    const ZLIB: &[u8] = &[0x78, 0x9c, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01, 0x68, 0x03, 0x08, 0xb1];
+    const RAW_DEFLATE: &[u8] = &[0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01];
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn gzip() {
+        assert_eq!(decode(Encoding::Gzip, GZIP, DEFAULT_LIMIT).unwrap(), PLAIN);
+        assert_eq!(decode(Encoding::Gzip, GZIP_NAMED, DEFAULT_LIMIT).unwrap(), PLAIN);
+    }
+
+    #[test]
+    fn gzip_corrupted() {
+        let mut data = GZIP.to_vec();
+        if let Some(b) = data.get_mut(20) {
+            *b ^= 0xff;
+        }
+        assert_eq!(decode(Encoding::Gzip, &data, DEFAULT_LIMIT), Err(CompressionError::Malformed));
+        assert_eq!(decode(Encoding::Gzip, GZIP.get(..20).unwrap(), DEFAULT_LIMIT), Err(CompressionError::Malformed));
+        assert_eq!(decode(Encoding::Gzip, ZLIB, DEFAULT_LIMIT), Err(CompressionError::Malformed));
+    }
+
+    #[test]
+    fn deflate_zlib_and_raw() {
+        assert_eq!(decode(Encoding::Deflate, ZLIB, DEFAULT_LIMIT).unwrap(), PLAIN);
+        assert_eq!(decode(Encoding::Deflate, RAW_DEFLATE, DEFAULT_LIMIT).unwrap(), PLAIN);
+        assert_eq!(decode(Encoding::Deflate, b"not deflate", DEFAULT_LIMIT), Err(CompressionError::Malformed));
+    }
+
+    #[test]
+    fn limit() {
+        assert_eq!(decode(Encoding::Gzip, GZIP, 10), Err(CompressionError::TooLarge));
+        assert_eq!(decode(Encoding::Deflate, ZLIB, 10), Err(CompressionError::TooLarge));
+        assert_eq!(decode(Encoding::Identity, PLAIN, 10), Err(CompressionError::TooLarge));
+    }
+
+    #[test]
+    fn encode_roundtrip() {
+        let data = PLAIN.repeat(100);
+        for encoding in [Encoding::Gzip, Encoding::Deflate, Encoding::Identity] {
+            let encoded = encode(encoding, &data, 6).unwrap();
+            assert_eq!(decode(encoding, &encoded, DEFAULT_LIMIT).unwrap(), data);
+        }
+        assert!(encode(Encoding::Gzip, &data, 6).unwrap().len() < data.len() / 10);
+        assert_eq!(encode(Encoding::Gzip, b"", 6).unwrap().len(), 20);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn brotli_roundtrip() {
+        let data = PLAIN.repeat(100);
+        let encoded = encode(Encoding::Brotli, &data, 11).unwrap();
+        assert!(encoded.len() < data.len() / 10);
+        assert_eq!(decode(Encoding::Brotli, &encoded, DEFAULT_LIMIT).unwrap(), data);
+        assert_eq!(decode(Encoding::Brotli, &encoded, 100), Err(CompressionError::TooLarge));
+        assert_eq!(decode(Encoding::Brotli, b"\xff\xff\xff", DEFAULT_LIMIT), Err(CompressionError::Malformed));
+    }
+
+    #[cfg(not(feature = "brotli"))]
+    #[test]
+    fn brotli_unsupported() {
+        assert_eq!(decode(Encoding::Brotli, b"", DEFAULT_LIMIT), Err(CompressionError::Unsupported("br".into())));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_roundtrip() {
+        let data = PLAIN.repeat(100);
+        let encoded = encode(Encoding::Zstd, &data, 3).unwrap();
+        assert!(encoded.len() < data.len() / 10);
+        assert_eq!(decode(Encoding::Zstd, &encoded, DEFAULT_LIMIT).unwrap(), data);
+        let stored = encode(Encoding::Zstd, &data, 0).unwrap();
+        assert_eq!(decode(Encoding::Zstd, &stored, DEFAULT_LIMIT).unwrap(), data);
+        assert_eq!(decode(Encoding::Zstd, &encoded, 100), Err(CompressionError::TooLarge));
+        assert_eq!(decode(Encoding::Zstd, b"not zstd", DEFAULT_LIMIT), Err(CompressionError::Malformed));
+    }
+
+    #[test]
+    fn content_encoding_list() {
+        assert_eq!(decode_content(b"gzip", GZIP, DEFAULT_LIMIT).unwrap(), PLAIN);
+        assert_eq!(decode_content(b"identity, deflate", ZLIB, DEFAULT_LIMIT).unwrap(), PLAIN);
+        assert_eq!(decode_content(b"", PLAIN, DEFAULT_LIMIT).unwrap(), PLAIN);
+        assert_eq!(decode_content(b"compress", PLAIN, DEFAULT_LIMIT), Err(CompressionError::Unsupported("compress".into())));
+    }
+}
@@ -0,0 +1,175 @@
+//! `application/x-www-form-urlencoded` fields.
+//!
+//! [`Form`] is an ordered multimap of decoded names and values, as sent by HTML forms
+//! in `POST` bodies and in query strings. Use [`Body::read_form`](crate::host::Body::read_form)
+//! and [`Body::write_form`](crate::host::Body::write_form) to work with request bodies.
+//!
+//! ```
+//! use http_wasm_guest::form::Form;
+//!
+//! let mut form = Form::parse(b"user=j%C3%BCrgen&role=admin&role=dev");
+//! assert_eq!(form.get("user"), Some("jürgen"));
+//! assert_eq!(form.get_all("role").collect::<Vec<_>>(), ["admin", "dev"]);
+//! form.set("role", "guest");
+//! assert_eq!(form.to_string(), "user=j%C3%BCrgen&role=guest");
+//! ```
+use std::fmt::{self, Display, Write};
+
+use crate::codec::percent_decode;
+
+/// Ordered form fields; a name may occur several times.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Form {
+    fields: Vec<(String, String)>,
+}
+
+impl Form {
+    /// Create an empty form.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse urlencoded fields; `+` is decoded as space and invalid UTF-8 is replaced.
+    ///
+    /// Empty segments are skipped and a name without `=` has an empty value.
+    pub fn parse(input: &[u8]) -> Self {
+        let decode = |bytes: &[u8]| String::from_utf8_lossy(&percent_decode(bytes, true)).into_owned();
+        let fields = input
+            .split(|b| *b == b'&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.iter().position(|b| *b == b'=') {
+                Some(eq) => (decode(pair.get(..eq).unwrap_or_default()), decode(pair.get(eq + 1..).unwrap_or_default())),
+                None => (decode(pair), String::new()),
+            })
+            .collect();
+        Self { fields }
+    }
+
+    /// First value of the field `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// All values of the field `name`, in order.
+    pub fn get_all(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.fields.iter().filter(move |(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Whether the field `name` is present.
+    pub fn contains(&self, name: &str) -> bool {
+        self.fields.iter().any(|(n, _)| n == name)
+    }
+
+    /// Add a value for `name`, keeping existing values.
+    pub fn append(&mut self, name: &str, value: &str) {
+        self.fields.push((name.to_string(), value.to_string()));
+    }
+
+    /// Replace all values of `name` with `value`, at the position of the first one.
+    pub fn set(&mut self, name: &str, value: &str) {
+        let mut found = false;
+        self.fields.retain_mut(|(n, v)| {
+            if n != name {
+                return true;
+            }
+            if !found {
+                *v = value.to_string();
+            }
+            !std::mem::replace(&mut found, true)
+        });
+        if !found {
+            self.append(name, value);
+        }
+    }
+
+    /// Remove all values of `name`.
+    pub fn remove(&mut self, name: &str) {
+        self.fields.retain(|(n, _)| n != name);
+    }
+
+    /// All fields as name and value, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// Number of fields.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Whether the form has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl Display for Form {
+    /// Serialize the fields in urlencoded form, with space encoded as `+`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_char('&')?;
+            }
+            encode(f, name)?;
+            f.write_char('=')?;
+            encode(f, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<N: Into<String>, V: Into<String>> FromIterator<(N, V)> for Form {
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(iter: I) -> Self {
+        Self { fields: iter.into_iter().map(|(n, v)| (n.into(), v.into())).collect() }
+    }
+}
+
+/// Write `value` with the urlencoded byte serializer of the URL standard.
+fn encode(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => f.write_char(b as char)?,
+            b' ' => f.write_char('+')?,
+            _ => write!(f, "%{b:02X}")?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fields() {
+        let form = Form::parse(b"a=1&&b&c=x+y%2Bz&a=2&%E2%9C%93=%ZZ&d=");
+        assert_eq!(form.len(), 6);
+        assert_eq!(form.get("a"), Some("1"));
+        assert_eq!(form.get_all("a").collect::<Vec<_>>(), ["1", "2"]);
+        assert_eq!(form.get("b"), Some(""));
+        assert_eq!(form.get("c"), Some("x y+z"));
+        assert_eq!(form.get("✓"), Some("%ZZ"));
+        assert!(form.contains("d"));
+        assert_eq!(form.get("e"), None);
+        assert!(Form::parse(b"").is_empty());
+    }
+
+    #[test]
+    fn modify_fields() {
+        let mut form = Form::parse(b"a=1&b=2&a=3&c=4");
+        form.set("a", "x");
+        assert_eq!(form.iter().collect::<Vec<_>>(), [("a", "x"), ("b", "2"), ("c", "4")]);
+        form.set("d", "5");
+        form.remove("b");
+        form.append("c", "6");
+        assert_eq!(form.to_string(), "a=x&c=4&d=5&c=6");
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let form: Form = [("q", "a b&c=d"), ("path", "/ä?*-._~")].into_iter().collect();
+        let encoded = form.to_string();
+        assert_eq!(encoded, "q=a+b%26c%3Dd&path=%2F%C3%A4%3F*-._%7E");
+        assert_eq!(Form::parse(encoded.as_bytes()), form);
+    }
+}
@@ -0,0 +1,181 @@
+use std::{fmt::Display, time::Duration};
+
+/// Builder for `Cache-Control` header values.
+///
+/// Directives are written in a fixed order, durations in whole seconds (rounded down).
+/// Use [`Response::set_cache_control`](crate::host::Response::set_cache_control) to
+/// set the header.
+///
+/// ```
+/// use std::time::Duration;
+/// use http_wasm_guest::headers::CacheControl;
+///
+/// let value = CacheControl::new()
+///     .public()
+///     .max_age(Duration::from_secs(60))
+///     .stale_while_revalidate(Duration::from_secs(30));
+/// assert_eq!(value.to_string(), "public, max-age=60, stale-while-revalidate=30");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheControl {
+    visibility: Option<Visibility>,
+    no_store: bool,
+    no_cache: bool,
+    max_age: Option<u64>,
+    s_maxage: Option<u64>,
+    must_revalidate: bool,
+    immutable: bool,
+    stale_while_revalidate: Option<u64>,
+    stale_if_error: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visibility {
+    Public,
+    Private,
+}
+
+impl CacheControl {
+    /// Create an empty value without directives.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shortcut for `no-store`, forbidding any cache to store the response.
+    pub fn no_store_only() -> Self {
+        Self::new().no_store()
+    }
+
+    /// Add `public`: shared caches may store the response. Replaces `private`.
+    pub fn public(mut self) -> Self {
+        self.visibility = Some(Visibility::Public);
+        self
+    }
+
+    /// Add `private`: only the client may store the response. Replaces `public`.
+    pub fn private(mut self) -> Self {
+        self.visibility = Some(Visibility::Private);
+        self
+    }
+
+    /// Add `no-store`.
+    pub fn no_store(mut self) -> Self {
+        self.no_store = true;
+        self
+    }
+
+    /// Add `no-cache`: caches must revalidate before each reuse.
+    pub fn no_cache(mut self) -> Self {
+        self.no_cache = true;
+        self
+    }
+
+    /// Add `max-age`.
+    pub fn max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age.as_secs());
+        self
+    }
+
+    /// Add `s-maxage`, the `max-age` for shared caches.
+    pub fn s_maxage(mut self, age: Duration) -> Self {
+        self.s_maxage = Some(age.as_secs());
+        self
+    }
+
+    /// Add `must-revalidate`.
+    pub fn must_revalidate(mut self) -> Self {
+        self.must_revalidate = true;
+        self
+    }
+
+    /// Add `immutable`: the response does not change while fresh.
+    pub fn immutable(mut self) -> Self {
+        self.immutable = true;
+        self
+    }
+
+    /// Add `stale-while-revalidate`.
+    pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+        self.stale_while_revalidate = Some(window.as_secs());
+        self
+    }
+
+    /// Add `stale-if-error`.
+    pub fn stale_if_error(mut self, window: Duration) -> Self {
+        self.stale_if_error = Some(window.as_secs());
+        self
+    }
+}
+
+impl Display for CacheControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut directives: Vec<String> = Vec::new();
+        match self.visibility {
+            Some(Visibility::Public) => directives.push("public".into()),
+            Some(Visibility::Private) => directives.push("private".into()),
+            None => {}
+        }
+        if self.no_store {
+            directives.push("no-store".into());
+        }
+        if self.no_cache {
+            directives.push("no-cache".into());
+        }
+        if let Some(secs) = self.max_age {
+            directives.push(format!("max-age={secs}"));
+        }
+        if let Some(secs) = self.s_maxage {
+            directives.push(format!("s-maxage={secs}"));
+        }
+        if self.must_revalidate {
+            directives.push("must-revalidate".into());
+        }
+        if self.immutable {
+            directives.push("immutable".into());
+        }
+        if let Some(secs) = self.stale_while_revalidate {
+            directives.push(format!("stale-while-revalidate={secs}"));
+        }
+        if let Some(secs) = self.stale_if_error {
+            directives.push(format!("stale-if-error={secs}"));
+        }
+        f.write_str(&directives.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert_eq!(CacheControl::new().to_string(), "");
+    }
+
+    #[test]
+    fn all_directives_in_order() {
+        let value = CacheControl::new()
+            .stale_if_error(Duration::from_secs(600))
+            .immutable()
+            .private()
+            .s_maxage(Duration::from_secs(10))
+            .max_age(Duration::from_millis(1999))
+            .no_cache()
+            .must_revalidate()
+            .stale_while_revalidate(Duration::from_secs(5));
+        assert_eq!(
+            value.to_string(),
+            "private, no-cache, max-age=1, s-maxage=10, must-revalidate, immutable, stale-while-revalidate=5, stale-if-error=600"
+        );
+    }
+
+    #[test]
+    fn visibility_is_exclusive() {
+        assert_eq!(CacheControl::new().private().public().to_string(), "public");
+    }
+
+    #[test]
+    fn no_store() {
+        assert_eq!(CacheControl::no_store_only().to_string(), "no-store");
+    }
+}
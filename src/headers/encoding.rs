@@ -0,0 +1,182 @@
+use std::fmt::Display;
+
+use crate::host::Request;
+
+use super::split_list;
+
+/// Content codings used in `Accept-Encoding` and `Content-Encoding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// `gzip` (also accepted as `x-gzip`)
+    Gzip,
+    /// `deflate` (zlib format)
+    Deflate,
+    /// `br`
+    Brotli,
+    /// `zstd`
+    Zstd,
+    /// `identity`, i.e. no encoding
+    Identity,
+}
+
+impl Encoding {
+    /// Parse a content coding, ignoring ASCII case. Returns `None` for unknown codings.
+    pub fn parse(coding: &[u8]) -> Option<Encoding> {
+        let coding = coding.trim_ascii();
+        [
+            (&b"gzip"[..], Encoding::Gzip),
+            (b"x-gzip", Encoding::Gzip),
+            (b"deflate", Encoding::Deflate),
+            (b"br", Encoding::Brotli),
+            (b"zstd", Encoding::Zstd),
+            (b"identity", Encoding::Identity),
+        ]
+        .into_iter()
+        .find_map(|(name, encoding)| coding.eq_ignore_ascii_case(name).then_some(encoding))
+    }
+
+    /// Return the coding name as used on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+            Encoding::Identity => "identity",
+        }
+    }
+
+    /// Select the preferred coding from `supported` for an `Accept-Encoding` header value.
+    ///
+    /// Codings are ranked by their q-value; ties are broken by the order of `supported`,
+    /// so list the codings the plugin prefers first. `*` matches every coding not listed
+    /// explicitly, and `identity` is acceptable as a fallback unless excluded with `q=0`. Returns
+    /// `None` if no supported coding is acceptable.
+    ///
+    /// ```
+    /// use http_wasm_guest::headers::Encoding;
+    ///
+    /// let supported = [Encoding::Brotli, Encoding::Gzip, Encoding::Identity];
+    /// assert_eq!(Encoding::negotiate(b"gzip, br;q=0.8", &supported), Some(Encoding::Gzip));
+    /// assert_eq!(Encoding::negotiate(b"zstd", &supported), Some(Encoding::Identity));
+    /// ```
+    pub fn negotiate(accept: &[u8], supported: &[Encoding]) -> Option<Encoding> {
+        let entries: Vec<(&[u8], u16)> = split_list(accept).filter_map(parse_entry).collect();
+        let wildcard = entries.iter().find(|(coding, _)| *coding == b"*").map(|(_, q)| *q);
+        let quality = |encoding: Encoding| {
+            let explicit = entries.iter().find(|(coding, _)| Encoding::parse(coding) == Some(encoding)).map(|(_, q)| *q);
+            match (explicit, wildcard) {
+                (Some(q), _) | (None, Some(q)) => q,
+                // identity is acceptable unless excluded, but only as a last resort
+                (None, None) if encoding == Encoding::Identity => 1,
+                (None, None) => 0,
+            }
+        };
+        let mut best: Option<(Encoding, u16)> = None;
+        for encoding in supported {
+            let q = quality(*encoding);
+            if q > 0 && best.is_none_or(|(_, best_q)| q > best_q) {
+                best = Some((*encoding, q));
+            }
+        }
+        best.map(|(encoding, _)| encoding)
+    }
+
+    /// Select the preferred coding from `supported` for the `Accept-Encoding` headers of the request.
+    ///
+    /// A request without `Accept-Encoding` only accepts `identity`.
+    pub fn negotiate_request(request: &Request, supported: &[Encoding]) -> Option<Encoding> {
+        let values = request.header.values(b"Accept-Encoding");
+        if values.is_empty() {
+            return supported.contains(&Encoding::Identity).then_some(Encoding::Identity);
+        }
+        let accept = values.iter().map(|v| v.as_ref()).collect::<Vec<&[u8]>>().join(&b","[..]);
+        Encoding::negotiate(&accept, supported)
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse `coding;q=0.5` into the coding and its q-value in thousandths.
+fn parse_entry(entry: &[u8]) -> Option<(&[u8], u16)> {
+    let mut parts = entry.split(|b| *b == b';');
+    let coding = parts.next()?.trim_ascii();
+    if coding.is_empty() {
+        return None;
+    }
+    let q = parts
+        .filter_map(|param| {
+            let param = param.trim_ascii();
+            param.strip_prefix(b"q=").or_else(|| param.strip_prefix(b"Q="))
+        })
+        .next()
+        .map_or(Some(1000), parse_qvalue)?;
+    Some((coding, q))
+}
+
+/// Parse a qvalue (`0`, `0.5`, `1.000`) into thousandths; `None` if malformed.
+fn parse_qvalue(value: &[u8]) -> Option<u16> {
+    let value = std::str::from_utf8(value).ok()?.trim();
+    let q: f64 = value.parse().ok()?;
+    (0.0..=1.0).contains(&q).then(|| (q * 1000.0).round() as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Encoding; 5] = [Encoding::Zstd, Encoding::Brotli, Encoding::Gzip, Encoding::Deflate, Encoding::Identity];
+
+    #[test]
+    fn parse_codings() {
+        assert_eq!(Encoding::parse(b"GZIP"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::parse(b"x-gzip"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::parse(b" br "), Some(Encoding::Brotli));
+        assert_eq!(Encoding::parse(b"compress"), None);
+        assert_eq!(Encoding::Zstd.to_string(), "zstd");
+    }
+
+    #[test]
+    fn server_preference_breaks_ties() {
+        assert_eq!(Encoding::negotiate(b"gzip, br, zstd", &ALL), Some(Encoding::Zstd));
+        assert_eq!(Encoding::negotiate(b"gzip, br", &[Encoding::Gzip, Encoding::Brotli]), Some(Encoding::Gzip));
+    }
+
+    #[test]
+    fn q_values_rank() {
+        assert_eq!(Encoding::negotiate(b"br;q=0.5, gzip;q=0.9", &ALL), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate(b"br;q=0, gzip", &ALL), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate(b"gzip; Q=0.1, deflate;q=0.2", &ALL), Some(Encoding::Deflate));
+    }
+
+    #[test]
+    fn wildcard() {
+        assert_eq!(Encoding::negotiate(b"*", &ALL), Some(Encoding::Zstd));
+        assert_eq!(Encoding::negotiate(b"zstd;q=0, *;q=0.5", &ALL), Some(Encoding::Brotli));
+        assert_eq!(Encoding::negotiate(b"*;q=0", &ALL), None);
+        assert_eq!(Encoding::negotiate(b"*;q=0, identity", &ALL), Some(Encoding::Identity));
+    }
+
+    #[test]
+    fn identity() {
+        assert_eq!(Encoding::negotiate(b"", &ALL), Some(Encoding::Identity));
+        assert_eq!(Encoding::negotiate(b"identity;q=0", &[Encoding::Identity]), None);
+        assert_eq!(Encoding::negotiate(b"gzip", &[Encoding::Brotli]), None);
+    }
+
+    #[test]
+    fn malformed_entries_are_ignored() {
+        assert_eq!(Encoding::negotiate(b",,gzip;q=abc, br;q=2, deflate", &ALL), Some(Encoding::Deflate));
+    }
+
+    #[test]
+    fn request_without_header() {
+        // The mock request has no Accept-Encoding header
+        assert_eq!(Encoding::negotiate_request(&Request::new(), &ALL), Some(Encoding::Identity));
+        assert_eq!(Encoding::negotiate_request(&Request::new(), &[Encoding::Gzip]), None);
+    }
+}
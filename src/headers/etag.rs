@@ -0,0 +1,155 @@
+use std::fmt::Display;
+
+use crate::middleware::stable_hash;
+
+use super::split_list;
+
+/// Value of an `ETag` header, an entity tag identifying a representation.
+///
+/// Use [`Response::set_etag`](crate::host::Response::set_etag) to tag a buffered
+/// response and [`Response::not_modified`](crate::host::Response::not_modified) to
+/// answer matching conditional requests with `304 Not Modified`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag {
+    weak: bool,
+    tag: String,
+}
+
+impl ETag {
+    /// Create a strong entity tag; `tag` is the opaque value without quotes.
+    ///
+    /// Returns `None` if `tag` contains characters not allowed in an entity tag
+    /// (`"`, controls, whitespace).
+    pub fn strong(tag: &str) -> Option<Self> {
+        Self::new(false, tag)
+    }
+
+    /// Create a weak entity tag; `tag` is the opaque value without quotes.
+    ///
+    /// Returns `None` if `tag` contains characters not allowed in an entity tag.
+    pub fn weak(tag: &str) -> Option<Self> {
+        Self::new(true, tag)
+    }
+
+    /// Derive an entity tag from the body bytes.
+    ///
+    /// The tag combines the body length and a 64-bit hash; it is stable across
+    /// instances but not cryptographically strong. Use a weak tag if the body can
+    /// differ in insignificant ways, e.g. after compression.
+    pub fn from_body(body: &[u8], weak: bool) -> Self {
+        Self { weak, tag: format!("{:x}-{:016x}", body.len(), stable_hash(&[body])) }
+    }
+
+    /// Parse an `ETag` header value such as `"abc"` or `W/"abc"`.
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        let value = std::str::from_utf8(value).ok()?.trim();
+        let (weak, quoted) = match value.strip_prefix("W/") {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+        let tag = quoted.strip_prefix('"')?.strip_suffix('"')?;
+        Self::new(weak, tag)
+    }
+
+    /// Whether this is a weak entity tag.
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// The opaque tag value without quotes and weakness indicator.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Weak comparison: the opaque tags are equal, regardless of weakness.
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+        self.tag == other.tag
+    }
+
+    /// Strong comparison: both tags are strong and the opaque tags are equal.
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Whether an `If-None-Match` header value matches this tag.
+    ///
+    /// Uses the weak comparison as required for `If-None-Match`; `*` matches any tag.
+    /// Malformed list members are ignored.
+    pub fn matches_if_none_match(&self, if_none_match: &[u8]) -> bool {
+        if if_none_match.trim_ascii() == b"*" {
+            return true;
+        }
+        split_list(if_none_match).filter_map(ETag::parse).any(|candidate| self.weak_eq(&candidate))
+    }
+
+    fn new(weak: bool, tag: &str) -> Option<Self> {
+        // etagc = %x21 / %x23-7E / obs-text
+        if tag.bytes().all(|b| b == 0x21 || (0x23..=0x7e).contains(&b) || b >= 0x80) {
+            Some(Self { weak, tag: tag.to_string() })
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for ETag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_and_parse() {
+        let strong = ETag::strong("abc").unwrap();
+        let weak = ETag::weak("abc").unwrap();
+        assert_eq!(strong.to_string(), "\"abc\"");
+        assert_eq!(weak.to_string(), "W/\"abc\"");
+        assert_eq!(ETag::parse(b" W/\"abc\" "), Some(weak));
+        assert_eq!(ETag::parse(b"\"abc\""), Some(strong));
+        assert_eq!(ETag::parse(b"abc"), None);
+        assert_eq!(ETag::parse(b"\"a\"b\""), None);
+    }
+
+    #[test]
+    fn invalid_tags_rejected() {
+        assert!(ETag::strong("a b").is_none());
+        assert!(ETag::strong("a\"b").is_none());
+        assert!(ETag::weak("").is_some());
+    }
+
+    #[test]
+    fn comparison() {
+        let strong = ETag::strong("1").unwrap();
+        let weak = ETag::weak("1").unwrap();
+        assert!(strong.weak_eq(&weak));
+        assert!(!strong.strong_eq(&weak));
+        assert!(strong.strong_eq(&strong));
+        assert!(!weak.strong_eq(&weak));
+    }
+
+    #[test]
+    fn from_body_is_stable() {
+        let a = ETag::from_body(b"hello", false);
+        assert_eq!(a, ETag::from_body(b"hello", false));
+        assert_ne!(a, ETag::from_body(b"hello!", false));
+        assert!(a.tag().starts_with("5-"));
+        assert!(ETag::from_body(b"hello", true).is_weak());
+    }
+
+    #[test]
+    fn if_none_match() {
+        let etag = ETag::strong("v2").unwrap();
+        assert!(etag.matches_if_none_match(b"*"));
+        assert!(etag.matches_if_none_match(b"\"v1\", W/\"v2\""));
+        assert!(!etag.matches_if_none_match(b"\"v1\""));
+        assert!(!etag.matches_if_none_match(b"v2"));
+        assert!(!etag.matches_if_none_match(b""));
+    }
+}
@@ -0,0 +1,103 @@
+/// Split a comma-separated list header value, such as `Vary`, `Accept-*`,
+/// `Cache-Control` or `Connection`, into its elements.
+///
+/// Commas inside quoted strings do not separate elements. Elements are trimmed of
+/// spaces and tabs and empty elements are skipped, as required by RFC 9110.
+///
+/// ```
+/// use http_wasm_guest::headers::split_list;
+///
+/// let elements: Vec<_> = split_list(br#"no-cache="Set-Cookie, Via", , max-age=60"#).collect();
+/// assert_eq!(elements, [&br#"no-cache="Set-Cookie, Via""#[..], b"max-age=60"]);
+/// ```
+pub fn split_list(value: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = Some(value);
+    std::iter::from_fn(move || {
+        loop {
+            let value = rest?;
+            let (element, next) = match element_end(value) {
+                Some(end) => (value.get(..end).unwrap_or_default(), value.get(end + 1..)),
+                None => (value, None),
+            };
+            rest = next;
+            let element = element.trim_ascii();
+            if !element.is_empty() {
+                return Some(element);
+            }
+        }
+    })
+}
+
+/// Join list elements into one header value separated by `, `.
+///
+/// Use it to fold the values of several header lines into one, or to write back a
+/// list after changing elements.
+///
+/// ```
+/// use http_wasm_guest::headers::{fold_list, split_list};
+///
+/// let lines: [&[u8]; 2] = [b"Accept-Encoding", b"Origin, accept-encoding"];
+/// let mut vary: Vec<&[u8]> = Vec::new();
+/// for element in lines.iter().flat_map(|line| split_list(line)) {
+///     if !vary.iter().any(|e| e.eq_ignore_ascii_case(element)) {
+///         vary.push(element);
+///     }
+/// }
+/// assert_eq!(fold_list(vary), b"Accept-Encoding, Origin");
+/// ```
+pub fn fold_list<'a>(elements: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut folded = Vec::new();
+    for element in elements {
+        if !folded.is_empty() {
+            folded.extend_from_slice(b", ");
+        }
+        folded.extend_from_slice(element);
+    }
+    folded
+}
+
+/// Whether the list contains `token`, compared ASCII case-insensitively.
+///
+/// Elements with parameters only match if the parameters are part of `token`, so
+/// `gzip` does not match `gzip;q=0.5`.
+pub fn list_contains(value: &[u8], token: &[u8]) -> bool {
+    split_list(value).any(|element| element.eq_ignore_ascii_case(token))
+}
+
+/// Position of the first comma outside of quoted strings.
+fn element_end(value: &[u8]) -> Option<usize> {
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, b) in value.iter().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if in_quotes => escaped = true,
+            b'"' => in_quotes = !in_quotes,
+            b',' if !in_quotes => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_respects_quotes_and_skips_empty() {
+        let elements: Vec<_> = split_list(br#" a ,, "b,\"c", d="e,f" ,"#).collect();
+        assert_eq!(elements, [&b"a"[..], br#""b,\"c""#, br#"d="e,f""#]);
+        assert_eq!(split_list(b"").count(), 0);
+        assert_eq!(split_list(b" , ").count(), 0);
+    }
+
+    #[test]
+    fn fold_and_contains() {
+        assert_eq!(fold_list(split_list(b"keep-alive,Upgrade")), b"keep-alive, Upgrade");
+        assert_eq!(fold_list([]), b"");
+        assert!(list_contains(b"keep-alive, Upgrade", b"upgrade"));
+        assert!(!list_contains(b"gzip;q=0.5", b"gzip"));
+        assert!(!list_contains(br#""a,b""#, b"b\""));
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt::Display;
+
+/// A media type such as `text/html; charset=utf-8`, as used in `Content-Type`.
+///
+/// Type, subtype and parameter names are ASCII-lowercased when parsing; parameter
+/// values keep their case with quotes and escapes resolved.
+///
+/// ```
+/// use http_wasm_guest::headers::Mime;
+///
+/// let mime = Mime::parse(b"Application/Problem+JSON; charset=\"UTF-8\"").unwrap();
+/// assert_eq!(mime.essence(), "application/problem+json");
+/// assert!(mime.matches("+json") && mime.matches("application/*"));
+/// assert_eq!(mime.charset(), Some("UTF-8"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mime {
+    essence: String,
+    slash: usize,
+    params: Vec<(String, String)>,
+}
+
+impl Mime {
+    /// Parse a `Content-Type` style value; malformed parameters are skipped.
+    ///
+    /// Returns `None` if the type or subtype is missing or not a token.
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        let value = std::str::from_utf8(value).ok()?;
+        let mut parts = split_params(value).into_iter();
+        let essence = parts.next()?.trim().to_ascii_lowercase();
+        let (main, sub) = essence.split_once('/')?;
+        if !is_token(main) || !is_token(sub) {
+            return None;
+        }
+        let slash = main.len();
+        let params = parts
+            .filter_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                let name = name.trim();
+                is_token(name).then(|| (name.to_ascii_lowercase(), unquote(value.trim())))
+            })
+            .collect();
+        Some(Self { essence, slash, params })
+    }
+
+    /// The type and subtype without parameters, e.g. `text/html`.
+    pub fn essence(&self) -> &str {
+        &self.essence
+    }
+
+    /// The top-level type, e.g. `text`.
+    pub fn main_type(&self) -> &str {
+        self.essence.get(..self.slash).unwrap_or_default()
+    }
+
+    /// The subtype including any suffix, e.g. `atom+xml`.
+    pub fn subtype(&self) -> &str {
+        self.essence.get(self.slash + 1..).unwrap_or_default()
+    }
+
+    /// The structured syntax suffix without `+`, e.g. `xml` for `application/atom+xml`.
+    pub fn suffix(&self) -> Option<&str> {
+        self.subtype().rsplit_once('+').map(|(_, suffix)| suffix)
+    }
+
+    /// The value of the parameter `name`, compared ASCII case-insensitively.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    /// The parameters in their original order.
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Set a parameter, replacing an existing value.
+    pub fn set_param(&mut self, name: &str, value: &str) {
+        let name = name.to_ascii_lowercase();
+        match self.params.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.params.push((name, value.to_string())),
+        }
+    }
+
+    /// The `charset` parameter, e.g. `utf-8`.
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// Whether the media type matches `pattern`, ignoring ASCII case and parameters.
+    ///
+    /// `*/*` matches everything, `text/*` or `text/` a whole type, `+json` a structured
+    /// syntax suffix and any other pattern the exact type and subtype.
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.trim().to_ascii_lowercase();
+        if pattern == "*/*" {
+            true
+        } else if let Some(main) = pattern.strip_suffix("/*").or_else(|| pattern.strip_suffix('/')) {
+            self.main_type() == main
+        } else if let Some(suffix) = pattern.strip_prefix('+') {
+            self.suffix() == Some(suffix)
+        } else {
+            self.essence == pattern
+        }
+    }
+}
+
+impl Display for Mime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.essence)?;
+        for (name, value) in &self.params {
+            match !value.is_empty() && is_token(value) {
+                true => write!(f, "; {name}={value}")?,
+                false => write!(f, "; {name}=\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_token(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Split a header value at `;` outside of quoted strings.
+pub(crate) fn split_params(value: &str) -> Vec<&str> {
+    let mut params = Vec::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                params.push(value.get(start..i).unwrap_or_default());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(value.get(start..).unwrap_or_default());
+    params
+}
+
+/// Value of a token or quoted string with escapes resolved.
+pub(crate) fn unquote(value: &str) -> String {
+    let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_string();
+    };
+    let mut out = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        out.push(if c == '\\' { chars.next().unwrap_or(c) } else { c });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_type_and_params() {
+        let mime = Mime::parse(b" Text/HTML ; Charset=UTF-8; q=\"a;b\\\"c\"; broken").unwrap();
+        assert_eq!((mime.main_type(), mime.subtype(), mime.suffix()), ("text", "html", None));
+        assert_eq!(mime.charset(), Some("UTF-8"));
+        assert_eq!(mime.params().collect::<Vec<_>>(), [("charset", "UTF-8"), ("q", "a;b\"c")]);
+        assert_eq!(Mime::parse(b"text"), None);
+        assert_eq!(Mime::parse(b"text/"), None);
+        assert_eq!(Mime::parse(b"te xt/html"), None);
+    }
+
+    #[test]
+    fn matches_patterns() {
+        let mime = Mime::parse(b"application/atom+xml").unwrap();
+        for pattern in ["*/*", "application/*", "Application/", "+XML", "application/atom+xml"] {
+            assert!(mime.matches(pattern), "{pattern}");
+        }
+        for pattern in ["text/*", "+json", "application/atom", "application/xml"] {
+            assert!(!mime.matches(pattern), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn display_quotes_values() {
+        let mut mime = Mime::parse(b"multipart/form-data; boundary=\"a b\"").unwrap();
+        assert_eq!(mime.to_string(), "multipart/form-data; boundary=\"a b\"");
+        mime.set_param("Boundary", "xyz");
+        mime.set_param("charset", "utf-8");
+        assert_eq!(mime.to_string(), "multipart/form-data; boundary=xyz; charset=utf-8");
+    }
+
+    #[test]
+    fn split_params_respects_quotes() {
+        assert_eq!(split_params(r#"a; b="c;d"; e"#), ["a", r#" b="c;d""#, " e"]);
+        assert_eq!(unquote(r#""a\"b""#), "a\"b");
+    }
+}
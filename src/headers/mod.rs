@@ -0,0 +1,24 @@
+//! Typed helpers for common HTTP header values.
+//!
+//! The host API exposes headers as raw bytes. The types in this module format and
+//! parse the values of headers whose syntax is easy to get wrong by hand.
+mod cache_control;
+mod date;
+mod encoding;
+mod etag;
+mod list;
+mod mime;
+mod retry_after;
+mod sniff;
+
+pub use cache_control::CacheControl;
+pub use date::{format_http_date, parse_http_date};
+pub use encoding::Encoding;
+pub use etag::ETag;
+pub use list::{fold_list, list_contains, split_list};
+pub use mime::Mime;
+pub use retry_after::RetryAfter;
+pub use sniff::sniff_content_type;
+
+pub(crate) use date::{days_from_civil, format_log_date};
+pub(crate) use mime::{split_params, unquote};
@@ -0,0 +1,74 @@
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
+
+use crate::host::time;
+
+use super::format_http_date;
+
+/// Value of a `Retry-After` header.
+///
+/// Use [`Response::retry_later`](crate::host::Response::retry_later) to answer a
+/// request with `429 Too Many Requests` or `503 Service Unavailable` and this header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAfter {
+    /// Delay in seconds, formatted as delta-seconds.
+    Seconds(u64),
+    /// Point in time, formatted as HTTP-date.
+    Date(SystemTime),
+}
+
+impl RetryAfter {
+    /// Retry after the given delay, expressed as delta-seconds.
+    ///
+    /// Sub-second delays are rounded up, so clients never retry too early.
+    pub fn after(delay: Duration) -> Self {
+        let secs = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+        RetryAfter::Seconds(secs)
+    }
+
+    /// Retry after the given delay, expressed as HTTP-date based on the host clock.
+    pub fn after_as_date(delay: Duration) -> Self {
+        RetryAfter::Date(time::now() + delay)
+    }
+}
+
+impl Display for RetryAfter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetryAfter::Seconds(secs) => write!(f, "{secs}"),
+            RetryAfter::Date(at) => f.write_str(&format_http_date(*at)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn seconds_rounded_up() {
+        assert_eq!(RetryAfter::after(Duration::from_secs(30)), RetryAfter::Seconds(30));
+        assert_eq!(RetryAfter::after(Duration::from_millis(1500)), RetryAfter::Seconds(2));
+        assert_eq!(RetryAfter::after(Duration::ZERO), RetryAfter::Seconds(0));
+    }
+
+    #[test]
+    fn display_seconds() {
+        assert_eq!(RetryAfter::Seconds(120).to_string(), "120");
+    }
+
+    #[test]
+    fn display_date() {
+        let retry = RetryAfter::Date(UNIX_EPOCH + Duration::from_secs(784111777));
+        assert_eq!(retry.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+
+    #[test]
+    fn date_from_clock() {
+        let now = time::now();
+        assert_eq!(RetryAfter::after_as_date(Duration::from_secs(60)), RetryAfter::Date(now + Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,160 @@
+/// Number of leading bytes inspected, the resource header of the MIME Sniffing Standard.
+const SNIFF_LEN: usize = 1445;
+
+/// Elements that identify a document as HTML when it starts with one of them.
+const HTML_TAGS: [&[u8]; 17] = [
+    b"<!DOCTYPE HTML",
+    b"<HTML",
+    b"<HEAD",
+    b"<SCRIPT",
+    b"<IFRAME",
+    b"<H1",
+    b"<DIV",
+    b"<FONT",
+    b"<TABLE",
+    b"<A",
+    b"<STYLE",
+    b"<TITLE",
+    b"<B",
+    b"<BODY",
+    b"<BR",
+    b"<P",
+    b"<!--",
+];
+
+/// Prefixes with a fixed signature; `?` marks bytes that may have any value.
+const SIGNATURES: [(&[Option<u8>], &str); 19] = {
+    const fn sig<const N: usize>(bytes: &[u8; N]) -> [Option<u8>; N] {
+        let mut out = [None; N];
+        let mut i = 0;
+        while i < N {
+            if bytes[i] != b'?' {
+                out[i] = Some(bytes[i]);
+            }
+            i += 1;
+        }
+        out
+    }
+    [
+        (&sig(b"%PDF-"), "application/pdf"),
+        (&sig(b"%!PS-Adobe-"), "application/postscript"),
+        (&sig(b"\xFE\xFF"), "text/plain"),
+        (&sig(b"\xFF\xFE"), "text/plain"),
+        (&sig(b"\xEF\xBB\xBF"), "text/plain"),
+        (&sig(b"GIF87a"), "image/gif"),
+        (&sig(b"GIF89a"), "image/gif"),
+        (&sig(b"\x89PNG\r\n\x1A\n"), "image/png"),
+        (&sig(b"\xFF\xD8\xFF"), "image/jpeg"),
+        (&sig(b"RIFF????WEBPVP"), "image/webp"),
+        (&sig(b"BM"), "image/bmp"),
+        (&sig(b"\0\0\x01\0"), "image/x-icon"),
+        (&sig(b"\0\0\x02\0"), "image/x-icon"),
+        (&sig(b"OggS\0"), "application/ogg"),
+        (&sig(b"RIFF????WAVE"), "audio/wave"),
+        (&sig(b"ID3"), "audio/mpeg"),
+        (&sig(b"\x1F\x8B\x08"), "application/x-gzip"),
+        (&sig(b"PK\x03\x04"), "application/zip"),
+        (&sig(b"\x1AE\xDF\xA3"), "video/webm"),
+    ]
+};
+
+/// Determine the media type of a body from its leading bytes.
+///
+/// Implements a subset of the unknown type rules of the MIME Sniffing Standard:
+/// HTML, XML, PDF, PostScript, common image, audio and video formats and archives.
+/// Other bodies are `text/plain` if they contain no binary control bytes and
+/// `application/octet-stream` otherwise. Use it to fill in a missing `Content-Type`
+/// or to check that a declared type plausibly matches the body.
+///
+/// ```
+/// use http_wasm_guest::headers::sniff_content_type;
+///
+/// assert_eq!(sniff_content_type(b"\n <!doctype html><title>x</title>"), "text/html");
+/// assert_eq!(sniff_content_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), "image/png");
+/// assert_eq!(sniff_content_type(b"plain words"), "text/plain");
+/// ```
+pub fn sniff_content_type(body: &[u8]) -> &'static str {
+    let header = body.get(..SNIFF_LEN).unwrap_or(body);
+    let start = header.iter().position(|b| !matches!(b, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')).unwrap_or(header.len());
+    let markup = header.get(start..).unwrap_or_default();
+    if HTML_TAGS.iter().any(|tag| is_html_tag(markup, tag)) {
+        return "text/html";
+    }
+    if markup.starts_with(b"<?xml") {
+        return "text/xml";
+    }
+    let signature = SIGNATURES.iter().find(|(signature, _)| {
+        header.len() >= signature.len() && signature.iter().zip(header).all(|(expected, b)| expected.is_none_or(|e| e == *b))
+    });
+    if let Some((_, media_type)) = signature {
+        return media_type;
+    }
+    if is_mp4(header) {
+        return "video/mp4";
+    }
+    match header.iter().any(|b| matches!(b, 0x00..=0x08 | 0x0B | 0x0E..=0x1A | 0x1C..=0x1F)) {
+        true => "application/octet-stream",
+        false => "text/plain",
+    }
+}
+
+/// Whether `markup` starts with `tag`, ignoring ASCII case, followed by a space or `>`.
+fn is_html_tag(markup: &[u8], tag: &[u8]) -> bool {
+    let terminated = tag == b"<!--" || matches!(markup.get(tag.len()), Some(b' ' | b'>'));
+    terminated && markup.get(..tag.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(tag))
+}
+
+/// Whether `header` starts with an ISO base media file type box with an `mp4` brand.
+fn is_mp4(header: &[u8]) -> bool {
+    let size = header.get(..4).and_then(|b| b.try_into().ok()).map_or(0, u32::from_be_bytes) as usize;
+    if size < 12 || size % 4 != 0 || header.len() < size || header.get(4..8) != Some(b"ftyp") {
+        return false;
+    }
+    // major brand, then the compatible brands after the minor version
+    std::iter::once(8).chain((16..size).step_by(4)).any(|i| header.get(i..i + 3) == Some(b"mp4"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_markup() {
+        assert_eq!(sniff_content_type(b"<HTML><body>"), "text/html");
+        assert_eq!(sniff_content_type(b"\t<p>text</p>"), "text/html");
+        assert_eq!(sniff_content_type(b"<!-- comment -->"), "text/html");
+        assert_eq!(sniff_content_type(b"<pre>"), "text/plain");
+        assert_eq!(sniff_content_type(b"<?xml version=\"1.0\"?><feed/>"), "text/xml");
+    }
+
+    #[test]
+    fn sniff_signatures() {
+        assert_eq!(sniff_content_type(b"%PDF-1.7\n"), "application/pdf");
+        assert_eq!(sniff_content_type(b"GIF89a\x01\0"), "image/gif");
+        assert_eq!(sniff_content_type(b"\xFF\xD8\xFF\xE0\0\x10JFIF"), "image/jpeg");
+        assert_eq!(sniff_content_type(b"RIFF\x24\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(sniff_content_type(b"RIFF\x24\0\0\0WAVEfmt "), "audio/wave");
+        assert_eq!(sniff_content_type(b"\x1F\x8B\x08\0\0\0\0\0"), "application/x-gzip");
+        assert_eq!(sniff_content_type(b"PK\x03\x04\x14\0"), "application/zip");
+        assert_eq!(sniff_content_type(b"\xEF\xBB\xBF{\"a\":1}"), "text/plain");
+    }
+
+    #[test]
+    fn sniff_mp4() {
+        let mut mp4 = b"\0\0\0\x18ftypisom\0\0\x02\0iso2mp41".to_vec();
+        assert_eq!(sniff_content_type(&mp4), "video/mp4");
+        mp4.truncate(20);
+        assert_eq!(sniff_content_type(&mp4), "application/octet-stream");
+    }
+
+    #[test]
+    fn sniff_text_or_binary() {
+        assert_eq!(sniff_content_type(b""), "text/plain");
+        assert_eq!(sniff_content_type("{\"name\": \"Grüße\"}\r\n".as_bytes()), "text/plain");
+        assert_eq!(sniff_content_type(b"\0asm\x01\0\0\0"), "application/octet-stream");
+        // only the resource header is inspected
+        let mut late_binary = vec![b'a'; SNIFF_LEN];
+        late_binary.push(0);
+        assert_eq!(sniff_content_type(&late_binary), "text/plain");
+    }
+}
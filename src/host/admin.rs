@@ -21,6 +21,21 @@ pub fn config() -> Bytes {
     Bytes::from(handler::get_config())
 }
 
+/// Decodes the configuration provided by the host as protobuf message `T`.
+///
+/// For hosts and orchestrators that push binary configuration blobs; use the types
+/// generated by `prost-build` for the configuration schema.
+///
+/// ```ignore
+/// use http_wasm_guest::host::admin;
+///
+/// let config: my_plugin::Config = admin::config_proto().expect("invalid configuration");
+/// ```
+#[cfg(feature = "protobuf")]
+pub fn config_proto<T: prost::Message + Default>() -> Result<T, prost::DecodeError> {
+    T::decode(handler::get_config().as_ref())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,6 +49,13 @@ mod tests {
         assert!(config_str.contains("test1"));
     }
 
+    #[cfg(feature = "protobuf")]
+    #[test]
+    fn admin_config_proto() {
+        // The mock returns JSON, which is not a valid protobuf message
+        assert!(config_proto::<String>().is_err());
+    }
+
     #[test]
     fn admin_enable_feature() {
         // Should not panic - mock handles feature enablement
@@ -0,0 +1,56 @@
+//! Recording of the mutations the guest makes through the host API.
+//!
+//! Recording is off unless a component such as
+//! [`Audit`](crate::middleware::Audit) starts it; the handler functions then report
+//! each mutation before passing it to the host.
+use std::cell::RefCell;
+
+thread_local! {
+    static MUTATIONS: RefCell<Option<Vec<Mutation>>> = const { RefCell::new(None) };
+}
+
+/// A change made to the request or response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Mutation {
+    SetHeader { kind: i32, name: Vec<u8>, value: Vec<u8> },
+    AddHeader { kind: i32, name: Vec<u8>, value: Vec<u8> },
+    RemoveHeader { kind: i32, name: Vec<u8> },
+    SetMethod(Vec<u8>),
+    SetUri(Vec<u8>),
+    SetStatus(i32),
+    WriteBody { kind: i32, len: usize },
+}
+
+/// Record the mutation built by `f` if recording is active.
+pub(crate) fn record(f: impl FnOnce() -> Mutation) {
+    MUTATIONS.with(|mutations| {
+        if let Some(mutations) = mutations.borrow_mut().as_mut() {
+            mutations.push(f());
+        }
+    });
+}
+
+/// Start recording, continuing after the given mutations.
+pub(crate) fn start(recorded: Vec<Mutation>) {
+    MUTATIONS.with(|mutations| *mutations.borrow_mut() = Some(recorded));
+}
+
+/// Stop recording and return the recorded mutations.
+pub(crate) fn stop() -> Vec<Mutation> {
+    MUTATIONS.with(|mutations| mutations.borrow_mut().take()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_while_started() {
+        record(|| Mutation::SetStatus(500));
+        start(vec![Mutation::SetStatus(403)]);
+        record(|| Mutation::SetUri(b"/a".to_vec()));
+        assert_eq!(stop(), vec![Mutation::SetStatus(403), Mutation::SetUri(b"/a".to_vec())]);
+        record(|| Mutation::SetStatus(500));
+        assert_eq!(stop(), Vec::new());
+    }
+}
@@ -1,4 +1,18 @@
-use crate::host::{Bytes, handler};
+use std::ops::ControlFlow;
+
+#[cfg(feature = "compression")]
+use crate::compression::{self, CompressionError};
+use crate::host::{Bytes, Header, handler};
+#[cfg(feature = "xml")]
+use crate::xml;
+use crate::{
+    codec,
+    digest::Sha256,
+    form::Form,
+    multipart::{self, MultipartError, Part},
+};
+#[cfg(any(feature = "json", feature = "cbor", feature = "xml"))]
+use serde::{Serialize, de::DeserializeOwned};
 
 /// Handle for accessing and mutating an HTTP body stream.
 ///
@@ -27,12 +41,241 @@ impl Body {
         Bytes::from(handler::body(self.0))
     }
 
+    /// Read the body chunk by chunk, without holding all of it in memory.
+    ///
+    /// `f` is called for each chunk as delivered by the host, in order, until the end
+    /// of the body or until it returns [`ControlFlow::Break`]. The same buffering rules
+    /// apply as for [`read`](Body::read). Use a [`StreamScanner`](crate::scan::StreamScanner)
+    /// to search for patterns across chunk boundaries.
+    pub fn read_chunks(&self, f: impl FnMut(&[u8]) -> ControlFlow<()>) {
+        handler::body_chunks(self.0, f);
+    }
+
     /// Replace the body with the provided bytes.
     ///
     /// Use this to set a new payload after inspecting or transforming the original.
     pub fn write(&self, body: &[u8]) {
         handler::write_body(self.0, body);
     }
+
+    /// SHA-256 digest of the body, hashed chunk by chunk.
+    ///
+    /// The same buffering requirements as for [`read`](Body::read) apply.
+    pub fn sha256(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        self.read_chunks(|chunk| {
+            hasher.update(chunk);
+            ControlFlow::Continue(())
+        });
+        hasher.finalize()
+    }
+
+    /// BLAKE3 digest of the body, hashed chunk by chunk.
+    #[cfg(feature = "blake3")]
+    pub fn blake3(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        self.read_chunks(|chunk| {
+            hasher.update(chunk);
+            ControlFlow::Continue(())
+        });
+        hasher.finalize().into()
+    }
+
+    /// Set `Repr-Digest` (RFC 9530) and the legacy `Digest` (RFC 3230) header of the
+    /// message to the SHA-256 digest of `body`.
+    ///
+    /// The headers describe the body sent on, so pass the bytes of the final
+    /// [`write`](Body::write), or those read if the body is passed on unchanged; the
+    /// body is not read back from the host.
+    pub fn set_digest_headers(&self, body: &[u8]) {
+        let (repr_digest, digest) = digest_headers(body);
+        let header = Header::new(self.0);
+        header.set(b"Repr-Digest", repr_digest.as_bytes());
+        header.set(b"Digest", digest.as_bytes());
+    }
+
+    /// Replace the body with `body` and set the digest headers for it, see
+    /// [`set_digest_headers`](Body::set_digest_headers).
+    pub fn write_with_digest(&self, body: &[u8]) {
+        self.write(body);
+        self.set_digest_headers(body);
+    }
+
+    /// Check the body against the SHA-256 digests in `Content-Digest`, `Repr-Digest`
+    /// and `Digest`.
+    ///
+    /// Returns `None` if none of the headers has a SHA-256 digest, otherwise whether
+    /// all of them match the body.
+    pub fn verify_digest(&self) -> Option<bool> {
+        let header = Header::new(self.0);
+        let mut expected = Vec::new();
+        for name in [&b"Content-Digest"[..], b"Repr-Digest"] {
+            for value in header.values(name) {
+                expected.extend(value.split(|b| *b == b',').filter_map(|member| {
+                    let (algorithm, digest) = split_digest(member)?;
+                    let digest = digest.strip_prefix(b":").and_then(|d| d.strip_suffix(b":"));
+                    (algorithm == b"sha-256").then(|| digest.map(|d| d.to_vec()).unwrap_or_default())
+                }));
+            }
+        }
+        for value in header.values(b"Digest") {
+            expected.extend(value.split(|b| *b == b',').filter_map(|member| {
+                let (algorithm, digest) = split_digest(member)?;
+                algorithm.eq_ignore_ascii_case(b"sha-256").then(|| digest.to_vec())
+            }));
+        }
+        if expected.is_empty() {
+            return None;
+        }
+        let digest = self.sha256();
+        Some(expected.iter().all(|encoded| codec::base64_decode(encoded, false).is_ok_and(|d| d == digest)))
+    }
+
+    /// Read the body and parse it as `application/x-www-form-urlencoded` fields.
+    ///
+    /// The same buffering requirements as for [`read`](Body::read) apply.
+    pub fn read_form(&self) -> Form {
+        Form::parse(&self.read())
+    }
+
+    /// Serialize `form` urlencoded and replace the body with it.
+    pub fn write_form(&self, form: &Form) {
+        self.write(form.to_string().as_bytes());
+    }
+
+    /// Read the body and parse it as `multipart/form-data` according to its `Content-Type`.
+    ///
+    /// The same buffering requirements as for [`read`](Body::read) apply. Parsing stops
+    /// with an error as soon as one of the `limits` is exceeded.
+    pub fn read_multipart(&self, limits: &multipart::Limits) -> Result<Vec<Part>, MultipartError> {
+        let content_type = Header::new(self.0).get(b"Content-Type").ok_or(MultipartError::NotMultipart)?;
+        let boundary = multipart::boundary(&content_type).ok_or(MultipartError::NotMultipart)?;
+        multipart::parse(&self.read(), &boundary, limits)
+    }
+
+    /// Read the body and deserialize it from JSON.
+    ///
+    /// The same buffering requirements as for [`read`](Body::read) apply.
+    #[cfg(feature = "json")]
+    pub fn read_json<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.read())
+    }
+
+    /// Serialize `value` to JSON and replace the body with it.
+    #[cfg(feature = "json")]
+    pub fn write_json<T: Serialize + ?Sized>(&self, value: &T) -> Result<(), serde_json::Error> {
+        self.write(&serde_json::to_vec(value)?);
+        Ok(())
+    }
+
+    /// Read the body as JSON [`Value`](serde_json::Value), modify it in place and write it back.
+    ///
+    /// ```no_run
+    /// # use http_wasm_guest::host::Response;
+    /// # fn handle(response: &Response) -> Result<(), serde_json::Error> {
+    /// response.body.patch_json(|value| {
+    ///     if let Some(object) = value.as_object_mut() {
+    ///         object.remove("internal_id");
+    ///     }
+    /// })
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn patch_json(&self, patch: impl FnOnce(&mut serde_json::Value)) -> Result<(), serde_json::Error> {
+        let mut value: serde_json::Value = self.read_json()?;
+        patch(&mut value);
+        self.write_json(&value)
+    }
+
+    /// Read the body and deserialize it from CBOR (RFC 8949).
+    ///
+    /// The same buffering requirements as for [`read`](Body::read) apply.
+    #[cfg(feature = "cbor")]
+    pub fn read_cbor<T: DeserializeOwned>(&self) -> Result<T, ciborium::de::Error<std::io::Error>> {
+        ciborium::from_reader(self.read().as_ref())
+    }
+
+    /// Serialize `value` to CBOR and replace the body with it.
+    #[cfg(feature = "cbor")]
+    pub fn write_cbor<T: Serialize + ?Sized>(&self, value: &T) -> Result<(), ciborium::ser::Error<std::io::Error>> {
+        let mut body = Vec::new();
+        ciborium::into_writer(value, &mut body)?;
+        self.write(&body);
+        Ok(())
+    }
+
+    /// Read the body and deserialize it from XML.
+    ///
+    /// The same buffering requirements as for [`read`](Body::read) apply.
+    #[cfg(feature = "xml")]
+    pub fn read_xml<T: DeserializeOwned>(&self) -> Result<T, quick_xml::DeError> {
+        quick_xml::de::from_reader(self.read().as_ref())
+    }
+
+    /// Serialize `value` to XML and replace the body with it.
+    #[cfg(feature = "xml")]
+    pub fn write_xml<T: Serialize + ?Sized>(&self, value: &T) -> Result<(), quick_xml::SeError> {
+        self.write(quick_xml::se::to_string(value)?.as_bytes());
+        Ok(())
+    }
+
+    /// Read the body as an [`xml::Element`] tree.
+    ///
+    /// The same buffering requirements as for [`read`](Body::read) apply.
+    #[cfg(feature = "xml")]
+    pub fn read_xml_element(&self) -> Result<xml::Element, xml::XmlError> {
+        xml::Element::parse(&self.read())
+    }
+
+    /// Replace the body with the serialized `element`, preceded by an XML declaration.
+    #[cfg(feature = "xml")]
+    pub fn write_xml_element(&self, element: &xml::Element) {
+        self.write(format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>{element}").as_bytes());
+    }
+
+    /// Read the body and decode it according to its `Content-Encoding` header.
+    ///
+    /// Supports `gzip` and `deflate`, plus `br` and `zstd` with the features of the
+    /// same name; decoded bodies are limited to
+    /// [`DEFAULT_LIMIT`](compression::DEFAULT_LIMIT). If the body was encoded, the
+    /// decoded bytes replace it, `Content-Encoding` is removed and `Content-Length` is
+    /// updated, so that the next handler sees a consistent message.
+    ///
+    /// The same buffering requirements as for [`read`](Body::read) apply.
+    #[cfg(feature = "compression")]
+    pub fn read_decompressed(&self) -> Result<Bytes, CompressionError> {
+        self.read_decompressed_with_limit(compression::DEFAULT_LIMIT)
+    }
+
+    /// Like [`read_decompressed`](Body::read_decompressed), with a custom limit for the decoded size.
+    #[cfg(feature = "compression")]
+    pub fn read_decompressed_with_limit(&self, limit: usize) -> Result<Bytes, CompressionError> {
+        let header = Header::new(self.0);
+        let body = self.read();
+        let Some(content_encoding) = header.get(b"Content-Encoding") else {
+            return Ok(body);
+        };
+        let decoded = compression::decode_content(&content_encoding, &body, limit)?;
+        self.write(&decoded);
+        header.remove(b"Content-Encoding");
+        if header.get(b"Content-Length").is_some() {
+            header.set(b"Content-Length", decoded.len().to_string().as_bytes());
+        }
+        Ok(Bytes::from(decoded))
+    }
+}
+
+/// Values of the `Repr-Digest` and `Digest` headers for `body`.
+fn digest_headers(body: &[u8]) -> (String, String) {
+    let digest = codec::base64_encode(&crate::digest::sha256(body), false, true);
+    (format!("sha-256=:{digest}:"), format!("SHA-256={digest}"))
+}
+
+/// Algorithm and value of a digest header member, split at the first `=`.
+fn split_digest(member: &[u8]) -> Option<(&[u8], &[u8])> {
+    let member = member.trim_ascii();
+    let eq = member.iter().position(|b| *b == b'=')?;
+    Some((member.get(..eq)?.trim_ascii(), member.get(eq + 1..)?.trim_ascii()))
 }
 
 #[cfg(test)]
@@ -48,10 +291,127 @@ mod tests {
         assert!(content.to_str().unwrap().contains("html"));
     }
 
+    #[test]
+    fn body_sha256() {
+        let digest = Body::new(1).sha256();
+        assert_eq!(codec::hex_encode(&digest), "e5d7d30d736126ca3d61af7b046be9565acf8a977df55847b8c1a32d27a33b9e");
+        // The mock response has no digest headers
+        assert_eq!(Body::new(1).verify_digest(), None);
+    }
+
+    #[test]
+    fn digest_headers_of_written_body() {
+        let body = Body::new(1);
+        body.write_with_digest(b"abc");
+        assert_eq!(crate::host::test::take_written_body(1), b"abc");
+        let (repr_digest, digest) = digest_headers(b"abc");
+        assert_eq!(repr_digest, "sha-256=:ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=:");
+        assert_eq!(digest, "SHA-256=ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=");
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn body_blake3() {
+        assert_eq!(Body::new(1).blake3(), *blake3::hash(b"<html><body>test</body>").as_bytes());
+    }
+
+    #[test]
+    fn split_digest_members() {
+        assert_eq!(split_digest(b" sha-256=:YWJj:"), Some((&b"sha-256"[..], &b":YWJj:"[..])));
+        assert_eq!(split_digest(b"SHA-256 = YWJj== "), Some((&b"SHA-256"[..], &b"YWJj=="[..])));
+        assert_eq!(split_digest(b"sha-256"), None);
+    }
+
+    #[test]
+    fn body_read_form() {
+        // The mock body is not urlencoded and becomes a single name without value
+        let form = Body::new(1).read_form();
+        assert_eq!(form.get("<html><body>test</body>"), Some(""));
+    }
+
+    #[test]
+    fn body_read_multipart_requires_content_type() {
+        // The mock request has no Content-Type header
+        assert_eq!(Body::new(0).read_multipart(&multipart::Limits::default()), Err(MultipartError::NotMultipart));
+    }
+
+    #[test]
+    fn body_read_chunks() {
+        let mut chunks = Vec::new();
+        Body::new(1).read_chunks(|chunk| {
+            chunks.push(chunk.to_vec());
+            ControlFlow::Continue(())
+        });
+        // The mock returns the whole body in one chunk
+        assert_eq!(chunks, vec![b"<html><body>test</body>".to_vec()]);
+    }
+
     #[test]
     fn body_read_response() {
         let body = Body::new(0);
         let content = body.read();
         assert!(content.is_empty());
     }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn body_read_decompressed_without_encoding() {
+        // The mock request has no Content-Encoding header
+        let body = Body::new(0);
+        assert_eq!(body.read_decompressed().unwrap(), body.read());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn body_read_json() {
+        use std::collections::BTreeMap;
+
+        let body = Body::new(handler::test::kinds::JSON_BODY);
+        let value: BTreeMap<String, serde_json::Value> = body.read_json().unwrap();
+        assert_eq!(value.get("name"), Some(&serde_json::Value::from("test")));
+        assert_eq!(value.get("count"), Some(&serde_json::Value::from(1)));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn body_read_json_invalid() {
+        // The mock returns HTML content
+        assert!(Body::new(1).read_json::<serde_json::Value>().is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn body_write_and_patch_json() {
+        let body = Body::new(handler::test::kinds::JSON_BODY);
+        body.write_json(&["a", "b"]).unwrap();
+        let mut seen = None;
+        body.patch_json(|value| seen = value.get("name").cloned()).unwrap();
+        assert_eq!(seen, Some(serde_json::Value::from("test")));
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn body_read_and_write_cbor() {
+        // The mock returns HTML content, `<` (0x3c) is a reserved CBOR initial byte
+        let body = Body::new(1);
+        assert!(body.read_cbor::<i64>().is_err());
+        body.write_cbor(&("a", 1)).unwrap();
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn body_read_xml_invalid() {
+        // The mock returns "<html><body>test</body>" without the end tag of html
+        let body = Body::new(1);
+        assert_eq!(body.read_xml_element().map_err(|e| e.message), Err("missing end tag".into()));
+        assert!(body.read_xml::<std::collections::BTreeMap<String, String>>().is_err());
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn body_write_xml() {
+        let body = Body::new(1);
+        body.write_xml_element(&xml::Element::new("ok"));
+        assert!(body.write_xml(&std::collections::BTreeMap::from([("a", 1)])).is_err());
+    }
 }
@@ -1,10 +1,14 @@
 use std::{
     borrow::Borrow,
+    cmp::Ordering,
     fmt::Display,
     ops::Deref,
     str::{Utf8Error, from_utf8},
+    string::FromUtf8Error,
 };
 
+use crate::codec::{self, DecodeError};
+
 /// Owned container for binary data used throughout the API.
 ///
 /// `Bytes` stores its contents as a boxed slice for efficient cloning and
@@ -25,6 +29,55 @@ impl Bytes {
     pub fn to_str(&self) -> Result<&str, Utf8Error> {
         from_utf8(self.0.as_ref())
     }
+
+    /// Creates a `Bytes` value by copying a byte slice, like `From<&[u8]>`.
+    pub fn copy_from_slice(data: &[u8]) -> Self {
+        Self::from(data)
+    }
+
+    /// Converts into a `Vec<u8>`, taking over the buffer without copying.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0.into_vec()
+    }
+
+    /// Converts into a `String` without copying if the contents are valid UTF-8.
+    ///
+    /// On error the original bytes can be recovered with
+    /// [`FromUtf8Error::into_bytes`].
+    pub fn into_string(self) -> Result<String, FromUtf8Error> {
+        String::from_utf8(self.into_vec())
+    }
+
+    /// Encodes the contents as padded base64 with the standard alphabet of RFC 4648.
+    pub fn to_base64(&self) -> String {
+        codec::base64_encode(&self.0, false, true)
+    }
+
+    /// Encodes the contents as unpadded base64 with the URL-safe alphabet (`-` and `_`),
+    /// as used in JWTs and cookie values.
+    pub fn to_base64_url(&self) -> String {
+        codec::base64_encode(&self.0, true, false)
+    }
+
+    /// Decodes base64 with the standard alphabet; padding is optional.
+    pub fn from_base64(input: &[u8]) -> Result<Self, DecodeError> {
+        codec::base64_decode(input, false).map(Self::from)
+    }
+
+    /// Decodes base64 with the URL-safe alphabet; padding is optional.
+    pub fn from_base64_url(input: &[u8]) -> Result<Self, DecodeError> {
+        codec::base64_decode(input, true).map(Self::from)
+    }
+
+    /// Encodes the contents as lowercase hex, e.g. for digests and signatures.
+    pub fn to_hex(&self) -> String {
+        codec::hex_encode(&self.0)
+    }
+
+    /// Decodes hex digits of either case.
+    pub fn from_hex(input: &[u8]) -> Result<Self, DecodeError> {
+        codec::hex_decode(input).map(Self::from)
+    }
 }
 
 // --- Standard Library Trait Implementations (for Bytes) ---
@@ -49,6 +102,12 @@ impl Borrow<[u8]> for Bytes {
     }
 }
 
+impl AsRef<[u8]> for Bytes {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
 // --- Comparison Trait Implementations ---
 impl PartialEq<Bytes> for [u8] {
     fn eq(&self, other: &Bytes) -> bool {
@@ -126,6 +185,55 @@ impl PartialEq<Bytes> for &str {
     }
 }
 
+impl PartialEq<String> for Bytes {
+    fn eq(&self, other: &String) -> bool {
+        self.0.as_ref() == other.as_bytes()
+    }
+}
+
+impl PartialEq<Bytes> for String {
+    fn eq(&self, other: &Bytes) -> bool {
+        self.as_bytes() == other.0.as_ref()
+    }
+}
+
+impl PartialEq<Vec<u8>> for Bytes {
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        self.0.as_ref() == other.as_slice()
+    }
+}
+
+impl PartialEq<Bytes> for Vec<u8> {
+    fn eq(&self, other: &Bytes) -> bool {
+        self.as_slice() == other.0.as_ref()
+    }
+}
+
+// Ordering is bytewise lexicographic, consistent with `Ord for Bytes`.
+impl PartialOrd<[u8]> for Bytes {
+    fn partial_cmp(&self, other: &[u8]) -> Option<Ordering> {
+        Some(self.0.as_ref().cmp(other))
+    }
+}
+
+impl PartialOrd<&[u8]> for Bytes {
+    fn partial_cmp(&self, other: &&[u8]) -> Option<Ordering> {
+        Some(self.0.as_ref().cmp(*other))
+    }
+}
+
+impl PartialOrd<str> for Bytes {
+    fn partial_cmp(&self, other: &str) -> Option<Ordering> {
+        Some(self.0.as_ref().cmp(other.as_bytes()))
+    }
+}
+
+impl PartialOrd<&str> for Bytes {
+    fn partial_cmp(&self, other: &&str) -> Option<Ordering> {
+        Some(self.0.as_ref().cmp(other.as_bytes()))
+    }
+}
+
 // --- Conversion Trait Implementations (From<...> for Bytes) ---
 
 /// Creates a `Bytes` value from an existing boxed slice without copying.
@@ -167,6 +275,34 @@ impl From<&str> for Bytes {
     }
 }
 
+/// Creates a `Bytes` value by taking ownership of the string's buffer.
+impl From<String> for Bytes {
+    fn from(value: String) -> Self {
+        Self::from(value.into_bytes())
+    }
+}
+
+/// Creates a `Bytes` value containing the UTF-8 encoding of a character.
+impl From<char> for Bytes {
+    fn from(value: char) -> Self {
+        Self::from(value.encode_utf8(&mut [0; 4]) as &str)
+    }
+}
+
+/// Creates a `Bytes` value by copying the contents of another one.
+impl From<&Bytes> for Bytes {
+    fn from(value: &Bytes) -> Self {
+        value.clone()
+    }
+}
+
+/// Collects bytes into a new `Bytes` value.
+impl FromIterator<u8> for Bytes {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<u8>>())
+    }
+}
+
 // --- Test Module ---
 
 #[cfg(test)]
@@ -187,6 +323,32 @@ mod tests {
         assert_eq!(format!("{b}"), "H�llo");
     }
 
+    #[test]
+    fn bytes_into_owned_buffers() {
+        assert_eq!(Bytes::from("abc").into_vec(), b"abc");
+        assert_eq!(Bytes::from("h\u{e4}llo").into_string().unwrap(), "h\u{e4}llo");
+        let err = Bytes::from(&b"\xFFx"[..]).into_string().unwrap_err();
+        assert_eq!(err.into_bytes(), b"\xFFx");
+    }
+
+    #[test]
+    fn bytes_base64_roundtrip() {
+        let bytes = Bytes::from(&b"user:p\xE4ss?"[..]);
+        assert_eq!(bytes.to_base64(), "dXNlcjpw5HNzPw==");
+        assert_eq!(bytes.to_base64_url(), "dXNlcjpw5HNzPw");
+        assert_eq!(Bytes::from_base64(b"dXNlcjpw5HNzPw==").unwrap(), bytes);
+        assert_eq!(Bytes::from_base64_url(b"dXNlcjpw5HNzPw").unwrap(), bytes);
+        assert_eq!(Bytes::from_base64(b"dXNl*"), Err(DecodeError::InvalidByte(4)));
+    }
+
+    #[test]
+    fn bytes_hex_roundtrip() {
+        let bytes = Bytes::from(&b"\xDE\xAD\xBE\xEF"[..]);
+        assert_eq!(bytes.to_hex(), "deadbeef");
+        assert_eq!(Bytes::from_hex(b"DEADbeef").unwrap(), bytes);
+        assert_eq!(Bytes::from_hex(b"dead-beef"), Err(DecodeError::InvalidByte(4)));
+    }
+
     #[test]
     fn bytes_from_string_slice_roundtrip() {
         let original = "Hello, http-wasm!";
@@ -332,4 +494,43 @@ mod tests {
         assert_eq!(set.get(&b[..]), Some(&b));
         assert_eq!(set.get(&b), Some(&b));
     }
+
+    #[test]
+    fn bytes_eq_owned_strings_and_vecs() {
+        let method = Bytes::from("POST");
+        assert!(method == "POST" && method == *"POST" && method == String::from("POST"));
+        assert_eq!(String::from("POST"), method);
+        assert_eq!(method, b"POST".to_vec());
+        assert_eq!(b"POST".to_vec(), method);
+        assert_ne!(method, String::from("post"));
+    }
+
+    #[test]
+    fn bytes_ordering() {
+        let b = Bytes::from("b");
+        assert!(b > "a" && b < "c" && b > *"a");
+        assert!(b < &b"ba"[..] && b > b""[..]);
+        let mut sorted = vec![Bytes::from("x-b"), Bytes::from("X-A"), Bytes::from("x-a")];
+        sorted.sort();
+        assert_eq!(sorted, ["X-A", "x-a", "x-b"]);
+    }
+
+    #[test]
+    fn bytes_map_lookup_by_slice() {
+        let map = std::collections::HashMap::from([(Bytes::from("Host"), 1)]);
+        assert_eq!(map.get(&b"Host"[..]), Some(&1));
+        assert_eq!(<Bytes as AsRef<[u8]>>::as_ref(&Bytes::from("x")), b"x");
+    }
+
+    #[test]
+    fn bytes_from_owned_and_collected_values() {
+        assert_eq!(Bytes::from(String::from("owned")), "owned");
+        assert_eq!(Bytes::from('ä'), "ä");
+        assert_eq!(Bytes::from(';'), b";");
+        let original = Bytes::from("copy");
+        assert_eq!(Bytes::from(&original), original);
+        assert_eq!(Bytes::copy_from_slice(b"slice"), "slice");
+        let lower: Bytes = b"X-Foo".iter().map(u8::to_ascii_lowercase).collect();
+        assert_eq!(lower, "x-foo");
+    }
 }
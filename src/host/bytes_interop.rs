@@ -0,0 +1,66 @@
+//! Conversions between [`Bytes`] and the buffers of the `bytes` crate.
+//!
+//! All conversions hand over the allocation without copying, except for converting a
+//! `bytes::Bytes` that shares its buffer with other handles or was created from a
+//! static slice.
+use crate::host::Bytes;
+
+/// Creates a `bytes::Bytes` from the buffer without copying.
+///
+/// ```
+/// use http_wasm_guest::host::Bytes;
+///
+/// let body = Bytes::from("hello");
+/// let shared = bytes::Bytes::from(body);
+/// assert_eq!(shared.slice(1..3), "el");
+/// ```
+impl From<Bytes> for bytes::Bytes {
+    fn from(value: Bytes) -> Self {
+        bytes::Bytes::from(value.into_vec())
+    }
+}
+
+/// Creates a `bytes::BytesMut` from the buffer without copying.
+impl From<Bytes> for bytes::BytesMut {
+    fn from(value: Bytes) -> Self {
+        bytes::BytesMut::from(bytes::Bytes::from(value))
+    }
+}
+
+/// Takes over the buffer if it is uniquely owned and copies it otherwise.
+impl From<bytes::Bytes> for Bytes {
+    fn from(value: bytes::Bytes) -> Self {
+        Bytes::from(Vec::from(value))
+    }
+}
+
+/// Takes over the buffer without copying.
+impl From<bytes::BytesMut> for Bytes {
+    fn from(value: bytes::BytesMut) -> Self {
+        Bytes::from(Vec::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let original = Bytes::from(&b"body \x00\xFF"[..]);
+        let shared = bytes::Bytes::from(original.clone());
+        assert_eq!(shared, &b"body \x00\xFF"[..]);
+        assert_eq!(Bytes::from(shared), original);
+        let mut buf = bytes::BytesMut::from(original.clone());
+        buf.extend_from_slice(b"!");
+        assert_eq!(Bytes::from(buf), Bytes::from(&b"body \x00\xFF!"[..]));
+    }
+
+    #[test]
+    fn shared_and_static_buffers_are_copied() {
+        let shared = bytes::Bytes::from_static(b"static");
+        let copy = shared.clone();
+        assert_eq!(Bytes::from(shared), "static");
+        assert_eq!(Bytes::from(copy.slice(1..)), "tatic");
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    str::{Utf8Error, from_utf8},
+};
+
+use crate::host::Bytes;
+
+/// Binary data that is either borrowed or owned, like a `Cow<[u8]>` that converts
+/// into [`Bytes`].
+///
+/// Closure-based accessors such as [`Header::with_values`](crate::host::Header::with_values)
+/// hand out borrowed values that are only valid for the duration of the call, so
+/// inspecting a header does not allocate per value. Call
+/// [`into_owned`](BytesRef::into_owned) to keep a value beyond the call.
+///
+/// ```
+/// use http_wasm_guest::host::{Bytes, BytesRef};
+///
+/// let borrowed = BytesRef::from(&b"gzip"[..]);
+/// assert!(borrowed.is_borrowed() && borrowed == "gzip");
+/// let owned: Bytes = borrowed.into_owned();
+/// assert_eq!(owned, "gzip");
+/// ```
+#[derive(Debug, Clone)]
+pub enum BytesRef<'a> {
+    /// Data borrowed for the lifetime `'a`.
+    Borrowed(&'a [u8]),
+    /// Owned data.
+    Owned(Bytes),
+}
+
+impl BytesRef<'_> {
+    /// Returns `true` if the data is borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, BytesRef::Borrowed(_))
+    }
+
+    /// Returns the contents as UTF-8 if valid.
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        from_utf8(self)
+    }
+
+    /// Converts into owned [`Bytes`], copying borrowed data.
+    pub fn into_owned(self) -> Bytes {
+        match self {
+            BytesRef::Borrowed(data) => Bytes::from(data),
+            BytesRef::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl Deref for BytesRef<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            BytesRef::Borrowed(data) => data,
+            BytesRef::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl AsRef<[u8]> for BytesRef<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Display for BytesRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(self))
+    }
+}
+
+// --- Comparison Trait Implementations ---
+// Borrowed and owned values with the same contents are equal and hash alike.
+
+impl PartialEq for BytesRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for BytesRef<'_> {}
+
+impl Hash for BytesRef<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl PartialEq<[u8]> for BytesRef<'_> {
+    fn eq(&self, other: &[u8]) -> bool {
+        **self == *other
+    }
+}
+
+impl PartialEq<&[u8]> for BytesRef<'_> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        **self == **other
+    }
+}
+
+impl PartialEq<str> for BytesRef<'_> {
+    fn eq(&self, other: &str) -> bool {
+        **self == *other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for BytesRef<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        **self == *other.as_bytes()
+    }
+}
+
+impl PartialEq<Bytes> for BytesRef<'_> {
+    fn eq(&self, other: &Bytes) -> bool {
+        **self == **other
+    }
+}
+
+impl PartialEq<BytesRef<'_>> for Bytes {
+    fn eq(&self, other: &BytesRef<'_>) -> bool {
+        **self == **other
+    }
+}
+
+// --- Conversion Trait Implementations ---
+
+impl<'a> From<&'a [u8]> for BytesRef<'a> {
+    fn from(value: &'a [u8]) -> Self {
+        BytesRef::Borrowed(value)
+    }
+}
+
+impl<'a> From<&'a Bytes> for BytesRef<'a> {
+    fn from(value: &'a Bytes) -> Self {
+        BytesRef::Borrowed(value)
+    }
+}
+
+impl From<Bytes> for BytesRef<'_> {
+    fn from(value: Bytes) -> Self {
+        BytesRef::Owned(value)
+    }
+}
+
+impl From<BytesRef<'_>> for Bytes {
+    fn from(value: BytesRef<'_>) -> Self {
+        value.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn borrowed_and_owned_compare_equal() {
+        let owned = BytesRef::from(Bytes::from("value"));
+        let borrowed = BytesRef::from(&b"value"[..]);
+        assert!(!owned.is_borrowed());
+        assert_eq!(owned, borrowed);
+        assert_eq!(HashSet::from([owned, borrowed]).len(), 1);
+    }
+
+    #[test]
+    fn compares_with_slices_strings_and_bytes() {
+        let value = BytesRef::from(&b"text/html"[..]);
+        assert!(value == "text/html" && value == &b"text/html"[..]);
+        assert_eq!(value, Bytes::from("text/html"));
+        assert_eq!(Bytes::from("text/html"), value);
+        assert_eq!(value.to_str(), Ok("text/html"));
+        assert_eq!(value.to_string(), "text/html");
+        assert!(BytesRef::from(&b"\xFF"[..]).to_str().is_err());
+    }
+
+    #[test]
+    fn into_owned_copies_borrowed_data() {
+        let data = b"abc".to_vec();
+        let owned = Bytes::from(BytesRef::from(&data[..]));
+        drop(data);
+        assert_eq!(owned, "abc");
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+};
+
+/// Request-scoped values keyed by their type.
+///
+/// Every request starts with an empty store. Values inserted during
+/// [`handle_request`](crate::Guest::handle_request) are handed to
+/// [`handle_response`](crate::Guest::handle_response) of the same request together
+/// with its context value, so components can pass parsed claims or routing decisions
+/// forward without parsing headers again. The store is dropped when the request is
+/// stopped or the response is handled.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     Guest,
+///     host::{Request, Response},
+/// };
+///
+/// #[derive(Clone)]
+/// struct Tenant(String);
+///
+/// struct Plugin;
+///
+/// impl Guest for Plugin {
+///     fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+///         if let Some(tenant) = request.header.get(b"X-Tenant") {
+///             request.extensions().insert(Tenant(tenant.to_string()));
+///         }
+///         (true, 0)
+///     }
+///
+///     fn handle_response(&self, _req_ctx: i32, request: &Request, response: &Response, _is_error: bool) {
+///         if let Some(Tenant(tenant)) = request.extensions().get::<Tenant>() {
+///             response.header.set(b"X-Tenant", tenant.as_bytes());
+///         }
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct Extensions {
+    map: RefCell<HashMap<TypeId, Box<dyn Any>>>,
+}
+
+impl Extensions {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value, returning the previous value of the same type.
+    pub fn insert<T: 'static>(&self, value: T) -> Option<T> {
+        let previous = self.map.borrow_mut().insert(TypeId::of::<T>(), Box::new(value));
+        previous.and_then(|previous| previous.downcast().ok()).map(|previous| *previous)
+    }
+
+    /// Return a clone of the value of type `T`.
+    pub fn get<T: Clone + 'static>(&self) -> Option<T> {
+        self.with(T::clone)
+    }
+
+    /// Call `f` with a reference to the value of type `T`.
+    ///
+    /// The store must not be changed from inside `f`.
+    pub fn with<T: 'static, R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        self.map.borrow().get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref()).map(f)
+    }
+
+    /// Call `f` with a mutable reference to the value of type `T`.
+    ///
+    /// The store must not be accessed from inside `f`.
+    pub fn with_mut<T: 'static, R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.map.borrow_mut().get_mut(&TypeId::of::<T>()).and_then(|value| value.downcast_mut()).map(f)
+    }
+
+    /// Return whether a value of type `T` is stored.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.map.borrow().contains_key(&TypeId::of::<T>())
+    }
+
+    /// Remove and return the value of type `T`.
+    pub fn remove<T: 'static>(&self) -> Option<T> {
+        let value = self.map.borrow_mut().remove(&TypeId::of::<T>());
+        value.and_then(|value| value.downcast().ok()).map(|value| *value)
+    }
+
+    /// Remove all values.
+    pub fn clear(&self) {
+        self.map.borrow_mut().clear();
+    }
+
+    /// Return the number of stored values.
+    pub fn len(&self) -> usize {
+        self.map.borrow().len()
+    }
+
+    /// Return whether the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.borrow().is_empty()
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions").field("len", &self.len()).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Claims {
+        sub: String,
+    }
+
+    #[test]
+    fn insert_and_get_by_type() {
+        let ext = Extensions::new();
+        assert!(ext.is_empty());
+        assert_eq!(ext.insert(Claims { sub: "alice".into() }), None);
+        assert_eq!(ext.insert(7u32), None);
+        assert_eq!(ext.get::<Claims>(), Some(Claims { sub: "alice".into() }));
+        assert_eq!(ext.get::<u32>(), Some(7));
+        assert_eq!(ext.get::<u64>(), None);
+        assert_eq!(ext.len(), 2);
+    }
+
+    #[test]
+    fn insert_replaces_same_type() {
+        let ext = Extensions::new();
+        ext.insert(1u8);
+        assert_eq!(ext.insert(2u8), Some(1));
+        assert_eq!(ext.get::<u8>(), Some(2));
+    }
+
+    #[test]
+    fn with_and_with_mut() {
+        let ext = Extensions::new();
+        ext.insert(vec![1, 2]);
+        assert_eq!(ext.with_mut(|v: &mut Vec<i32>| v.push(3)), Some(()));
+        assert_eq!(ext.with(|v: &Vec<i32>| v.len()), Some(3));
+        assert_eq!(ext.with(|s: &String| s.len()), None);
+    }
+
+    #[test]
+    fn remove_and_clear() {
+        let ext = Extensions::new();
+        ext.insert("route");
+        ext.insert(1i32);
+        assert!(ext.contains::<&str>());
+        assert_eq!(ext.remove::<&str>(), Some("route"));
+        assert!(!ext.contains::<&str>());
+        ext.clear();
+        assert!(ext.is_empty());
+        assert_eq!(format!("{ext:?}"), "Extensions { len: 0, .. }");
+    }
+}
@@ -45,10 +45,41 @@ pub const Trailers: Feature = Feature(4);
 
 /// Bitflag wrapper used to configure host capabilities.
 ///
-/// Combine flags with `|` and pass the result to `admin::enable`.
-#[derive(Debug, PartialEq, Clone, Copy)]
+/// Combine flags with `|` and pass the result to `admin::enable`. The default value
+/// contains no flags.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub struct Feature(i32);
 
+impl Feature {
+    pub(crate) const fn from_bits(bits: i32) -> Self {
+        Feature(bits)
+    }
+
+    /// Returns `true` if no flag is set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all flags of `other` are set.
+    pub const fn contains(self, other: Feature) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the flags of `self` that are not set in `other`.
+    pub const fn difference(self, other: Feature) -> Feature {
+        Feature(self.0 & !other.0)
+    }
+
+    /// Names of the known flags that are set, e.g. `["BufferRequest", "Trailers"]`.
+    pub fn names(self) -> Vec<&'static str> {
+        [(BufferRequest, "BufferRequest"), (BufferResponse, "BufferResponse"), (Trailers, "Trailers")]
+            .into_iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| name)
+            .collect()
+    }
+}
+
 impl From<Feature> for i32 {
     fn from(val: Feature) -> Self {
         val.0
@@ -120,6 +151,16 @@ mod tests {
         assert_ne!(Feature(1), Feature(2));
     }
 
+    #[test]
+    fn feature_set_operations() {
+        let set = BufferRequest | Trailers;
+        assert!(Feature::default().is_empty());
+        assert!(set.contains(Trailers));
+        assert!(!set.contains(BufferRequest | BufferResponse));
+        assert_eq!(set.difference(BufferRequest), Trailers);
+        assert_eq!(set.names(), ["BufferRequest", "Trailers"]);
+    }
+
     #[test]
     fn feature_into_i32() {
         let f = Feature(123);
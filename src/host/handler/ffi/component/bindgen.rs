@@ -0,0 +1,30 @@
+// Imports and exports of `wit/http-handler.wit` generated by wit-bindgen.
+//
+// The generated code embeds the component type, so no linker arguments are needed.
+
+wit_bindgen::generate!({
+    path: "wit/http-handler.wit",
+    world: "guest",
+});
+
+pub(super) use http_wasm::http_handler::handler::*;
+
+/// Returns the data read and whether the end of the body was reached.
+pub(super) fn read_body(kind: u32, limit: u32) -> (Vec<u8>, bool) {
+    let chunk = http_wasm::http_handler::handler::read_body(kind, limit);
+    (chunk.data, chunk.eof)
+}
+
+struct Component;
+
+impl Guest for Component {
+    fn handle_request() -> i64 {
+        crate::http_request()
+    }
+
+    fn handle_response(req_ctx: i32, is_error: i32) {
+        crate::http_response(req_ctx, is_error);
+    }
+}
+
+export!(Component);
@@ -0,0 +1,118 @@
+// Imports of `wit/http-handler.wit` lowered by hand to the canonical ABI.
+//
+// The module carries no component type, so the WIT file has to be passed to the
+// component linker with `--component-type`.
+
+use std::ptr;
+
+use super::{ListRet, into_vec, take_list};
+
+/// Return area of a `body-chunk` result.
+#[repr(C)]
+struct BodyChunkRet {
+    data: ListRet,
+    eof: u8,
+}
+
+#[rustfmt::skip]
+#[link(wasm_import_module = "http-wasm:http-handler/handler@0.1.0")]
+unsafe extern "C" {
+    #[link_name = "log"] fn wit_log(level: i32, message: *const u8, len: usize);
+    #[link_name = "log-enabled"] fn wit_log_enabled(level: i32) -> i32;
+    #[link_name = "get-config"] fn wit_get_config(ret: *mut ListRet);
+    #[link_name = "enable-features"] fn wit_enable_features(features: u32) -> u32;
+    #[link_name = "get-method"] fn wit_get_method(ret: *mut ListRet);
+    #[link_name = "set-method"] fn wit_set_method(method: *const u8, len: usize);
+    #[link_name = "get-uri"] fn wit_get_uri(ret: *mut ListRet);
+    #[link_name = "set-uri"] fn wit_set_uri(uri: *const u8, len: usize);
+    #[link_name = "get-protocol-version"] fn wit_get_protocol_version(ret: *mut ListRet);
+    #[link_name = "get-source-addr"] fn wit_get_source_addr(ret: *mut ListRet);
+    #[link_name = "get-header-names"] fn wit_get_header_names(kind: u32, ret: *mut ListRet);
+    #[link_name = "get-header-values"] fn wit_get_header_values(kind: u32, name: *const u8, len: usize, ret: *mut ListRet);
+    #[link_name = "set-header-value"] fn wit_set_header_value(kind: u32, name: *const u8, name_len: usize, value: *const u8, value_len: usize);
+    #[link_name = "add-header-value"] fn wit_add_header_value(kind: u32, name: *const u8, name_len: usize, value: *const u8, value_len: usize);
+    #[link_name = "remove-header"] fn wit_remove_header(kind: u32, name: *const u8, len: usize);
+    #[link_name = "read-body"] fn wit_read_body(kind: u32, limit: u32, ret: *mut BodyChunkRet);
+    #[link_name = "write-body"] fn wit_write_body(kind: u32, body: *const u8, len: usize);
+    #[link_name = "get-status-code"] fn wit_get_status_code() -> u32;
+    #[link_name = "set-status-code"] fn wit_set_status_code(status: u32);
+}
+
+pub(super) fn log(level: i32, message: &[u8]) {
+    unsafe { wit_log(level, message.as_ptr(), message.len()) }
+}
+
+pub(super) fn log_enabled(level: i32) -> bool {
+    unsafe { wit_log_enabled(level) != 0 }
+}
+
+pub(super) fn get_config() -> Vec<u8> {
+    take_list(|ret| unsafe { wit_get_config(ret) })
+}
+
+pub(super) fn enable_features(features: u32) -> u32 {
+    unsafe { wit_enable_features(features) }
+}
+
+pub(super) fn get_method() -> Vec<u8> {
+    take_list(|ret| unsafe { wit_get_method(ret) })
+}
+
+pub(super) fn set_method(method: &[u8]) {
+    unsafe { wit_set_method(method.as_ptr(), method.len()) }
+}
+
+pub(super) fn get_uri() -> Vec<u8> {
+    take_list(|ret| unsafe { wit_get_uri(ret) })
+}
+
+pub(super) fn set_uri(uri: &[u8]) {
+    unsafe { wit_set_uri(uri.as_ptr(), uri.len()) }
+}
+
+pub(super) fn get_protocol_version() -> Vec<u8> {
+    take_list(|ret| unsafe { wit_get_protocol_version(ret) })
+}
+
+pub(super) fn get_source_addr() -> Vec<u8> {
+    take_list(|ret| unsafe { wit_get_source_addr(ret) })
+}
+
+pub(super) fn get_header_names(kind: u32) -> Vec<u8> {
+    take_list(|ret| unsafe { wit_get_header_names(kind, ret) })
+}
+
+pub(super) fn get_header_values(kind: u32, name: &[u8]) -> Vec<u8> {
+    take_list(|ret| unsafe { wit_get_header_values(kind, name.as_ptr(), name.len(), ret) })
+}
+
+pub(super) fn set_header_value(kind: u32, name: &[u8], value: &[u8]) {
+    unsafe { wit_set_header_value(kind, name.as_ptr(), name.len(), value.as_ptr(), value.len()) }
+}
+
+pub(super) fn add_header_value(kind: u32, name: &[u8], value: &[u8]) {
+    unsafe { wit_add_header_value(kind, name.as_ptr(), name.len(), value.as_ptr(), value.len()) }
+}
+
+pub(super) fn remove_header(kind: u32, name: &[u8]) {
+    unsafe { wit_remove_header(kind, name.as_ptr(), name.len()) }
+}
+
+/// Returns the data read and whether the end of the body was reached.
+pub(super) fn read_body(kind: u32, limit: u32) -> (Vec<u8>, bool) {
+    let mut ret = BodyChunkRet { data: ListRet { ptr: ptr::null_mut(), len: 0 }, eof: 0 };
+    unsafe { wit_read_body(kind, limit, &mut ret) };
+    (into_vec(ret.data), ret.eof != 0)
+}
+
+pub(super) fn write_body(kind: u32, body: &[u8]) {
+    unsafe { wit_write_body(kind, body.as_ptr(), body.len()) }
+}
+
+pub(super) fn get_status_code() -> u32 {
+    unsafe { wit_get_status_code() }
+}
+
+pub(super) fn set_status_code(status: u32) {
+    unsafe { wit_set_status_code(status) }
+}
@@ -0,0 +1,175 @@
+// =============================================================================
+// Component FFI - WASI 0.2 component model (wasm32-wasip2 target)
+// =============================================================================
+//
+// Adapts the imports of the `guest` world in `wit/http-handler.wit` to the
+// signatures of the core-module ABI, so the handler layer is shared. The imports
+// are lowered by hand, or generated by wit-bindgen with the `wit` feature; both
+// expose the same functions.
+
+use std::{ptr, slice};
+
+#[cfg(feature = "wit")]
+mod bindgen;
+#[cfg(not(feature = "wit"))]
+mod lowered;
+mod wasi;
+
+#[cfg(feature = "wit")]
+use bindgen as imports;
+#[cfg(not(feature = "wit"))]
+use lowered as imports;
+
+/// Return area of a `list<u8>` result.
+#[repr(C)]
+struct ListRet {
+    ptr: *mut u8,
+    len: usize,
+}
+
+/// Calls an import returning `list<u8>` and takes ownership of the list.
+///
+/// Lists returned by the host are allocated through `cabi_realloc`, which the standard
+/// library exports on wasm32-wasip2 and backs with the global allocator.
+fn take_list(f: impl FnOnce(*mut ListRet)) -> Vec<u8> {
+    let mut ret = ListRet { ptr: ptr::null_mut(), len: 0 };
+    f(&mut ret);
+    into_vec(ret)
+}
+
+fn into_vec(list: ListRet) -> Vec<u8> {
+    match list.len {
+        0 => Vec::new(),
+        // SAFETY: allocated by `cabi_realloc` with alignment 1 and exactly `len` bytes.
+        len => unsafe { Vec::from_raw_parts(list.ptr, len, len) },
+    }
+}
+
+/// Copies `data` into `buf` if it fits, like the core-module ABI, and returns its length.
+unsafe fn copy_to_buf(data: &[u8], buf: *mut u8, buf_limit: i32) -> usize {
+    if data.len() <= buf_limit as usize {
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), buf, data.len()) };
+    }
+    data.len()
+}
+
+/// Like `copy_to_buf`, returning `count << 32 | len` for NUL-terminated values.
+unsafe fn copy_multi_to_buf(data: &[u8], buf: *mut u8, buf_limit: i32) -> i64 {
+    let count = data.iter().filter(|b| **b == 0).count() as i64;
+    let len = unsafe { copy_to_buf(data, buf, buf_limit) } as i64;
+    count << 32 | len
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: i32) -> &'a [u8] {
+    match len {
+        0 => &[],
+        len => unsafe { slice::from_raw_parts(data, len as usize) },
+    }
+}
+
+// -------------------------------------------------------------------------
+// http-wasm handler ABI
+// -------------------------------------------------------------------------
+
+pub(crate) unsafe fn log(level: i32, buf: *const u8, len: i32) {
+    imports::log(level, unsafe { bytes(buf, len) });
+}
+
+pub(crate) unsafe fn log_enabled(level: i32) -> i32 {
+    i32::from(imports::log_enabled(level))
+}
+
+pub(crate) unsafe fn get_config(buf: *mut u8, buf_limit: i32) -> i32 {
+    unsafe { copy_to_buf(&imports::get_config(), buf, buf_limit) as i32 }
+}
+
+pub(crate) unsafe fn enable_features(feature: i32) -> i32 {
+    imports::enable_features(feature as u32) as i32
+}
+
+pub(crate) unsafe fn get_method(buf: *mut u8, buf_limit: i32) -> i32 {
+    unsafe { copy_to_buf(&imports::get_method(), buf, buf_limit) as i32 }
+}
+
+pub(crate) unsafe fn set_method(method: *const u8, len: i32) {
+    imports::set_method(unsafe { bytes(method, len) });
+}
+
+pub(crate) unsafe fn get_uri(buf: *mut u8, buf_limit: i32) -> i32 {
+    unsafe { copy_to_buf(&imports::get_uri(), buf, buf_limit) as i32 }
+}
+
+pub(crate) unsafe fn set_uri(uri: *const u8, len: i32) {
+    imports::set_uri(unsafe { bytes(uri, len) });
+}
+
+pub(crate) unsafe fn get_protocol_version(buf: *mut u8, buf_limit: i32) -> i32 {
+    unsafe { copy_to_buf(&imports::get_protocol_version(), buf, buf_limit) as i32 }
+}
+
+pub(crate) unsafe fn get_source_addr(buf: *mut u8, buf_limit: i32) -> i32 {
+    unsafe { copy_to_buf(&imports::get_source_addr(), buf, buf_limit) as i32 }
+}
+
+pub(crate) unsafe fn get_header_names(kind: i32, buf: *mut u8, buf_limit: i32) -> i64 {
+    unsafe { copy_multi_to_buf(&imports::get_header_names(kind as u32), buf, buf_limit) }
+}
+
+pub(crate) unsafe fn get_header_values(kind: i32, name: *const u8, len: i32, buf: *mut u8, buf_limit: i32) -> i64 {
+    let values = imports::get_header_values(kind as u32, unsafe { bytes(name, len) });
+    unsafe { copy_multi_to_buf(&values, buf, buf_limit) }
+}
+
+pub(crate) unsafe fn set_header_value(kind: i32, name: *const u8, name_len: i32, value: *const u8, value_len: i32) {
+    imports::set_header_value(kind as u32, unsafe { bytes(name, name_len) }, unsafe { bytes(value, value_len) });
+}
+
+pub(crate) unsafe fn add_header_value(kind: i32, name: *const u8, name_len: i32, value: *const u8, value_len: i32) {
+    imports::add_header_value(kind as u32, unsafe { bytes(name, name_len) }, unsafe { bytes(value, value_len) });
+}
+
+pub(crate) unsafe fn remove_header(kind: i32, name: *const u8, len: i32) {
+    imports::remove_header(kind as u32, unsafe { bytes(name, len) });
+}
+
+/// Returns `eof << 32 | len`; the host returns at most `buf_limit` bytes.
+pub(crate) unsafe fn read_body(kind: i32, buf: *mut u8, buf_limit: i32) -> i64 {
+    let (data, eof) = imports::read_body(kind as u32, buf_limit as u32);
+    let len = data.len().min(buf_limit as usize);
+    unsafe { ptr::copy_nonoverlapping(data.as_ptr(), buf, len) };
+    i64::from(eof) << 32 | len as i64
+}
+
+pub(crate) unsafe fn write_body(kind: i32, body: *const u8, len: i32) {
+    imports::write_body(kind as u32, unsafe { bytes(body, len) });
+}
+
+pub(crate) unsafe fn get_status_code() -> i32 {
+    imports::get_status_code() as i32
+}
+
+pub(crate) unsafe fn set_status_code(code: i32) {
+    imports::set_status_code(code as u32);
+}
+
+// -------------------------------------------------------------------------
+// WASI 0.2 clocks and random, adapted to the preview1 signatures
+// -------------------------------------------------------------------------
+
+/// Supports the realtime (0) and monotonic (1) clocks; other ids return EINVAL (28).
+pub(crate) unsafe fn clock_time_get(id: u32, _precision: u64, time: *mut u64) -> u16 {
+    let nanos = match id {
+        0 => wasi::wall_clock_nanos(),
+        1 => wasi::monotonic_clock_nanos(),
+        _ => return 28,
+    };
+    unsafe { *time = nanos };
+    0
+}
+
+pub(crate) unsafe fn random_get(buf: *mut u8, len: u32) -> u16 {
+    let bytes = wasi::random_bytes(u64::from(len));
+    let len = bytes.len().min(len as usize);
+    unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), buf, len) };
+    0
+}
@@ -0,0 +1,47 @@
+// WASI 0.2 clocks and random, lowered by hand.
+//
+// The standard library imports these interfaces itself, so the component type it
+// embeds already describes them.
+
+use super::{ListRet, take_list};
+
+/// Return area of a `wasi:clocks/wall-clock.datetime` result.
+#[repr(C)]
+struct DatetimeRet {
+    seconds: u64,
+    nanoseconds: u32,
+}
+
+#[rustfmt::skip]
+#[link(wasm_import_module = "wasi:clocks/wall-clock@0.2.0")]
+unsafe extern "C" {
+    #[link_name = "now"] fn wall_clock_now(ret: *mut DatetimeRet);
+}
+
+// Both clocks name their import `now`; the import modules keep them apart.
+#[allow(clashing_extern_declarations)]
+#[rustfmt::skip]
+#[link(wasm_import_module = "wasi:clocks/monotonic-clock@0.2.0")]
+unsafe extern "C" {
+    #[link_name = "now"] fn monotonic_clock_now() -> u64;
+}
+
+#[rustfmt::skip]
+#[link(wasm_import_module = "wasi:random/random@0.2.0")]
+unsafe extern "C" {
+    #[link_name = "get-random-bytes"] fn get_random_bytes(len: u64, ret: *mut ListRet);
+}
+
+pub(super) fn wall_clock_nanos() -> u64 {
+    let mut now = DatetimeRet { seconds: 0, nanoseconds: 0 };
+    unsafe { wall_clock_now(&mut now) };
+    now.seconds.saturating_mul(1_000_000_000).saturating_add(u64::from(now.nanoseconds))
+}
+
+pub(super) fn monotonic_clock_nanos() -> u64 {
+    unsafe { monotonic_clock_now() }
+}
+
+pub(super) fn random_bytes(len: u64) -> Vec<u8> {
+    take_list(|ret| unsafe { get_random_bytes(len, ret) })
+}
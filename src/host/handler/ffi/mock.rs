@@ -3,7 +3,7 @@
 // =============================================================================
 
 // Re-export mock functions with the same names as the extern declarations
-use std::{ptr, slice::from_raw_parts};
+use std::{cell::Cell, ptr, slice::from_raw_parts};
 
 use crate::host::handler::test;
 
@@ -87,8 +87,13 @@ pub(crate) unsafe fn remove_header(_kind: i32, _name: *const u8, _len: i32) {
 }
 
 /// Returns header names: X-FOO, x-bar, x-baz
+/// For kind=98, returns X-Dup, x-dup, Other, X-Dup.
 /// Return value: count in upper 32 bits, length in lower 32 bits
-pub(crate) unsafe fn get_header_names(_kind: i32, buf: *mut u8, buf_limit: i32) -> i64 {
+pub(crate) unsafe fn get_header_names(kind: i32, buf: *mut u8, buf_limit: i32) -> i64 {
+    if kind == test::kinds::DUPLICATE_HEADER_NAMES {
+        let len = copy_to_buf(b"X-Dup\0x-dup\0Other\0X-Dup\0", buf, buf_limit);
+        return (4i64 << 32) | (len as i64);
+    }
     let data = b"X-FOO\0x-bar\0x-baz\0";
     let len = copy_to_buf(data, buf, buf_limit);
     (3i64 << 32) | (len as i64)
@@ -97,11 +102,20 @@ pub(crate) unsafe fn get_header_names(_kind: i32, buf: *mut u8, buf_limit: i32)
 /// Returns header values based on name:
 /// - X-FOO: ["test1"]
 /// - x-bar: ["test2", "test3"]
-/// - x-baz: ["test4"]
+/// - x-baz: ["test4", "test4"]
+///
+/// For kind=98, X-Dup: ["a", "b"] and Other: ["c"], matched case-insensitively.
 ///
 /// Return value: count in upper 32 bits, length in lower 32 bits
-pub(crate) unsafe fn get_header_values(_kind: i32, name: *const u8, name_len: i32, buf: *mut u8, buf_limit: i32) -> i64 {
+pub(crate) unsafe fn get_header_values(kind: i32, name: *const u8, name_len: i32, buf: *mut u8, buf_limit: i32) -> i64 {
     let name = unsafe { from_raw_parts(name, name_len as usize) };
+    if kind == test::kinds::DUPLICATE_HEADER_NAMES {
+        return match name.to_ascii_lowercase().as_slice() {
+            b"x-dup" => (2i64 << 32) | copy_to_buf(b"a\0b\0", buf, buf_limit) as i64,
+            b"other" => (1i64 << 32) | copy_to_buf(b"c\0", buf, buf_limit) as i64,
+            _ => 0i64,
+        };
+    }
 
     match name {
         b"X-FOO" => (1i64 << 32) | copy_to_buf(b"test1\0", buf, buf_limit) as i64,
@@ -117,6 +131,7 @@ pub(crate) unsafe fn get_header_values(_kind: i32, name: *const u8, name_len: i3
 
 /// Returns mock body content with EOF flag set.
 /// For kind=99, returns a full buffer of data without EOF to simulate an oversized body.
+/// For kind=97, returns a JSON object.
 /// Return value: EOF (1) in upper 32 bits, length in lower 32 bits
 #[allow(clippy::identity_op, clippy::eq_op, reason = "clarity, both upper and lower i32 are relevant")]
 pub(crate) unsafe fn read_body(kind: i32, buf: *mut u8, buf_limit: i32) -> i64 {
@@ -128,6 +143,10 @@ pub(crate) unsafe fn read_body(kind: i32, buf: *mut u8, buf_limit: i32) -> i64 {
             let len = copy_to_buf(&data, buf, buf_limit);
             (0i64 << 32) | len as i64
         }
+        test::kinds::JSON_BODY => {
+            let len = copy_to_buf(br#"{"name":"test","count":1}"#, buf, buf_limit);
+            (1i64 << 32) | (len as i64)
+        }
         0 => (1i64 << 32) | 0,
         _ => {
             let len = copy_to_buf(b"<html><body>test</body>", buf, buf_limit);
@@ -136,8 +155,15 @@ pub(crate) unsafe fn read_body(kind: i32, buf: *mut u8, buf_limit: i32) -> i64 {
     }
 }
 
-pub(crate) unsafe fn write_body(_kind: i32, _body: *const u8, _len: i32) {
-    // No-op in tests
+thread_local! {
+    /// Bodies written per kind, see `test::take_written_body`.
+    pub(crate) static WRITTEN_BODY: std::cell::RefCell<Vec<(i32, Vec<u8>)>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Records the written bytes; reading the body is not affected.
+pub(crate) unsafe fn write_body(kind: i32, body: *const u8, len: i32) {
+    let body = unsafe { from_raw_parts(body, len as usize) };
+    WRITTEN_BODY.with_borrow_mut(|written| written.push((kind, body.to_vec())));
 }
 
 // -------------------------------------------------------------------------
@@ -156,8 +182,14 @@ pub(crate) unsafe fn set_status_code(_code: i32) {
 // Features
 // -------------------------------------------------------------------------
 
-pub(crate) unsafe fn enable_features(_feature: i32) -> i32 {
-    0 // Success
+thread_local! {
+    /// Features the mock host grants, see `test::set_feature_grant`; none by default.
+    pub(crate) static FEATURE_GRANT: Cell<i32> = const { Cell::new(0) };
+}
+
+/// Returns the requested features the host grants.
+pub(crate) unsafe fn enable_features(feature: i32) -> i32 {
+    feature & FEATURE_GRANT.get()
 }
 
 // -------------------------------------------------------------------------
@@ -167,3 +199,53 @@ pub(crate) unsafe fn enable_features(_feature: i32) -> i32 {
 pub(crate) unsafe fn get_source_addr(buf: *mut u8, buf_limit: i32) -> i32 {
     copy_to_buf(b"192.168.1.1", buf, buf_limit)
 }
+
+// -------------------------------------------------------------------------
+// WASI Clock
+// -------------------------------------------------------------------------
+
+thread_local! {
+    /// Current mock time in nanoseconds, shared by all clocks.
+    pub(crate) static CLOCK_NANOS: Cell<u64> = const { Cell::new(test::CLOCK_START_NANOS) };
+}
+
+/// Returns the mock time for the realtime (0) and monotonic (1) clocks.
+/// Other clock ids return errno 28 (EINVAL).
+pub(crate) unsafe fn clock_time_get(id: u32, _precision: u64, time: *mut u64) -> u16 {
+    match id {
+        0 | 1 => {
+            unsafe { *time = CLOCK_NANOS.get() };
+            0
+        }
+        _ => 28,
+    }
+}
+
+// -------------------------------------------------------------------------
+// WASI Random
+// -------------------------------------------------------------------------
+
+thread_local! {
+    /// splitmix64 state of the mock random source.
+    static RANDOM_STATE: Cell<u64> = const { Cell::new(0) };
+    /// Whether `random_get` fails, see `test::set_random_unavailable`.
+    pub(crate) static RANDOM_UNAVAILABLE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Fills the buffer with a reproducible splitmix64 sequence per test thread.
+pub(crate) unsafe fn random_get(buf: *mut u8, len: u32) -> u16 {
+    if RANDOM_UNAVAILABLE.get() {
+        return 52; // ENOSYS
+    }
+    let buf = unsafe { std::slice::from_raw_parts_mut(buf, len as usize) };
+    for chunk in buf.chunks_mut(8) {
+        let state = RANDOM_STATE.get().wrapping_add(0x9e3779b97f4a7c15);
+        RANDOM_STATE.set(state);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+    }
+    0
+}
@@ -1,13 +1,15 @@
 //! FFI declarations for http-wasm host functions.
 //!
 //! This module provides the interface to the WebAssembly host runtime.
-//! In production (WASM target), these are external functions provided by the host.
+//! In production (WASM target), these are external functions provided by the host,
+//! either as core-module imports or, with the `wasip2` feature, as the component
+//! imports of `wit/http-handler.wit`.
 //! In tests, these are mock implementations that simulate host behavior.
 
 // =============================================================================
 // Production FFI - External host functions (WASM target)
 // =============================================================================
-#[cfg(not(test))]
+#[cfg(not(any(test, feature = "wasip2")))]
 #[rustfmt::skip]
 #[link(wasm_import_module = "http_handler")]
 unsafe extern "C" {
@@ -32,6 +34,23 @@ unsafe extern "C" {
     pub(crate) unsafe fn get_source_addr(buf: *mut u8, buf_limit: i32) -> i32;
 }
 
+// =============================================================================
+// Production FFI - WASI functions (wasm32-wasip1 target)
+// =============================================================================
+#[cfg(not(any(test, feature = "wasip2")))]
+#[rustfmt::skip]
+#[link(wasm_import_module = "wasi_snapshot_preview1")]
+unsafe extern "C" {
+    pub(crate) unsafe fn clock_time_get(id: u32, precision: u64, time: *mut u64) -> u16;
+    pub(crate) unsafe fn random_get(buf: *mut u8, len: u32) -> u16;
+}
+
+#[cfg(all(not(test), feature = "wasip2"))]
+mod component;
+
+#[cfg(all(not(test), feature = "wasip2"))]
+pub(crate) use component::*;
+
 // =============================================================================
 // Test FFI - Mock implementations
 // =============================================================================
@@ -1,4 +1,13 @@
-use crate::memory;
+use std::{
+    cell::{Cell, RefCell},
+    ops::ControlFlow,
+    thread::LocalKey,
+};
+
+use crate::{
+    host::audit::{self, Mutation},
+    memory,
+};
 
 mod ffi;
 
@@ -7,6 +16,15 @@ pub(crate) mod test;
 
 const MAX_ALLOC_SIZE: usize = 0xFFFFFF; // 16 MB
 
+thread_local! {
+    /// Incremented on every header mutation, see `Header::snapshot`.
+    static HEADER_GENERATION: Cell<u64> = const { Cell::new(0) };
+    /// Reused by `with_header_values` and for the values of `header_for_each`.
+    static SCRATCH: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    /// Reused for the names of `header_for_each`.
+    static NAME_SCRATCH: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
 pub(crate) fn log(level: i32, message: &[u8]) {
     unsafe { ffi::log(level, message.as_ptr(), as_i32(message.len())) };
 }
@@ -27,22 +45,99 @@ pub(crate) fn header_values(kind: i32, name: &[u8]) -> Vec<Box<[u8]>> {
     read_buf_multi(|buf, limit| unsafe { ffi::get_header_values(kind, name.as_ptr(), as_i32(name.len()), buf, limit) })
 }
 
+/// Number of values of a header, without copying them.
+pub(crate) fn header_value_count(kind: i32, name: &[u8]) -> usize {
+    let dangling = std::ptr::NonNull::<u8>::dangling().as_ptr();
+    split_i64(unsafe { ffi::get_header_values(kind, name.as_ptr(), as_i32(name.len()), dangling, 0) }).0
+}
+
+/// Returns `true` if `f` returns `true` for any value of a header, checking the values
+/// in the shared buffer without allocating unless they exceed it.
+pub(crate) fn header_values_any(kind: i32, name: &[u8], mut f: impl FnMut(&[u8]) -> bool) -> bool {
+    let fetch = |buf: *mut u8, limit: i32| unsafe { ffi::get_header_values(kind, name.as_ptr(), as_i32(name.len()), buf, limit) };
+    let found = memory::with_buffer(|buffer| {
+        let (_, len) = split_i64(fetch(buffer.as_mut_ptr(), as_i32(buffer.capacity())));
+        let values = buffer.as_slice().get(..len)?;
+        Some(nul_terminated(values).any(&mut f))
+    });
+    // Values exceeding the shared buffer are read like `header_values`.
+    found.unwrap_or_else(|| read_buf_multi(fetch).iter().any(|value| f(value)))
+}
+
+/// Call `f` with the values of a header borrowed from a reused scratch buffer.
+///
+/// The scratch buffer is not the shared buffer, so `f` may call the host; nested calls
+/// read into a fresh buffer.
+pub(crate) fn with_header_values<R>(kind: i32, name: &[u8], f: impl FnOnce(&mut dyn Iterator<Item = &[u8]>) -> R) -> R {
+    with_scratch(&SCRATCH, |buf| {
+        let len =
+            read_into(buf, |ptr, limit| unsafe { ffi::get_header_values(kind, name.as_ptr(), as_i32(name.len()), ptr, limit) });
+        f(&mut nul_terminated(buf.get(..len).unwrap_or_default()))
+    })
+}
+
+/// Call `f` with `scratch`, or with a fresh buffer while an outer call uses it.
+fn with_scratch<R>(scratch: &'static LocalKey<RefCell<Vec<u8>>>, f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+    scratch.with(|cell| match cell.try_borrow_mut() {
+        Ok(mut buf) => f(&mut buf),
+        Err(_) => f(&mut Vec::new()),
+    })
+}
+
 pub(crate) fn header_names(kind: i32) -> Vec<Box<[u8]>> {
     read_buf_multi(|buf, limit| unsafe { ffi::get_header_names(kind, buf, limit) })
 }
 
+/// Call `f` for every header value with borrowed slices.
+///
+/// Names and values are read into the reused scratch buffers rather than the shared
+/// buffer, so `f` may call the host itself.
+pub(crate) fn header_for_each(kind: i32, mut f: impl FnMut(&[u8], &[u8])) {
+    with_scratch(&NAME_SCRATCH, |names| {
+        let len = read_into(names, |buf, limit| unsafe { ffi::get_header_names(kind, buf, limit) });
+        let names = names.get(..len).unwrap_or_default();
+        let mut offset = 0;
+        for name in nul_terminated(names) {
+            let earlier = names.get(..offset).unwrap_or_default();
+            offset += name.len() + 1;
+            // Names reported more than once are visited once, see `Header::entries_iter`.
+            if nul_terminated(earlier).any(|n| n.eq_ignore_ascii_case(name)) {
+                continue;
+            }
+            with_scratch(&SCRATCH, |values| {
+                let len = read_into(values, |buf, limit| unsafe {
+                    ffi::get_header_values(kind, name.as_ptr(), as_i32(name.len()), buf, limit)
+                });
+                for value in nul_terminated(values.get(..len).unwrap_or_default()) {
+                    f(name, value);
+                }
+            });
+        }
+    });
+}
+
 pub(crate) fn remove_header(kind: i32, name: &[u8]) {
+    HEADER_GENERATION.with(|generation| generation.set(generation.get() + 1));
+    audit::record(|| Mutation::RemoveHeader { kind, name: name.to_vec() });
     unsafe { ffi::remove_header(kind, name.as_ptr(), as_i32(name.len())) }
 }
 
 pub(crate) fn set_header(kind: i32, name: &[u8], value: &[u8]) {
+    HEADER_GENERATION.with(|generation| generation.set(generation.get() + 1));
+    audit::record(|| Mutation::SetHeader { kind, name: name.to_vec(), value: value.to_vec() });
     unsafe { ffi::set_header_value(kind, name.as_ptr(), as_i32(name.len()), value.as_ptr(), as_i32(value.len())) };
 }
 
 pub(crate) fn add_header_value(kind: i32, name: &[u8], value: &[u8]) {
+    HEADER_GENERATION.with(|generation| generation.set(generation.get() + 1));
+    audit::record(|| Mutation::AddHeader { kind, name: name.to_vec(), value: value.to_vec() });
     unsafe { ffi::add_header_value(kind, name.as_ptr(), as_i32(name.len()), value.as_ptr(), as_i32(value.len())) };
 }
 
+pub(crate) fn header_generation() -> u64 {
+    HEADER_GENERATION.with(Cell::get)
+}
+
 pub(crate) fn source_addr() -> Box<[u8]> {
     read_buf(|buf, limit| unsafe { ffi::get_source_addr(buf, limit) })
 }
@@ -52,10 +147,12 @@ pub(crate) fn method() -> Box<[u8]> {
 }
 
 pub(crate) fn set_method(method: &[u8]) {
+    audit::record(|| Mutation::SetMethod(method.to_vec()));
     unsafe { ffi::set_method(method.as_ptr(), as_i32(method.len())) };
 }
 
 pub(crate) fn set_uri(uri: &[u8]) {
+    audit::record(|| Mutation::SetUri(uri.to_vec()));
     unsafe { ffi::set_uri(uri.as_ptr(), as_i32(uri.len())) };
 }
 
@@ -72,6 +169,7 @@ pub(crate) fn status_code() -> i32 {
 }
 
 pub(crate) fn set_status_code(code: i32) {
+    audit::record(|| Mutation::SetStatus(code));
     unsafe { ffi::set_status_code(code) }
 }
 
@@ -91,12 +189,44 @@ pub(crate) fn body(kind: i32) -> Box<[u8]> {
     out.into_boxed_slice()
 }
 
+/// Reads the body chunk by chunk until EOF or until `f` breaks.
+///
+/// Each chunk is copied out of the shared buffer before `f` runs, so `f` may call
+/// other host functions.
+pub(crate) fn body_chunks(kind: i32, mut f: impl FnMut(&[u8]) -> ControlFlow<()>) {
+    loop {
+        let (eof, chunk) = memory::with_buffer(|buffer| {
+            let (eof, size) = eof_size(unsafe { ffi::read_body(kind, buffer.as_mut_ptr(), as_i32(buffer.capacity())) });
+            debug_assert!(size <= buffer.capacity(), "host returned size {size} exceeds buffer capacity {}", buffer.capacity());
+            (eof || size == 0, buffer.to_boxed_slice(size))
+        });
+        if (!chunk.is_empty() && f(&chunk).is_break()) || eof {
+            break;
+        }
+    }
+}
+
 pub(crate) fn write_body(kind: i32, body: &[u8]) {
+    audit::record(|| Mutation::WriteBody { kind, len: body.len() });
     unsafe {
         ffi::write_body(kind, body.as_ptr(), as_i32(body.len()));
     }
 }
 
+/// Reads the WASI clock with the given id in nanoseconds; returns 0 if the clock is unavailable.
+pub(crate) fn clock_time(id: u32) -> u64 {
+    let mut time: u64 = 0;
+    match unsafe { ffi::clock_time_get(id, 1, &mut time) } {
+        0 => time,
+        _ => 0,
+    }
+}
+
+/// Fills `buf` from the WASI random source; returns `false` if the host failed.
+pub(crate) fn random(buf: &mut [u8]) -> bool {
+    unsafe { ffi::random_get(buf.as_mut_ptr(), buf.len() as u32) == 0 }
+}
+
 /// Calls an FFI function that writes into a buffer and returns the actual size.
 /// If the data exceeds the shared buffer, a larger allocation is made and the call is retried.
 fn read_buf(f: impl Fn(*mut u8, i32) -> i32) -> Box<[u8]> {
@@ -131,6 +261,26 @@ fn read_buf_multi(f: impl Fn(*mut u8, i32) -> i64) -> Vec<Box<[u8]>> {
     })
 }
 
+/// Calls an FFI function returning NUL-terminated parts into `buf`, growing it if the
+/// host needs more space, and returns the length written.
+fn read_into(buf: &mut Vec<u8>, f: impl Fn(*mut u8, i32) -> i64) -> usize {
+    if buf.is_empty() {
+        buf.resize(1024, 0);
+    }
+    let (_, len) = split_i64(f(buf.as_mut_ptr(), as_i32(buf.len())));
+    if len <= buf.len() {
+        return len;
+    }
+    debug_assert!(len <= MAX_ALLOC_SIZE, "host response too large: {len} bytes (max {})", MAX_ALLOC_SIZE);
+    buf.resize(len.min(MAX_ALLOC_SIZE), 0);
+    split_i64(f(buf.as_mut_ptr(), as_i32(buf.len()))).1.min(buf.len())
+}
+
+/// Iterates over the NUL-terminated parts of a buffer.
+fn nul_terminated(buf: &[u8]) -> impl Iterator<Item = &[u8]> {
+    buf.strip_suffix(b"\0").map(|parts| parts.split(|b| *b == 0)).into_iter().flatten()
+}
+
 /// splits a buffer with NUL-terminated parts into a Vec
 fn split(buf: &[u8], count: usize, len: usize) -> Vec<Box<[u8]>> {
     let mut out = Vec::with_capacity(count);
@@ -318,6 +468,43 @@ mod tests {
         assert!(content.len() >= MAX_ALLOC_SIZE);
     }
 
+    #[test]
+    fn test_random() {
+        let (mut a, mut b) = ([0u8; 13], [0u8; 13]);
+        assert!(random(&mut a));
+        assert!(random(&mut b));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_clock_time() {
+        assert_eq!(clock_time(0), test::CLOCK_START_NANOS);
+        assert_eq!(clock_time(1), test::CLOCK_START_NANOS);
+        assert_eq!(clock_time(42), 0);
+    }
+
+    #[test]
+    fn test_body_chunks_until_break() {
+        // OVERSIZED_BODY returns full buffer chunks without EOF
+        let mut chunks = 0;
+        body_chunks(test::kinds::OVERSIZED_BODY, |chunk| {
+            assert!(!chunk.is_empty(), "chunks are never empty");
+            chunks += 1;
+            if chunks == 3 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+        assert_eq!(chunks, 3);
+    }
+
+    #[test]
+    fn test_body_chunks_until_eof() {
+        let mut out = Vec::new();
+        body_chunks(1, |chunk| {
+            out.extend_from_slice(chunk);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(out, b"<html><body>test</body>");
+    }
+
     #[test]
     fn test_body_no_eof_empty_buf() {
         // EMPTY_BODY_WITHOUT_EOF returns full buffer chunks without EOF
@@ -2,9 +2,46 @@
 
 #[cfg(test)]
 pub(crate) mod kinds {
+    /// Mock kind: Returns a JSON object body
+    pub(crate) const JSON_BODY: i32 = 97;
     /// Mock kind: Returns empty buffers indefinitely without EOF
     pub(crate) const EMPTY_BODY_WITHOUT_EOF: i32 = 98;
+    /// Mock kind: Reports the header names X-Dup, x-dup, Other, X-Dup; the values are
+    /// looked up case-insensitively: X-Dup: ["a", "b"], Other: ["c"]
+    pub(crate) const DUPLICATE_HEADER_NAMES: i32 = 98;
     /// Mock kind: Returns full buffers indefinitely without EOF
     /// Used to test MAX_BODY_SIZE enforcement
     pub(crate) const OVERSIZED_BODY: i32 = 99;
 }
+
+/// Initial value of the mock clock: 2023-11-14T22:13:20Z
+#[cfg(test)]
+pub(crate) const CLOCK_START_NANOS: u64 = 1_700_000_000_000_000_000;
+
+/// Set the mock clock of the current test thread to `millis` since the unix epoch.
+#[cfg(test)]
+pub(crate) fn set_clock_millis(millis: u64) {
+    super::ffi::CLOCK_NANOS.set(millis * 1_000_000);
+}
+
+/// Set the features the mock host of the current test thread grants.
+#[cfg(test)]
+pub(crate) fn set_feature_grant(features: i32) {
+    super::ffi::FEATURE_GRANT.set(features);
+}
+
+/// Make `random_get` of the mock host fail on the current test thread.
+#[cfg(test)]
+pub(crate) fn set_random_unavailable(unavailable: bool) {
+    super::ffi::RANDOM_UNAVAILABLE.set(unavailable);
+}
+
+/// Take the bytes written to the body of `kind` on the current test thread.
+#[cfg(test)]
+pub(crate) fn take_written_body(kind: i32) -> Vec<u8> {
+    super::ffi::WRITTEN_BODY.with_borrow_mut(|written| {
+        let (taken, kept) = std::mem::take(written).into_iter().partition::<Vec<_>, _>(|(k, _)| *k == kind);
+        *written = kept;
+        taken.into_iter().flat_map(|(_, body)| body).collect()
+    })
+}
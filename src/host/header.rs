@@ -1,12 +1,39 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, error::Error, fmt::Display, rc::Rc};
 
-use crate::host::{Bytes, handler};
+use crate::{
+    headers::list_contains,
+    host::{Bytes, BytesRef, HeaderMap, handler},
+};
 
 /// Handle for accessing and mutating HTTP headers.
 ///
 /// A `Header` is scoped to either the request or response, depending on how it
 /// is constructed.
-pub struct Header(i32);
+pub struct Header {
+    kind: i32,
+    /// Cached snapshot and the header generation it was taken at.
+    snapshot: RefCell<Option<(u64, Rc<HeaderMap>)>>,
+}
+
+/// Error returned by [`Header::try_set`] and [`Header::try_add`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderError {
+    /// The name is empty or contains CR, LF or NUL.
+    InvalidName,
+    /// The value contains CR, LF or NUL.
+    InvalidValue,
+}
+
+impl Display for HeaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderError::InvalidName => write!(f, "invalid header name"),
+            HeaderError::InvalidValue => write!(f, "invalid header value"),
+        }
+    }
+}
+
+impl Error for HeaderError {}
 
 impl Header {
     /// Create a header handle for a specific host-defined kind.
@@ -14,23 +41,25 @@ impl Header {
     /// The `kind` value is used by the host API to distinguish between
     /// request and response headers.
     pub(crate) fn new(kind: i32) -> Self {
-        Self(kind)
+        Self { kind, snapshot: RefCell::new(None) }
     }
 
     /// Returns an iterator over all header names as raw bytes without allocating into a vector.
     ///
-    /// Header names are returned in the order provided by the host runtime.
-    /// This method is zero-allocation and returns an iterator that yields each
+    /// Header names are returned in the order provided by the host runtime, as
+    /// reported: a host may list the same name more than once, possibly in different
+    /// case. This method is zero-allocation and returns an iterator that yields each
     /// header name as `Bytes`. For heap-allocated results, use [`names`](Header::names).
     pub fn names_iter(&self) -> impl Iterator<Item = Bytes> + use<'_> {
-        handler::header_names(self.0).into_iter().map(Bytes::from)
+        handler::header_names(self.kind).into_iter().map(Bytes::from)
     }
 
     /// Returns all header names as raw bytes, allocating into a vector.
     ///
-    /// Header names are returned in the order provided by the host runtime.
-    /// This method collects results into a `Vec`, which allocates heap memory.
-    /// Use [`names_iter`](Header::names_iter) for zero-allocation access.
+    /// Header names are returned in the order provided by the host runtime, including
+    /// repeated names as for [`names_iter`](Header::names_iter). This method collects
+    /// results into a `Vec`, which allocates heap memory. Use
+    /// [`names_iter`](Header::names_iter) for zero-allocation access.
     pub fn names(&self) -> Vec<Bytes> {
         self.names_iter().collect()
     }
@@ -42,7 +71,7 @@ impl Header {
     /// an iterator that yields each header value as `Bytes`. For heap-allocated results,
     /// use [`values`](Header::values).
     pub fn values_iter(&self, name: &[u8]) -> impl Iterator<Item = Bytes> + use<'_> {
-        handler::header_values(self.0, name).into_iter().map(Bytes::from)
+        handler::header_values(self.kind, name).into_iter().map(Bytes::from)
     }
 
     /// Return the first value for the given header name, if present.
@@ -60,48 +89,199 @@ impl Header {
         self.values_iter(name).collect()
     }
 
+    /// Call `f` with the first value of a header, borrowed for the duration of the call.
+    ///
+    /// Unlike [`get`](Header::get) no `Bytes` is allocated for the value; use it to
+    /// inspect a value without keeping it. `f` may use the host API, including this header.
+    ///
+    /// ```no_run
+    /// use http_wasm_guest::host::Request;
+    ///
+    /// fn is_json(request: &Request) -> bool {
+    ///     request.header.with_value(b"Content-Type", |value| value.is_some_and(|v| v.starts_with(b"application/json")))
+    /// }
+    /// ```
+    pub fn with_value<R>(&self, name: &[u8], f: impl FnOnce(Option<BytesRef<'_>>) -> R) -> R {
+        handler::with_header_values(self.kind, name, |values| f(values.next().map(BytesRef::Borrowed)))
+    }
+
+    /// Call `f` with all values of a header, borrowed for the duration of the call.
+    ///
+    /// The values are empty if the header is not present.
+    pub fn with_values<R>(&self, name: &[u8], f: impl FnOnce(&[BytesRef<'_>]) -> R) -> R {
+        handler::with_header_values(self.kind, name, |values| f(&values.map(BytesRef::Borrowed).collect::<Vec<_>>()))
+    }
+
+    /// Returns `true` if the header is present, without copying its values.
+    ///
+    /// The `name` is matched by the host according to its header normalization
+    /// rules (often case-insensitive).
+    pub fn contains(&self, name: &[u8]) -> bool {
+        handler::header_value_count(self.kind, name) > 0
+    }
+
+    /// Returns `true` if any value of the header equals `value` exactly.
+    ///
+    /// The values are compared in the shared host buffer instead of being collected
+    /// into `Bytes`; use [`has_token`](Header::has_token) for comma-separated lists.
+    pub fn has_value(&self, name: &[u8], value: &[u8]) -> bool {
+        handler::header_values_any(self.kind, name, |v| v == value)
+    }
+
+    /// Returns `true` if any value of the header, read as a comma-separated list,
+    /// contains `token`, compared ASCII case-insensitively.
+    ///
+    /// Matches e.g. `upgrade` in `Connection: keep-alive, Upgrade` or `gzip` in
+    /// `Accept-Encoding: br, gzip`. Parameters are part of the list members, so `gzip`
+    /// does not match `gzip;q=0.5`.
+    pub fn has_token(&self, name: &[u8], token: &[u8]) -> bool {
+        handler::header_values_any(self.kind, name, |value| list_contains(value, token))
+    }
+
     /// Set a header value, replacing any existing values.
     pub fn set(&self, name: &[u8], value: &[u8]) {
-        handler::set_header(self.0, name, value);
+        handler::set_header(self.kind, name, value);
     }
 
     /// Add an additional value for a header name.
     pub fn add(&self, name: &[u8], value: &[u8]) {
-        handler::add_header_value(self.0, name, value);
+        handler::add_header_value(self.kind, name, value);
+    }
+
+    /// Set a header value like [`set`](Header::set), after validating name and value.
+    ///
+    /// Use this instead of `set` for values derived from user input: CR, LF and NUL
+    /// bytes would allow response splitting or header injection if the host passed
+    /// them on. Nothing is changed if validation fails.
+    pub fn try_set(&self, name: &[u8], value: &[u8]) -> Result<(), HeaderError> {
+        validate(name, value)?;
+        self.set(name, value);
+        Ok(())
+    }
+
+    /// Add a header value like [`add`](Header::add), after validating name and value
+    /// as in [`try_set`](Header::try_set).
+    pub fn try_add(&self, name: &[u8], value: &[u8]) -> Result<(), HeaderError> {
+        validate(name, value)?;
+        self.add(name, value);
+        Ok(())
     }
 
     /// Remove a header and all of its values.
     pub fn remove(&self, name: &[u8]) {
-        handler::remove_header(self.0, name);
+        handler::remove_header(self.kind, name);
+    }
+
+    /// Call `f` with every header name and value, in the order provided by the host.
+    ///
+    /// Names and values are borrowed from two buffers that are reused for all headers
+    /// instead of being copied into `Bytes` one by one, for hot paths that only scan
+    /// the headers. A name with several values is passed once per value; names the
+    /// host reports more than once are visited once, as in
+    /// [`entries_iter`](Header::entries_iter).
+    ///
+    /// ```no_run
+    /// use http_wasm_guest::host::Request;
+    ///
+    /// fn forwarded_header_count(request: &Request) -> usize {
+    ///     let mut count = 0;
+    ///     request.header.for_each(|name, _value| {
+    ///         if name.len() > 2 && name[..2].eq_ignore_ascii_case(b"x-") {
+    ///             count += 1;
+    ///         }
+    ///     });
+    ///     count
+    /// }
+    /// ```
+    pub fn for_each(&self, f: impl FnMut(&[u8], &[u8])) {
+        handler::header_for_each(self.kind, f);
     }
 
     /// Return all headers as an iterator of names to value lists.
     ///
     /// This returns an iterator over all header entries. Each entry contains
     /// the header name paired with a vector containing its associated values.
+    /// Names the host reports more than once, compared ASCII case-insensitively, are
+    /// merged into one entry under their first spelling; their values are queried
+    /// once, so they are neither counted twice nor dropped. Use
+    /// [`raw_entries`](Header::raw_entries) to see the names as reported.
     /// For zero-allocation access, use [`names_iter`](Header::names_iter) and
     /// [`values_iter`](Header::values_iter).
     pub fn entries_iter(&self) -> impl Iterator<Item = (Bytes, Vec<Bytes>)> + '_ {
-        self.names_iter().map(|name| {
+        let mut seen: Vec<Bytes> = Vec::new();
+        self.names_iter().filter_map(move |name| {
+            if seen.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+                return None;
+            }
+            seen.push(name.clone());
             let values: Vec<Bytes> = self.values_iter(&name).collect();
-            (name, values)
+            Some((name, values))
         })
     }
 
+    /// Return one entry per header name as reported by the host, preserving repeated
+    /// names and their order.
+    ///
+    /// The values of a name are the host's values for it, so a repeated name carries
+    /// the same values in each of its entries. Use this to diagnose how the host
+    /// reports headers; [`entries_iter`](Header::entries_iter) merges the duplicates.
+    pub fn raw_entries(&self) -> Vec<(Bytes, Vec<Bytes>)> {
+        self.names_iter().map(|name| (name.clone(), self.values_iter(&name).collect())).collect()
+    }
+
     /// Return all headers as a map of names to value lists.
     ///
     /// This collects all names and then queries each set of values, allocating
     /// into a `HashMap` and multiple `Vec`s for the values. Each header name is
     /// paired with a vector containing its associated values. Use
-    /// [`entries_iter`](Header::entries_iter) for zero-allocation access.
+    /// [`entries_iter`](Header::entries_iter) for zero-allocation access, or
+    /// [`snapshot`](Header::snapshot) to keep the order of the headers.
     pub fn entries(&self) -> HashMap<Bytes, Vec<Bytes>> {
         self.entries_iter().collect()
     }
+
+    /// Return an owned copy of all headers, fetched in one sweep over names and values.
+    ///
+    /// The snapshot is cached until a header of the request or response is changed, so
+    /// handlers that consult many headers pay for the host calls and allocations once.
+    ///
+    /// ```no_run
+    /// use http_wasm_guest::host::Request;
+    ///
+    /// fn is_json_api(request: &Request) -> bool {
+    ///     let headers = request.header.snapshot();
+    ///     headers.get(b"Accept").is_some_and(|v| v.starts_with(b"application/json")) && headers.contains(b"Authorization")
+    /// }
+    /// ```
+    pub fn snapshot(&self) -> Rc<HeaderMap> {
+        let generation = handler::header_generation();
+        let mut cached = self.snapshot.borrow_mut();
+        if let Some((taken, snapshot)) = cached.as_ref() {
+            if *taken == generation {
+                return Rc::clone(snapshot);
+            }
+        }
+        let snapshot = Rc::new(self.entries_iter().collect::<HeaderMap>());
+        *cached = Some((generation, Rc::clone(&snapshot)));
+        snapshot
+    }
+}
+
+fn validate(name: &[u8], value: &[u8]) -> Result<(), HeaderError> {
+    let forbidden = |b: &u8| matches!(b, b'\r' | b'\n' | 0);
+    if name.is_empty() || name.iter().any(forbidden) {
+        return Err(HeaderError::InvalidName);
+    }
+    if value.iter().any(forbidden) {
+        return Err(HeaderError::InvalidValue);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::host::handler::test::kinds;
 
     #[test]
     fn header_get_existing() {
@@ -112,6 +292,83 @@ mod tests {
         assert_eq!(&value.unwrap(), b"test1");
     }
 
+    #[test]
+    fn header_try_set_validates() {
+        let header = Header::new(0);
+        // Should not panic - mock accepts any header
+        assert_eq!(header.try_set(b"X-Ok", b"value\twith tab"), Ok(()));
+        assert_eq!(header.try_add(b"X-Ok", b""), Ok(()));
+        assert_eq!(header.try_set(b"X-Bad", b"a\r\nSet-Cookie: x=1"), Err(HeaderError::InvalidValue));
+        assert_eq!(header.try_add(b"X-Bad", b"a\0b"), Err(HeaderError::InvalidValue));
+        assert_eq!(header.try_set(b"X-Bad\n", b"a"), Err(HeaderError::InvalidName));
+        assert_eq!(header.try_set(b"", b"a"), Err(HeaderError::InvalidName));
+    }
+
+    #[test]
+    fn header_snapshot_is_cached_until_mutation() {
+        let header = Header::new(0);
+        let snapshot = header.snapshot();
+        assert_eq!(snapshot.get(b"x-foo"), Some(&Bytes::from("test1")));
+        assert_eq!(snapshot.values(b"x-bar").len(), 2);
+        assert!(Rc::ptr_eq(&snapshot, &header.snapshot()));
+        Header::new(1).set(b"X-Other", b"1");
+        assert!(!Rc::ptr_eq(&snapshot, &header.snapshot()));
+    }
+
+    #[test]
+    fn header_contains_and_has_value() {
+        let header = Header::new(0);
+        // The mock has "X-FOO: test1", "x-bar: test2, test3" and "x-baz: test4, test4"
+        assert!(header.contains(b"x-bar"));
+        assert!(!header.contains(b"X-NONEXISTENT"));
+        assert!(header.has_value(b"x-bar", b"test3"));
+        assert!(!header.has_value(b"x-bar", b"test"));
+        assert!(!header.has_value(b"X-NONEXISTENT", b""));
+        assert!(header.has_token(b"X-FOO", b"TEST1"));
+        assert!(!header.has_token(b"X-FOO", b"test"));
+    }
+
+    #[test]
+    fn header_for_each_borrows_values() {
+        let mut seen = Vec::new();
+        Header::new(0).for_each(|name, value| seen.push(format!("{}={}", name.escape_ascii(), value.escape_ascii())));
+        assert_eq!(seen, ["X-FOO=test1", "x-bar=test2", "x-bar=test3", "x-baz=test4", "x-baz=test4"]);
+    }
+
+    #[test]
+    fn header_for_each_may_read_headers() {
+        let header = Header::new(0);
+        let mut nested = 0;
+        header.for_each(|_, _| {
+            header.for_each(|_, _| nested += 1);
+            assert!(header.has_value(b"x-bar", b"test3"));
+        });
+        assert_eq!(nested, 25);
+    }
+
+    #[test]
+    fn header_duplicate_names_are_merged() {
+        let header = Header::new(kinds::DUPLICATE_HEADER_NAMES);
+        assert_eq!(header.names().len(), 4);
+        let entries: Vec<_> = header.entries_iter().collect();
+        assert_eq!(
+            entries,
+            [(Bytes::from("X-Dup"), vec![Bytes::from("a"), Bytes::from("b")]), (Bytes::from("Other"), vec![Bytes::from("c")])]
+        );
+        assert_eq!(header.entries().len(), 2);
+        assert_eq!(header.snapshot().values(b"x-dup").len(), 2);
+        let mut pairs = Vec::new();
+        header.for_each(|name, value| pairs.push(format!("{}={}", name.escape_ascii(), value.escape_ascii())));
+        assert_eq!(pairs, ["X-Dup=a", "X-Dup=b", "Other=c"]);
+    }
+
+    #[test]
+    fn header_raw_entries_preserve_duplicates() {
+        let raw = Header::new(kinds::DUPLICATE_HEADER_NAMES).raw_entries();
+        let names: Vec<_> = raw.iter().map(|(name, values)| format!("{name}:{}", values.len())).collect();
+        assert_eq!(names, ["X-Dup:2", "x-dup:2", "Other:1", "X-Dup:2"]);
+    }
+
     #[test]
     fn header_get_nonexistent() {
         let header = Header::new(0);
@@ -182,4 +439,24 @@ mod tests {
         let dup_values = values_map.get(&Bytes::from("x-baz")).unwrap();
         assert_eq!(dup_values.len(), 2);
     }
+
+    #[test]
+    fn borrowed_values() {
+        let header = Header::new(0);
+        assert!(header.with_value(b"X-FOO", |value| value.is_some_and(|v| v.is_borrowed() && v == "test1")));
+        assert_eq!(header.with_value(b"missing", |value| value.map(BytesRef::into_owned)), None);
+        let values = header.with_values(b"x-bar", |values| values.iter().map(ToString::to_string).collect::<Vec<_>>());
+        assert_eq!(values, ["test2", "test3"]);
+        assert!(header.with_values(b"missing", |values| values.is_empty()));
+    }
+
+    #[test]
+    fn borrowed_values_allow_nested_calls() {
+        let header = Header::new(0);
+        let nested = header.with_value(b"x-bar", |outer| {
+            let inner = header.with_value(b"X-FOO", |inner| inner.map(BytesRef::into_owned));
+            (outer.map(BytesRef::into_owned), inner, header.get(b"x-baz"))
+        });
+        assert_eq!(nested, (Some(Bytes::from("test2")), Some(Bytes::from("test1")), Some(Bytes::from("test4"))));
+    }
 }
@@ -0,0 +1,168 @@
+use crate::host::Bytes;
+
+/// Insertion-ordered multimap of header names to values.
+///
+/// Returned by [`Header::snapshot`](crate::host::Header::snapshot) as a copy of the
+/// request or response headers. Lookups compare names ASCII case-insensitively and run
+/// on guest memory, without calls to the host. Names keep the order in which they were
+/// first inserted, i.e. the order provided by the host runtime for snapshots, and
+/// values keep their order per name; duplicates of a name differing only in case are
+/// merged under the first spelling. This makes the map suitable for plugins that
+/// re-emit or sign headers and need a deterministic order, unlike
+/// [`Header::entries`](crate::host::Header::entries).
+///
+/// ```
+/// use http_wasm_guest::host::HeaderMap;
+///
+/// let mut headers = HeaderMap::new();
+/// headers.append(b"Via", b"1.1 a");
+/// headers.insert(b"Host", b"example.com");
+/// headers.append(b"via", b"1.1 b");
+/// let pairs: Vec<_> = headers.pairs().map(|(n, v)| format!("{n}: {v}")).collect();
+/// assert_eq!(pairs, ["Via: 1.1 a", "Via: 1.1 b", "Host: example.com"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(Bytes, Vec<Bytes>)>,
+}
+
+impl HeaderMap {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the first value for the given header name, if present.
+    pub fn get(&self, name: &[u8]) -> Option<&Bytes> {
+        self.values(name).first()
+    }
+
+    /// Return all values for the given header name; empty if it is not present.
+    pub fn values(&self, name: &[u8]) -> &[Bytes] {
+        self.position(name).and_then(|i| self.entries.get(i)).map_or(&[], |(_, values)| values)
+    }
+
+    /// Returns `true` if the header is present.
+    pub fn contains(&self, name: &[u8]) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// Set the value of a header, replacing existing values but keeping its position.
+    pub fn insert(&mut self, name: &[u8], value: &[u8]) {
+        match self.position(name).and_then(|i| self.entries.get_mut(i)) {
+            Some((_, values)) => *values = vec![Bytes::from(value)],
+            None => self.entries.push((Bytes::from(name), vec![Bytes::from(value)])),
+        }
+    }
+
+    /// Add a value to a header, after its existing values.
+    pub fn append(&mut self, name: &[u8], value: &[u8]) {
+        match self.position(name).and_then(|i| self.entries.get_mut(i)) {
+            Some((_, values)) => values.push(Bytes::from(value)),
+            None => self.entries.push((Bytes::from(name), vec![Bytes::from(value)])),
+        }
+    }
+
+    /// Remove a header and return its values.
+    pub fn remove(&mut self, name: &[u8]) -> Vec<Bytes> {
+        self.position(name).map(|i| self.entries.remove(i).1).unwrap_or_default()
+    }
+
+    /// Iterate over the header names and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&Bytes, &[Bytes])> {
+        self.entries.iter().map(|(name, values)| (name, values.as_slice()))
+    }
+
+    /// Iterate over all name/value pairs, one per value.
+    pub fn pairs(&self) -> impl Iterator<Item = (&Bytes, &Bytes)> {
+        self.entries.iter().flat_map(|(name, values)| values.iter().map(move |value| (name, value)))
+    }
+
+    /// Iterate over the header names.
+    pub fn names(&self) -> impl Iterator<Item = &Bytes> {
+        self.entries.iter().map(|(name, _)| name)
+    }
+
+    /// Number of distinct header names.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no headers.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn position(&self, name: &[u8]) -> Option<usize> {
+        self.entries.iter().position(|(n, _)| n.eq_ignore_ascii_case(name))
+    }
+}
+
+impl IntoIterator for HeaderMap {
+    type Item = (Bytes, Vec<Bytes>);
+    type IntoIter = std::vec::IntoIter<(Bytes, Vec<Bytes>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl FromIterator<(Bytes, Vec<Bytes>)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (Bytes, Vec<Bytes>)>>(iter: I) -> Self {
+        let mut map = HeaderMap::new();
+        for (name, values) in iter {
+            match map.position(&name).and_then(|i| map.entries.get_mut(i)) {
+                Some((_, existing)) => existing.extend(values),
+                None => map.entries.push((name, values)),
+            }
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map() -> HeaderMap {
+        [
+            (Bytes::from("Content-Type"), vec![Bytes::from("text/plain")]),
+            (Bytes::from("Via"), vec![Bytes::from("a"), Bytes::from("b")]),
+            (Bytes::from("via"), vec![Bytes::from("c")]),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn lookup_ignores_case() {
+        let map = map();
+        assert_eq!(map.get(b"content-type"), Some(&Bytes::from("text/plain")));
+        assert!(map.contains(b"VIA"));
+        assert!(!map.contains(b"Host"));
+        assert_eq!(map.values(b"Host"), &[] as &[Bytes]);
+    }
+
+    #[test]
+    fn merges_names_in_order() {
+        let map = map();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.values(b"via"), &[Bytes::from("a"), Bytes::from("b"), Bytes::from("c")]);
+        assert_eq!(map.names().collect::<Vec<_>>(), [&Bytes::from("Content-Type"), &Bytes::from("Via")]);
+        assert!(HeaderMap::new().is_empty());
+    }
+
+    #[test]
+    fn mutation_keeps_positions() {
+        let mut map = map();
+        map.append(b"X-Id", b"1");
+        map.insert(b"content-type", b"application/json");
+        map.append(b"VIA", b"d");
+        let pairs = map.pairs().map(|(n, v)| format!("{n}: {v}")).collect::<Vec<_>>();
+        assert_eq!(pairs, ["Content-Type: application/json", "Via: a", "Via: b", "Via: c", "Via: d", "X-Id: 1"]);
+        assert_eq!(map.remove(b"via").len(), 4);
+        assert_eq!(map.remove(b"via"), Vec::<Bytes>::new());
+        let names = map.into_iter().map(|(name, _)| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(names, ["Content-Type", "X-Id"]);
+    }
+}
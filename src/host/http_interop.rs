@@ -0,0 +1,192 @@
+//! Conversions between the host handles and the types of the `http` crate.
+use http::{
+    HeaderName, HeaderValue, StatusCode, Uri, Version, method::InvalidMethod, status::InvalidStatusCode, uri::InvalidUri,
+};
+
+use crate::host::{Header, HeaderMap, Method, Request, Response};
+
+impl Request {
+    /// Return the request method as [`http::Method`], including extension methods.
+    pub fn http_method(&self) -> Result<http::Method, InvalidMethod> {
+        http::Method::from_bytes(&self.method())
+    }
+
+    /// Return the request URI as [`http::Uri`].
+    pub fn http_uri(&self) -> Result<Uri, InvalidUri> {
+        Uri::try_from(&self.uri()[..])
+    }
+
+    /// Return the protocol version as [`http::Version`], or `None` if the host reports
+    /// a version the `http` crate does not know.
+    pub fn http_version(&self) -> Option<Version> {
+        match &self.version()[..] {
+            b"HTTP/0.9" => Some(Version::HTTP_09),
+            b"HTTP/1.0" => Some(Version::HTTP_10),
+            b"HTTP/1.1" => Some(Version::HTTP_11),
+            b"HTTP/2" | b"HTTP/2.0" => Some(Version::HTTP_2),
+            b"HTTP/3" | b"HTTP/3.0" => Some(Version::HTTP_3),
+            _ => None,
+        }
+    }
+
+    /// Return method, URI, version and headers as [`http::request::Parts`], to call
+    /// code written against the `http` crate.
+    ///
+    /// Unknown versions are reported as HTTP/1.1.
+    ///
+    /// ```no_run
+    /// use http_wasm_guest::host::Request;
+    ///
+    /// fn is_api_call(request: &Request) -> bool {
+    ///     request.to_http_parts().is_ok_and(|parts| parts.uri.path().starts_with("/api/"))
+    /// }
+    /// ```
+    pub fn to_http_parts(&self) -> Result<http::request::Parts, http::Error> {
+        let mut parts = http::Request::builder()
+            .method(self.http_method()?)
+            .uri(self.http_uri()?)
+            .version(self.http_version().unwrap_or(Version::HTTP_11))
+            .body(())?
+            .into_parts()
+            .0;
+        parts.headers = self.header.to_http()?;
+        Ok(parts)
+    }
+}
+
+impl Response {
+    /// Return the status code as [`http::StatusCode`].
+    pub fn http_status(&self) -> Result<StatusCode, InvalidStatusCode> {
+        StatusCode::from_u16(u16::try_from(self.status()).unwrap_or_default())
+    }
+
+    /// Set the status code from an [`http::StatusCode`].
+    pub fn set_http_status(&self, status: StatusCode) {
+        self.set_status(i32::from(status.as_u16()));
+    }
+
+    /// Return status and headers as [`http::response::Parts`].
+    pub fn to_http_parts(&self) -> Result<http::response::Parts, http::Error> {
+        let mut parts = http::Response::builder().status(self.http_status()?).body(())?.into_parts().0;
+        parts.headers = self.header.to_http()?;
+        Ok(parts)
+    }
+}
+
+impl Header {
+    /// Return all headers as an [`http::HeaderMap`], keeping the order of the values.
+    ///
+    /// Fails if the host reports a name or value the `http` crate rejects.
+    pub fn to_http(&self) -> Result<http::HeaderMap, http::Error> {
+        http::HeaderMap::try_from(self.snapshot().as_ref())
+    }
+
+    /// Replace the headers named in `headers` with their values from the map.
+    ///
+    /// Headers not contained in `headers` are left unchanged.
+    pub fn apply_http(&self, headers: &http::HeaderMap) {
+        for name in headers.keys() {
+            let mut values = headers.get_all(name).iter();
+            if let Some(first) = values.next() {
+                self.set(name.as_str().as_bytes(), first.as_bytes());
+            }
+            for value in values {
+                self.add(name.as_str().as_bytes(), value.as_bytes());
+            }
+        }
+    }
+}
+
+impl TryFrom<&HeaderMap> for http::HeaderMap {
+    type Error = http::Error;
+
+    fn try_from(map: &HeaderMap) -> Result<Self, Self::Error> {
+        let mut headers = http::HeaderMap::with_capacity(map.len());
+        for (name, value) in map.pairs() {
+            headers.append(HeaderName::from_bytes(name)?, HeaderValue::from_bytes(value)?);
+        }
+        Ok(headers)
+    }
+}
+
+impl From<&http::HeaderMap> for HeaderMap {
+    fn from(headers: &http::HeaderMap) -> Self {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(name.as_str().as_bytes(), value.as_bytes());
+        }
+        map
+    }
+}
+
+impl From<Method> for http::Method {
+    fn from(method: Method) -> Self {
+        match method {
+            Method::Get => http::Method::GET,
+            Method::Head => http::Method::HEAD,
+            Method::Post => http::Method::POST,
+            Method::Put => http::Method::PUT,
+            Method::Delete => http::Method::DELETE,
+            Method::Connect => http::Method::CONNECT,
+            Method::Options => http::Method::OPTIONS,
+            Method::Trace => http::Method::TRACE,
+            Method::Patch => http::Method::PATCH,
+        }
+    }
+}
+
+impl TryFrom<&http::Method> for Method {
+    type Error = ();
+
+    /// Fails for extension methods.
+    fn try_from(method: &http::Method) -> Result<Self, Self::Error> {
+        Method::parse(method.as_str().as_bytes()).ok_or(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::Bytes;
+
+    #[test]
+    fn request_parts() {
+        // The mock request is "GET https://test HTTP/2.0" with the headers X-FOO, x-bar and x-baz
+        let parts = Request::new().to_http_parts().unwrap();
+        assert_eq!(parts.method, http::Method::GET);
+        assert_eq!(parts.uri.host(), Some("test"));
+        assert_eq!(parts.version, Version::HTTP_2);
+        assert_eq!(parts.headers.get("x-foo").map(HeaderValue::as_bytes), Some(&b"test1"[..]));
+        assert_eq!(parts.headers.get_all("x-bar").iter().count(), 2);
+    }
+
+    #[test]
+    fn response_parts() {
+        let response = Response::new();
+        assert_eq!(response.http_status().ok(), Some(StatusCode::OK));
+        assert_eq!(response.to_http_parts().unwrap().status, StatusCode::OK);
+        response.set_http_status(StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn header_maps_round_trip() {
+        let mut map = HeaderMap::new();
+        map.append(b"Via", b"a");
+        map.append(b"Host", b"example.com");
+        map.append(b"via", b"b");
+        let headers = http::HeaderMap::try_from(&map).unwrap();
+        assert_eq!(headers.get_all("via").iter().collect::<Vec<_>>(), ["a", "b"]);
+        let back = HeaderMap::from(&headers);
+        assert_eq!(back.values(b"VIA"), [Bytes::from("a"), Bytes::from("b")]);
+        map.append(b"Bad Name", b"x");
+        assert!(http::HeaderMap::try_from(&map).is_err());
+        Header::new(1).apply_http(&headers);
+    }
+
+    #[test]
+    fn methods() {
+        assert_eq!(http::Method::from(Method::Patch), http::Method::PATCH);
+        assert_eq!(Method::try_from(&http::Method::DELETE), Ok(Method::Delete));
+        assert_eq!(Method::try_from(&http::Method::from_bytes(b"PURGE").unwrap()), Err(()));
+    }
+}
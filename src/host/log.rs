@@ -46,7 +46,61 @@
 //!     log::write(0, b"Hello from plugin!");
 //! }
 //! ```
-use crate::host::handler;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{host::handler, redact::Redactor};
+
+/// The installed redactor and whether a message is being redacted.
+struct Redaction {
+    redactor: Option<Rc<Redactor>>,
+    active: bool,
+}
+
+thread_local! {
+    static REDACTION: RefCell<Redaction> = const { RefCell::new(Redaction { redactor: None, active: false }) };
+}
+
+/// Call `f` with the redaction state; `f` must not call back into this module.
+fn with_redaction<R>(f: impl FnOnce(&mut Redaction) -> R) -> R {
+    REDACTION.with(|redaction| f(&mut redaction.borrow_mut()))
+}
+
+/// Marks a message as being redacted until dropped, also when redaction panics.
+struct Redacting;
+
+impl Redacting {
+    /// Enter redaction, or `None` if a message is already being redacted.
+    fn enter() -> Option<Self> {
+        with_redaction(|redaction| match redaction.active {
+            true => None,
+            false => {
+                redaction.active = true;
+                Some(Redacting)
+            }
+        })
+    }
+}
+
+impl Drop for Redacting {
+    fn drop(&mut self) {
+        with_redaction(|redaction| redaction.active = false);
+    }
+}
+
+/// Installs a [`Redactor`] applied to every message passed to [`write`], including
+/// those of the `HostLogger`; `None` removes it.
+///
+/// Messages are converted to UTF-8 lossily before redaction.
+///
+/// ```no_run
+/// use http_wasm_guest::{host::log, redact::Redactor};
+///
+/// log::set_redactor(Some(Redactor::default()));
+/// log::write(0, b"user jane@example.com logged in");
+/// ```
+pub fn set_redactor(redactor: Option<Redactor>) {
+    with_redaction(|redaction| redaction.redactor = redactor.map(Rc::new));
+}
 
 /// Forwards a log message to the host logger with the specified severity level.
 ///
@@ -64,7 +118,15 @@ use crate::host::handler;
 /// log::write(0, b"Hello from plugin!");
 /// ```
 pub fn write(level: i32, message: &[u8]) {
-    handler::log(level, message);
+    let Some(redactor) = with_redaction(|redaction| redaction.redactor.clone()) else {
+        return handler::log(level, message);
+    };
+    // A message written while another one is redacted, e.g. by the panic hook, is
+    // dropped rather than written unredacted.
+    let Some(_redacting) = Redacting::enter() else {
+        return;
+    };
+    handler::log(level, redactor.redact(&String::from_utf8_lossy(message)).as_bytes());
 }
 
 /// Checks if logging is enabled for the specified severity level.
@@ -89,6 +151,148 @@ pub fn enabled(level: i32) -> bool {
     handler::log_enabled(level)
 }
 
+/// Installs a panic hook that logs the panic message and location at error level.
+///
+/// A panicking guest traps, which aborts the request; without the hook the host log
+/// shows no reason. The previously installed hook runs afterwards.
+///
+/// ```no_run
+/// use http_wasm_guest::host::log;
+///
+/// log::install_panic_hook();
+/// // host log on a panic: "panicked at src/lib.rs:42:9: invalid state"
+/// ```
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = payload_message(info.payload());
+        let line = match info.location() {
+            Some(location) => format!("panicked at {}:{}:{}: {message}", location.file(), location.line(), location.column()),
+            None => format!("panicked: {message}"),
+        };
+        write(2, line.as_bytes());
+        previous(info);
+    }));
+}
+
+/// The message of a panic payload, which is a `&str` or `String` for `panic!`.
+fn payload_message(payload: &dyn std::any::Any) -> &str {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => payload.downcast_ref::<String>().map_or("Box<dyn Any>", String::as_str),
+    }
+}
+
+/// Installs the [`HostLogger`](crate::HostLogger) with the levels configured by the operator.
+///
+/// Reads the string member `logLevel` of the JSON plugin configuration in the `RUST_LOG`
+/// syntax of [`LogFilter::parse`](crate::LogFilter::parse), so debug logging can be
+/// turned on without rebuilding the plugin. Without the member, the level is `info`.
+///
+/// ```no_run
+/// // plugin configuration: {"logLevel": "warn,my_plugin::auth=debug", ...}
+/// let _ = http_wasm_guest::host::log::init_from_config();
+/// ```
+#[cfg(feature = "log")]
+pub fn init_from_config() -> Result<(), ::log::SetLoggerError> {
+    let spec = json_member(&handler::get_config(), "logLevel").unwrap_or_default();
+    crate::HostLogger::init_with_filter(crate::LogFilter::parse(&spec))
+}
+
+/// The string member `key` of a JSON object.
+#[cfg(all(feature = "log", feature = "json"))]
+fn json_member(json: &[u8], key: &str) -> Option<String> {
+    match serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(json).ok()?.remove(key)? {
+        serde_json::Value::String(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// The string member `key` of a JSON object, without parsing other members.
+///
+/// Without the `json` feature there is no JSON parser to rely on, and the logger only
+/// needs one top-level string, so a small scanner skips over everything else.
+#[cfg(all(feature = "log", not(feature = "json")))]
+fn json_member(json: &[u8], key: &str) -> Option<String> {
+    let mut depth = 0;
+    let mut expect_key = false;
+    let mut i = 0;
+    while let Some(&b) = json.get(i) {
+        match b {
+            b'{' => {
+                depth += 1;
+                expect_key = depth == 1;
+            }
+            b'[' => depth += 1,
+            b'}' | b']' => depth -= 1,
+            b',' if depth == 1 => expect_key = true,
+            b'"' => {
+                let (name, end) = json_string(json, i)?;
+                i = end;
+                if depth == 1 && expect_key {
+                    expect_key = false;
+                    let rest = json.get(i..)?.trim_ascii_start().strip_prefix(b":")?.trim_ascii_start();
+                    if name == key {
+                        return json_string(rest, 0).map(|(value, _)| value);
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Decode the JSON string starting with the quote at `start`; returns it and the index
+/// after the closing quote.
+#[cfg(all(feature = "log", not(feature = "json")))]
+fn json_string(json: &[u8], start: usize) -> Option<(String, usize)> {
+    if json.get(start) != Some(&b'"') {
+        return None;
+    }
+    let mut out = Vec::new();
+    let mut i = start + 1;
+    loop {
+        match *json.get(i)? {
+            b'"' => return Some((String::from_utf8_lossy(&out).into_owned(), i + 1)),
+            b'\\' => {
+                i += 1;
+                match *json.get(i)? {
+                    b'n' => out.push(b'\n'),
+                    b't' => out.push(b'\t'),
+                    b'r' => out.push(b'\r'),
+                    b'b' => out.push(0x08),
+                    b'f' => out.push(0x0c),
+                    b'u' => {
+                        let mut code = json_hex(json, i + 1)?;
+                        i += 4;
+                        // A high surrogate followed by an escaped low surrogate is one character.
+                        if (0xd800..0xdc00).contains(&code) && json.get(i + 1..i + 3) == Some(b"\\u") {
+                            if let Some(low @ 0xdc00..0xe000) = json_hex(json, i + 3) {
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                                i += 6;
+                            }
+                        }
+                        let c = char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER);
+                        out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                    }
+                    b => out.push(b),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+}
+
+/// The four hex digits at `start` of a `\u` escape.
+#[cfg(all(feature = "log", not(feature = "json")))]
+fn json_hex(json: &[u8], start: usize) -> Option<u32> {
+    u32::from_str_radix(std::str::from_utf8(json.get(start..start + 4)?).ok()?, 16).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,6 +301,65 @@ mod tests {
         // Should not panic - mock accepts any log call
         write(2, b"test log message");
     }
+    #[test]
+    fn redactor_installed() {
+        set_redactor(Some(Redactor::default()));
+        assert!(with_redaction(|r| r.redactor.is_some()));
+        // Should not panic - mock accepts any log call
+        write(0, b"card 4111 1111 1111 1111");
+        set_redactor(None);
+        assert!(with_redaction(|r| r.redactor.is_none()));
+    }
+    #[test]
+    fn reentrant_write_is_suppressed() {
+        set_redactor(Some(Redactor::default()));
+        let redacting = Redacting::enter();
+        assert!(redacting.is_some());
+        assert!(Redacting::enter().is_none());
+        // Dropped while the outer message is redacted
+        write(2, b"panicked in redactor");
+        drop(redacting);
+        assert!(Redacting::enter().is_some());
+        assert!(!with_redaction(|r| r.active));
+        set_redactor(None);
+    }
+    #[cfg(feature = "log")]
+    #[test]
+    fn json_member_lookup() {
+        let config = br#"{"nested": {"logLevel": "trace"}, "list": ["logLevel", "x"], "esc\"aped": "a,\"logLevel\": 1",
+            "logLevel" : "warn,plugin=debug\u00e4\n", "other": 1}"#;
+        assert_eq!(json_member(config, "logLevel").as_deref(), Some("warn,plugin=debug\u{e4}\n"));
+        assert_eq!(json_member(config, "other"), None);
+        assert_eq!(json_member(config, "missing"), None);
+        assert_eq!(json_member(br#"{"logLevel": "unterminated"#, "logLevel"), None);
+        assert_eq!(json_member(br#"{"logLevel": "\ud83d\ude00 \u00e4"}"#, "logLevel").as_deref(), Some("\u{1f600} \u{e4}"));
+        // The mock configuration is {"config": "test1"}
+        assert_eq!(json_member(&handler::get_config(), "config").as_deref(), Some("test1"));
+        let _ = init_from_config();
+    }
+
+    #[cfg(all(feature = "log", not(feature = "json")))]
+    #[test]
+    fn lone_surrogate_is_replaced() {
+        let config = br#"{"logLevel": "\ud83d \ude00\ud83dx"}"#;
+        assert_eq!(json_member(config, "logLevel").as_deref(), Some("\u{fffd} \u{fffd}\u{fffd}x"));
+    }
+
+    #[test]
+    fn panic_payload_message() {
+        assert_eq!(payload_message(&"static"), "static");
+        assert_eq!(payload_message(&String::from("formatted")), "formatted");
+        assert_eq!(payload_message(&42), "Box<dyn Any>");
+    }
+
+    #[test]
+    fn panic_hook_reports() {
+        install_panic_hook();
+        // Should not panic in the hook - mock accepts any log call
+        assert!(std::panic::catch_unwind(|| std::panic::panic_any("boom")).is_err());
+        let _ = std::panic::take_hook();
+    }
+
     #[test]
     fn log_enabled_check() {
         // The mock enables levels 0-3 (Error, Warn, Info, Debug)
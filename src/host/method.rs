@@ -0,0 +1,134 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::host::Bytes;
+
+/// Standard HTTP request methods.
+///
+/// Use [`Method::parse`] to convert the raw value returned by
+/// [`Request::method`](crate::host::Request::method).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    /// `GET`
+    Get,
+    /// `HEAD`
+    Head,
+    /// `POST`
+    Post,
+    /// `PUT`
+    Put,
+    /// `DELETE`
+    Delete,
+    /// `CONNECT`
+    Connect,
+    /// `OPTIONS`
+    Options,
+    /// `TRACE`
+    Trace,
+    /// `PATCH`
+    Patch,
+}
+
+impl Method {
+    /// Parse a method from its raw bytes.
+    ///
+    /// Methods are case-sensitive, so `get` is not recognized. Returns `None` for
+    /// extension methods.
+    pub fn parse(method: &[u8]) -> Option<Method> {
+        match method {
+            b"GET" => Some(Method::Get),
+            b"HEAD" => Some(Method::Head),
+            b"POST" => Some(Method::Post),
+            b"PUT" => Some(Method::Put),
+            b"DELETE" => Some(Method::Delete),
+            b"CONNECT" => Some(Method::Connect),
+            b"OPTIONS" => Some(Method::Options),
+            b"TRACE" => Some(Method::Trace),
+            b"PATCH" => Some(Method::Patch),
+            _ => None,
+        }
+    }
+
+    /// Return the method name as used on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Connect => "CONNECT",
+            Method::Options => "OPTIONS",
+            Method::Trace => "TRACE",
+            Method::Patch => "PATCH",
+        }
+    }
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Method {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Method::parse(s.as_bytes()).ok_or(())
+    }
+}
+
+impl PartialEq<Method> for Bytes {
+    fn eq(&self, other: &Method) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Bytes> for Method {
+    fn eq(&self, other: &Bytes) -> bool {
+        other == self.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Method; 9] = [
+        Method::Get,
+        Method::Head,
+        Method::Post,
+        Method::Put,
+        Method::Delete,
+        Method::Connect,
+        Method::Options,
+        Method::Trace,
+        Method::Patch,
+    ];
+
+    #[test]
+    fn method_roundtrip() {
+        for method in ALL {
+            assert_eq!(Method::parse(method.as_str().as_bytes()), Some(method));
+            assert_eq!(method.as_str().parse::<Method>(), Ok(method));
+        }
+    }
+
+    #[test]
+    fn method_case_sensitive() {
+        assert_eq!(Method::parse(b"get"), None);
+        assert_eq!(Method::parse(b"PURGE"), None);
+    }
+
+    #[test]
+    fn method_display() {
+        assert_eq!(format!("{}", Method::Patch), "PATCH");
+    }
+
+    #[test]
+    fn method_eq_bytes() {
+        assert_eq!(Bytes::from("GET"), Method::Get);
+        assert_eq!(Method::Post, Bytes::from("POST"));
+        assert_ne!(Bytes::from("GET"), Method::Head);
+    }
+}
@@ -1,21 +1,43 @@
 //! Host-side API surface for HTTP request/response interaction.
 //!
 //! This module provides access to request and response handles, header and body
-//! manipulation utilities, logging, and feature configuration for `http-wasm`
+//! manipulation utilities, logging, clocks, and feature configuration for `http-wasm`
 //! guest plugins.
+pub(crate) mod audit;
 mod body;
 mod bytes;
+#[cfg(feature = "bytes")]
+mod bytes_interop;
+mod bytes_ref;
+mod extensions;
 mod handler;
 mod header;
+mod header_map;
+#[cfg(feature = "http")]
+mod http_interop;
+mod method;
 mod request;
 mod response;
+#[cfg(feature = "url")]
+mod url_interop;
 
 pub mod admin;
 pub mod feature;
 pub mod log;
+pub mod random;
+pub mod time;
 
+pub use crate::codec::DecodeError;
 pub use body::Body;
 pub use bytes::Bytes;
-pub use header::Header;
+pub use bytes_ref::BytesRef;
+pub use extensions::Extensions;
+pub use header::{Header, HeaderError};
+pub use header_map::HeaderMap;
+pub use method::Method;
 pub use request::Request;
-pub use response::Response;
+pub use response::{RedirectError, Response};
+
+#[cfg(test)]
+pub(crate) use handler::test;
+pub(crate) use response::escape_html;
@@ -0,0 +1,162 @@
+//! Random numbers and identifiers for http-wasm guest plugins.
+//!
+//! This module reads the WASI random source of the host runtime, so plugins need no
+//! `getrandom` setup of their own. [`try_fill`], [`bytes`], [`u64`] and [`uuid_v4`]
+//! fail with [`RandomError`] when the source is unavailable and are suitable for
+//! nonces, keys and session identifiers. [`fill`] falls back to a generator seeded from
+//! the clocks instead and is meant for values that need not be secret, such as trace
+//! and request IDs.
+//!
+//! ```no_run
+//! use http_wasm_guest::host::random;
+//!
+//! let session_id = random::uuid_v4()?;
+//! let nonce: [u8; 16] = random::bytes()?;
+//! # Ok::<(), random::RandomError>(())
+//! ```
+use std::{cell::Cell, error::Error, fmt::Display};
+
+use crate::{
+    codec::hex_encode,
+    host::{handler, log, time},
+};
+
+thread_local! {
+    /// State of the fallback generator, seeded on first use.
+    static FALLBACK: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Error returned when the random source of the host is unavailable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RandomError;
+
+impl Display for RandomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "random source unavailable")
+    }
+}
+
+impl Error for RandomError {}
+
+/// Fill `buf` with random bytes from the host.
+///
+/// # Errors
+///
+/// Returns [`RandomError`] if the host fails `random_get`; `buf` must not be used then.
+pub fn try_fill(buf: &mut [u8]) -> Result<(), RandomError> {
+    match handler::random(buf) {
+        true => Ok(()),
+        false => Err(RandomError),
+    }
+}
+
+/// Fill `buf` with random bytes that need not be secret.
+///
+/// WASI hosts provide `random_get`; should the call fail, the bytes come from a
+/// generator seeded from the clocks, which is not suitable for secrets. The first use of
+/// the fallback is logged at error level. Use [`try_fill`] for secrets.
+pub fn fill(buf: &mut [u8]) {
+    if try_fill(buf).is_ok() {
+        return;
+    }
+    for chunk in buf.chunks_mut(8) {
+        let value = fallback().to_le_bytes();
+        chunk.copy_from_slice(value.get(..chunk.len()).unwrap_or_default());
+    }
+}
+
+/// Returns `N` random bytes from the host.
+///
+/// # Errors
+///
+/// Returns [`RandomError`] if the random source is unavailable.
+pub fn bytes<const N: usize>() -> Result<[u8; N], RandomError> {
+    let mut buf = [0; N];
+    try_fill(&mut buf)?;
+    Ok(buf)
+}
+
+/// Returns a random `u64` from the host.
+///
+/// # Errors
+///
+/// Returns [`RandomError`] if the random source is unavailable.
+pub fn u64() -> Result<u64, RandomError> {
+    bytes().map(u64::from_le_bytes)
+}
+
+/// Returns a random version 4 UUID (RFC 9562) in lowercase hyphenated form, e.g.
+/// `3f2b8c1e-9d4a-4f6b-8e2c-1a7d5b9e0c34`.
+///
+/// # Errors
+///
+/// Returns [`RandomError`] if the random source is unavailable.
+pub fn uuid_v4() -> Result<String, RandomError> {
+    bytes().map(format_uuid_v4)
+}
+
+/// `N` bytes from [`fill`], for identifiers that need not be secret.
+pub(crate) fn weak_bytes<const N: usize>() -> [u8; N] {
+    let mut buf = [0; N];
+    fill(&mut buf);
+    buf
+}
+
+/// Formats 16 random bytes as a version 4 UUID.
+pub(crate) fn format_uuid_v4(mut uuid: [u8; 16]) -> String {
+    uuid[6] = (uuid[6] & 0x0f) | 0x40;
+    uuid[8] = (uuid[8] & 0x3f) | 0x80;
+    let hex = hex_encode(&uuid);
+    [0..8, 8..12, 12..16, 16..20, 20..32].map(|range| hex.get(range).unwrap_or_default()).join("-")
+}
+
+/// splitmix64 seeded from the clocks.
+fn fallback() -> u64 {
+    let mut state = FALLBACK.get();
+    if state == 0 {
+        log::write(2, b"host random source unavailable, using a clock-seeded generator for non-secret values");
+        state = time::monotonic_nanos() ^ time::unix_millis().rotate_left(32);
+    }
+    state = state.wrapping_add(0x9e3779b97f4a7c15);
+    FALLBACK.set(state);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uuid_v4_format() {
+        let uuid = uuid_v4().unwrap();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(uuid.split('-').map(str::len).collect::<Vec<_>>(), [8, 4, 4, 4, 12]);
+        assert_eq!(uuid.get(14..15), Some("4"));
+        assert!(matches!(uuid.get(19..20), Some("8" | "9" | "a" | "b")));
+        assert_ne!(uuid, uuid_v4().unwrap());
+    }
+
+    #[test]
+    fn random_values_differ() {
+        assert_ne!(u64(), u64());
+        assert_ne!(bytes::<3>(), bytes::<3>());
+        assert_ne!(weak_bytes::<3>(), weak_bytes::<3>());
+        assert_ne!(fallback(), fallback());
+    }
+
+    #[test]
+    fn unavailable_source_fails_without_fallback() {
+        crate::host::test::set_random_unavailable(true);
+        assert_eq!(try_fill(&mut [0; 4]), Err(RandomError));
+        assert_eq!(bytes::<16>(), Err(RandomError));
+        assert_eq!(u64(), Err(RandomError));
+        assert_eq!(uuid_v4(), Err(RandomError));
+        // Non-secret values still come from the fallback generator
+        assert_ne!(weak_bytes::<8>(), [0; 8]);
+        assert_eq!(format_uuid_v4(weak_bytes()).len(), 36);
+        crate::host::test::set_random_unavailable(false);
+    }
+}
@@ -1,17 +1,66 @@
-use crate::host::{Body, Bytes, Header, handler};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::host::{Body, Bytes, Extensions, Header, handler};
 /// Handle for accessing and mutating the current HTTP request.
+///
+/// The source address, version, method and URI are fetched from the host on first
+/// access and cached for the rest of the phase; [`set_method`](Request::set_method) and
+/// [`set_uri`](Request::set_uri) invalidate the cached values. The `with_*` accessors
+/// lend the cached bytes without copying them.
 pub struct Request {
     /// Handle for accessing and mutating request headers.
     pub header: Header,
     /// Handle for reading or writing the request body.
     pub body: Body,
+    source_addr: Memo,
+    version: Memo,
+    method: Memo,
+    uri: Memo,
+    extensions: Extensions,
 }
 const KIND_REQ: i32 = 0;
 
+/// A value fetched from the host once per phase.
+#[derive(Default)]
+struct Memo(RefCell<Option<Rc<[u8]>>>);
+
+impl Memo {
+    fn get(&self, fetch: impl FnOnce() -> Box<[u8]>) -> Rc<[u8]> {
+        self.0.borrow_mut().get_or_insert_with(|| Rc::from(fetch())).clone()
+    }
+
+    /// Call `f` with the value; no borrow is held, so `f` may change the request.
+    fn with<R>(&self, fetch: impl FnOnce() -> Box<[u8]>, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.get(fetch))
+    }
+
+    fn invalidate(&self) {
+        self.0.borrow_mut().take();
+    }
+}
+
 impl Request {
     /// Creates a new `Request` instance with header and body handles.
     pub(crate) fn new() -> Self {
-        Self { header: Header::new(KIND_REQ), body: Body::new(KIND_REQ) }
+        Self::with_extensions(Extensions::new())
+    }
+
+    /// Creates a `Request` carrying the extensions of an earlier phase.
+    pub(crate) fn with_extensions(extensions: Extensions) -> Self {
+        Self {
+            header: Header::new(KIND_REQ),
+            body: Body::new(KIND_REQ),
+            source_addr: Memo::default(),
+            version: Memo::default(),
+            method: Memo::default(),
+            uri: Memo::default(),
+            extensions,
+        }
+    }
+
+    /// Consumes the request, returning its extensions.
+    pub(crate) fn into_extensions(self) -> Extensions {
+        self.extensions
     }
 
     /// Return the client source address (ip:port) as raw bytes.
@@ -24,32 +73,65 @@ impl Request {
     /// ## Note ##
     /// A host who fails to get the remote address will trap (aka panic, "unreachable" instruction).
     pub fn source_addr(&self) -> Bytes {
-        Bytes::from(handler::source_addr())
+        Bytes::from(&*self.source_addr.get(handler::source_addr))
+    }
+
+    /// Call `f` with the client source address, see [`source_addr`](Request::source_addr).
+    pub fn with_source_addr<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        self.source_addr.with(handler::source_addr, f)
     }
 
     /// Return the HTTP protocol version (for example, `HTTP/1.1`).
     pub fn version(&self) -> Bytes {
-        Bytes::from(handler::version())
+        Bytes::from(&*self.version.get(handler::version))
+    }
+
+    /// Call `f` with the HTTP protocol version.
+    pub fn with_version<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        self.version.with(handler::version, f)
     }
 
     /// Return the request method (for example, `GET` or `POST`).
     pub fn method(&self) -> Bytes {
-        Bytes::from(handler::method())
+        Bytes::from(&*self.method.get(handler::method))
+    }
+
+    /// Call `f` with the request method.
+    pub fn with_method<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        self.method.with(handler::method, f)
     }
 
     /// Replace the request method with the provided bytes.
     pub fn set_method(&self, method: &[u8]) {
         handler::set_method(method);
+        self.method.invalidate();
     }
 
     /// Return the request URI as raw bytes.
     pub fn uri(&self) -> Bytes {
-        Bytes::from(handler::uri())
+        Bytes::from(&*self.uri.get(handler::uri))
+    }
+
+    /// Call `f` with the request URI.
+    ///
+    /// ```no_run
+    /// # fn handle(request: &http_wasm_guest::host::Request) {
+    /// let is_health = request.with_uri(|uri| uri.starts_with(b"/healthz"));
+    /// # }
+    /// ```
+    pub fn with_uri<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        self.uri.with(handler::uri, f)
     }
 
     /// Replace the request URI with the provided bytes.
     pub fn set_uri(&self, uri: &[u8]) {
         handler::set_uri(uri);
+        self.uri.invalidate();
+    }
+
+    /// Return the request-scoped values shared between request and response handling.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
     }
 
     /// Return a handle for accessing and mutating request headers.
@@ -133,4 +215,29 @@ mod tests {
         // Should not panic - mock accepts any URI
         request.set_uri(b"/new/path?query=value");
     }
+
+    #[test]
+    fn request_metadata_is_memoized() {
+        let request = Request::new();
+        assert!(request.uri.0.borrow().is_none());
+        assert_eq!(request.uri(), "https://test");
+        assert_eq!(request.uri.0.borrow().as_ref().map(|uri| uri.to_vec()), Some(b"https://test".to_vec()));
+        assert_eq!(request.method(), "GET");
+        request.set_method(b"POST");
+        assert!(request.method.0.borrow().is_none());
+        request.set_uri(b"/new");
+        assert!(request.uri.0.borrow().is_none());
+    }
+
+    #[test]
+    fn borrowing_accessors_share_the_cached_value() {
+        let request = Request::new();
+        let uri = request.with_uri(|uri| uri.as_ptr());
+        assert_eq!(request.with_uri(|uri| uri.as_ptr()), uri);
+        assert!(request.with_method(|method| method == b"GET"));
+        assert_eq!(request.with_version(<[u8]>::to_vec), b"HTTP/2.0");
+        assert_eq!(request.with_source_addr(<[u8]>::len), 11);
+        // The request may be changed from inside the closure
+        request.with_method(|_| request.set_method(b"POST"));
+    }
 }
@@ -1,4 +1,10 @@
-use crate::host::{Body, Header, handler};
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    Decision,
+    headers::{CacheControl, ETag, RetryAfter},
+    host::{Body, Header, Request, handler},
+};
 /// Handle for accessing and mutating the current HTTP response.
 pub struct Response {
     /// Handle for accessing and mutating response headers.
@@ -8,6 +14,30 @@ pub struct Response {
 }
 const KIND_RES: i32 = 1;
 
+/// Error returned by [`Response::redirect`] for invalid arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectError {
+    /// The status code is not a redirect status (`300`-`303`, `307`, `308`).
+    Status(i32),
+    /// The location is empty or contains control characters.
+    Location,
+}
+
+impl Display for RedirectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RedirectError::Status(status) => write!(f, "invalid redirect status: {status}"),
+            RedirectError::Location => write!(f, "invalid redirect location"),
+        }
+    }
+}
+
+impl Error for RedirectError {}
+
+/// Headers that describe the exact bytes of the body.
+const BODY_METADATA_HEADERS: [&[u8]; 6] =
+    [b"Content-Encoding", b"Content-Length", b"ETag", b"Digest", b"Content-Digest", b"Repr-Digest"];
+
 impl Response {
     /// Creates a new `Response` instance with header and body handles.
     pub(crate) fn new() -> Self {
@@ -25,6 +55,174 @@ impl Response {
         handler::set_status_code(code);
     }
 
+    /// Set the response status code together with a `Retry-After` header.
+    ///
+    /// Intended for `429 Too Many Requests` and `503 Service Unavailable`, so that
+    /// clients learn when to try again.
+    pub fn retry_later(&self, code: i32, retry_after: RetryAfter) {
+        self.header.set(b"Retry-After", retry_after.to_string().as_bytes());
+        self.set_status(code);
+    }
+
+    /// Answer with `status` and `value` serialized as JSON body.
+    ///
+    /// Sets `Content-Type: application/json` and `Content-Length`. Nothing is changed if
+    /// serialization fails.
+    ///
+    /// ```no_run
+    /// # use http_wasm_guest::host::{Request, Response};
+    /// fn handle_request(_request: &Request, response: &Response) -> (bool, i32) {
+    ///     // {"error":"forbidden"}
+    ///     let _ = response.respond_json(403, &serde_json::json!({"error": "forbidden"}));
+    ///     (false, 0)
+    /// }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn respond_json<T: serde::Serialize + ?Sized>(&self, status: i32, value: &T) -> Result<(), serde_json::Error> {
+        let body = serde_json::to_vec(value)?;
+        self.respond(status, b"application/json", &body);
+        Ok(())
+    }
+
+    /// Reject the request with `status` and a plain text body.
+    ///
+    /// Returns [`Decision::Stop`], so the response is sent instead of passing the
+    /// request to the upstream when the result is returned from `handle_request`.
+    #[must_use = "return the decision from handle_request, otherwise the request continues upstream"]
+    pub fn deny(&self, status: i32, body: &str) -> Decision {
+        self.respond_text(status, body);
+        Decision::Stop
+    }
+
+    /// Answer with `status` and a plain text body (`text/plain; charset=utf-8`).
+    pub fn respond_text(&self, status: i32, text: &str) {
+        self.respond(status, b"text/plain; charset=utf-8", text.as_bytes());
+    }
+
+    /// Answer with `status` and an HTML body (`text/html; charset=utf-8`).
+    pub fn respond_html(&self, status: i32, html: &str) {
+        self.respond(status, b"text/html; charset=utf-8", html.as_bytes());
+    }
+
+    /// Redirect the client to `location` with an empty body.
+    ///
+    /// `status` must be one of `300`-`303`, `307` or `308`; use `302`/`303` for
+    /// temporary and `301`/`308` for permanent redirects. Nothing is changed if the
+    /// arguments are invalid.
+    pub fn redirect(&self, status: i32, location: &str) -> Result<(), RedirectError> {
+        self.set_redirect(status, location)?;
+        self.header.set(b"Content-Length", b"0");
+        self.body.write(b"");
+        Ok(())
+    }
+
+    /// Redirect like [`redirect`](Response::redirect), with a short HTML body linking to
+    /// `location` for clients that do not follow redirects.
+    pub fn redirect_html(&self, status: i32, location: &str) -> Result<(), RedirectError> {
+        self.set_redirect(status, location)?;
+        let href = escape_html(location);
+        self.respond(status, b"text/html; charset=utf-8", format!("<a href=\"{href}\">{href}</a>\n").as_bytes());
+        Ok(())
+    }
+
+    fn set_redirect(&self, status: i32, location: &str) -> Result<(), RedirectError> {
+        if !matches!(status, 300..=303 | 307 | 308) {
+            return Err(RedirectError::Status(status));
+        }
+        if location.is_empty() || location.bytes().any(|b| b.is_ascii_control()) {
+            return Err(RedirectError::Location);
+        }
+        self.set_status(status);
+        self.header.set(b"Location", location.as_bytes());
+        Ok(())
+    }
+
+    /// Set status, `Content-Type`, `Content-Length` and body in one step.
+    ///
+    /// Headers describing an upstream body, such as `Content-Encoding`, are removed
+    /// first, see [`prepare_for_body_rewrite`](Response::prepare_for_body_rewrite).
+    pub(crate) fn respond(&self, status: i32, content_type: &[u8], body: &[u8]) {
+        self.prepare_for_body_rewrite();
+        self.set_status(status);
+        self.header.set(b"Content-Type", content_type);
+        self.header.set(b"Content-Length", body.len().to_string().as_bytes());
+        self.body.write(body);
+    }
+
+    /// Remove the headers describing the current body before it is rewritten.
+    ///
+    /// Strips `Content-Encoding`, `Content-Length`, `ETag` and the digest headers
+    /// (`Digest`, `Content-Digest`, `Repr-Digest`), which would no longer match a
+    /// transformed body. Call this before [`Body::write`] in `handle_response`; the host
+    /// computes the length of the new body. If the original body was compressed, decode
+    /// it first, e.g. with `Body::read_decompressed` from the `compression` feature.
+    pub fn prepare_for_body_rewrite(&self) {
+        for name in BODY_METADATA_HEADERS {
+            self.header.remove(name);
+        }
+    }
+
+    /// Set the `Cache-Control` header.
+    pub fn set_cache_control(&self, cache_control: &CacheControl) {
+        self.header.set(b"Cache-Control", cache_control.to_string().as_bytes());
+    }
+
+    /// Return the `ETag` of the response, if present and well-formed.
+    pub fn etag(&self) -> Option<ETag> {
+        self.header.get(b"ETag").and_then(|value| ETag::parse(&value))
+    }
+
+    /// Set the `ETag` header.
+    pub fn set_etag(&self, etag: &ETag) {
+        self.header.set(b"ETag", etag.to_string().as_bytes());
+    }
+
+    /// Tag the buffered body with an `ETag` derived from its bytes, unless the
+    /// response has an `ETag` already; returns the tag in effect.
+    ///
+    /// Requires `feature::BufferResponse`.
+    pub fn tag_body(&self, weak: bool) -> ETag {
+        if let Some(etag) = self.etag() {
+            return etag;
+        }
+        let etag = ETag::from_body(&self.body.read(), weak);
+        self.set_etag(&etag);
+        etag
+    }
+
+    /// Turn the response into `304 Not Modified` if the `If-None-Match` header of
+    /// `request` matches the response `ETag`.
+    ///
+    /// Only successful (`2xx`) responses to `GET` and `HEAD` are converted. The body is
+    /// stripped together with `Content-Length` and `Content-Type`; other headers such
+    /// as `ETag`, `Cache-Control` and `Vary` are kept. Returns `true` if the response
+    /// was converted. Requires `feature::BufferResponse`.
+    ///
+    /// ```no_run
+    /// # use http_wasm_guest::host::{Request, Response};
+    /// fn handle_response(request: &Request, response: &Response) {
+    ///     response.tag_body(false);
+    ///     response.not_modified(request);
+    /// }
+    /// ```
+    pub fn not_modified(&self, request: &Request) -> bool {
+        let method = request.method();
+        if !(200..=299).contains(&self.status()) || !(method == "GET" || method == "HEAD") {
+            return false;
+        }
+        let (Some(etag), Some(if_none_match)) = (self.etag(), request.header.get(b"If-None-Match")) else {
+            return false;
+        };
+        if !etag.matches_if_none_match(&if_none_match) {
+            return false;
+        }
+        self.set_status(304);
+        self.header.remove(b"Content-Length");
+        self.header.remove(b"Content-Type");
+        self.body.write(b"");
+        true
+    }
+
     /// Return a handle for accessing and mutating response headers.
     #[deprecated(since = "0.11.2", note = "use the `header` field directly instead")]
     pub fn header(&self) -> &Header {
@@ -38,6 +236,22 @@ impl Response {
     }
 }
 
+/// Escape `&`, `<`, `>`, `"` and `'` for use in HTML text and attribute values.
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,6 +278,85 @@ mod tests {
         response.set_status(404);
     }
 
+    #[test]
+    fn response_retry_later() {
+        let response = Response::new();
+        // Should not panic - mock accepts any status and header
+        response.retry_later(503, RetryAfter::Seconds(30));
+    }
+
+    #[test]
+    fn response_prepare_for_body_rewrite() {
+        let response = Response::new();
+        // Should not panic - mock accepts any header removal
+        response.prepare_for_body_rewrite();
+        response.body.write(b"rewritten");
+    }
+
+    #[test]
+    fn response_respond_text_and_html() {
+        let response = Response::new();
+        response.respond_text(404, "not found");
+        assert_eq!(crate::host::test::take_written_body(1), b"not found");
+        response.respond_html(503, "<h1>maintenance</h1>");
+        assert_eq!(crate::host::test::take_written_body(1), b"<h1>maintenance</h1>");
+    }
+
+    #[test]
+    fn response_deny() {
+        let response = Response::new();
+        assert_eq!(response.deny(403, "forbidden"), Decision::Stop);
+    }
+
+    #[test]
+    fn response_redirect() {
+        let response = Response::new();
+        assert_eq!(response.redirect(302, "/login"), Ok(()));
+        assert_eq!(response.redirect_html(308, "https://example.com/?a=1&b=2"), Ok(()));
+        assert_eq!(response.redirect(200, "/login"), Err(RedirectError::Status(200)));
+        assert_eq!(response.redirect(304, "/login"), Err(RedirectError::Status(304)));
+        assert_eq!(response.redirect(301, ""), Err(RedirectError::Location));
+        assert_eq!(response.redirect_html(301, "/a\r\nSet-Cookie: x=1"), Err(RedirectError::Location));
+    }
+
+    #[test]
+    fn response_etag() {
+        let response = Response::new();
+        // The mock response has no ETag header, so the tag is derived from the mock body
+        assert_eq!(response.etag(), None);
+        assert_eq!(response.tag_body(true), ETag::from_body(b"<html><body>test</body>", true));
+        response.set_etag(&ETag::strong("v1").unwrap());
+    }
+
+    #[test]
+    fn response_set_cache_control() {
+        let response = Response::new();
+        // Should not panic - mock accepts any header
+        response.set_cache_control(&CacheControl::new().no_cache());
+    }
+
+    #[test]
+    fn response_not_modified_without_etag() {
+        // The mock response has no ETag and the mock request no If-None-Match header
+        assert!(!Response::new().not_modified(&Request::new()));
+    }
+
+    #[test]
+    fn html_escaping() {
+        assert_eq!(escape_html("/?a=1&b=<2>"), "/?a=1&amp;b=&lt;2&gt;");
+        assert_eq!(escape_html("\"x\" 'y'"), "&quot;x&quot; &#39;y&#39;");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn response_respond_json() {
+        let response = Response::new();
+        response.respond_json(400, &serde_json::json!({"error": "bad request"})).unwrap();
+        assert_eq!(crate::host::test::take_written_body(1), br#"{"error":"bad request"}"#);
+        let invalid: std::collections::HashMap<(i32, i32), i32> = [((1, 2), 3)].into();
+        assert!(response.respond_json(500, &invalid).is_err());
+    }
+
     #[test]
     fn response_header_access() {
         let response = Response::new();
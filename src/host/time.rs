@@ -0,0 +1,63 @@
+//! Clock access for http-wasm guest plugins.
+//!
+//! This module reads the WASI clocks provided by the host runtime, so plugins can
+//! implement token expiry, maintenance windows or log timestamps without shims of
+//! their own.
+//!
+//! ```no_run
+//! use http_wasm_guest::host::time;
+//!
+//! let expires_at: u64 = 1_900_000_000;
+//! if time::unix_secs() > expires_at {
+//!     // token expired
+//! }
+//! ```
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::host::handler;
+
+const CLOCK_REALTIME: u32 = 0;
+const CLOCK_MONOTONIC: u32 = 1;
+
+/// Returns the current wall-clock time.
+pub fn now() -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(handler::clock_time(CLOCK_REALTIME))
+}
+
+/// Returns the current wall-clock time in milliseconds since the unix epoch.
+pub fn unix_millis() -> u64 {
+    handler::clock_time(CLOCK_REALTIME) / 1_000_000
+}
+
+/// Returns the current wall-clock time in seconds since the unix epoch.
+pub fn unix_secs() -> u64 {
+    handler::clock_time(CLOCK_REALTIME) / 1_000_000_000
+}
+
+/// Returns the value of the monotonic clock in nanoseconds.
+///
+/// The value has no relation to the wall-clock time and is only meaningful for
+/// measuring elapsed time, e.g. request latency.
+pub fn monotonic_nanos() -> u64 {
+    handler::clock_time(CLOCK_MONOTONIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::handler::test;
+
+    #[test]
+    fn time_now() {
+        test::set_clock_millis(1_700_000_000_123);
+        assert_eq!(now(), UNIX_EPOCH + Duration::from_millis(1_700_000_000_123));
+        assert_eq!(unix_millis(), 1_700_000_000_123);
+        assert_eq!(unix_secs(), 1_700_000_000);
+    }
+
+    #[test]
+    fn time_monotonic() {
+        test::set_clock_millis(5);
+        assert_eq!(monotonic_nanos(), 5_000_000);
+    }
+}
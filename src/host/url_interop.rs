@@ -0,0 +1,99 @@
+//! Conversions between the request target and [`url::Url`].
+use url::{ParseError, Position, Url};
+
+use crate::{headers::split_list, host::Request};
+
+impl Request {
+    /// Return the full request URL, parsed with the RFC 3986 / WHATWG semantics of the
+    /// `url` crate.
+    ///
+    /// Requests in absolute form are parsed as they are. For requests in origin form the
+    /// scheme is taken from the first `X-Forwarded-Proto` element if it is `http` or
+    /// `https` and defaults to `https`; the authority is taken from the `Host` header.
+    ///
+    /// ```no_run
+    /// use http_wasm_guest::host::Request;
+    ///
+    /// fn page(request: &Request) -> Option<u32> {
+    ///     let url = request.url().ok()?;
+    ///     url.query_pairs().find(|(name, _)| name == "page")?.1.parse().ok()
+    /// }
+    /// ```
+    pub fn url(&self) -> Result<Url, ParseError> {
+        let proto = self.header.get(b"X-Forwarded-Proto");
+        let host = self.header.get(b"Host");
+        reconstruct(
+            &String::from_utf8_lossy(&self.uri()),
+            proto.as_ref().and_then(|p| split_list(p).next()),
+            host.as_ref().map(|h| String::from_utf8_lossy(h)).as_deref(),
+        )
+    }
+
+    /// Replace the request target with `url`, keeping the form of the current target.
+    ///
+    /// Origin-form targets are replaced by path and query of `url`, absolute-form targets
+    /// by the whole URL; the fragment is dropped. The `Host` header is updated if the
+    /// authority of `url` differs from it. The scheme of an origin-form request cannot be
+    /// changed by the guest.
+    pub fn set_url(&self, url: &Url) {
+        let absolute = !is_origin_form(&self.uri());
+        self.set_uri(target(url, absolute).as_bytes());
+        let authority = &url[Position::BeforeHost..Position::AfterPort];
+        if !authority.is_empty() && self.header.get(b"Host").is_none_or(|host| host != authority.as_bytes()) {
+            self.header.set(b"Host", authority.as_bytes());
+        }
+    }
+}
+
+fn is_origin_form(uri: &[u8]) -> bool {
+    uri.is_empty() || uri.starts_with(b"/")
+}
+
+fn reconstruct(uri: &str, proto: Option<&[u8]>, host: Option<&str>) -> Result<Url, ParseError> {
+    if !is_origin_form(uri.as_bytes()) {
+        return Url::parse(uri);
+    }
+    let scheme = match proto {
+        Some(proto) if proto.eq_ignore_ascii_case(b"http") => "http",
+        _ => "https",
+    };
+    let host = host.map(str::trim).filter(|host| !host.is_empty()).ok_or(ParseError::EmptyHost)?;
+    Url::parse(&format!("{scheme}://{host}{}", if uri.is_empty() { "/" } else { uri }))
+}
+
+fn target(url: &Url, absolute: bool) -> &str {
+    match absolute {
+        true => &url[..Position::AfterQuery],
+        false => &url[Position::BeforePath..Position::AfterQuery],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_form() {
+        // The mock request target is "https://test"
+        assert_eq!(Request::new().url().unwrap().as_str(), "https://test/");
+        assert_eq!(reconstruct("http://u@h:8080/a?b#c", None, Some("other")).unwrap().as_str(), "http://u@h:8080/a?b#c");
+    }
+
+    #[test]
+    fn origin_form_uses_forwarded_proto_and_host() {
+        let url = reconstruct("/a/../b?q=1", Some(b"HTTP"), Some("Example.com:8080")).unwrap();
+        assert_eq!(url.as_str(), "http://example.com:8080/b?q=1");
+        assert_eq!(reconstruct("", Some(b"ftp"), Some("h")).unwrap().as_str(), "https://h/");
+        assert_eq!(reconstruct("//evil/x", None, Some("h")).unwrap().host_str(), Some("h"));
+        assert_eq!(reconstruct("/", None, None), Err(ParseError::EmptyHost));
+        assert!(reconstruct("/", None, Some("a b")).is_err());
+    }
+
+    #[test]
+    fn target_keeps_form() {
+        let url = Url::parse("https://h:8443/p?q=1#frag").unwrap();
+        assert_eq!(target(&url, false), "/p?q=1");
+        assert_eq!(target(&url, true), "https://h:8443/p?q=1");
+        Request::new().set_url(&url);
+    }
+}
@@ -1,10 +1,196 @@
-use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
-use std::io::Write;
+use log::{
+    Level, LevelFilter, Log, Metadata, Record, SetLoggerError,
+    kv::{self, Key, Value, VisitSource},
+};
+use std::{cell::RefCell, collections::BTreeMap, fmt::Display, io::Write};
 
-use crate::{host, memory};
+use crate::{codec::escape_json, host, span::Logfmt};
 
 static LOGGER: HostLogger = HostLogger;
 const TRUNC_MARKER: &[u8] = b"... [truncated]";
+/// Maximum length of a formatted message in bytes.
+const MESSAGE_LIMIT: usize = 2048;
+
+/// Output format of the [`HostLogger`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// The formatted message, followed by the key-values of the record and the attached
+    /// fields as ` key=value`.
+    #[default]
+    Text,
+    /// One JSON object per line with `level`, `target`, `message`, the key-values of the
+    /// record and the attached fields,
+    /// e.g. `{"level":"INFO","target":"my_plugin","message":"started","plugin":"auth"}`.
+    Json,
+}
+
+/// Maximum levels per log target, configured with the `RUST_LOG` syntax of `env_logger`.
+///
+/// A target matches its own records and those of its submodules; the most specific
+/// target wins. Records of other targets use the default level.
+///
+/// ```
+/// use http_wasm_guest::LogFilter;
+/// use log::LevelFilter;
+///
+/// let filter = LogFilter::parse("warn,my_plugin::auth=debug");
+/// assert_eq!(filter.level_for("my_plugin::auth::basic"), LevelFilter::Debug);
+/// assert_eq!(filter.level_for("my_plugin"), LevelFilter::Warn);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self::new(LevelFilter::Info)
+    }
+}
+
+impl LogFilter {
+    /// Create a filter with the given default level and no target directives.
+    pub fn new(default: LevelFilter) -> Self {
+        Self { default, targets: Vec::new() }
+    }
+
+    /// Set the maximum level of `target` and its submodules.
+    pub fn target(mut self, target: &str, level: LevelFilter) -> Self {
+        self.targets.retain(|(t, _)| t != target);
+        self.targets.push((target.to_string(), level));
+        self
+    }
+
+    /// Parse comma-separated directives such as `info,my_plugin::auth=debug,noisy=off`.
+    ///
+    /// A bare level sets the default level (`info` if none is given), a bare target
+    /// enables all levels of that target. Invalid directives are ignored.
+    pub fn parse(spec: &str) -> Self {
+        let mut filter = Self::default();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => match (target.trim(), level.trim().parse()) {
+                    ("", _) | (_, Err(_)) => {}
+                    (target, Ok(level)) => filter = filter.target(target, level),
+                },
+                None => match directive.parse() {
+                    Ok(level) => filter.default = level,
+                    Err(_) => filter = filter.target(directive, LevelFilter::Trace),
+                },
+            }
+        }
+        filter
+    }
+
+    /// The maximum level of records with the given target.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| {
+                target.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// The most verbose level of all directives.
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets.iter().map(|(_, level)| *level).fold(self.default, Ord::max)
+    }
+}
+
+/// Per-call-site limit on the records forwarded by the [`HostLogger`].
+///
+/// Protects the host from log floods on hot paths: of the records logged at one call
+/// site (module and line) within a second, the first `max_per_second` are forwarded and
+/// of the rest only every `sample_every`-th, or none if it is `0`. The next forwarded
+/// record of the call site carries the number of dropped records as `suppressed=N`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogSampling {
+    /// Records forwarded per call site and second before sampling starts.
+    pub max_per_second: u32,
+    /// Forward every n-th record above the limit; `0` drops them all.
+    pub sample_every: u32,
+}
+
+impl Default for LogSampling {
+    fn default() -> Self {
+        Self { max_per_second: 10, sample_every: 0 }
+    }
+}
+
+/// Call sites tracked at most; all counters are reset when a new one exceeds the limit.
+const MAX_SITES: usize = 1024;
+
+#[derive(Debug, Default)]
+struct SiteCounter {
+    second: u64,
+    count: u32,
+    suppressed: u32,
+}
+
+/// Produces the message of a record, see [`HostLogger::set_formatter`].
+pub type LogFormatter = Box<dyn Fn(&Record) -> String>;
+
+/// Output settings shared by all records.
+#[derive(Default)]
+struct Settings {
+    format: LogFormat,
+    fields: Vec<(String, String)>,
+    filter: Option<LogFilter>,
+    sampling: Option<LogSampling>,
+    sites: BTreeMap<(String, u32), SiteCounter>,
+    formatter: Option<LogFormatter>,
+}
+
+impl Settings {
+    const fn new() -> Self {
+        Self {
+            format: LogFormat::Text,
+            fields: Vec::new(),
+            filter: None,
+            sampling: None,
+            sites: BTreeMap::new(),
+            formatter: None,
+        }
+    }
+
+    /// Count a record of the call site; returns `None` if it is dropped, otherwise the
+    /// number of records dropped since the last forwarded one. `second` reads the clock
+    /// and is only called when sampling is enabled.
+    fn admit(&mut self, site: (&str, u32), second: impl FnOnce() -> u64) -> Option<u32> {
+        let Some(sampling) = self.sampling else {
+            return Some(0);
+        };
+        let second = second();
+        if self.sites.len() >= MAX_SITES && !self.sites.contains_key(&(site.0.to_string(), site.1)) {
+            self.sites.clear();
+        }
+        let counter = self.sites.entry((site.0.to_string(), site.1)).or_default();
+        if counter.second != second {
+            counter.second = second;
+            counter.count = 0;
+        }
+        counter.count = counter.count.saturating_add(1);
+        let over = counter.count.saturating_sub(sampling.max_per_second);
+        if over == 0 || (sampling.sample_every > 0 && over % sampling.sample_every == 0) {
+            Some(std::mem::take(&mut counter.suppressed))
+        } else {
+            counter.suppressed = counter.suppressed.saturating_add(1);
+            None
+        }
+    }
+}
+
+thread_local! {
+    static SETTINGS: RefCell<Settings> = const { RefCell::new(Settings::new()) };
+}
+
+/// Call `f` with the logger settings; `f` must not log.
+fn with_settings<R>(f: impl FnOnce(&mut Settings) -> R) -> R {
+    SETTINGS.with(|settings| f(&mut settings.borrow_mut()))
+}
 
 /// Logger implementation that forwards records to the host.
 ///
@@ -16,34 +202,160 @@ impl Log for HostLogger {
     #[inline]
     fn enabled(&self, metadata: &Metadata) -> bool {
         metadata.level() <= log::max_level()
+            && with_settings(|settings| {
+                settings.filter.as_ref().is_none_or(|f| metadata.level() <= f.level_for(metadata.target()))
+            })
     }
 
     fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) {
-            memory::with_buffer(|buf| {
-                let written = format_log_message(buf, record.args());
-                host::log::write(host_level(record.metadata()), buf.as_subslice(written));
-            });
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let site = (record.module_path().unwrap_or(record.target()), record.line().unwrap_or_default());
+        let Some(suppressed) = with_settings(|settings| settings.admit(site, || host::time::monotonic_nanos() / 1_000_000_000))
+        else {
+            return;
+        };
+        let mut key_values = KeyValues(Vec::new());
+        let _ = record.key_values().visit(&mut key_values);
+        if suppressed > 0 {
+            key_values.0.push((Key::from("suppressed"), Value::from(suppressed)));
+        }
+        // The formatter is taken out while it runs, so records it logs itself are not formatted.
+        let formatter = with_settings(|settings| settings.formatter.take());
+        let formatted = formatter.as_ref().map(|formatter| formatter(record));
+        with_settings(|settings| {
+            if settings.formatter.is_none() {
+                settings.formatter = formatter;
+            }
+        });
+        let message: &dyn Display = match &formatted {
+            Some(formatted) => formatted,
+            None => record.args(),
+        };
+        // Messages are formatted outside the settings and the shared buffer, since a
+        // `Display` implementation may log itself.
+        let (format, fields) = with_settings(|settings| (settings.format, settings.fields.clone()));
+        let level = host_level(record.metadata());
+        match format {
+            LogFormat::Text if fields.is_empty() && key_values.0.is_empty() => {
+                host::log::write(level, &format_log_message(message));
+            }
+            LogFormat::Text => {
+                let mut message = truncated_message(message);
+                for (key, value) in &key_values.0 {
+                    message.push_str(&format!(" {}={}", Logfmt(key), Logfmt(value)));
+                }
+                for (key, value) in &fields {
+                    message.push_str(&format!(" {}={}", Logfmt(key), Logfmt(value)));
+                }
+                host::log::write(level, message.as_bytes());
+            }
+            LogFormat::Json => host::log::write(level, format_json(record, message, &key_values.0, &fields).as_bytes()),
         }
     }
 
     fn flush(&self) {}
 }
 
-/// Formats the log message into the provided buffer, applying truncation if needed.
-/// Returns the number of bytes written.
-fn format_log_message(buf: &mut memory::Buffer, args: &std::fmt::Arguments) -> usize {
-    let capacity = buf.capacity();
-    let mut slice = buf.as_mut_slice();
-    match write!(slice, "{}", args) {
-        Ok(()) => capacity - slice.len(),
-        Err(_) => {
-            let start = capacity - TRUNC_MARKER.len();
-            let slice = buf.as_mut_slice();
-            slice[start..].copy_from_slice(TRUNC_MARKER);
-            buf.capacity()
+/// Formats the log message, cut to `MESSAGE_LIMIT` bytes ending with the truncation
+/// marker if it is longer.
+fn format_log_message(args: &dyn Display) -> Vec<u8> {
+    let mut message = Vec::new();
+    if write!(LimitedWriter(&mut message), "{args}").is_err() {
+        message.truncate(MESSAGE_LIMIT - TRUNC_MARKER.len());
+        message.extend_from_slice(TRUNC_MARKER);
+    }
+    message
+}
+
+/// The formatted message, cut to `MESSAGE_LIMIT` bytes with the truncation marker.
+fn truncated_message(args: &dyn Display) -> String {
+    String::from_utf8_lossy(&format_log_message(args)).into_owned()
+}
+
+/// Fails once more than `MESSAGE_LIMIT` bytes are written.
+struct LimitedWriter<'a>(&'a mut Vec<u8>);
+
+impl Write for LimitedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let room = MESSAGE_LIMIT - self.0.len();
+        match buf.len() <= room {
+            true => {
+                self.0.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            false => {
+                self.0.extend_from_slice(buf.get(..room).unwrap_or_default());
+                Err(std::io::ErrorKind::WriteZero.into())
+            }
         }
     }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Collects the key-values of a record, e.g. `info!(user = id; "rejected")`.
+struct KeyValues<'kvs>(Vec<(Key<'kvs>, Value<'kvs>)>);
+
+impl<'kvs> VisitSource<'kvs> for KeyValues<'kvs> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.push((key, value));
+        Ok(())
+    }
+}
+
+/// Format a record as a single-line JSON object.
+///
+/// Booleans and numbers of the record's key-values are kept as JSON literals, all
+/// other values become strings.
+fn format_json(record: &Record, message: &dyn Display, key_values: &[(Key, Value)], fields: &[(String, String)]) -> String {
+    let mut json = String::from("{");
+    let mut member = |key: &str, value: Result<String, &str>| {
+        if json.len() > 1 {
+            json.push(',');
+        }
+        json.push('"');
+        escape_json(key, &mut json);
+        json.push_str("\":");
+        match value {
+            Ok(literal) => json.push_str(&literal),
+            Err(text) => {
+                json.push('"');
+                escape_json(text, &mut json);
+                json.push('"');
+            }
+        }
+    };
+    member("level", Err(record.level().as_str()));
+    member("target", Err(record.target()));
+    member("message", Err(&truncated_message(message)));
+    for (key, value) in key_values {
+        match json_literal(value) {
+            Some(literal) => member(key.as_str(), Ok(literal)),
+            None => member(key.as_str(), Err(&value.to_string())),
+        }
+    }
+    for (key, value) in fields {
+        member(key, Err(value));
+    }
+    json.push('}');
+    json
+}
+
+fn json_literal(value: &Value) -> Option<String> {
+    if let Some(b) = value.to_bool() {
+        return Some(b.to_string());
+    }
+    if let Some(n) = value.to_i64() {
+        return Some(n.to_string());
+    }
+    if let Some(n) = value.to_u64() {
+        return Some(n.to_string());
+    }
+    value.to_f64().filter(|f| f.is_finite()).map(|f| f.to_string())
 }
 
 impl HostLogger {
@@ -63,6 +375,67 @@ impl HostLogger {
         log::set_max_level(max_level(level.to_level_filter()));
         log::set_logger(&LOGGER)
     }
+
+    /// Initialize the host-backed logger with per-target levels.
+    ///
+    /// See [`host::log::init_from_config`](crate::host::log::init_from_config) to read the
+    /// filter from the plugin configuration.
+    pub fn init_with_filter(filter: LogFilter) -> Result<(), SetLoggerError> {
+        log::set_max_level(max_level(filter.max_level()));
+        with_settings(|settings| settings.filter = Some(filter));
+        log::set_logger(&LOGGER)
+    }
+
+    /// Initialize the logger with a specific maximum level and output format.
+    pub fn init_with_format(level: Level, format: LogFormat) -> Result<(), SetLoggerError> {
+        HostLogger::set_format(format);
+        HostLogger::init_with_level(level)
+    }
+
+    /// Limit the records forwarded per call site; `None` forwards all records.
+    pub fn set_sampling(sampling: Option<LogSampling>) {
+        with_settings(|settings| {
+            settings.sampling = sampling;
+            settings.sites.clear();
+        });
+    }
+
+    /// Install a formatter that produces the message of each record in place of the
+    /// plain `format!("{}", record.args())`; `None` removes it.
+    ///
+    /// Key-values and attached fields are still added, and the result is truncated like
+    /// other messages. Records logged by the formatter itself are not formatted.
+    ///
+    /// ```no_run
+    /// use http_wasm_guest::{HostLogger, headers::format_http_date, host::time};
+    ///
+    /// HostLogger::set_formatter(Some(Box::new(|record: &log::Record| {
+    ///     format!("{} [auth-plugin] {}: {}", format_http_date(time::now()), record.level(), record.args())
+    /// })));
+    /// ```
+    pub fn set_formatter(formatter: Option<LogFormatter>) {
+        with_settings(|settings| settings.formatter = formatter);
+    }
+
+    /// Change the output format of all following records.
+    pub fn set_format(format: LogFormat) {
+        with_settings(|settings| settings.format = format);
+    }
+
+    /// Attach a field to all following records, e.g. the plugin name; replaces a field
+    /// with the same key.
+    pub fn set_field(key: &str, value: impl std::fmt::Display) {
+        let value = value.to_string();
+        with_settings(|settings| match settings.fields.iter_mut().find(|(k, _)| k == key) {
+            Some((_, existing)) => *existing = value,
+            None => settings.fields.push((key.to_string(), value)),
+        });
+    }
+
+    /// Remove an attached field.
+    pub fn remove_field(key: &str) {
+        with_settings(|settings| settings.fields.retain(|(k, _)| k != key));
+    }
 }
 
 /// Determine the max_log_level as configured by the host.
@@ -127,33 +500,37 @@ mod tests {
     fn test_log_truncation_marker() {
         // Compose a message that will overflow the buffer
         let long_msg = "A".repeat(3000);
-        memory::with_buffer(|buf| {
-            let written = super::format_log_message(buf, &format_args!("{}", long_msg));
-            let slice = buf.as_subslice(written);
-            assert_eq!(slice.len(), buf.capacity(), "Truncated log should fill the buffer");
-            assert!(slice.ends_with(TRUNC_MARKER), "Log message should end with truncation marker");
-        });
+        let message = super::format_log_message(&format_args!("{}", long_msg));
+        assert_eq!(message.len(), MESSAGE_LIMIT, "Truncated log should fill the limit");
+        assert!(message.ends_with(TRUNC_MARKER), "Log message should end with truncation marker");
     }
 
     #[test]
     fn test_format_log_message() {
         let msg = "Test";
-        memory::with_buffer(|buf| {
-            let written = super::format_log_message(buf, &format_args!("{}", msg));
-            assert_eq!(written, msg.len(), "message should not be truncated");
-            assert_eq!(buf.as_subslice(written), msg.as_bytes());
-        });
+        assert_eq!(super::format_log_message(&format_args!("{}", msg)), msg.as_bytes());
     }
 
     #[test]
     fn test_format_log_message_limit() {
         let msg = "A".repeat(2048);
-        memory::with_buffer(|buf| {
-            let written = super::format_log_message(buf, &format_args!("{}", msg));
-            assert_eq!(written, msg.len(), "message should not be truncated");
-            assert_eq!(buf.as_subslice(written), msg.as_bytes());
-        });
+        assert_eq!(super::format_log_message(&format_args!("{}", msg)), msg.as_bytes(), "message should not be truncated");
     }
+
+    #[test]
+    fn display_that_logs_is_formatted_outside_settings() {
+        struct Noisy;
+        impl Display for Noisy {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                HostLogger.log(&Record::builder().args(format_args!("inner")).level(Level::Error).build());
+                f.write_str("outer")
+            }
+        }
+        with_settings(|settings| settings.fields = vec![("plugin".into(), "auth".into())]);
+        HostLogger.log(&Record::builder().args(format_args!("{}", Noisy)).level(Level::Error).build());
+        with_settings(|settings| settings.fields.clear());
+    }
+
     #[test]
     fn host_logger_enabled_within_max_level() {
         // Set max level to Info
@@ -178,6 +555,156 @@ mod tests {
         assert!(!LOGGER.enabled(&metadata));
     }
 
+    #[test]
+    fn json_format() {
+        let record = log::Record::builder().level(Level::Warn).target("plugin").args(format_args!("say \"hi\"\n")).build();
+        let fields = [("plugin".to_string(), "auth".to_string())];
+        assert_eq!(
+            format_json(&record, record.args(), &[], &fields),
+            r#"{"level":"WARN","target":"plugin","message":"say \"hi\"\n","plugin":"auth"}"#
+        );
+    }
+
+    #[test]
+    fn json_message_is_truncated() {
+        let long_msg = "A".repeat(5000);
+        let json = format_json(
+            &log::Record::builder().level(Level::Info).target("t").args(format_args!("m")).build(),
+            &long_msg,
+            &[],
+            &[],
+        );
+        assert!(json.ends_with("... [truncated]\"}"), "message should end with truncation marker");
+    }
+
+    #[test]
+    fn key_values() {
+        let kvs: [(&str, Value); 5] = [
+            ("user", Value::from("jane")),
+            ("count", Value::from(3)),
+            ("ok", Value::from(true)),
+            ("ratio", Value::from(0.5)),
+            ("nan", Value::from(f64::NAN)),
+        ];
+        let record = log::Record::builder().level(Level::Info).target("t").args(format_args!("m")).key_values(&kvs).build();
+        let mut key_values = KeyValues(Vec::new());
+        record.key_values().visit(&mut key_values).unwrap();
+        assert_eq!(
+            format_json(&record, record.args(), &key_values.0, &[]),
+            r#"{"level":"INFO","target":"t","message":"m","user":"jane","count":3,"ok":true,"ratio":0.5,"nan":"NaN"}"#
+        );
+        // Should not panic - mock accepts any log call
+        log::set_max_level(LevelFilter::Info);
+        LOGGER.log(&record);
+    }
+
+    #[test]
+    fn filter_directives() {
+        let filter = LogFilter::parse("debug, plugin::auth=trace ,plugin::auth::basic=off,noisy,bad=loud,=warn");
+        assert_eq!(filter.level_for("other"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("plugin::auth"), LevelFilter::Trace);
+        assert_eq!(filter.level_for("plugin::auth::basic::inner"), LevelFilter::Off);
+        assert_eq!(filter.level_for("plugin::authz"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("noisy"), LevelFilter::Trace);
+        assert_eq!(filter.level_for("bad"), LevelFilter::Debug);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+        assert_eq!(LogFilter::parse(""), LogFilter::new(LevelFilter::Info));
+        assert_eq!(LogFilter::parse("x=error").max_level(), LevelFilter::Info);
+    }
+
+    #[test]
+    fn filter_applies_to_records() {
+        log::set_max_level(LevelFilter::Trace);
+        with_settings(|settings| settings.filter = Some(LogFilter::parse("warn,plugin=debug")));
+        let enabled = |level, target| LOGGER.enabled(&log::Metadata::builder().level(level).target(target).build());
+        assert!(enabled(Level::Debug, "plugin::auth"));
+        assert!(!enabled(Level::Info, "other"));
+        assert!(enabled(Level::Warn, "other"));
+        with_settings(|settings| settings.filter = None);
+    }
+
+    #[test]
+    fn sampling_limits_per_call_site() {
+        let mut settings = Settings::new();
+        let clock_reads = std::cell::Cell::new(0);
+        let clock = || {
+            clock_reads.set(clock_reads.get() + 1);
+            0
+        };
+        // Without sampling every record passes and the clock is not read
+        assert_eq!(settings.admit(("m", 1), clock), Some(0));
+        assert_eq!(clock_reads.get(), 0);
+        settings.sampling = Some(LogSampling { max_per_second: 2, sample_every: 3 });
+        let admitted = (0..9).map(|_| settings.admit(("m", 1), || 5)).collect::<Vec<_>>();
+        assert_eq!(admitted, [Some(0), Some(0), None, None, Some(2), None, None, Some(2), None]);
+        // other call sites and the next second are counted separately
+        assert_eq!(settings.admit(("m", 2), || 5), Some(0));
+        assert_eq!(settings.admit(("m", 1), || 6), Some(1));
+
+        settings.sampling = Some(LogSampling { max_per_second: 1, sample_every: 0 });
+        assert_eq!((0..5).filter_map(|_| settings.admit(("n", 1), || 0)).count(), 1);
+    }
+
+    #[test]
+    fn sampling_bounds_tracked_sites() {
+        let mut settings = Settings::new();
+        settings.sampling = Some(LogSampling::default());
+        for line in 0..=MAX_SITES as u32 {
+            settings.admit(("m", line), || 0);
+        }
+        assert_eq!(settings.sites.len(), 1);
+        HostLogger::set_sampling(Some(LogSampling::default()));
+        log::set_max_level(LevelFilter::Info);
+        // Should not panic - mock accepts any log call
+        for _ in 0..20 {
+            LOGGER.log(&log::Record::builder().level(Level::Info).target("test").line(Some(1)).args(format_args!("x")).build());
+        }
+        HostLogger::set_sampling(None);
+    }
+
+    #[test]
+    fn formatter_replaces_message() {
+        HostLogger::set_formatter(Some(Box::new(|record: &Record| {
+            // Should not recurse - records of the formatter are not formatted
+            LOGGER.log(&log::Record::builder().level(Level::Info).target("nested").args(format_args!("inner")).build());
+            format!("[plugin] {}", record.args())
+        })));
+        log::set_max_level(LevelFilter::Info);
+        for format in [LogFormat::Text, LogFormat::Json] {
+            HostLogger::set_format(format);
+            LOGGER.log(&log::Record::builder().level(Level::Info).target("outer").args(format_args!("outer")).build());
+        }
+        HostLogger::set_format(LogFormat::Text);
+        let record = log::Record::builder().level(Level::Info).target("t").args(format_args!("m")).build();
+        assert_eq!(
+            with_settings(|settings| settings.formatter.take()).map(|formatter| formatter(&record)).unwrap(),
+            "[plugin] m"
+        );
+        HostLogger::set_formatter(None);
+        assert!(with_settings(|settings| settings.formatter.is_none()));
+    }
+
+    #[test]
+    fn attached_fields() {
+        HostLogger::set_field("plugin", "auth");
+        HostLogger::set_field("plugin", 2);
+        HostLogger::set_field("zone", "eu");
+        with_settings(|settings| {
+            assert_eq!(settings.fields, [("plugin".to_string(), "2".to_string()), ("zone".to_string(), "eu".to_string())])
+        });
+        HostLogger::remove_field("plugin");
+        with_settings(|settings| assert_eq!(settings.fields.len(), 1));
+
+        // Should not panic - mock accepts any log call
+        log::set_max_level(LevelFilter::Info);
+        for format in [LogFormat::Text, LogFormat::Json] {
+            HostLogger::set_format(format);
+            LOGGER.log(&log::Record::builder().level(Level::Info).target("test").args(format_args!("with fields")).build());
+        }
+        HostLogger::set_format(LogFormat::Text);
+        HostLogger::remove_field("zone");
+    }
+
     #[test]
     fn host_logger_flush() {
         // Flush is a no-op, should not panic
@@ -0,0 +1,309 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Write},
+    sync::Mutex,
+};
+
+use tracing_core::{
+    Event, Level, LevelFilter, Metadata, Subscriber,
+    dispatcher::{self, Dispatch, SetGlobalDefaultError},
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+};
+
+use crate::{
+    host::{self, time},
+    span::Logfmt,
+};
+
+/// `tracing` subscriber that forwards events and spans to the host log.
+///
+/// Events are written as their message followed by their fields and the name of the
+/// current span, e.g. `rejected user=42 span=auth`. Spans log a begin event when they
+/// are created and an end event with their duration when they are closed, in the same
+/// `logfmt` format as [`span!`](crate::span!):
+///
+/// ```text
+/// span=auth event=begin id=2 parent=1 user=42
+/// span=auth event=end id=2 parent=1 duration_us=1520 user=42 outcome="ok"
+/// ```
+///
+/// ```no_run
+/// use http_wasm_guest::HostSubscriber;
+///
+/// let _ = HostSubscriber::init();
+/// let span = tracing::info_span!("auth", user = 42);
+/// let _entered = span.enter();
+/// tracing::warn!(reason = "expired", "token rejected");
+/// ```
+pub struct HostSubscriber {
+    max_level: LevelFilter,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: u64,
+    spans: BTreeMap<u64, SpanData>,
+    entered: Vec<u64>,
+    #[cfg(test)]
+    lines: Vec<String>,
+}
+
+struct SpanData {
+    name: &'static str,
+    level: Level,
+    parent: Option<u64>,
+    fields: String,
+    start: u64,
+    refs: usize,
+}
+
+impl HostSubscriber {
+    /// Create a subscriber for records up to `max_level`, reduced to the levels the host
+    /// has enabled.
+    pub fn new(max_level: LevelFilter) -> Self {
+        let mut max_level = max_level;
+        while let Some(level) = max_level.into_level() {
+            if host::log::enabled(host_level(&level)) {
+                break;
+            }
+            max_level = match level {
+                Level::TRACE => LevelFilter::DEBUG,
+                Level::DEBUG => LevelFilter::INFO,
+                Level::INFO => LevelFilter::WARN,
+                Level::WARN => LevelFilter::ERROR,
+                _ => LevelFilter::OFF,
+            };
+        }
+        Self { max_level, state: Mutex::new(State { next_id: 1, ..Default::default() }) }
+    }
+
+    /// Install the subscriber as global default with the level `INFO`.
+    pub fn init() -> Result<(), SetGlobalDefaultError> {
+        Self::init_with_level(LevelFilter::INFO)
+    }
+
+    /// Install the subscriber as global default with a specific maximum level.
+    pub fn init_with_level(max_level: LevelFilter) -> Result<(), SetGlobalDefaultError> {
+        dispatcher::set_global_default(Dispatch::new(Self::new(max_level)))
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut state)
+    }
+
+    fn write(&self, level: &Level, line: String) {
+        let level = host_level(level);
+        host::log::write(level, line.as_bytes());
+        #[cfg(test)]
+        self.with_state(|state| state.lines.push(line));
+    }
+
+    fn span_line(id: u64, span: &SpanData, event: &str, duration_us: Option<u64>) -> String {
+        let mut line = format!("span={} event={event} id={id}", Logfmt(span.name));
+        if let Some(parent) = span.parent {
+            let _ = write!(line, " parent={parent}");
+        }
+        if let Some(duration_us) = duration_us {
+            let _ = write!(line, " duration_us={duration_us}");
+        }
+        line.push_str(&span.fields);
+        line
+    }
+}
+
+impl Subscriber for HostSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= &self.max_level
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.max_level)
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Fields::default();
+        span.record(&mut fields);
+        let (id, line) = self.with_state(|state| {
+            let id = state.next_id;
+            state.next_id += 1;
+            let parent = match span.parent() {
+                Some(parent) => Some(parent.into_u64()),
+                None if span.is_contextual() => state.entered.last().copied(),
+                None => None,
+            };
+            let data = SpanData {
+                name: span.metadata().name(),
+                level: *span.metadata().level(),
+                parent,
+                fields: fields.fields,
+                start: time::monotonic_nanos(),
+                refs: 1,
+            };
+            let line = Self::span_line(id, &data, "begin", None);
+            state.spans.insert(id, data);
+            (id, line)
+        });
+        self.write(span.metadata().level(), line);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        self.with_state(|state| {
+            if let Some(data) = state.spans.get_mut(&span.into_u64()) {
+                data.fields.push_str(&fields.fields);
+            }
+        });
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let current = self.with_state(|state| {
+            let id = event.parent().map(Id::into_u64).or_else(|| state.entered.last().copied().filter(|_| event.is_contextual()));
+            id.and_then(|id| state.spans.get(&id)).map(|span| span.name)
+        });
+        let mut line = fields.message;
+        line.push_str(&fields.fields);
+        if let Some(span) = current {
+            let _ = write!(line, " span={}", Logfmt(span));
+        }
+        self.write(event.metadata().level(), line.trim_start().to_string());
+    }
+
+    fn enter(&self, span: &Id) {
+        self.with_state(|state| state.entered.push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        self.with_state(|state| {
+            if let Some(position) = state.entered.iter().rposition(|id| *id == span.into_u64()) {
+                state.entered.remove(position);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        self.with_state(|state| {
+            if let Some(data) = state.spans.get_mut(&span.into_u64()) {
+                data.refs += 1;
+            }
+        });
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let id = span.into_u64();
+        let closed = self.with_state(|state| {
+            let data = state.spans.get_mut(&id)?;
+            data.refs = data.refs.saturating_sub(1);
+            match data.refs {
+                0 => state.spans.remove(&id),
+                _ => None,
+            }
+        });
+        match closed {
+            Some(data) => {
+                let duration_us = time::monotonic_nanos().saturating_sub(data.start) / 1000;
+                self.write(&data.level, Self::span_line(id, &data, "end", Some(duration_us)));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Collects the `message` and the other fields as ` key=value`.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    fields: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_string(),
+            name => {
+                let _ = write!(self.fields, " {}={}", Logfmt(name), Logfmt(value));
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+}
+
+/// Map a `tracing` level to the host severity code, see `HostLogger`.
+fn host_level(level: &Level) -> i32 {
+    match *level {
+        Level::ERROR => 2,
+        Level::WARN => 1,
+        Level::INFO => 0,
+        Level::DEBUG => -1,
+        _ => -2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(dispatch: &Dispatch) -> Vec<String> {
+        dispatch.downcast_ref::<HostSubscriber>().map(|s| s.with_state(|state| state.lines.clone())).unwrap_or_default()
+    }
+
+    #[test]
+    fn events_with_fields_and_span() {
+        let dispatch = Dispatch::new(HostSubscriber::new(LevelFilter::INFO));
+        dispatcher::with_default(&dispatch, || {
+            tracing::info!(user = 42, name = "jane doe", "login");
+            let span = tracing::info_span!("auth", method = "basic");
+            let _entered = span.enter();
+            tracing::warn!("rejected");
+            tracing::debug!("filtered");
+        });
+        let lines = lines(&dispatch);
+        assert_eq!(lines.first().map(String::as_str), Some(r#"login user=42 name="jane doe""#));
+        assert_eq!(lines.get(1).map(String::as_str), Some("span=auth event=begin id=1 method=basic"));
+        assert_eq!(lines.get(2).map(String::as_str), Some("rejected span=auth"));
+        assert!(lines.get(3).is_some_and(|line| line.starts_with("span=auth event=end id=1 duration_us=")), "{lines:?}");
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn nested_spans_and_recorded_fields() {
+        let dispatch = Dispatch::new(HostSubscriber::new(LevelFilter::TRACE));
+        dispatcher::with_default(&dispatch, || {
+            let outer = tracing::info_span!("outer");
+            let _entered = outer.enter();
+            let inner = tracing::info_span!("inner", outcome = tracing::field::Empty);
+            let copy = inner.clone();
+            inner.record("outcome", "ok");
+            drop(inner);
+            drop(copy);
+        });
+        let lines = lines(&dispatch);
+        assert_eq!(lines.get(1).map(String::as_str), Some("span=inner event=begin id=2 parent=1"));
+        let end = lines.get(2).map(String::as_str).unwrap_or_default();
+        assert!(end.starts_with("span=inner event=end id=2 parent=1 duration_us=") && end.ends_with(" outcome=ok"), "{end}");
+        assert!(lines.get(3).is_some_and(|line| line.starts_with("span=outer event=end id=1 duration_us=")));
+        assert!(dispatch.downcast_ref::<HostSubscriber>().is_some_and(|s| s.with_state(|state| state.spans.is_empty())));
+    }
+
+    #[test]
+    fn max_level_follows_host() {
+        // The mock disables debug (-1) and trace (-2)
+        assert_eq!(HostSubscriber::new(LevelFilter::TRACE).max_level, LevelFilter::INFO);
+        assert_eq!(HostSubscriber::new(LevelFilter::WARN).max_level, LevelFilter::WARN);
+        assert_eq!(host_level(&Level::ERROR), 2);
+        assert_eq!(host_level(&Level::TRACE), -2);
+    }
+}
@@ -0,0 +1,307 @@
+use std::borrow::Cow;
+
+use crate::host::escape_html;
+
+use super::tokenizer::StartTag;
+
+/// Elements without content or end tag.
+const VOID: [&str; 14] =
+    ["area", "base", "br", "col", "embed", "hr", "img", "input", "keygen", "link", "meta", "param", "source", "track"];
+
+/// A start tag passed to element handlers of an [`HtmlRewriter`](super::HtmlRewriter).
+///
+/// Content given to the insertion methods is inserted as HTML without escaping; use
+/// [`html::escape`](super::escape) for text. Insertions and removals that concern the
+/// content or end tag are applied when the matching end tag is seen.
+#[derive(Debug)]
+pub struct Element {
+    raw: Vec<u8>,
+    name: String,
+    tag_name: String,
+    attributes: Vec<(String, Option<String>)>,
+    self_closing: bool,
+    attributes_changed: bool,
+    pub(super) before: String,
+    pub(super) after: String,
+    pub(super) prepend: String,
+    pub(super) append: String,
+    pub(super) inner: Option<String>,
+    pub(super) removed: bool,
+    pub(super) unwrapped: bool,
+}
+
+impl Element {
+    pub(super) fn new(tag: StartTag) -> Self {
+        Self {
+            raw: tag.raw,
+            tag_name: tag.name.to_ascii_lowercase(),
+            name: tag.name,
+            attributes: tag.attributes.into_iter().map(|(name, value)| (name, value.map(|v| decode(&v).into_owned()))).collect(),
+            self_closing: tag.self_closing,
+            attributes_changed: false,
+            before: String::new(),
+            after: String::new(),
+            prepend: String::new(),
+            append: String::new(),
+            inner: None,
+            removed: false,
+            unwrapped: false,
+        }
+    }
+
+    /// Tag name in lowercase.
+    pub fn tag_name(&self) -> &str {
+        &self.tag_name
+    }
+
+    /// Whether the element has no content and end tag, as `<img>` or `<br/>`.
+    pub fn is_void(&self) -> bool {
+        self.self_closing || VOID.contains(&self.tag_name.as_str())
+    }
+
+    /// Value of the attribute `name` with character references decoded; `""` for
+    /// attributes without value. Names ignore ASCII case.
+    pub fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_deref().unwrap_or_default())
+    }
+
+    /// Whether the attribute `name` is present.
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.get_attribute(name).is_some()
+    }
+
+    /// All attributes in source order, with decoded values.
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes.iter().map(|(n, v)| (n.as_str(), v.as_deref().unwrap_or_default()))
+    }
+
+    /// Set the attribute `name` to `value`, which is escaped.
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        match self.attributes.iter_mut().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            Some((_, v)) => *v = Some(value.to_string()),
+            None => self.attributes.push((name.to_string(), Some(value.to_string()))),
+        }
+        self.attributes_changed = true;
+    }
+
+    /// Remove the attribute `name`.
+    pub fn remove_attribute(&mut self, name: &str) {
+        let len = self.attributes.len();
+        self.attributes.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        self.attributes_changed |= self.attributes.len() != len;
+    }
+
+    /// Insert `html` before the start tag.
+    pub fn before(&mut self, html: &str) {
+        self.before.push_str(html);
+    }
+
+    /// Insert `html` after the element, i.e. after its end tag; insertions of
+    /// repeated calls are closest to the element.
+    pub fn after(&mut self, html: &str) {
+        self.after.insert_str(0, html);
+    }
+
+    /// Insert `html` at the start of the content; insertions of repeated calls are
+    /// closest to the start tag. Ignored for void elements.
+    pub fn prepend(&mut self, html: &str) {
+        self.prepend.insert_str(0, html);
+    }
+
+    /// Insert `html` at the end of the content, before the end tag. Ignored for void
+    /// elements.
+    pub fn append(&mut self, html: &str) {
+        self.append.push_str(html);
+    }
+
+    /// Replace the content with `html`. Ignored for void elements.
+    pub fn set_inner_content(&mut self, html: &str) {
+        self.inner = Some(html.to_string());
+    }
+
+    /// Remove the element with its content.
+    pub fn remove(&mut self) {
+        self.removed = true;
+    }
+
+    /// Remove the start and end tag, keeping the content.
+    pub fn remove_and_keep_content(&mut self) {
+        self.unwrapped = true;
+    }
+
+    /// Whether a handler changed the element.
+    pub(super) fn is_modified(&self) -> bool {
+        self.attributes_changed
+            || self.removed
+            || self.unwrapped
+            || self.inner.is_some()
+            || !(self.before.is_empty() && self.after.is_empty() && self.prepend.is_empty() && self.append.is_empty())
+    }
+
+    /// The start tag, serialized again if attributes were changed.
+    pub(super) fn start_tag(&self) -> Cow<'_, [u8]> {
+        if !self.attributes_changed {
+            return Cow::Borrowed(&self.raw);
+        }
+        let mut tag = format!("<{}", self.name);
+        for (name, value) in &self.attributes {
+            tag.push(' ');
+            tag.push_str(name);
+            if let Some(value) = value {
+                tag.push_str("=\"");
+                tag.push_str(&escape_html(value));
+                tag.push('"');
+            }
+        }
+        tag.push_str(if self.self_closing { " />" } else { ">" });
+        Cow::Owned(tag.into_bytes())
+    }
+}
+
+/// A piece of text passed to text handlers of an [`HtmlRewriter`](super::HtmlRewriter).
+///
+/// Text is passed on as it arrives, so one text node may be split into several
+/// pieces when the body is rewritten in chunks. Character references are not decoded.
+#[derive(Debug)]
+pub struct Text {
+    raw: Vec<u8>,
+    pub(super) replacement: Option<String>,
+}
+
+impl Text {
+    pub(super) fn new(raw: Vec<u8>) -> Self {
+        Self { raw, replacement: None }
+    }
+
+    /// The text as in the document; invalid UTF-8 is replaced.
+    pub fn as_str(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.raw)
+    }
+
+    /// Replace the text with `html`.
+    pub fn replace(&mut self, html: &str) {
+        self.replacement = Some(html.to_string());
+    }
+
+    /// Remove the text.
+    pub fn remove(&mut self) {
+        self.replace("");
+    }
+
+    pub(super) fn into_bytes(self) -> Vec<u8> {
+        self.replacement.map_or(self.raw, String::into_bytes)
+    }
+}
+
+/// A comment passed to comment handlers of an [`HtmlRewriter`](super::HtmlRewriter).
+#[derive(Debug)]
+pub struct Comment {
+    raw: Vec<u8>,
+    pub(super) replacement: Option<String>,
+}
+
+impl Comment {
+    pub(super) fn new(raw: Vec<u8>) -> Self {
+        Self { raw, replacement: None }
+    }
+
+    /// The text between `<!--` and `-->`.
+    pub fn text(&self) -> Cow<'_, str> {
+        let inner = self.raw.get(4..self.raw.len().saturating_sub(3)).unwrap_or_default();
+        String::from_utf8_lossy(inner)
+    }
+
+    /// Replace the comment with `html`.
+    pub fn replace(&mut self, html: &str) {
+        self.replacement = Some(html.to_string());
+    }
+
+    /// Remove the comment.
+    pub fn remove(&mut self) {
+        self.replace("");
+    }
+
+    pub(super) fn into_bytes(self) -> Vec<u8> {
+        self.replacement.map_or(self.raw, String::into_bytes)
+    }
+}
+
+/// Decode the character references of attribute values that occur in URLs and
+/// text: `&amp;`, `&lt;`, `&gt;`, `&quot;`, `&apos;` and numeric references.
+fn decode(value: &str) -> Cow<'_, str> {
+    if !value.contains('&') {
+        return Cow::Borrowed(value);
+    }
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(rest.get(..amp).unwrap_or_default());
+        rest = rest.get(amp..).unwrap_or_default();
+        let reference = rest.find(';').and_then(|end| Some((rest.get(1..end)?, end + 1)));
+        let decoded = reference.and_then(|(name, len)| {
+            let c = match name {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                _ => {
+                    let number = name.strip_prefix('#')?;
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => number.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, len))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = rest.get(len..).unwrap_or_default();
+            }
+            None => {
+                out.push('&');
+                rest = rest.get(1..).unwrap_or_default();
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_references() {
+        assert_eq!(decode("/search?q=a&amp;page=2"), "/search?q=a&page=2");
+        assert_eq!(decode("&#60;&#x3E;&quot;&unknown; & x"), "<>\"&unknown; & x");
+    }
+
+    #[test]
+    fn serialize_changed_attributes() {
+        let mut element = Element::new(StartTag {
+            raw: b"<IMG SRC=a.png alt>".to_vec(),
+            name: "IMG".into(),
+            attributes: vec![("SRC".into(), Some("a.png".into())), ("alt".into(), None)],
+            self_closing: false,
+        });
+        assert_eq!(element.start_tag().as_ref(), b"<IMG SRC=a.png alt>");
+        assert!(element.is_void());
+        element.set_attribute("src", "b.png?x=\"1\"&y");
+        element.remove_attribute("title");
+        assert_eq!(element.get_attribute("Src"), Some("b.png?x=\"1\"&y"));
+        assert_eq!(element.start_tag().as_ref(), b"<IMG SRC=\"b.png?x=&quot;1&quot;&amp;y\" alt>");
+    }
+
+    #[test]
+    fn comment_text() {
+        let mut comment = Comment::new(b"<!-- build 42 -->".to_vec());
+        assert_eq!(comment.text(), " build 42 ");
+        comment.remove();
+        assert_eq!(comment.into_bytes(), b"");
+    }
+}
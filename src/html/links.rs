@@ -0,0 +1,90 @@
+use crate::proxy::UrlMapping;
+
+use super::{Element, HtmlRewriter, Selector};
+
+/// Attributes holding a single URL, with the elements they occur on.
+const URL_ATTRIBUTES: [(&str, &[&str]); 6] = [
+    ("href", &["a", "area", "base", "link"]),
+    ("src", &["audio", "embed", "iframe", "img", "input", "script", "source", "track", "video"]),
+    ("action", &["form"]),
+    ("formaction", &["button", "input"]),
+    ("poster", &["video"]),
+    ("data", &["object"]),
+];
+
+/// Rewriter that maps the URLs of links, resources and forms with `mapping`, to keep
+/// a proxied service navigable under its public URL.
+///
+/// Rewrites `href`, `src`, `action`, `formaction`, `poster` and `data` attributes of
+/// the elements they apply to, and the URLs of `srcset` lists; URLs the mapping does
+/// not cover are kept.
+///
+/// ```
+/// use http_wasm_guest::{html, proxy::UrlMapping};
+///
+/// let mapping = UrlMapping::new("http://wiki.internal:8080", "https://example.com/wiki");
+/// let html = html::rewrite_links(&mapping).rewrite(br#"<a href="http://wiki.internal:8080/page">page</a>"#);
+/// assert_eq!(html, br#"<a href="https://example.com/wiki/page">page</a>"#);
+/// ```
+pub fn rewrite_links(mapping: &UrlMapping) -> HtmlRewriter<'_> {
+    HtmlRewriter::new().on_element(Selector::any(), move |element| rewrite_element(mapping, element))
+}
+
+fn rewrite_element(mapping: &UrlMapping, element: &mut Element) {
+    for (attribute, tags) in URL_ATTRIBUTES {
+        if !tags.contains(&element.tag_name()) {
+            continue;
+        }
+        if let Some(mapped) = element.get_attribute(attribute).and_then(|url| mapping.map(url.trim())) {
+            element.set_attribute(attribute, &mapped);
+        }
+    }
+    if let Some(srcset) = element.get_attribute("srcset").and_then(|srcset| map_srcset(mapping, srcset)) {
+        element.set_attribute("srcset", &srcset);
+    }
+}
+
+/// Map the URLs of a `srcset` list (`url [descriptor], ...`); `None` if none changed.
+fn map_srcset(mapping: &UrlMapping, srcset: &str) -> Option<String> {
+    let mut changed = false;
+    let candidates: Vec<String> = srcset
+        .split(',')
+        .map(|candidate| {
+            let candidate = candidate.trim();
+            let (url, descriptor) = candidate.split_once(char::is_whitespace).unwrap_or((candidate, ""));
+            match mapping.map(url) {
+                Some(mapped) => {
+                    changed = true;
+                    if descriptor.is_empty() { mapped } else { format!("{mapped} {}", descriptor.trim()) }
+                }
+                None => candidate.to_string(),
+            }
+        })
+        .collect();
+    changed.then(|| candidates.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_url_attributes() {
+        let mapping = UrlMapping::new("http://app:8080", "https://example.com/app").with_root_relative(true);
+        let html = br#"<link rel=stylesheet href=/s.css><a href="http://app:8080/x?a=1&amp;b=2">x</a><img src="https://cdn/y.png" alt=/z><form action=/post></form>"#;
+        assert_eq!(
+            String::from_utf8_lossy(&rewrite_links(&mapping).rewrite(html)),
+            r#"<link rel="stylesheet" href="/app/s.css"><a href="https://example.com/app/x?a=1&amp;b=2">x</a><img src="https://cdn/y.png" alt=/z><form action="/app/post"></form>"#
+        );
+    }
+
+    #[test]
+    fn rewrites_srcset() {
+        let mapping = UrlMapping::new("http://app", "https://example.com");
+        assert_eq!(
+            map_srcset(&mapping, "http://app/a.png 1x,  https://cdn/b.png 2x, http://app/c.png").as_deref(),
+            Some("https://example.com/a.png 1x, https://cdn/b.png 2x, https://example.com/c.png")
+        );
+        assert_eq!(map_srcset(&mapping, "/a.png 1x"), None);
+    }
+}
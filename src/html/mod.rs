@@ -0,0 +1,426 @@
+//! Rewriting of HTML documents.
+//!
+//! An [`HtmlRewriter`] runs handlers for the elements, text and comments of a
+//! document while it is tokenized, and produces the rewritten document. It covers
+//! injection of snippets, rewriting of links and removal of markup from one API,
+//! similar to `lol_html`, but without a tree builder: elements are closed by their
+//! matching end tag or by the end of an enclosing element, and content is never
+//! reparented. The input may be passed in chunks; only incomplete markup at the end
+//! of a chunk is held back.
+//!
+//! ```
+//! use http_wasm_guest::html::{HtmlRewriter, Selector};
+//!
+//! let mut rewriter = HtmlRewriter::new()
+//!     .on_element(Selector::new("a[target=_blank]").unwrap(), |a| a.set_attribute("rel", "noopener"))
+//!     .on_element(Selector::tag("script"), |script| script.remove())
+//!     .on_comment(|comment| comment.remove());
+//! let html = rewriter.rewrite(b"<a href=/x target=_blank>x</a><!-- y --><script>z()</script>");
+//! assert_eq!(html, b"<a href=\"/x\" target=\"_blank\" rel=\"noopener\">x</a>");
+//! ```
+use std::ops::ControlFlow;
+
+use crate::host::{Body, Response, escape_html};
+
+mod element;
+mod links;
+mod selector;
+mod tokenizer;
+
+pub use element::{Comment, Element, Text};
+pub use links::rewrite_links;
+pub use selector::{Selector, SelectorError};
+use tokenizer::{Token, Tokenizer};
+
+/// Escape `text` for insertion as HTML content or attribute value.
+pub fn escape(text: &str) -> String {
+    escape_html(text)
+}
+
+/// Rewriter that inserts `snippet` before the end tag of each `tag` element, e.g. an
+/// analytics or consent script before `</head>` or `</body>`.
+///
+/// If the end tag is missing, the snippet is inserted at the end of the document;
+/// documents without the element are not changed.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     Guest,
+///     host::{Request, Response, admin, feature},
+///     html,
+/// };
+///
+/// const SNIPPET: &str = r#"<script src="/consent.js" defer></script>"#;
+///
+/// struct Consent;
+///
+/// impl Guest for Consent {
+///     fn handle_request(&self, _request: &Request, _response: &Response) -> (bool, i32) {
+///         admin::enable(feature::BufferResponse);
+///         (true, 0)
+///     }
+///
+///     fn handle_response(&self, _req_ctx: i32, _request: &Request, response: &Response, _is_error: bool) {
+///         html::inject_before_end_of(b"head", SNIPPET).rewrite_response(response);
+///     }
+/// }
+/// ```
+pub fn inject_before_end_of<'h>(tag: &[u8], snippet: &'h str) -> HtmlRewriter<'h> {
+    HtmlRewriter::new().on_element(Selector::tag(&String::from_utf8_lossy(tag)), move |element| element.append(snippet))
+}
+
+type ElementHandler<'h> = Box<dyn FnMut(&mut Element) + 'h>;
+type TextHandler<'h> = Box<dyn FnMut(&mut Text) + 'h>;
+type CommentHandler<'h> = Box<dyn FnMut(&mut Comment) + 'h>;
+
+/// Streaming HTML rewriter with element, text and comment handlers.
+///
+/// Element handlers run for each start tag matching their selector, in the order they
+/// were added. Text handlers run for text whose innermost enclosing element matched
+/// their selector at its start tag; text of `<script>`, `<style>`, `<textarea>` and
+/// `<title>` is text as well. Markup inside removed or replaced content is skipped
+/// without running handlers.
+#[derive(Default)]
+pub struct HtmlRewriter<'h> {
+    element_handlers: Vec<(Selector, ElementHandler<'h>)>,
+    text_handlers: Vec<(Selector, TextHandler<'h>)>,
+    comment_handlers: Vec<CommentHandler<'h>>,
+    tokenizer: Tokenizer,
+    open: Vec<Open>,
+    out: Vec<u8>,
+    modified: bool,
+}
+
+/// An element whose end tag has not been seen yet.
+struct Open {
+    name: String,
+    append: String,
+    after: String,
+    /// Content is removed or replaced.
+    skip: bool,
+    /// The end tag is removed.
+    drop_end: bool,
+    text_handlers: Vec<usize>,
+}
+
+impl<'h> HtmlRewriter<'h> {
+    /// Create a rewriter without handlers, which passes documents through unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a handler for start tags matching `selector`.
+    pub fn on_element(mut self, selector: Selector, handler: impl FnMut(&mut Element) + 'h) -> Self {
+        self.element_handlers.push((selector, Box::new(handler)));
+        self
+    }
+
+    /// Add a handler for text directly inside elements matching `selector`.
+    pub fn on_text(mut self, selector: Selector, handler: impl FnMut(&mut Text) + 'h) -> Self {
+        self.text_handlers.push((selector, Box::new(handler)));
+        self
+    }
+
+    /// Add a handler for all comments.
+    pub fn on_comment(mut self, handler: impl FnMut(&mut Comment) + 'h) -> Self {
+        self.comment_handlers.push(Box::new(handler));
+        self
+    }
+
+    /// Rewrite the next chunk of the document; returns the output that is complete.
+    pub fn write(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.feed(chunk, false);
+        std::mem::take(&mut self.out)
+    }
+
+    /// End the document; returns the remaining output.
+    ///
+    /// Pending content of elements without end tag, as [`Element::append`], is written
+    /// at the end. The rewriter can be reused for the next document afterwards.
+    pub fn end(&mut self) -> Vec<u8> {
+        self.feed(b"", true);
+        let open = std::mem::take(&mut self.open);
+        self.close(open, None);
+        std::mem::take(&mut self.out)
+    }
+
+    /// Rewrite a complete document.
+    pub fn rewrite(&mut self, html: &[u8]) -> Vec<u8> {
+        let mut out = self.write(html);
+        out.extend(self.end());
+        out
+    }
+
+    /// Whether a handler changed the document so far.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Rewrite `body` chunk by chunk; the body is replaced only if a handler changed
+    /// it. Returns whether it was replaced.
+    ///
+    /// The buffering rules of [`Body::read`] apply.
+    pub fn rewrite_body(&mut self, body: &Body) -> bool {
+        self.modified = false;
+        let mut out = Vec::new();
+        body.read_chunks(|chunk| {
+            out.extend(self.write(chunk));
+            ControlFlow::Continue(())
+        });
+        out.extend(self.end());
+        if self.modified {
+            body.write(&out);
+        }
+        self.modified
+    }
+
+    /// Rewrite the body of an HTML response, i.e. with a `text/html` or
+    /// `application/xhtml+xml` `Content-Type` and without `Content-Encoding`.
+    ///
+    /// If the body was changed, the body metadata headers are removed as in
+    /// [`Response::prepare_for_body_rewrite`]. Requires `feature::BufferResponse`.
+    pub fn rewrite_response(&mut self, response: &Response) -> bool {
+        if !is_html(response) {
+            return false;
+        }
+        let rewritten = self.rewrite_body(&response.body);
+        if rewritten {
+            response.prepare_for_body_rewrite();
+        }
+        rewritten
+    }
+
+    fn feed(&mut self, chunk: &[u8], at_end: bool) {
+        let mut tokenizer = std::mem::take(&mut self.tokenizer);
+        tokenizer.feed(chunk, at_end, |token| self.token(token));
+        self.tokenizer = tokenizer;
+    }
+
+    fn token(&mut self, token: Token) {
+        let skipping = self.open.iter().any(|open| open.skip);
+        match token {
+            Token::StartTag(tag) => self.start_tag(Element::new(tag), skipping),
+            Token::EndTag { raw, name } => self.end_tag(&raw, &name, skipping),
+            _ if skipping => {}
+            Token::Text(raw) => self.text(raw),
+            Token::Comment(raw) => self.comment(raw),
+            Token::Other(raw) => self.out.extend(raw),
+        }
+    }
+
+    fn start_tag(&mut self, mut element: Element, skipping: bool) {
+        let void = element.is_void();
+        if skipping {
+            if !void {
+                self.open.push(Open::skipped(element.tag_name()));
+            }
+            return;
+        }
+        for (selector, handler) in &mut self.element_handlers {
+            if selector.matches(&element) {
+                handler(&mut element);
+            }
+        }
+        self.modified |= element.is_modified();
+        self.out.extend(element.before.as_bytes());
+        if !element.removed && !element.unwrapped {
+            self.out.extend(element.start_tag().as_ref());
+        }
+        if void {
+            self.out.extend(element.after.as_bytes());
+            return;
+        }
+        if !element.removed {
+            self.out.extend(element.inner.as_deref().unwrap_or(&element.prepend).as_bytes());
+        }
+        let text_handlers =
+            self.text_handlers.iter().enumerate().filter(|(_, (s, _))| s.matches(&element)).map(|(i, _)| i).collect();
+        self.open.push(Open {
+            name: element.tag_name().to_string(),
+            append: if element.removed { String::new() } else { element.append },
+            after: element.after,
+            skip: element.removed || element.inner.is_some(),
+            drop_end: element.removed || element.unwrapped,
+            text_handlers,
+        });
+    }
+
+    fn end_tag(&mut self, raw: &[u8], name: &str, skipping: bool) {
+        let Some(index) = self.open.iter().rposition(|open| open.name == name) else {
+            if !skipping {
+                self.out.extend(raw);
+            }
+            return;
+        };
+        let closed = self.open.split_off(index);
+        if !self.open.iter().any(|open| open.skip) {
+            self.close(closed, Some(raw));
+        }
+    }
+
+    /// Write the pending content of closed elements, innermost first; `end_tag` ends
+    /// the outermost one.
+    fn close(&mut self, closed: Vec<Open>, end_tag: Option<&[u8]>) {
+        let first_skip = closed.iter().position(|open| open.skip);
+        for (i, open) in closed.into_iter().enumerate().rev() {
+            if first_skip.is_some_and(|skip| skip < i) {
+                continue;
+            }
+            self.out.extend(open.append.as_bytes());
+            if let Some(end_tag) = end_tag.filter(|_| i == 0 && !open.drop_end) {
+                self.out.extend(end_tag);
+            }
+            self.out.extend(open.after.as_bytes());
+        }
+    }
+
+    fn text(&mut self, raw: Vec<u8>) {
+        let handlers = self.open.last().map(|open| open.text_handlers.clone()).unwrap_or_default();
+        if handlers.is_empty() {
+            self.out.extend(raw);
+            return;
+        }
+        let mut text = Text::new(raw);
+        for i in handlers {
+            if let Some((_, handler)) = self.text_handlers.get_mut(i) {
+                handler(&mut text);
+            }
+        }
+        self.modified |= text.replacement.is_some();
+        self.out.extend(text.into_bytes());
+    }
+
+    fn comment(&mut self, raw: Vec<u8>) {
+        let mut comment = Comment::new(raw);
+        for handler in &mut self.comment_handlers {
+            handler(&mut comment);
+        }
+        self.modified |= comment.replacement.is_some();
+        self.out.extend(comment.into_bytes());
+    }
+}
+
+impl Open {
+    fn skipped(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            append: String::new(),
+            after: String::new(),
+            skip: false,
+            drop_end: false,
+            text_handlers: Vec::new(),
+        }
+    }
+}
+
+/// Whether the response has an HTML body that can be rewritten.
+fn is_html(response: &Response) -> bool {
+    response.header.get(b"Content-Encoding").is_none()
+        && response.header.get(b"Content-Type").is_some_and(|ct| {
+            crate::middleware::media_type_matches(&ct, &["text/html".to_string(), "application/xhtml+xml".to_string()])
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite_chunked(rewriter: &mut HtmlRewriter<'_>, html: &[u8], size: usize) -> String {
+        let mut out: Vec<u8> = html.chunks(size).flat_map(|chunk| rewriter.write(chunk)).collect();
+        out.extend(rewriter.end());
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    #[test]
+    fn unchanged_document_is_identical() {
+        let html = b"<!DOCTYPE html><html><head><title>a < b</title></head><body class=x><p>1<br>2</p><!-- c --></body></html>";
+        for size in 1..html.len() {
+            let mut rewriter = HtmlRewriter::new().on_element(Selector::any(), |_| {});
+            assert_eq!(rewrite_chunked(&mut rewriter, html, size).as_bytes(), html, "chunk size {size}");
+            assert!(!rewriter.is_modified());
+        }
+    }
+
+    #[test]
+    fn insert_around_and_inside_elements() {
+        let mut rewriter = HtmlRewriter::new().on_element(Selector::tag("div"), |div| {
+            div.before("[b]");
+            div.prepend("[p]");
+            div.append("[a]");
+            div.after("[f]");
+        });
+        let html = b"<div>x<div>y</div></div><img>";
+        for size in 1..html.len() {
+            assert_eq!(
+                rewrite_chunked(&mut rewriter, html, size),
+                "[b]<div>[p]x[b]<div>[p]y[a]</div>[f][a]</div>[f]<img>",
+                "chunk size {size}"
+            );
+        }
+        assert!(rewriter.is_modified());
+    }
+
+    #[test]
+    fn remove_and_replace_content() {
+        let mut rewriter = HtmlRewriter::new()
+            .on_element(Selector::new(".ad").unwrap(), |e| e.remove())
+            .on_element(Selector::tag("b"), |e| e.remove_and_keep_content())
+            .on_element(Selector::tag("p"), |e| e.set_inner_content("new"));
+        let html = b"<div class=ad><p>x</p></div><b>bold <i>it</i></b><p>old <span>x</span></p>";
+        assert_eq!(rewrite_chunked(&mut rewriter, html, 7), "bold <i>it</i><p>new</p>");
+    }
+
+    #[test]
+    fn text_and_comment_handlers() {
+        let mut rewriter = HtmlRewriter::new()
+            .on_text(Selector::tag("title"), |text| {
+                let title = text.as_str().replace("Internal", "Public");
+                text.replace(&title);
+            })
+            .on_comment(|comment| {
+                if !comment.text().contains("keep") {
+                    comment.remove();
+                }
+            });
+        let html = b"<title>Internal wiki</title><p>Internal</p><!-- x --><!-- keep -->";
+        assert_eq!(rewrite_chunked(&mut rewriter, html, 64), "<title>Public wiki</title><p>Internal</p><!-- keep -->");
+    }
+
+    #[test]
+    fn unclosed_elements_at_end() {
+        let mut rewriter = HtmlRewriter::new().on_element(Selector::tag("body"), |body| body.append("<script></script>"));
+        assert_eq!(rewrite_chunked(&mut rewriter, b"<body><p>x", 3), "<body><p>x<script></script>");
+        // reusable for the next document
+        assert_eq!(rewrite_chunked(&mut rewriter, b"<body></body>", 3), "<body><script></script></body>");
+    }
+
+    #[test]
+    fn implicitly_closed_elements() {
+        let mut rewriter = HtmlRewriter::new().on_element(Selector::tag("li"), |li| li.append("!"));
+        assert_eq!(rewrite_chunked(&mut rewriter, b"<ul><li>a<li>b</ul></span>", 64), "<ul><li>a<li>b!!</ul></span>");
+    }
+
+    #[test]
+    fn inject_snippet() {
+        let mut rewriter = inject_before_end_of(b"HEAD", "<script src=a.js></script>");
+        let html = b"<html><head><title>x</title></head><body></body></html>";
+        assert_eq!(
+            rewrite_chunked(&mut rewriter, html, 5),
+            "<html><head><title>x</title><script src=a.js></script></head><body></body></html>"
+        );
+        assert_eq!(
+            rewrite_chunked(&mut rewriter, b"<head><meta charset=utf-8>", 5),
+            "<head><meta charset=utf-8><script src=a.js></script>"
+        );
+        assert_eq!(rewrite_chunked(&mut rewriter, b"<p>no head</p>", 5), "<p>no head</p>");
+    }
+
+    #[test]
+    fn rewrite_response_requires_html() {
+        // The mock response has no Content-Type header
+        let mut rewriter = HtmlRewriter::new().on_element(Selector::any(), |e| e.remove());
+        assert!(!rewriter.rewrite_response(&Response::new()));
+        // The mock response body is "<html><body>test</body>"
+        assert!(rewriter.rewrite_body(&Response::new().body));
+    }
+}
@@ -0,0 +1,207 @@
+use std::{fmt, str::FromStr};
+
+use super::Element;
+
+/// Simple CSS selector matched against start tags.
+///
+/// Supports type selectors (`a`, `*`), `#id`, `.class` and attribute selectors
+/// (`[href]`, `[rel=nofollow]`, `[type="text/javascript"]`), combined without spaces
+/// (`link[rel=stylesheet]`), and comma-separated lists of those. Combinators and
+/// pseudo-classes are not supported. Tag and attribute names ignore ASCII case,
+/// values are compared exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector(Vec<Compound>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Compound {
+    tag: Option<String>,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    Has(String),
+    Equals(String, String),
+    Class(String),
+}
+
+/// Error returned for selectors with unsupported or invalid syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError {
+    /// The rejected selector.
+    pub selector: String,
+    /// Description of the problem.
+    pub message: &'static str,
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid selector `{}`: {}", self.selector, self.message)
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+impl Selector {
+    /// Parse a selector.
+    pub fn new(selector: &str) -> Result<Self, SelectorError> {
+        let error = |message| SelectorError { selector: selector.to_string(), message };
+        split_list(selector).into_iter().map(|part| compound(part.trim()).map_err(error)).collect::<Result<_, _>>().map(Self)
+    }
+
+    /// Selector for all elements with the tag `name`.
+    pub fn tag(name: &str) -> Self {
+        Self(vec![Compound { tag: Some(name.to_ascii_lowercase()), conditions: Vec::new() }])
+    }
+
+    /// Selector for all elements.
+    pub fn any() -> Self {
+        Self(vec![Compound { tag: None, conditions: Vec::new() }])
+    }
+
+    /// Whether the selector matches the element.
+    pub fn matches(&self, element: &Element) -> bool {
+        self.0.iter().any(|compound| {
+            compound.tag.as_ref().is_none_or(|tag| element.tag_name() == tag)
+                && compound.conditions.iter().all(|condition| match condition {
+                    Condition::Has(name) => element.has_attribute(name),
+                    Condition::Equals(name, value) => element.get_attribute(name) == Some(value.as_str()),
+                    Condition::Class(class) => {
+                        element.get_attribute("class").is_some_and(|classes| classes.split_ascii_whitespace().any(|c| c == class))
+                    }
+                })
+        })
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        Self::new(selector)
+    }
+}
+
+/// Split at commas outside of attribute selectors.
+fn split_list(selector: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_brackets = false;
+    for (i, c) in selector.char_indices() {
+        match c {
+            '[' => in_brackets = true,
+            ']' => in_brackets = false,
+            ',' if !in_brackets => {
+                parts.push(selector.get(start..i).unwrap_or_default());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(selector.get(start..).unwrap_or_default());
+    parts
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+fn compound(part: &str) -> Result<Compound, &'static str> {
+    if part.is_empty() {
+        return Err("empty selector");
+    }
+    let ident_len = |s: &str| s.find(|c| !is_ident(c)).unwrap_or(s.len());
+    let (tag, mut rest) = if let Some(rest) = part.strip_prefix('*') {
+        (None, rest)
+    } else {
+        let len = ident_len(part);
+        ((len > 0).then(|| part.get(..len).unwrap_or_default().to_ascii_lowercase()), part.get(len..).unwrap_or_default())
+    };
+    let mut conditions = Vec::new();
+    while let Some(c) = rest.chars().next() {
+        let tail = rest.get(1..).unwrap_or_default();
+        match c {
+            '#' | '.' => {
+                let len = ident_len(tail);
+                if len == 0 {
+                    return Err("expected a name after `#` or `.`");
+                }
+                let name = tail.get(..len).unwrap_or_default().to_string();
+                conditions.push(if c == '#' { Condition::Equals("id".into(), name) } else { Condition::Class(name) });
+                rest = tail.get(len..).unwrap_or_default();
+            }
+            '[' => {
+                let end = tail.find(']').ok_or("unterminated attribute selector")?;
+                conditions.push(attribute(tail.get(..end).unwrap_or_default())?);
+                rest = tail.get(end + 1..).unwrap_or_default();
+            }
+            c if c.is_whitespace() || c == '>' || c == '+' || c == '~' => return Err("combinators are not supported"),
+            ':' => return Err("pseudo-classes are not supported"),
+            _ => return Err("unexpected character"),
+        }
+    }
+    Ok(Compound { tag, conditions })
+}
+
+fn attribute(inner: &str) -> Result<Condition, &'static str> {
+    let Some((name, value)) = inner.split_once('=') else {
+        let name = inner.trim();
+        if name.is_empty() || !name.chars().all(is_ident) {
+            return Err("invalid attribute name");
+        }
+        return Ok(Condition::Has(name.to_ascii_lowercase()));
+    };
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(is_ident) {
+        return Err("only `[name]` and `[name=value]` attribute selectors are supported");
+    }
+    let value = value.trim();
+    let unquoted = ['"', '\''].into_iter().find_map(|q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q))).unwrap_or(value);
+    Ok(Condition::Equals(name.to_ascii_lowercase(), unquoted.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::tokenizer::StartTag;
+
+    fn element(name: &str, attributes: &[(&str, &str)]) -> Element {
+        Element::new(StartTag {
+            raw: Vec::new(),
+            name: name.into(),
+            attributes: attributes.iter().map(|(n, v)| (n.to_string(), Some(v.to_string()))).collect(),
+            self_closing: false,
+        })
+    }
+
+    fn matches(selector: &str, element: &Element) -> bool {
+        Selector::new(selector).is_ok_and(|s| s.matches(element))
+    }
+
+    #[test]
+    fn compound_selectors() {
+        let link = element("A", &[("HREF", "/x"), ("class", "nav  external"), ("id", "home")]);
+        assert!(matches("a", &link));
+        assert!(matches("*", &link));
+        assert!(matches("a[href]", &link));
+        assert!(matches("[href=\"/x\"]", &link));
+        assert!(matches("a.external#home", &link));
+        assert!(matches("img, a[href='/x']", &link));
+        assert!(!matches("a[href=/y]", &link));
+        assert!(!matches("a.nav-external", &link));
+        assert!(!matches("img", &link));
+    }
+
+    #[test]
+    fn unsupported_syntax() {
+        assert_eq!(Selector::new("div a").map_err(|e| e.message), Err("combinators are not supported"));
+        assert_eq!(Selector::new("a:hover").map_err(|e| e.message), Err("pseudo-classes are not supported"));
+        assert!(Selector::new("a[href^=http]").is_err());
+        assert!(Selector::new("a,").is_err());
+        assert!(Selector::new("a[href").is_err());
+        assert_eq!(
+            "div a".parse::<Selector>().map_err(|e| e.to_string()),
+            Err("invalid selector `div a`: combinators are not supported".into())
+        );
+    }
+}
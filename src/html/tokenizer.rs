@@ -0,0 +1,288 @@
+/// Elements whose content is text up to the matching end tag.
+const RAW_TEXT: [&str; 4] = ["script", "style", "textarea", "title"];
+
+/// Lexical unit of an HTML document; each carries its source bytes.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Token {
+    Text(Vec<u8>),
+    StartTag(StartTag),
+    EndTag {
+        raw: Vec<u8>,
+        name: String,
+    },
+    Comment(Vec<u8>),
+    /// Doctype, processing instruction or CDATA section.
+    Other(Vec<u8>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct StartTag {
+    pub raw: Vec<u8>,
+    /// Name as written.
+    pub name: String,
+    /// Attribute names as written with their raw values, without quotes.
+    pub attributes: Vec<(String, Option<String>)>,
+    pub self_closing: bool,
+}
+
+/// Incremental HTML tokenizer.
+///
+/// Input may be split anywhere; incomplete markup is kept until the next chunk.
+/// Text is emitted as it arrives, so a text node may span several tokens.
+#[derive(Default)]
+pub(super) struct Tokenizer {
+    pending: Vec<u8>,
+    raw_text: Option<&'static str>,
+}
+
+impl Tokenizer {
+    /// Tokenize `chunk`; with `at_end`, incomplete markup is flushed as text.
+    pub fn feed(&mut self, chunk: &[u8], at_end: bool, mut emit: impl FnMut(Token)) {
+        self.pending.extend_from_slice(chunk);
+        let mut pos = 0;
+        while let Some((token, len)) = next(self.pending.get(pos..).unwrap_or_default(), &mut self.raw_text, at_end) {
+            emit(token);
+            pos += len;
+        }
+        self.pending.drain(..pos);
+    }
+}
+
+/// The next complete token of `input` and its length.
+fn next(input: &[u8], raw_text: &mut Option<&'static str>, at_end: bool) -> Option<(Token, usize)> {
+    let first = *input.first()?;
+    if let Some(name) = *raw_text {
+        return match find_end_tag(input, name) {
+            Some(0) => {
+                let token = markup(input, at_end)?;
+                *raw_text = None;
+                Some(token)
+            }
+            Some(end) => Some(text(input, end)),
+            None if at_end => Some(text(input, input.len())),
+            // hold back a tail that may be the start of the end tag
+            None => {
+                let end = input.iter().rposition(|b| *b == b'<').filter(|i| input.len() - i <= name.len() + 2);
+                Some(end.unwrap_or(input.len())).filter(|end| *end > 0).map(|end| text(input, end))
+            }
+        };
+    }
+    if first != b'<' {
+        return Some(text(input, find(input, b"<", 0).unwrap_or(input.len())));
+    }
+    let token = markup(input, at_end)?;
+    if let (Token::StartTag(tag), _) = &token {
+        if !tag.self_closing {
+            *raw_text = RAW_TEXT.into_iter().find(|name| tag.name.eq_ignore_ascii_case(name));
+        }
+    }
+    Some(token)
+}
+
+/// Tokenize markup starting with `<`; returns `None` if more input is needed.
+fn markup(input: &[u8], at_end: bool) -> Option<(Token, usize)> {
+    let rest = input.get(1..).unwrap_or_default();
+    let complete = |end: Option<usize>, token: fn(Vec<u8>) -> Token| match end {
+        Some(end) => Some((token(input.get(..end).unwrap_or_default().to_vec()), end)),
+        None if at_end => Some(text(input, input.len())),
+        None => None,
+    };
+    if !at_end && input.len() < 4 && b"<!--".starts_with(input) {
+        return None;
+    }
+    if rest.starts_with(b"!--") {
+        return complete(find(input, b"-->", 4).map(|i| i + 3), Token::Comment);
+    }
+    match rest.first() {
+        Some(b'!' | b'?') => complete(find(input, b">", 1).map(|i| i + 1), Token::Other),
+        Some(b'/') => match rest.get(1) {
+            Some(b) if b.is_ascii_alphabetic() => match find(input, b">", 2) {
+                Some(i) => {
+                    let raw = input.get(..=i).unwrap_or_default();
+                    let name = raw.get(2..).unwrap_or_default();
+                    let len = name.iter().position(|b| b.is_ascii_whitespace() || matches!(b, b'/' | b'>')).unwrap_or(name.len());
+                    let name = String::from_utf8_lossy(name.get(..len).unwrap_or_default()).to_ascii_lowercase();
+                    Some((Token::EndTag { raw: raw.to_vec(), name }, i + 1))
+                }
+                None if at_end => Some(text(input, input.len())),
+                None => None,
+            },
+            None if !at_end => None,
+            _ => Some(literal_lt(input)),
+        },
+        Some(b) if b.is_ascii_alphabetic() => match tag_end(input) {
+            Some(end) => {
+                let raw = input.get(..end).unwrap_or_default();
+                Some((Token::StartTag(start_tag(raw)), end))
+            }
+            None if at_end => Some(text(input, input.len())),
+            None => None,
+        },
+        None if !at_end => None,
+        _ => Some(literal_lt(input)),
+    }
+}
+
+fn text(input: &[u8], end: usize) -> (Token, usize) {
+    (Token::Text(input.get(..end).unwrap_or_default().to_vec()), end)
+}
+
+/// A `<` that does not start markup, with the text up to the next `<`.
+fn literal_lt(input: &[u8]) -> (Token, usize) {
+    text(input, find(input, b"<", 1).unwrap_or(input.len()))
+}
+
+fn find(input: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    input.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|i| i + from)
+}
+
+/// Offset of `</name` followed by whitespace, `/` or `>`, ignoring ASCII case.
+fn find_end_tag(input: &[u8], name: &str) -> Option<usize> {
+    let len = name.len() + 2;
+    input.windows(len + 1).position(|w| {
+        w.starts_with(b"</")
+            && w.get(2..len).is_some_and(|n| n.eq_ignore_ascii_case(name.as_bytes()))
+            && w.get(len).is_some_and(|b| b.is_ascii_whitespace() || matches!(b, b'/' | b'>'))
+    })
+}
+
+/// Length of the start tag at the beginning of `input`, skipping `>` in quoted values.
+fn tag_end(input: &[u8]) -> Option<usize> {
+    let mut quote = None;
+    for (i, b) in input.iter().enumerate() {
+        match (quote, b) {
+            (None, b'"' | b'\'')
+                if i.checked_sub(1).and_then(|p| input.get(p)).is_some_and(|p| *p == b'=' || p.is_ascii_whitespace()) =>
+            {
+                quote = Some(*b)
+            }
+            (None, b'>') => return Some(i + 1),
+            (Some(q), b) if q == *b => quote = None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn start_tag(raw: &[u8]) -> StartTag {
+    let inner = raw.get(1..raw.len() - 1).unwrap_or_default();
+    let is_name_end = |b: &u8| b.is_ascii_whitespace() || matches!(b, b'/' | b'>' | b'=');
+    let name_len = inner.iter().position(is_name_end).unwrap_or(inner.len());
+    let name = String::from_utf8_lossy(inner.get(..name_len).unwrap_or_default()).into_owned();
+    let mut attributes = Vec::new();
+    let mut pos = name_len;
+    let mut value_end = 0;
+    loop {
+        let rest = inner.get(pos..).unwrap_or_default();
+        let skip = rest.iter().take_while(|b| b.is_ascii_whitespace() || **b == b'/').count();
+        pos += skip;
+        let rest = inner.get(pos..).unwrap_or_default();
+        if rest.is_empty() {
+            break;
+        }
+        // a name may start with `=`
+        let len = 1 + rest.get(1..).unwrap_or_default().iter().position(is_name_end).unwrap_or(rest.len() - 1);
+        let attr_name = String::from_utf8_lossy(rest.get(..len).unwrap_or_default()).into_owned();
+        pos += len;
+        let after_name = inner.get(pos..).unwrap_or_default();
+        let ws = after_name.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        if after_name.get(ws) != Some(&b'=') {
+            attributes.push((attr_name, None));
+            continue;
+        }
+        pos += ws + 1;
+        let rest = inner.get(pos..).unwrap_or_default();
+        pos += rest.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        let rest = inner.get(pos..).unwrap_or_default();
+        let (value, len) = match rest.first() {
+            Some(q @ (b'"' | b'\'')) => {
+                let body = rest.get(1..).unwrap_or_default();
+                let end = body.iter().position(|b| b == q).unwrap_or(body.len());
+                (body.get(..end).unwrap_or_default(), (end + 2).min(rest.len()))
+            }
+            _ => {
+                let end = rest.iter().position(|b| b.is_ascii_whitespace()).unwrap_or(rest.len());
+                (rest.get(..end).unwrap_or_default(), end)
+            }
+        };
+        attributes.push((attr_name, Some(String::from_utf8_lossy(value).into_owned())));
+        pos += len;
+        value_end = pos;
+    }
+    // `<a href=x/>` is not self-closing, the value is `x/`
+    let self_closing = inner.ends_with(b"/") && value_end < inner.len();
+    StartTag { raw: raw.to_vec(), name, attributes, self_closing }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(chunks: &[&[u8]]) -> Vec<Token> {
+        let mut tokenizer = Tokenizer::default();
+        let mut tokens = Vec::new();
+        for chunk in chunks {
+            tokenizer.feed(chunk, false, |t| tokens.push(t));
+        }
+        tokenizer.feed(b"", true, |t| tokens.push(t));
+        tokens
+    }
+
+    fn kinds(tokens: &[Token]) -> Vec<String> {
+        tokens
+            .iter()
+            .map(|t| match t {
+                Token::Text(raw) => format!("text:{}", String::from_utf8_lossy(raw)),
+                Token::StartTag(tag) => format!("start:{}", tag.name),
+                Token::EndTag { name, .. } => format!("end:{name}"),
+                Token::Comment(raw) => format!("comment:{}", String::from_utf8_lossy(raw)),
+                Token::Other(raw) => format!("other:{}", String::from_utf8_lossy(raw)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn document() {
+        let tokens = tokens(&[b"<!DOCTYPE html><p class=x>a &lt; b<!-- c --></P>"]);
+        assert_eq!(kinds(&tokens), ["other:<!DOCTYPE html>", "start:p", "text:a &lt; b", "comment:<!-- c -->", "end:p"]);
+    }
+
+    #[test]
+    fn markup_split_across_chunks() {
+        let tokens = tokens(&[b"<a hr", b"ef=\"/x>y\">li", b"nk</", b"a><!", b"-- x -", b"->"]);
+        assert_eq!(kinds(&tokens), ["start:a", "text:li", "text:nk", "end:a", "comment:<!-- x -->"]);
+        assert!(matches!(tokens.first(), Some(Token::StartTag(tag)) if tag.attributes == [("href".into(), Some("/x>y".into()))]));
+    }
+
+    #[test]
+    fn attributes() {
+        let tag = start_tag(b"<input type='text' disabled value = \"a b\" data-x=1 />");
+        assert_eq!(tag.name, "input");
+        assert!(tag.self_closing);
+        assert_eq!(
+            tag.attributes,
+            [
+                ("type".into(), Some("text".into())),
+                ("disabled".into(), None),
+                ("value".into(), Some("a b".into())),
+                ("data-x".into(), Some("1".into())),
+            ]
+        );
+        let tag = start_tag(b"<a href=x/>");
+        assert!(!tag.self_closing);
+        assert_eq!(tag.attributes, [("href".into(), Some("x/".into()))]);
+    }
+
+    #[test]
+    fn raw_text_elements() {
+        let tokens = tokens(&[b"<script>if (a<b) x = '</p>'; </scr", b"ipt ><p>"]);
+        assert_eq!(kinds(&tokens), ["start:script", "text:if (a<b) x = '</p>'; ", "end:script", "start:p"]);
+    }
+
+    #[test]
+    fn stray_less_than_and_unterminated_markup() {
+        assert_eq!(kinds(&tokens(&[b"1 < 2 <", b"3"])), ["text:1 ", "text:< 2 ", "text:<3"]);
+        assert_eq!(kinds(&tokens(&[b"x<a href=\"y"])), ["text:x", "text:<a href=\"y"]);
+        assert_eq!(kinds(&tokens(&[b"<!-- open"])), ["text:<!-- open"]);
+    }
+}
@@ -4,13 +4,35 @@
 //! requests and responses within a host runtime. Implement [`Guest`] and
 //! call [`register`] to wire up your plugin entry points.
 
-use crate::host::{Request, Response};
-#[cfg(not(test))]
-use crate::memory::SyncCell;
+use std::cell::{Cell, RefCell};
 
+use crate::host::{Extensions, Request, Response, admin, feature::Feature, log};
+
+pub mod cache;
+mod codec;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod digest;
+pub mod form;
+pub mod headers;
 /// Host interface for requests, responses, logging, and feature management.
 pub mod host;
+pub mod html;
 mod memory;
+pub mod metrics;
+pub mod middleware;
+pub mod multipart;
+pub mod proxy;
+pub mod redact;
+pub mod scan;
+pub mod signature;
+pub mod sigv4;
+pub mod span;
+mod state;
+pub mod tls;
+pub mod trace;
+#[cfg(feature = "xml")]
+pub mod xml;
 
 struct Handler {
     guest: Box<dyn Guest>,
@@ -36,68 +58,135 @@ pub trait Guest {
     /// Use this hook to inspect or mutate headers and body before the response
     /// is sent back to the client.
     fn handle_response(&self, _req_ctx: i32, _request: &Request, _response: &Response, _is_error: bool) {}
-}
 
-#[cfg(not(test))]
-static GUEST: SyncCell<Option<Handler>> = SyncCell::new(None);
+    /// Host features the guest requires, such as `BufferResponse` for rewriting bodies.
+    ///
+    /// [`register`] enables them and logs an error naming any feature the host denies.
+    /// The default requires none.
+    fn features(&self) -> Feature {
+        Feature::default()
+    }
+}
 
-#[cfg(test)]
-thread_local! {
-    static GUEST: std::cell::UnsafeCell<Option<Handler>> = const { std::cell::UnsafeCell::new(None) };
+/// Outcome of [`Guest::handle_request`] as a type instead of a `(bool, i32)` tuple.
+///
+/// Convert it with `.into()` when returning from `handle_request`:
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     Decision, Guest,
+///     host::{Request, Response},
+/// };
+///
+/// struct Plugin;
+///
+/// impl Guest for Plugin {
+///     fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+///         if request.header.get(b"Authorization").is_none() {
+///             return response.deny(401, "unauthorized").into();
+///         }
+///         Decision::Continue(0).into()
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Pass the request to the upstream and hand the context value to `handle_response`.
+    Continue(i32),
+    /// Stop processing and send the response configured by the guest.
+    Stop,
 }
 
-#[cfg(not(test))]
-fn with_guest<R>(f: impl FnOnce(&mut Option<Handler>) -> R) -> R {
-    // SAFETY: WASM guest is single-threaded.
-    let g = unsafe { &mut *GUEST.get() };
-    f(g)
+impl From<Decision> for (bool, i32) {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Continue(ctx) => (true, ctx),
+            Decision::Stop => (false, 0),
+        }
+    }
 }
 
-#[cfg(test)]
-fn with_guest<R>(f: impl FnOnce(&mut Option<Handler>) -> R) -> R {
-    GUEST.with(|cell| {
-        // SAFETY: thread-local; no cross-thread aliasing.
-        let g = unsafe { &mut *cell.get() };
-        f(g)
-    })
+thread_local! {
+    /// The registered guest.
+    ///
+    /// Thread-local storage keeps the guest on the thread that registered it, so it
+    /// needs to be neither `Send` nor `Sync`. A WASM guest has a single thread, where
+    /// this compiles down to a plain static.
+    static GUEST: RefCell<Option<Handler>> = const { RefCell::new(None) };
+
+    /// Extensions of a request that continued, kept for its `handle_response`.
+    static EXTENSIONS: Cell<Option<Extensions>> = const { Cell::new(None) };
 }
 
 /// Register a guest plugin implementation with the runtime.
 ///
 /// Call this once from your guest module initialization to install your
-/// [`Guest`] implementation. Subsequent calls are ignored.
+/// [`Guest`] implementation. Subsequent calls are ignored. The features returned by
+/// [`Guest::features`] are enabled on the host.
+///
+/// The guest is never shared across threads, so it may keep per-plugin state in
+/// [`GuestState`], `Cell`, `RefCell` or `Rc` without any `Send` or `Sync` bounds.
 pub fn register<T: Guest + 'static>(guest: T) {
-    with_guest(|g| {
-        if g.is_none() {
-            *g = Some(Handler { guest: Box::new(guest) });
+    let features = guest.features();
+    let registered = GUEST.with(|g| {
+        // Borrowed while a handler runs; a guest is registered then anyway.
+        match g.try_borrow_mut() {
+            Ok(mut g) if g.is_none() => {
+                *g = Some(Handler { guest: Box::new(guest) });
+                true
+            }
+            _ => false,
         }
     });
+    if registered && !features.is_empty() {
+        enable_required(features);
+    }
+}
+
+/// Enable `features` and log an error if the host denies any of them; returns the
+/// denied features.
+fn enable_required(features: Feature) -> Feature {
+    let denied = features.difference(Feature::from_bits(admin::enable(features)));
+    if !denied.is_empty() {
+        let message = format!("host denied features required by the guest: {}", denied.names().join(", "));
+        log::write(2, message.as_bytes());
+    }
+    denied
 }
 
-#[unsafe(export_name = "handle_request")]
+#[cfg_attr(not(feature = "wasip2"), unsafe(export_name = "handle_request"))]
+#[cfg_attr(all(feature = "wasip2", not(feature = "wit")), unsafe(export_name = "handle-request"))]
 fn http_request() -> i64 {
-    with_guest(|g| {
-        let (next, ctx_next) = match g {
-            Some(handler) => handler.guest.handle_request(&Request::new(), &Response::new()),
-            None => (true, 0),
-        };
-        if next { (ctx_next as i64) << 32 | 1 } else { 0 }
-    })
+    let request = Request::new();
+    let (next, ctx_next) = GUEST.with(|g| match &*g.borrow() {
+        Some(handler) => handler.guest.handle_request(&request, &Response::new()),
+        None => (true, 0),
+    });
+    EXTENSIONS.with(|e| e.set(next.then(|| request.into_extensions())));
+    if next { (ctx_next as i64) << 32 | 1 } else { 0 }
 }
 
-#[unsafe(export_name = "handle_response")]
+#[cfg_attr(not(feature = "wasip2"), unsafe(export_name = "handle_response"))]
+#[cfg_attr(all(feature = "wasip2", not(feature = "wit")), unsafe(export_name = "handle-response"))]
 fn http_response(req_ctx: i32, is_error: i32) {
-    with_guest(|g| {
-        if let Some(handler) = g {
-            handler.guest.handle_response(req_ctx, &Request::new(), &Response::new(), is_error == 1);
+    let request = Request::with_extensions(EXTENSIONS.with(Cell::take).unwrap_or_default());
+    GUEST.with(|g| {
+        if let Some(handler) = &*g.borrow() {
+            handler.guest.handle_response(req_ctx, &request, &Response::new(), is_error == 1);
         }
     });
 }
 
+pub use state::GuestState;
+
 #[cfg(feature = "log")]
 mod host_logger;
 #[cfg(feature = "log")]
-pub use host_logger::HostLogger;
+pub use host_logger::{HostLogger, LogFilter, LogFormat, LogFormatter, LogSampling};
+#[cfg(feature = "tracing")]
+mod host_subscriber;
+#[cfg(feature = "tracing")]
+pub use host_subscriber::HostSubscriber;
 
 #[cfg(test)]
 mod tests {
@@ -129,6 +218,12 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decision_into_tuple() {
+        assert_eq!(<(bool, i32)>::from(Decision::Continue(7)), (true, 7));
+        assert_eq!(<(bool, i32)>::from(Decision::Stop), (false, 0));
+    }
+
     #[test]
     fn guest_default_implementation() {
         struct DefaultGuest;
@@ -445,4 +540,102 @@ mod tests {
         // Should still have the continue bit set
         assert_eq!(result & 1, 1);
     }
+
+    #[test]
+    fn register_accepts_guest_without_send_or_sync() {
+        struct Counter {
+            calls: std::rc::Rc<std::cell::Cell<i32>>,
+        }
+        impl Guest for Counter {
+            fn handle_request(&self, _request: &Request, _response: &Response) -> (bool, i32) {
+                self.calls.set(self.calls.get() + 1);
+                (true, self.calls.get())
+            }
+        }
+
+        let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+        register(Counter { calls: calls.clone() });
+        assert_eq!(http_request(), 1 << 32 | 1);
+        assert_eq!(http_request(), 2 << 32 | 1);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn extensions_pass_from_request_to_response() {
+        struct Tagger {
+            seen: Arc<AtomicI32>,
+        }
+        impl Guest for Tagger {
+            fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+                request.extensions().insert(5i32);
+                (true, 1)
+            }
+            fn handle_response(&self, _req_ctx: i32, request: &Request, _response: &Response, _is_error: bool) {
+                self.seen.store(request.extensions().get::<i32>().unwrap_or(-1), Ordering::SeqCst);
+            }
+        }
+
+        let seen = Arc::new(AtomicI32::new(0));
+        register(Tagger { seen: seen.clone() });
+        http_request();
+        http_response(1, 0);
+        assert_eq!(seen.load(Ordering::SeqCst), 5);
+        // handed over once
+        http_response(1, 0);
+        assert_eq!(seen.load(Ordering::SeqCst), -1);
+    }
+
+    #[test]
+    fn extensions_dropped_when_request_stops() {
+        struct Stopper;
+        impl Guest for Stopper {
+            fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+                request.extensions().insert(5i32);
+                (false, 0)
+            }
+        }
+
+        register(Stopper);
+        assert_eq!(http_request(), 0);
+        assert!(EXTENSIONS.with(Cell::take).is_none());
+    }
+
+    #[test]
+    fn register_enables_required_features() {
+        struct Rewriter;
+        impl Guest for Rewriter {
+            fn features(&self) -> Feature {
+                feature::BufferRequest | feature::BufferResponse
+            }
+        }
+
+        assert!(Rewriter.features().contains(feature::BufferResponse));
+        assert!(SimplePlugin.features().is_empty());
+        host::test::set_feature_grant(i32::from(feature::BufferRequest));
+        assert_eq!(enable_required(Rewriter.features()), feature::BufferResponse);
+        host::test::set_feature_grant(7);
+        assert!(enable_required(Rewriter.features()).is_empty());
+        register(Rewriter);
+    }
+
+    #[test]
+    fn register_inside_handler_is_ignored() {
+        struct Reentrant;
+        impl Guest for Reentrant {
+            fn handle_request(&self, _request: &Request, _response: &Response) -> (bool, i32) {
+                register(Stop);
+                (true, 7)
+            }
+        }
+        struct Stop;
+        impl Guest for Stop {
+            fn handle_request(&self, _request: &Request, _response: &Response) -> (bool, i32) {
+                (false, 0)
+            }
+        }
+
+        register(Reentrant);
+        assert_eq!(http_request(), 7 << 32 | 1);
+        assert_eq!(http_request(), 7 << 32 | 1);
+    }
 }
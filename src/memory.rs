@@ -58,6 +58,7 @@ impl Buffer {
     pub(crate) fn as_slice(&self) -> &[u8] {
         &self.data
     }
+    #[cfg(test)]
     pub(crate) fn as_mut_slice(&mut self) -> &mut [u8] {
         &mut self.data
     }
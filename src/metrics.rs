@@ -0,0 +1,590 @@
+//! In-guest metrics: counters, gauges and histograms with labels.
+//!
+//! A [`Registry`] owns metric families; the handles returned when registering a family
+//! share its data and can be stored next to the registry in the plugin. Each family
+//! keeps at most [`max_series`](Registry::with_max_series) label combinations, so
+//! labels taken from requests cannot grow the memory without bound; observations of
+//! further combinations are dropped and counted in [`Registry::dropped`].
+//!
+//! ```no_run
+//! use http_wasm_guest::{
+//!     Guest,
+//!     host::{Request, Response, time},
+//!     metrics::{Counter, Histogram, Registry},
+//! };
+//!
+//! struct Plugin {
+//!     registry: Registry,
+//!     requests: Counter,
+//!     latency: Histogram,
+//! }
+//!
+//! impl Plugin {
+//!     fn new() -> Self {
+//!         let registry = Registry::new();
+//!         let requests = registry.counter("plugin_requests_total", "Requests seen by the plugin").expect("valid name");
+//!         let latency = registry.histogram("plugin_check_seconds", "Duration of the check", &[0.001, 0.01, 0.1]).expect("valid name");
+//!         Self { registry, requests, latency }
+//!     }
+//! }
+//!
+//! impl Guest for Plugin {
+//!     fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+//!         let start = time::monotonic_nanos();
+//!         self.requests.inc(&[("method", &request.method().to_string())]);
+//!         self.latency.observe(&[], (time::monotonic_nanos() - start) as f64 / 1e9);
+//!         (true, 0)
+//!     }
+//! }
+//! ```
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{Display, Write},
+    rc::Rc,
+};
+
+use crate::{
+    Decision, GuestState,
+    host::{Request, Response},
+};
+
+/// Default limit of label combinations per family.
+pub const DEFAULT_MAX_SERIES: usize = 100;
+
+/// Default histogram buckets in seconds, as used by Prometheus client libraries.
+pub const DEFAULT_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Error returned when registering a metric family.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricsError {
+    /// The name is not a valid Prometheus metric name.
+    InvalidName(String),
+    /// A family with the same name but another kind is registered.
+    KindMismatch(String),
+}
+
+impl Display for MetricsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetricsError::InvalidName(name) => write!(f, "invalid metric name {name:?}"),
+            MetricsError::KindMismatch(name) => write!(f, "metric {name:?} is registered with another kind"),
+        }
+    }
+}
+
+impl Error for MetricsError {}
+
+/// Kind of a metric family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    /// A value that only increases.
+    Counter,
+    /// A value that can go up and down.
+    Gauge,
+    /// Observations counted in buckets.
+    Histogram,
+}
+
+/// Value of one series of a family.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricValue {
+    /// Current counter value.
+    Counter(u64),
+    /// Current gauge value.
+    Gauge(f64),
+    /// Histogram state.
+    Histogram {
+        /// Upper bounds with the cumulative count of observations less than or equal to them.
+        buckets: Vec<(f64, u64)>,
+        /// Sum of all observations.
+        sum: f64,
+        /// Number of observations.
+        count: u64,
+    },
+}
+
+/// A series: label combination and value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    /// Label names and values, sorted by name.
+    pub labels: Vec<(String, String)>,
+    /// Current value.
+    pub value: MetricValue,
+}
+
+/// Snapshot of a metric family, returned by [`Registry::gather`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricFamily {
+    /// Metric name.
+    pub name: String,
+    /// Help text.
+    pub help: String,
+    /// Kind of all series.
+    pub kind: MetricKind,
+    /// Series ordered by labels.
+    pub series: Vec<Series>,
+}
+
+type Labels = Vec<(String, String)>;
+
+struct Family {
+    name: String,
+    help: String,
+    kind: MetricKind,
+    buckets: Vec<f64>,
+    /// Series sorted by their labels, which are sorted by name.
+    series: GuestState<Vec<(Labels, MetricValue)>>,
+    max_series: usize,
+    dropped: Rc<GuestState<u64>>,
+}
+
+impl Family {
+    /// Update the series of `labels`, creating it if the series limit permits.
+    fn update(&self, labels: &[(&str, &str)], update: impl FnOnce(&mut MetricValue)) {
+        if !labels.iter().all(|(name, _)| is_valid_label_name(name)) {
+            self.dropped.update(|dropped| dropped + 1);
+            return;
+        }
+        let labels = sorted(labels);
+        self.series.with_mut(|series| {
+            let index = match search(series, &labels) {
+                Ok(index) => index,
+                Err(_) if series.len() >= self.max_series => {
+                    self.dropped.update(|dropped| dropped + 1);
+                    return;
+                }
+                Err(index) => {
+                    let key = labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+                    let value = match self.kind {
+                        MetricKind::Counter => MetricValue::Counter(0),
+                        MetricKind::Gauge => MetricValue::Gauge(0.0),
+                        MetricKind::Histogram => {
+                            MetricValue::Histogram { buckets: self.buckets.iter().map(|b| (*b, 0)).collect(), sum: 0.0, count: 0 }
+                        }
+                    };
+                    series.insert(index, (key, value));
+                    index
+                }
+            };
+            if let Some((_, value)) = series.get_mut(index) {
+                update(value);
+            }
+        });
+    }
+}
+
+/// `labels` sorted by name, so the order they are given in does not create new series.
+fn sorted<'a, 'b>(labels: &'a [(&'b str, &'b str)]) -> Cow<'a, [(&'b str, &'b str)]> {
+    match labels.is_sorted_by_key(|(name, _)| *name) {
+        true => Cow::Borrowed(labels),
+        false => {
+            let mut labels = labels.to_vec();
+            labels.sort_by_key(|(name, _)| *name);
+            Cow::Owned(labels)
+        }
+    }
+}
+
+/// Binary search of the series by sorted borrowed `labels`.
+fn search(series: &[(Labels, MetricValue)], labels: &[(&str, &str)]) -> Result<usize, usize> {
+    series.binary_search_by(|(key, _)| key.iter().map(|(k, v)| (k.as_str(), v.as_str())).cmp(labels.iter().copied()))
+}
+
+/// Collection of metric families.
+pub struct Registry {
+    families: GuestState<Vec<Rc<Family>>>,
+    max_series: usize,
+    dropped: Rc<GuestState<u64>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry {
+    /// Create an empty registry with [`DEFAULT_MAX_SERIES`] series per family.
+    pub fn new() -> Self {
+        Self::with_max_series(DEFAULT_MAX_SERIES)
+    }
+
+    /// Create an empty registry that keeps at most `max_series` label combinations per family.
+    pub fn with_max_series(max_series: usize) -> Self {
+        Self { families: GuestState::default(), max_series, dropped: Rc::default() }
+    }
+
+    /// Register a counter, or return the registered counter with this name.
+    pub fn counter(&self, name: &str, help: &str) -> Result<Counter, MetricsError> {
+        self.register(name, help, MetricKind::Counter, &[]).map(Counter)
+    }
+
+    /// Register a gauge, or return the registered gauge with this name.
+    pub fn gauge(&self, name: &str, help: &str) -> Result<Gauge, MetricsError> {
+        self.register(name, help, MetricKind::Gauge, &[]).map(Gauge)
+    }
+
+    /// Register a histogram with the given bucket upper bounds, or return the registered
+    /// histogram with this name. Bounds are sorted, non-finite ones are ignored.
+    pub fn histogram(&self, name: &str, help: &str, buckets: &[f64]) -> Result<Histogram, MetricsError> {
+        self.register(name, help, MetricKind::Histogram, buckets).map(Histogram)
+    }
+
+    fn register(&self, name: &str, help: &str, kind: MetricKind, buckets: &[f64]) -> Result<Rc<Family>, MetricsError> {
+        if !is_valid_metric_name(name) {
+            return Err(MetricsError::InvalidName(name.to_string()));
+        }
+        self.families.with_mut(|families| {
+            if let Some(family) = families.iter().find(|f| f.name == name) {
+                return match family.kind == kind {
+                    true => Ok(family.clone()),
+                    false => Err(MetricsError::KindMismatch(name.to_string())),
+                };
+            }
+            let mut buckets = buckets.iter().copied().filter(|b| b.is_finite()).collect::<Vec<_>>();
+            buckets.sort_by(f64::total_cmp);
+            buckets.dedup();
+            let family = Rc::new(Family {
+                name: name.to_string(),
+                help: help.to_string(),
+                kind,
+                buckets,
+                series: GuestState::default(),
+                max_series: self.max_series,
+                dropped: self.dropped.clone(),
+            });
+            families.push(family.clone());
+            Ok(family)
+        })
+    }
+
+    /// Snapshot of all families in registration order.
+    pub fn gather(&self) -> Vec<MetricFamily> {
+        self.families.with(|families| {
+            families
+                .iter()
+                .map(|family| MetricFamily {
+                    name: family.name.clone(),
+                    help: family.help.clone(),
+                    kind: family.kind,
+                    series: family.series.with(|series| {
+                        series.iter().map(|(labels, value)| Series { labels: labels.clone(), value: value.clone() }).collect()
+                    }),
+                })
+                .collect()
+        })
+    }
+
+    /// Number of observations dropped because of the series limit or invalid label names.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.get()
+    }
+
+    /// Encode all families in the Prometheus text exposition format (version 0.0.4).
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        for family in self.gather() {
+            let kind = match family.kind {
+                MetricKind::Counter => "counter",
+                MetricKind::Gauge => "gauge",
+                MetricKind::Histogram => "histogram",
+            };
+            let help = family.help.replace('\\', "\\\\").replace('\n', "\\n");
+            let _ = writeln!(out, "# HELP {} {help}\n# TYPE {} {kind}", family.name, family.name);
+            for series in &family.series {
+                match &series.value {
+                    MetricValue::Counter(count) => sample(&mut out, &family.name, &series.labels, None, count),
+                    MetricValue::Gauge(value) => sample(&mut out, &family.name, &series.labels, None, PromFloat(*value)),
+                    MetricValue::Histogram { buckets, sum, count } => {
+                        let bucket = format!("{}_bucket", family.name);
+                        for (bound, n) in buckets {
+                            sample(&mut out, &bucket, &series.labels, Some(PromFloat(*bound)), n);
+                        }
+                        sample(&mut out, &bucket, &series.labels, Some(PromFloat(f64::INFINITY)), count);
+                        sample(&mut out, &format!("{}_sum", family.name), &series.labels, None, PromFloat(*sum));
+                        sample(&mut out, &format!("{}_count", family.name), &series.labels, None, count);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Answer requests for `endpoint`, e.g. `/_plugin/metrics`, with the metrics in the
+    /// Prometheus text format instead of passing them to the upstream.
+    ///
+    /// Returns `None` for other paths and methods other than `GET` and `HEAD`.
+    ///
+    /// ```no_run
+    /// use http_wasm_guest::{
+    ///     host::{Request, Response},
+    ///     metrics::Registry,
+    /// };
+    ///
+    /// fn handle_request(registry: &Registry, request: &Request, response: &Response) -> (bool, i32) {
+    ///     if let Some(decision) = registry.serve("/_plugin/metrics", request, response) {
+    ///         return decision.into();
+    ///     }
+    ///     (true, 0)
+    /// }
+    /// ```
+    pub fn serve(&self, endpoint: &str, request: &Request, response: &Response) -> Option<Decision> {
+        let path_matches = request.with_uri(|uri| uri.split(|b| *b == b'?').next() == Some(endpoint.as_bytes()));
+        if !path_matches || !request.with_method(|method| matches!(method, b"GET" | b"HEAD")) {
+            return None;
+        }
+        response.respond(200, b"text/plain; version=0.0.4; charset=utf-8", self.to_prometheus().as_bytes());
+        Some(Decision::Stop)
+    }
+}
+
+/// Append one sample line, with the `le` label of histogram buckets.
+fn sample(out: &mut String, name: &str, labels: &[(String, String)], le: Option<PromFloat>, value: impl Display) {
+    out.push_str(name);
+    let le = le.map(|le| ("le".to_string(), le.to_string()));
+    for (i, (label, value)) in labels.iter().chain(le.as_ref()).enumerate() {
+        let separator = if i == 0 { '{' } else { ',' };
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+        let _ = write!(out, "{separator}{label}=\"{value}\"");
+    }
+    if !labels.is_empty() || le.is_some() {
+        out.push('}');
+    }
+    let _ = writeln!(out, " {value}");
+}
+
+/// Formats floats as Prometheus expects, e.g. `+Inf` and `NaN`.
+struct PromFloat(f64);
+
+impl Display for PromFloat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            v if v.is_nan() => f.write_str("NaN"),
+            v if v == f64::INFINITY => f.write_str("+Inf"),
+            v if v == f64::NEG_INFINITY => f.write_str("-Inf"),
+            v => write!(f, "{v}"),
+        }
+    }
+}
+
+/// Handle of a counter family, see [`Registry::counter`].
+#[derive(Clone)]
+pub struct Counter(Rc<Family>);
+
+impl Counter {
+    /// Increment the series of `labels` by one.
+    pub fn inc(&self, labels: &[(&str, &str)]) {
+        self.inc_by(labels, 1);
+    }
+
+    /// Increment the series of `labels` by `n`.
+    pub fn inc_by(&self, labels: &[(&str, &str)], n: u64) {
+        self.0.update(labels, |value| {
+            if let MetricValue::Counter(count) = value {
+                *count = count.saturating_add(n);
+            }
+        });
+    }
+
+    /// Current value of the series of `labels`, `0` if it does not exist.
+    pub fn get(&self, labels: &[(&str, &str)]) -> u64 {
+        match find(&self.0, labels) {
+            Some(MetricValue::Counter(count)) => count,
+            _ => 0,
+        }
+    }
+}
+
+/// Handle of a gauge family, see [`Registry::gauge`].
+#[derive(Clone)]
+pub struct Gauge(Rc<Family>);
+
+impl Gauge {
+    /// Set the series of `labels` to `value`.
+    pub fn set(&self, labels: &[(&str, &str)], value: f64) {
+        self.0.update(labels, |current| *current = MetricValue::Gauge(value));
+    }
+
+    /// Add `delta` (possibly negative) to the series of `labels`.
+    pub fn add(&self, labels: &[(&str, &str)], delta: f64) {
+        self.0.update(labels, |current| {
+            if let MetricValue::Gauge(value) = current {
+                *value += delta;
+            }
+        });
+    }
+
+    /// Current value of the series of `labels`, `0.0` if it does not exist.
+    pub fn get(&self, labels: &[(&str, &str)]) -> f64 {
+        match find(&self.0, labels) {
+            Some(MetricValue::Gauge(value)) => value,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Handle of a histogram family, see [`Registry::histogram`].
+#[derive(Clone)]
+pub struct Histogram(Rc<Family>);
+
+impl Histogram {
+    /// Record an observation in the series of `labels`.
+    pub fn observe(&self, labels: &[(&str, &str)], value: f64) {
+        self.0.update(labels, |current| {
+            if let MetricValue::Histogram { buckets, sum, count } = current {
+                for (bound, bucket) in buckets.iter_mut() {
+                    if value <= *bound {
+                        *bucket += 1;
+                    }
+                }
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+
+    /// Number of observations in the series of `labels`.
+    pub fn count(&self, labels: &[(&str, &str)]) -> u64 {
+        match find(&self.0, labels) {
+            Some(MetricValue::Histogram { count, .. }) => count,
+            _ => 0,
+        }
+    }
+}
+
+fn find(family: &Family, labels: &[(&str, &str)]) -> Option<MetricValue> {
+    let labels = sorted(labels);
+    family.series.with(|series| {
+        let index = search(series, &labels).ok()?;
+        series.get(index).map(|(_, value)| value.clone())
+    })
+}
+
+/// `[a-zA-Z_:][a-zA-Z0-9_:]*`
+fn is_valid_metric_name(name: &str) -> bool {
+    name.bytes().next().is_some_and(|b| b.is_ascii_alphabetic() || b == b'_' || b == b':')
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b':')
+}
+
+/// `[a-zA-Z_][a-zA-Z0-9_]*`, names starting with `__` are reserved.
+fn is_valid_label_name(name: &str) -> bool {
+    name.bytes().next().is_some_and(|b| b.is_ascii_alphabetic() || b == b'_')
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        && !name.starts_with("__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter_and_gauge() {
+        let registry = Registry::new();
+        let requests = registry.counter("requests_total", "Requests").unwrap();
+        requests.inc(&[("method", "GET")]);
+        requests.inc_by(&[("method", "GET")], 2);
+        requests.inc(&[("method", "POST")]);
+        assert_eq!(requests.get(&[("method", "GET")]), 3);
+        assert_eq!(requests.get(&[("method", "PUT")]), 0);
+
+        let inflight = registry.gauge("inflight", "In-flight requests").unwrap();
+        inflight.add(&[], 2.0);
+        inflight.add(&[], -0.5);
+        assert_eq!(inflight.get(&[]), 1.5);
+        inflight.set(&[], 7.0);
+        assert_eq!(inflight.get(&[]), 7.0);
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let registry = Registry::new();
+        let latency = registry.histogram("latency_seconds", "Latency", &[1.0, 0.1, f64::NAN, 0.1]).unwrap();
+        for value in [0.05, 0.5, 5.0] {
+            latency.observe(&[], value);
+        }
+        assert_eq!(latency.count(&[]), 3);
+        let families = registry.gather();
+        assert_eq!(
+            families.first().and_then(|f| f.series.first()).map(|s| &s.value),
+            Some(&MetricValue::Histogram { buckets: vec![(0.1, 1), (1.0, 2)], sum: 5.55, count: 3 })
+        );
+    }
+
+    #[test]
+    fn registration() {
+        let registry = Registry::new();
+        let first = registry.counter("hits", "Hits").unwrap();
+        let second = registry.counter("hits", "ignored").unwrap();
+        first.inc(&[]);
+        assert_eq!(second.get(&[]), 1);
+        assert!(matches!(registry.gauge("hits", ""), Err(MetricsError::KindMismatch(_))));
+        assert!(matches!(registry.counter("1st", ""), Err(MetricsError::InvalidName(_))));
+        assert!(matches!(registry.counter("", ""), Err(MetricsError::InvalidName(_))));
+        assert!(registry.counter("ns:sub_total", "").is_ok());
+        assert_eq!(registry.gather().len(), 2);
+    }
+
+    #[test]
+    fn prometheus_text_format() {
+        let registry = Registry::new();
+        registry.counter("requests_total", "Requests\nhandled").unwrap().inc(&[("path", "/a\"b"), ("code", "200")]);
+        registry.gauge("temperature", "Temperature").unwrap().set(&[], f64::NEG_INFINITY);
+        registry.histogram("latency_seconds", "Latency", &[0.1, 1.0]).unwrap().observe(&[("route", "x")], 0.5);
+        registry.counter("unused_total", "Never incremented").unwrap();
+        assert_eq!(
+            registry.to_prometheus(),
+            "# HELP requests_total Requests\\nhandled\n\
+             # TYPE requests_total counter\n\
+             requests_total{code=\"200\",path=\"/a\\\"b\"} 1\n\
+             # HELP temperature Temperature\n\
+             # TYPE temperature gauge\n\
+             temperature -Inf\n\
+             # HELP latency_seconds Latency\n\
+             # TYPE latency_seconds histogram\n\
+             latency_seconds_bucket{route=\"x\",le=\"0.1\"} 0\n\
+             latency_seconds_bucket{route=\"x\",le=\"1\"} 1\n\
+             latency_seconds_bucket{route=\"x\",le=\"+Inf\"} 1\n\
+             latency_seconds_sum{route=\"x\"} 0.5\n\
+             latency_seconds_count{route=\"x\"} 1\n\
+             # HELP unused_total Never incremented\n\
+             # TYPE unused_total counter\n"
+        );
+    }
+
+    #[test]
+    fn serve_matches_endpoint() {
+        // The mock request is "GET https://test"
+        let registry = Registry::new();
+        assert_eq!(registry.serve("/_plugin/metrics", &Request::new(), &Response::new()), None);
+        assert_eq!(registry.serve("https://test", &Request::new(), &Response::new()), Some(Decision::Stop));
+    }
+
+    #[test]
+    fn series_limit_bounds_cardinality() {
+        let registry = Registry::with_max_series(2);
+        let counter = registry.counter("by_path", "").unwrap();
+        for path in ["/a", "/b", "/c", "/a"] {
+            counter.inc(&[("path", path)]);
+        }
+        counter.inc(&[("__reserved", "x")]);
+        counter.inc(&[("bad-name", "x")]);
+        assert_eq!(counter.get(&[("path", "/a")]), 2);
+        assert_eq!(counter.get(&[("path", "/c")]), 0);
+        assert_eq!(registry.dropped(), 3);
+        assert_eq!(registry.gather().first().map(|f| f.series.len()), Some(2));
+    }
+
+    #[test]
+    fn label_order_does_not_create_series() {
+        let registry = Registry::new();
+        let counter = registry.counter("responses_total", "").unwrap();
+        counter.inc(&[("method", "GET"), ("code", "200")]);
+        counter.inc(&[("code", "200"), ("method", "GET")]);
+        counter.inc(&[("code", "404"), ("method", "GET")]);
+        assert_eq!(counter.get(&[("method", "GET"), ("code", "200")]), 2);
+        let series = registry.gather().into_iter().flat_map(|f| f.series).map(|s| s.labels).collect::<Vec<_>>();
+        let labels = |code: &str| vec![("code".to_string(), code.to_string()), ("method".to_string(), "GET".to_string())];
+        assert_eq!(series, [labels("200"), labels("404")]);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    Guest,
+    host::{Request, Response},
+};
+
+use super::{client_ip, cookie, stable_hash};
+
+/// A variant of an [`AbTest`] with its share of the traffic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    /// Name of the variant, forwarded in the variant header and cookie.
+    pub name: String,
+    /// Relative weight; a variant with weight `0` is never assigned.
+    pub weight: u32,
+}
+
+impl Variant {
+    /// Create a variant with the given name and weight.
+    pub fn new(name: impl Into<String>, weight: u32) -> Self {
+        Self { name: name.into(), weight }
+    }
+}
+
+/// Source of the key a client is assigned to a variant by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbTestKey {
+    /// The client IP address (without port).
+    ClientIp,
+    /// The value of the named cookie, e.g. a session id.
+    Cookie(String),
+    /// The first value of the named request header, e.g. a user id.
+    Header(String),
+}
+
+/// Configuration for the [`AbTest`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbTestConfig {
+    /// Name of the experiment; clients are hashed independently per experiment.
+    pub name: String,
+    /// Variants to split the traffic between.
+    pub variants: Vec<Variant>,
+    /// How clients are identified.
+    pub key: AbTestKey,
+    /// Request header carrying the assigned variant to the upstream.
+    pub header: Option<String>,
+    /// Cookie persisting the assigned variant, so that it survives key changes.
+    pub cookie: Option<String>,
+}
+
+impl Default for AbTestConfig {
+    fn default() -> Self {
+        Self {
+            name: "ab".into(),
+            variants: vec![Variant::new("a", 50), Variant::new("b", 50)],
+            key: AbTestKey::ClientIp,
+            header: Some("X-Variant".into()),
+            cookie: None,
+        }
+    }
+}
+
+/// A/B traffic splitting by consistent hashing.
+///
+/// Every client is assigned a stable variant by hashing its key together with the
+/// experiment name and mapping the hash onto the configured weights. The variant is
+/// forwarded to the upstream in a request header and, if configured, persisted in a
+/// cookie that takes precedence over the hash on later requests.
+///
+/// Plugins that need the assignment themselves call [`variant`](AbTest::variant).
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{AbTest, AbTestConfig, Variant},
+///     register,
+/// };
+///
+/// register(AbTest::new(AbTestConfig {
+///     variants: vec![Variant::new("stable", 90), Variant::new("canary", 10)],
+///     cookie: Some("variant".into()),
+///     ..Default::default()
+/// }));
+/// ```
+pub struct AbTest {
+    config: AbTestConfig,
+    total_weight: u64,
+}
+
+impl AbTest {
+    /// Create a new component with the given configuration.
+    pub fn new(config: AbTestConfig) -> Self {
+        let total_weight = config.variants.iter().map(|v| v.weight as u64).sum();
+        Self { config, total_weight }
+    }
+
+    /// Return the variant assigned to the client of `request`, or `None` if no variant
+    /// has a weight.
+    pub fn variant(&self, request: &Request) -> Option<&Variant> {
+        self.assign(request).and_then(|(index, _)| self.config.variants.get(index))
+    }
+
+    /// Return the variant for the given client key.
+    pub fn variant_for(&self, key: &[u8]) -> Option<&Variant> {
+        self.index_for(key).and_then(|index| self.config.variants.get(index))
+    }
+
+    fn index_for(&self, key: &[u8]) -> Option<usize> {
+        if self.total_weight == 0 {
+            return None;
+        }
+        let mut point = stable_hash(&[self.config.name.as_bytes(), key]) % self.total_weight;
+        self.config.variants.iter().position(|v| {
+            let weight = v.weight as u64;
+            if point < weight {
+                return true;
+            }
+            point -= weight;
+            false
+        })
+    }
+
+    /// Assign a variant index; the flag is set if it was taken from the cookie.
+    fn assign(&self, request: &Request) -> Option<(usize, bool)> {
+        let from_cookie =
+            self.config.cookie.as_deref().and_then(|name| cookie(request, name)).and_then(|value| {
+                self.config.variants.iter().position(|v| v.weight > 0 && v.name.as_bytes() == value.as_slice())
+            });
+        if let Some(index) = from_cookie {
+            return Some((index, true));
+        }
+        self.index_for(&self.key(request)).map(|index| (index, false))
+    }
+
+    fn key(&self, request: &Request) -> Vec<u8> {
+        match &self.config.key {
+            AbTestKey::ClientIp => client_ip(&request.source_addr()).to_vec(),
+            AbTestKey::Cookie(name) => cookie(request, name).unwrap_or_default(),
+            AbTestKey::Header(name) => request.header.get(name.as_bytes()).map(|v| v.to_vec()).unwrap_or_default(),
+        }
+    }
+
+    fn set_cookie_value(&self, variant: &Variant) -> Option<String> {
+        let name = self.config.cookie.as_ref()?;
+        Some(format!("{name}={}; Path=/; HttpOnly; SameSite=Lax", variant.name))
+    }
+}
+
+impl Guest for AbTest {
+    fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+        let Some((index, from_cookie)) = self.assign(request) else {
+            return (true, 0);
+        };
+        if let (Some(header), Some(variant)) = (&self.config.header, self.config.variants.get(index)) {
+            request.header.set(header.as_bytes(), variant.name.as_bytes());
+        }
+        // the context carries the variant whose cookie still has to be set
+        let ctx = if from_cookie || self.config.cookie.is_none() { 0 } else { index as i32 + 1 };
+        (true, ctx)
+    }
+
+    fn handle_response(&self, req_ctx: i32, _request: &Request, response: &Response, _is_error: bool) {
+        let variant = usize::try_from(req_ctx - 1).ok().and_then(|index| self.config.variants.get(index));
+        if let Some(value) = variant.and_then(|v| self.set_cookie_value(v)) {
+            response.header.add(b"Set-Cookie", value.as_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ab(weights: &[u32]) -> AbTest {
+        let variants = weights.iter().enumerate().map(|(i, w)| Variant::new(format!("v{i}"), *w)).collect();
+        AbTest::new(AbTestConfig { variants, ..Default::default() })
+    }
+
+    #[test]
+    fn assignment_is_stable() {
+        let ab = ab(&[50, 50]);
+        let first = ab.variant_for(b"client-1");
+        for _ in 0..10 {
+            assert_eq!(ab.variant_for(b"client-1"), first);
+        }
+    }
+
+    #[test]
+    fn weights_are_respected() {
+        let ab = ab(&[90, 10]);
+        let canary = (0..1000).filter(|i| ab.variant_for(format!("client-{i}").as_bytes()).unwrap().name == "v1").count();
+        assert!((50..150).contains(&canary), "{canary}");
+    }
+
+    #[test]
+    fn zero_weight_is_never_assigned() {
+        let ab = ab(&[0, 1]);
+        assert!((0..100).all(|i| ab.variant_for(format!("client-{i}").as_bytes()).unwrap().name == "v1"));
+        assert!(self::ab(&[0, 0]).variant_for(b"client").is_none());
+        assert!(self::ab(&[]).variant_for(b"client").is_none());
+    }
+
+    #[test]
+    fn experiments_hash_independently() {
+        let a = AbTest::new(AbTestConfig { name: "one".into(), ..Default::default() });
+        let b = AbTest::new(AbTestConfig { name: "two".into(), ..Default::default() });
+        let differs = (0..100).any(|i| {
+            let key = format!("client-{i}");
+            a.variant_for(key.as_bytes()) != b.variant_for(key.as_bytes())
+        });
+        assert!(differs);
+    }
+
+    #[test]
+    fn key_from_request() {
+        // The mock source address is "192.168.1.1" and X-FOO is "test1"
+        assert_eq!(ab(&[1]).key(&Request::new()), b"192.168.1.1");
+        let ab = AbTest::new(AbTestConfig { key: AbTestKey::Header("X-FOO".into()), ..Default::default() });
+        assert_eq!(ab.key(&Request::new()), b"test1");
+        let ab = AbTest::new(AbTestConfig { key: AbTestKey::Cookie("session".into()), ..Default::default() });
+        assert!(ab.key(&Request::new()).is_empty());
+    }
+
+    #[test]
+    fn variant_of_request_matches_hash() {
+        let ab = ab(&[50, 50]);
+        assert_eq!(ab.variant(&Request::new()), ab.variant_for(b"192.168.1.1"));
+    }
+
+    #[test]
+    fn cookie_is_set_only_when_configured() {
+        let ab = AbTest::new(AbTestConfig { cookie: Some("variant".into()), ..Default::default() });
+        let (next, ctx) = ab.handle_request(&Request::new(), &Response::new());
+        assert!(next);
+        assert!(ctx > 0);
+        ab.handle_response(ctx, &Request::new(), &Response::new(), false);
+        assert_eq!(ab.set_cookie_value(&Variant::new("b", 1)).unwrap(), "variant=b; Path=/; HttpOnly; SameSite=Lax");
+
+        let (_, ctx) = self::ab(&[1]).handle_request(&Request::new(), &Response::new());
+        assert_eq!(ctx, 0);
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt::Write;
+
+use crate::{
+    Guest,
+    headers::format_log_date,
+    host::{Request, Response, log, time},
+};
+
+use super::{Template, client_ip, template::path};
+
+const COMMON: &str = r#"{{remote_addr}} - - [{{time}}] "{{request_line}}" {{status}} {{size}}"#;
+const COMBINED: &str = r#"{{remote_addr}} - - [{{time}}] "{{request_line}}" {{status}} {{size}} "{{referer}}" "{{user_agent}}""#;
+
+/// Line format of the [`AccessLog`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessLogFormat {
+    /// Common Log Format: `192.168.1.1 - - [10/Oct/2000:13:55:36 +0000] "GET / HTTP/1.1" 200 2326`.
+    Common,
+    /// Combined Log Format: the common format followed by the quoted referer and user agent.
+    Combined,
+    /// A template with `{{placeholder}}`s, see [`AccessLog`].
+    Custom(String),
+}
+
+/// Configuration for the [`AccessLog`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessLogConfig {
+    /// Format of the log lines.
+    pub format: AccessLogFormat,
+    /// Host log level of the lines: debug=−1, info=0, warn=1, error=2.
+    pub level: i32,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self { format: AccessLogFormat::Combined, level: 0 }
+    }
+}
+
+/// Access logging in Common, Combined or a custom log format.
+///
+/// Writes one line per request through the host logger in
+/// [`handle_response`](Guest::handle_response). Custom formats can use the placeholders
+/// `remote_addr`, `time`, `request_line`, `method`, `uri`, `path`, `protocol`, `status`,
+/// `size`, `referer`, `user_agent`, `host`, `request_id`, `latency_us` and `latency_ms`.
+/// Missing values are logged as `-`, and quotes and control characters in values are
+/// escaped so clients cannot forge log lines. `size` is taken from the `Content-Length`
+/// response header.
+///
+/// [`handle_request`](Guest::handle_request) returns the start time as context for the
+/// latency; a plugin that combines components passes it on to
+/// [`line`](AccessLog::line).
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{AccessLog, AccessLogConfig, AccessLogFormat},
+///     register,
+/// };
+///
+/// let format = AccessLogFormat::Custom("{{method}} {{path}} {{status}} {{latency_ms}}ms id={{request_id}}".into());
+/// register(AccessLog::new(AccessLogConfig { format, ..Default::default() }));
+/// ```
+pub struct AccessLog {
+    config: AccessLogConfig,
+    template: Template,
+}
+
+impl AccessLog {
+    /// Create a new component with the given configuration.
+    pub fn new(config: AccessLogConfig) -> Self {
+        let template = Template::text(match &config.format {
+            AccessLogFormat::Common => COMMON,
+            AccessLogFormat::Combined => COMBINED,
+            AccessLogFormat::Custom(format) => format,
+        });
+        Self { config, template }
+    }
+
+    /// Context for [`line`](AccessLog::line) marking the start of the request, as
+    /// returned by [`handle_request`](Guest::handle_request).
+    pub fn start() -> i32 {
+        now_micros() as i32
+    }
+
+    /// Format the log line for a request that started at `start` (see [`start`](AccessLog::start)).
+    pub fn line(&self, start: i32, request: &Request, response: &Response) -> String {
+        // The context holds the lower 32 bits, so latencies up to ~71 minutes are exact.
+        let latency_us = now_micros().wrapping_sub(start as u32);
+        let header = |name: &[u8]| request.header.get(name).map(|v| v.to_string());
+        self.template.render(|name| {
+            let value = match name {
+                "remote_addr" => Some(String::from_utf8_lossy(client_ip(&request.source_addr())).into_owned()),
+                "time" => Some(format_log_date(time::now())),
+                "request_line" => Some(format!("{} {} {}", request.method(), request.uri(), request.version())),
+                "method" => Some(request.method().to_string()),
+                "uri" => Some(request.uri().to_string()),
+                "path" => Some(path(&request.uri())),
+                "protocol" => Some(request.version().to_string()),
+                "status" => Some(response.status().to_string()),
+                "size" => response.header.get(b"Content-Length").map(|v| v.to_string()),
+                "referer" => header(b"Referer"),
+                "user_agent" => header(b"User-Agent"),
+                "host" => header(b"Host"),
+                "request_id" => header(b"X-Request-Id"),
+                "latency_us" => Some(latency_us.to_string()),
+                "latency_ms" => Some(format!("{:.3}", f64::from(latency_us) / 1000.0)),
+                _ => None,
+            };
+            Some(value.filter(|v| !v.is_empty()).map_or_else(|| "-".to_string(), |v| escape(&v)))
+        })
+    }
+}
+
+impl Guest for AccessLog {
+    fn handle_request(&self, _request: &Request, _response: &Response) -> (bool, i32) {
+        (true, Self::start())
+    }
+
+    fn handle_response(&self, req_ctx: i32, request: &Request, response: &Response, _is_error: bool) {
+        if log::enabled(self.config.level) {
+            log::write(self.config.level, self.line(req_ctx, request, response).as_bytes());
+        }
+    }
+}
+
+fn now_micros() -> u32 {
+    (time::monotonic_nanos() / 1000) as u32
+}
+
+/// Escape quotes, backslashes and control characters like the `\xHH` escapes of Apache httpd.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => {
+                let _ = write!(out, "\\x{:02x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(format: AccessLogFormat) -> String {
+        let access_log = AccessLog::new(AccessLogConfig { format, ..Default::default() });
+        access_log.line(AccessLog::start(), &Request::new(), &Response::new())
+    }
+
+    #[test]
+    fn common_format() {
+        // The mock request is "GET https://test HTTP/2.0" from 192.168.1.1, the mock clock
+        // starts at 1_700_000_000 and the mock response has status 200 and no Content-Length.
+        let line = line(AccessLogFormat::Common);
+        assert!(line.starts_with("192.168.1.1 - - [14/Nov/2023:22:13:"), "{line}");
+        assert!(line.ends_with(r#" +0000] "GET https://test HTTP/2.0" 200 -"#), "{line}");
+    }
+
+    #[test]
+    fn combined_format() {
+        assert!(line(AccessLogFormat::Combined).ends_with(r#" 200 - "-" "-""#));
+    }
+
+    #[test]
+    fn custom_format() {
+        let line = line(AccessLogFormat::Custom("{{method}} {{path}} {{host}} {{x-foo}} {{latency_us}}".into()));
+        let fields = line.split(' ').collect::<Vec<_>>();
+        assert_eq!(fields.get(..4), Some(&["GET", "https://test", "-", "-"][..]), "{line}");
+        assert!(fields.get(4).is_some_and(|latency| latency.parse::<u32>().is_ok()), "{line}");
+    }
+
+    #[test]
+    fn values_are_escaped() {
+        assert_eq!(escape("a\"b\\c\nd"), r#"a\"b\\c\x0ad"#);
+    }
+
+    #[test]
+    fn handle_request_returns_start() {
+        let access_log = AccessLog::new(AccessLogConfig::default());
+        assert!(access_log.handle_request(&Request::new(), &Response::new()).0);
+        access_log.handle_response(0, &Request::new(), &Response::new(), false);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{
+    Guest, GuestState,
+    codec::escape_json,
+    host::{
+        Request, Response,
+        audit::{self, Mutation},
+        feature::Feature,
+        log, time,
+    },
+};
+
+/// Configuration for the [`Audit`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditConfig {
+    /// Host log level of the audit lines: debug=−1, info=0, warn=1, error=2.
+    pub level: i32,
+    /// Headers whose values are logged as `[redacted]`, compared case-insensitively.
+    pub redact: Vec<String>,
+    /// Whether requests without mutations are logged too.
+    pub log_unchanged: bool,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            level: 0,
+            redact: ["Authorization", "Cookie", "Set-Cookie", "Proxy-Authorization"].map(String::from).to_vec(),
+            log_unchanged: false,
+        }
+    }
+}
+
+/// Audit log of the changes a component makes to requests and responses.
+///
+/// Wraps another component and records every mutation it makes through the host API:
+/// headers set, added or removed, method and URI rewrites, status overrides and body
+/// replacements. One JSON line per request is written through the host logger once the
+/// request is done, i.e. after [`handle_response`](Guest::handle_response) or when
+/// [`handle_request`](Guest::handle_request) answers the request itself:
+///
+/// ```json
+/// {"ts":1700000000000,"method":"GET","uri":"/api","status":403,"mutations":[
+///  {"op":"set_header","target":"response","name":"Content-Type","value":"text/plain"},
+///  {"op":"set_status","value":403},{"op":"write_body","target":"response","bytes":9}]}
+/// ```
+///
+/// Bodies are logged by size only; values of the configured headers are redacted.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{Audit, AuditConfig, Cors, CorsConfig},
+///     register,
+/// };
+///
+/// register(Audit::new(AuditConfig::default(), Cors::new(CorsConfig::default())));
+/// ```
+pub struct Audit<G> {
+    config: AuditConfig,
+    inner: G,
+    pending: GuestState<Pending>,
+}
+
+#[derive(Default)]
+struct Pending {
+    method: String,
+    uri: String,
+    mutations: Vec<Mutation>,
+}
+
+impl<G: Guest> Audit<G> {
+    /// Wrap `inner` with the given configuration.
+    pub fn new(config: AuditConfig, inner: G) -> Self {
+        Self { config, inner, pending: GuestState::default() }
+    }
+
+    fn emit(&self, status: i32) {
+        let pending = self.pending.take();
+        if (pending.mutations.is_empty() && !self.config.log_unchanged) || !log::enabled(self.config.level) {
+            return;
+        }
+        log::write(self.config.level, self.line(&pending, status).as_bytes());
+    }
+
+    fn line(&self, pending: &Pending, status: i32) -> String {
+        let mut line = format!(r#"{{"ts":{},"method":""#, time::unix_millis());
+        escape_json(&pending.method, &mut line);
+        line.push_str(r#"","uri":""#);
+        escape_json(&pending.uri, &mut line);
+        line.push_str(&format!(r#"","status":{status},"mutations":["#));
+        for (i, mutation) in pending.mutations.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            self.push_mutation(&mut line, mutation);
+        }
+        line.push_str("]}");
+        line
+    }
+
+    fn push_mutation(&self, line: &mut String, mutation: &Mutation) {
+        let target = |kind: &i32| if *kind == 0 { "request" } else { "response" };
+        let (op, kind, name, value) = match mutation {
+            Mutation::SetHeader { kind, name, value } => ("set_header", Some(kind), Some(name), Some(value)),
+            Mutation::AddHeader { kind, name, value } => ("add_header", Some(kind), Some(name), Some(value)),
+            Mutation::RemoveHeader { kind, name } => ("remove_header", Some(kind), Some(name), None),
+            Mutation::SetMethod(method) => ("set_method", None, None, Some(method)),
+            Mutation::SetUri(uri) => ("set_uri", None, None, Some(uri)),
+            Mutation::SetStatus(status) => {
+                line.push_str(&format!(r#"{{"op":"set_status","value":{status}}}"#));
+                return;
+            }
+            Mutation::WriteBody { kind, len } => {
+                line.push_str(&format!(r#"{{"op":"write_body","target":"{}","bytes":{len}}}"#, target(kind)));
+                return;
+            }
+        };
+        line.push_str(&format!(r#"{{"op":"{op}""#));
+        if let Some(kind) = kind {
+            line.push_str(&format!(r#","target":"{}""#, target(kind)));
+        }
+        if let Some(name) = name {
+            line.push_str(r#","name":""#);
+            escape_json(&String::from_utf8_lossy(name), line);
+            line.push('"');
+        }
+        if let Some(value) = value {
+            let redacted = name.is_some_and(|name| self.config.redact.iter().any(|r| name.eq_ignore_ascii_case(r.as_bytes())));
+            line.push_str(r#","value":""#);
+            match redacted {
+                true => line.push_str("[redacted]"),
+                false => escape_json(&String::from_utf8_lossy(value), line),
+            }
+            line.push('"');
+        }
+        line.push('}');
+    }
+}
+
+impl<G: Guest> Guest for Audit<G> {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        self.pending.replace(Pending {
+            method: request.method().to_string(),
+            uri: request.uri().to_string(),
+            mutations: Vec::new(),
+        });
+        audit::start(Vec::new());
+        let (next, ctx) = self.inner.handle_request(request, response);
+        let mutations = audit::stop();
+        self.pending.with_mut(|pending| pending.mutations = mutations);
+        if !next {
+            self.emit(response.status());
+        }
+        (next, ctx)
+    }
+
+    fn handle_response(&self, req_ctx: i32, request: &Request, response: &Response, is_error: bool) {
+        audit::start(self.pending.with_mut(|pending| std::mem::take(&mut pending.mutations)));
+        self.inner.handle_response(req_ctx, request, response, is_error);
+        let mutations = audit::stop();
+        self.pending.with_mut(|pending| pending.mutations = mutations);
+        self.emit(response.status());
+    }
+
+    fn features(&self) -> Feature {
+        self.inner.features()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Mutate;
+
+    impl Guest for Mutate {
+        fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+            request.header.set(b"X-Tenant", b"a\"b");
+            request.header.set(b"authorization", b"Bearer secret");
+            request.set_uri(b"/rewritten");
+            (true, 0)
+        }
+
+        fn handle_response(&self, _req_ctx: i32, _request: &Request, response: &Response, _is_error: bool) {
+            response.header.remove(b"Server");
+            response.set_status(418);
+            response.body.write(b"teapot");
+        }
+    }
+
+    #[test]
+    fn records_mutations_of_both_phases() {
+        let audit = Audit::new(AuditConfig::default(), Mutate);
+        assert_eq!(audit.handle_request(&Request::new(), &Response::new()), (true, 0));
+        let line = audit.pending.with(|pending| {
+            assert_eq!(pending.mutations.len(), 3);
+            audit.line(pending, 200)
+        });
+        let mutations = line.split_once(r#""mutations":"#).map(|(_, m)| m).unwrap_or_default();
+        assert_eq!(
+            mutations,
+            r#"[{"op":"set_header","target":"request","name":"X-Tenant","value":"a\"b"},{"op":"set_header","target":"request","name":"authorization","value":"[redacted]"},{"op":"set_uri","value":"/rewritten"}]}"#
+        );
+        assert!(line.starts_with(r#"{"ts":"#) && line.contains(r#","method":"GET","uri":"https://test","status":200,"#));
+
+        audit.handle_response(0, &Request::new(), &Response::new(), false);
+        assert!(audit.pending.with(|pending| pending.mutations.is_empty()));
+    }
+
+    #[test]
+    fn response_mutations() {
+        let audit = Audit::new(AuditConfig::default(), Mutate);
+        let pending = Pending {
+            mutations: vec![
+                Mutation::RemoveHeader { kind: 1, name: b"Server".to_vec() },
+                Mutation::SetStatus(418),
+                Mutation::WriteBody { kind: 1, len: 6 },
+            ],
+            ..Default::default()
+        };
+        assert!(audit.line(&pending, 418).ends_with(
+            r#""mutations":[{"op":"remove_header","target":"response","name":"Server"},{"op":"set_status","value":418},{"op":"write_body","target":"response","bytes":6}]}"#
+        ));
+    }
+
+    #[test]
+    fn nothing_recorded_outside_the_component() {
+        Request::new().header.set(b"X-Other", b"1");
+        assert_eq!(audit::stop(), Vec::new());
+    }
+}
@@ -0,0 +1,145 @@
+use std::borrow::Cow;
+
+use crate::{
+    Guest,
+    host::{Request, Response, admin, feature},
+    redact::{Pattern, Redactor},
+};
+
+use super::media_type_matches;
+
+/// Configuration for the [`BodyMasking`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyMaskingConfig {
+    /// Patterns masked in the response body.
+    pub patterns: Vec<Pattern>,
+    /// Number of trailing letters and digits of each match left visible.
+    pub keep_last: usize,
+    /// Character replacing masked letters and digits.
+    pub mask: char,
+    /// Inspected media types. An entry ending in `/` matches the whole type (`text/`),
+    /// an entry starting with `+` matches a structured syntax suffix (`+json`).
+    pub content_types: Vec<String>,
+    /// Larger bodies are passed through unchanged.
+    pub max_size: usize,
+}
+
+impl Default for BodyMaskingConfig {
+    fn default() -> Self {
+        Self {
+            patterns: vec![Pattern::CardNumber],
+            keep_last: 4,
+            mask: '*',
+            content_types: ["text/", "application/json", "application/xml", "+json", "+xml"].map(String::from).to_vec(),
+            max_size: 1024 * 1024,
+        }
+    }
+}
+
+/// Masks sensitive data in response bodies, for data leak prevention.
+///
+/// Buffers responses and masks all matches of the configured patterns, by default
+/// payment card numbers that pass the Luhn check, keeping separators and the last
+/// four digits (`**** **** **** 1111`). Only bodies with a matching `Content-Type`,
+/// without `Content-Encoding` and of at most `max_size` bytes are inspected; if
+/// anything was masked, the body metadata headers are removed as in
+/// [`Response::prepare_for_body_rewrite`].
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{BodyMasking, BodyMaskingConfig},
+///     redact::Pattern,
+///     register,
+/// };
+///
+/// register(BodyMasking::new(BodyMaskingConfig {
+///     patterns: vec![Pattern::CardNumber, Pattern::Email],
+///     ..Default::default()
+/// }));
+/// ```
+pub struct BodyMasking {
+    config: BodyMaskingConfig,
+    redactor: Redactor,
+}
+
+impl BodyMasking {
+    /// Create a new component with the given configuration.
+    pub fn new(config: BodyMaskingConfig) -> Self {
+        let redactor = Redactor::new(config.patterns.clone()).with_mask(config.keep_last, config.mask);
+        Self { config, redactor }
+    }
+
+    /// Mask the buffered response body; returns `true` if it was changed.
+    ///
+    /// Requires `feature::BufferResponse`.
+    pub fn mask(&self, response: &Response) -> bool {
+        let inspected = response.header.get(b"Content-Encoding").is_none()
+            && response.header.get(b"Content-Type").is_some_and(|ct| media_type_matches(&ct, &self.config.content_types));
+        if !inspected {
+            return false;
+        }
+        let body = response.body.read();
+        if body.len() > self.config.max_size {
+            return false;
+        }
+        let Some(masked) = self.mask_text(&body) else {
+            return false;
+        };
+        response.prepare_for_body_rewrite();
+        response.body.write(masked.as_bytes());
+        true
+    }
+
+    /// Return the masked body, or `None` if nothing matched or it is not UTF-8.
+    fn mask_text(&self, body: &[u8]) -> Option<String> {
+        let text = std::str::from_utf8(body).ok()?;
+        match self.redactor.redact(text) {
+            Cow::Owned(masked) => Some(masked),
+            Cow::Borrowed(_) => None,
+        }
+    }
+}
+
+impl Guest for BodyMasking {
+    fn handle_request(&self, _request: &Request, _response: &Response) -> (bool, i32) {
+        admin::enable(feature::BufferResponse);
+        (true, 0)
+    }
+
+    fn handle_response(&self, _req_ctx: i32, _request: &Request, response: &Response, is_error: bool) {
+        if !is_error {
+            self.mask(response);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_card_numbers() {
+        let masking = BodyMasking::new(BodyMaskingConfig::default());
+        assert_eq!(
+            masking.mask_text(br#"{"pan":"4111111111111111","id":1234567890123}"#).as_deref(),
+            Some(r#"{"pan":"************1111","id":1234567890123}"#)
+        );
+        assert_eq!(masking.mask_text(b"no card here"), None);
+        assert_eq!(masking.mask_text(b"\xff 4111111111111111"), None);
+    }
+
+    #[test]
+    fn configured_patterns() {
+        let masking =
+            BodyMasking::new(BodyMaskingConfig { patterns: vec![Pattern::Email], keep_last: 0, mask: 'x', ..Default::default() });
+        assert_eq!(masking.mask_text(b"a@b.io").as_deref(), Some("x@x.xx"));
+    }
+
+    #[test]
+    fn response_without_content_type_is_untouched() {
+        // The mock response has no Content-Type header
+        let masking = BodyMasking::new(BodyMaskingConfig::default());
+        assert!(!masking.mask(&Response::new()));
+        assert_eq!(masking.handle_request(&Request::new(), &Response::new()), (true, 0));
+    }
+}
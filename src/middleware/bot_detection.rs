@@ -0,0 +1,170 @@
+use crate::{
+    Guest,
+    host::{Request, Response},
+};
+
+/// What happens to requests classified as bots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotAction {
+    /// Pass the request on with the `header` set to the reason of the classification.
+    Tag,
+    /// Reject the request with `block_status`.
+    Block,
+}
+
+/// Configuration for the [`BotDetection`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotDetectionConfig {
+    /// `User-Agent` substrings identifying bots, compared case-insensitively.
+    pub patterns: Vec<String>,
+    /// `User-Agent` substrings of clients that are never classified as bots, e.g. a
+    /// trusted monitoring service; checked before `patterns`.
+    pub allow: Vec<String>,
+    /// Classify requests without or with an empty `User-Agent` as bots.
+    pub missing_user_agent: bool,
+    /// Action for bots.
+    pub action: BotAction,
+    /// Request header added to tagged requests.
+    pub header: String,
+    /// Status of blocked requests, e.g. `403`, or `429` to ask for a retry.
+    pub block_status: i32,
+}
+
+impl Default for BotDetectionConfig {
+    fn default() -> Self {
+        Self {
+            patterns: [
+                "bot",
+                "crawler",
+                "spider",
+                "scraper",
+                "curl/",
+                "wget/",
+                "python-requests",
+                "python-urllib",
+                "go-http-client",
+                "java/",
+                "libwww-perl",
+                "httpclient",
+                "headlesschrome",
+                "phantomjs",
+            ]
+            .map(String::from)
+            .to_vec(),
+            allow: Vec::new(),
+            missing_user_agent: true,
+            action: BotAction::Tag,
+            header: "X-Bot".into(),
+            block_status: 403,
+        }
+    }
+}
+
+/// Classifies requests as bots by their `User-Agent`.
+///
+/// A request is a bot if its `User-Agent` contains one of the configured patterns,
+/// or, with `missing_user_agent`, if it has none. Bots are tagged with a header whose
+/// value is the reason (the matched pattern or `missing-user-agent`), or blocked.
+/// The header is removed from other requests, so clients cannot set it themselves.
+///
+/// [`handle_request`](Guest::handle_request) returns `1` as context for bots and `0`
+/// otherwise. `User-Agent` matching only stops honest bots; it complements, but does
+/// not replace, rate limiting.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{BotAction, BotDetection, BotDetectionConfig},
+///     register,
+/// };
+///
+/// register(BotDetection::new(BotDetectionConfig { action: BotAction::Block, ..Default::default() }));
+/// ```
+pub struct BotDetection {
+    config: BotDetectionConfig,
+    patterns: Vec<String>,
+    allow: Vec<String>,
+}
+
+impl BotDetection {
+    /// Create a new component with the given configuration.
+    pub fn new(config: BotDetectionConfig) -> Self {
+        let lowercase = |list: &[String]| list.iter().map(|p| p.to_ascii_lowercase()).filter(|p| !p.is_empty()).collect();
+        Self { patterns: lowercase(&config.patterns), allow: lowercase(&config.allow), config }
+    }
+
+    /// Classify the request; returns the reason if it is a bot.
+    pub fn classify(&self, request: &Request) -> Option<String> {
+        self.classify_user_agent(request.header.get(b"User-Agent").as_deref())
+    }
+
+    fn classify_user_agent(&self, user_agent: Option<&[u8]>) -> Option<String> {
+        let user_agent = user_agent.map(|ua| String::from_utf8_lossy(ua.trim_ascii()).to_ascii_lowercase()).unwrap_or_default();
+        if user_agent.is_empty() {
+            return self.config.missing_user_agent.then(|| "missing-user-agent".to_string());
+        }
+        if self.allow.iter().any(|p| user_agent.contains(p.as_str())) {
+            return None;
+        }
+        self.patterns.iter().find(|p| user_agent.contains(p.as_str())).cloned()
+    }
+}
+
+impl Guest for BotDetection {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        let header = self.config.header.as_bytes();
+        let Some(reason) = self.classify(request) else {
+            request.header.remove(header);
+            return (true, 0);
+        };
+        match self.config.action {
+            BotAction::Tag => {
+                request.header.set(header, reason.as_bytes());
+                (true, 1)
+            }
+            BotAction::Block => response.deny(self.config.block_status, "Forbidden").into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_bots() {
+        let sut = BotDetection::new(BotDetectionConfig::default());
+        assert_eq!(sut.classify_user_agent(Some(b"curl/8.4.0")), Some("curl/".into()));
+        assert_eq!(sut.classify_user_agent(Some(b"Mozilla/5.0 (compatible; Googlebot/2.1)")), Some("bot".into()));
+        assert_eq!(sut.classify_user_agent(Some(b"python-requests/2.31")), Some("python-requests".into()));
+        assert_eq!(
+            sut.classify_user_agent(Some(b"Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 Chrome/120.0 Safari/537.36")),
+            None
+        );
+    }
+
+    #[test]
+    fn missing_user_agent() {
+        let sut = BotDetection::new(BotDetectionConfig::default());
+        assert_eq!(sut.classify_user_agent(None), Some("missing-user-agent".into()));
+        assert_eq!(sut.classify_user_agent(Some(b"  ")), Some("missing-user-agent".into()));
+        let lenient = BotDetection::new(BotDetectionConfig { missing_user_agent: false, ..Default::default() });
+        assert_eq!(lenient.classify_user_agent(None), None);
+    }
+
+    #[test]
+    fn allow_list_wins() {
+        let sut = BotDetection::new(BotDetectionConfig { allow: vec!["UptimeRobot".into()], ..Default::default() });
+        assert_eq!(sut.classify_user_agent(Some(b"Mozilla/5.0+(compatible; UptimeRobot/2.0)")), None);
+    }
+
+    #[test]
+    fn tag_or_block() {
+        // The mock request has no User-Agent header
+        let tagging = BotDetection::new(BotDetectionConfig::default());
+        assert_eq!(tagging.handle_request(&Request::new(), &Response::new()), (true, 1));
+        let blocking = BotDetection::new(BotDetectionConfig { action: BotAction::Block, ..Default::default() });
+        assert_eq!(blocking.handle_request(&Request::new(), &Response::new()), (false, 0));
+        let lenient = BotDetection::new(BotDetectionConfig { missing_user_agent: false, ..Default::default() });
+        assert_eq!(lenient.handle_request(&Request::new(), &Response::new()), (true, 0));
+    }
+}
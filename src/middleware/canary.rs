@@ -0,0 +1,160 @@
+use crate::{
+    Guest,
+    host::{Request, Response, random},
+};
+
+use super::{client_ip, stable_hash};
+
+/// How requests are selected for the canary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CanaryKey {
+    /// Every request is selected independently at random.
+    Random,
+    /// Clients are selected deterministically by IP address (without port).
+    ClientIp,
+    /// Clients are selected deterministically by the first value of the named request header.
+    Header(String),
+}
+
+/// Configuration for the [`Canary`] component.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanaryConfig {
+    /// Percentage of requests to tag, `0.0..=100.0`.
+    pub percent: f64,
+    /// How requests are selected.
+    pub key: CanaryKey,
+    /// Name of the request header added to tagged requests.
+    pub header: String,
+    /// Value of the header added to tagged requests.
+    pub value: String,
+}
+
+impl Default for CanaryConfig {
+    fn default() -> Self {
+        Self { percent: 5.0, key: CanaryKey::ClientIp, header: "X-Canary".into(), value: "true".into() }
+    }
+}
+
+/// Percentage-based canary tagging.
+///
+/// Tags the configured share of requests with a header (`X-Canary: true` by default)
+/// that upstream routing rules use to split traffic. Selection by client key is
+/// deterministic, so a client stays on the same side; random selection decides per
+/// request. The header is removed from requests that are not selected, so clients
+/// cannot opt in themselves.
+///
+/// [`handle_request`](Guest::handle_request) returns `1` as context for tagged requests
+/// and `0` otherwise, and [`is_tagged`](Canary::is_tagged) reports the decision later in
+/// the request, e.g. for logging.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{Canary, CanaryConfig},
+///     register,
+/// };
+///
+/// register(Canary::new(CanaryConfig { percent: 10.0, ..Default::default() }));
+/// ```
+pub struct Canary {
+    config: CanaryConfig,
+    /// Selection threshold in basis points of a percent (`0..=10_000`).
+    threshold: u64,
+}
+
+impl Canary {
+    /// Create a new component with the given configuration.
+    pub fn new(config: CanaryConfig) -> Self {
+        let threshold = (config.percent.clamp(0.0, 100.0) * 100.0).round() as u64;
+        Self { config, threshold }
+    }
+
+    /// Decide whether the request is selected for the canary.
+    pub fn decide(&self, request: &Request) -> bool {
+        match &self.config.key {
+            CanaryKey::Random => self.selects(u64::from_le_bytes(random::weak_bytes())),
+            CanaryKey::ClientIp => self.is_canary_key(client_ip(&request.source_addr())),
+            CanaryKey::Header(name) => self.is_canary_key(&request.header.get(name.as_bytes()).unwrap_or_default()),
+        }
+    }
+
+    /// Returns `true` if clients with the given key are selected for the canary.
+    pub fn is_canary_key(&self, key: &[u8]) -> bool {
+        self.selects(stable_hash(&[key]))
+    }
+
+    /// Returns `true` if the request has been tagged by [`handle_request`](Guest::handle_request).
+    pub fn is_tagged(&self, request: &Request) -> bool {
+        request.header.get(self.config.header.as_bytes()).is_some_and(|v| v == self.config.value.as_str())
+    }
+
+    fn selects(&self, hash: u64) -> bool {
+        hash % 10_000 < self.threshold
+    }
+}
+
+impl Guest for Canary {
+    fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+        if self.decide(request) {
+            request.header.set(self.config.header.as_bytes(), self.config.value.as_bytes());
+            (true, 1)
+        } else {
+            request.header.remove(self.config.header.as_bytes());
+            (true, 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canary(percent: f64, key: CanaryKey) -> Canary {
+        Canary::new(CanaryConfig { percent, key, ..Default::default() })
+    }
+
+    #[test]
+    fn deterministic_by_key() {
+        let canary = canary(50.0, CanaryKey::ClientIp);
+        let first = canary.is_canary_key(b"10.0.0.1");
+        assert!((0..10).all(|_| canary.is_canary_key(b"10.0.0.1") == first));
+    }
+
+    #[test]
+    fn percentage_is_respected() {
+        let canary = canary(10.0, CanaryKey::ClientIp);
+        let tagged = (0..10_000).filter(|i| canary.is_canary_key(format!("client-{i}").as_bytes())).count();
+        assert!((800..1200).contains(&tagged), "{tagged}");
+    }
+
+    #[test]
+    fn random_percentage_is_respected() {
+        let canary = canary(25.0, CanaryKey::Random);
+        let tagged = (0..10_000).filter(|_| canary.decide(&Request::new())).count();
+        assert!((2200..2800).contains(&tagged), "{tagged}");
+    }
+
+    #[test]
+    fn bounds() {
+        assert!(!canary(0.0, CanaryKey::Random).decide(&Request::new()));
+        assert!(canary(100.0, CanaryKey::Random).decide(&Request::new()));
+        assert!(canary(250.0, CanaryKey::ClientIp).is_canary_key(b"any"));
+        assert!(!canary(-1.0, CanaryKey::ClientIp).is_canary_key(b"any"));
+    }
+
+    #[test]
+    fn handle_request_returns_decision() {
+        // The mock source address is "192.168.1.1"
+        let canary = canary(100.0, CanaryKey::ClientIp);
+        assert_eq!(canary.handle_request(&Request::new(), &Response::new()), (true, 1));
+        let canary = self::canary(0.0, CanaryKey::Header("X-FOO".into()));
+        assert_eq!(canary.handle_request(&Request::new(), &Response::new()), (true, 0));
+    }
+
+    #[test]
+    fn tagged_from_header() {
+        // The mock request header X-FOO is "test1"
+        let canary = Canary::new(CanaryConfig { header: "X-FOO".into(), value: "test1".into(), ..Default::default() });
+        assert!(canary.is_tagged(&Request::new()));
+        assert!(!self::canary(100.0, CanaryKey::Random).is_tagged(&Request::new()));
+    }
+}
@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use crate::{
+    Guest, GuestState,
+    headers::RetryAfter,
+    host::{Request, Response, time},
+};
+
+use super::SlidingWindow;
+
+/// Configuration for the [`CircuitBreaker`] component.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Ratio of failed responses (`0.0..=1.0`) within the window that opens the circuit.
+    pub failure_ratio: f64,
+    /// Minimum number of responses in the window before the ratio is evaluated.
+    pub min_requests: u32,
+    /// Length of the observation window in seconds.
+    pub window_secs: u32,
+    /// Seconds the circuit stays open before a probe request is let through.
+    pub open_secs: u32,
+    /// Path prefixes protected by the breaker; an empty list matches all requests.
+    pub path_prefixes: Vec<String>,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self { failure_ratio: 0.5, min_requests: 20, window_secs: 10, open_secs: 30, path_prefixes: Vec::new() }
+    }
+}
+
+/// Current state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests pass and responses are tracked.
+    Closed,
+    /// Requests are rejected until the given time (unix milliseconds).
+    Open {
+        /// Time at which a probe request is let through.
+        until_ms: u64,
+    },
+    /// A single probe request has been let through and its outcome decides the next state.
+    HalfOpen {
+        /// Time at which the probe was started.
+        since_ms: u64,
+    },
+}
+
+/// Request context values used to correlate requests with their responses.
+const CTX_UNTRACKED: i32 = 0;
+const CTX_TRACKED: i32 = 1;
+const CTX_PROBE: i32 = 2;
+
+/// Circuit breaker driven by upstream status codes.
+///
+/// Responses with a `5xx` status (or reported as error by the host) count as failures.
+/// Once the failure ratio within the window exceeds the configured threshold, the
+/// circuit opens and matching requests are answered with `503 Service Unavailable`
+/// and `Retry-After` without reaching the upstream. After `open_secs`, one probe
+/// request is let through: if it succeeds the circuit closes, otherwise it opens again.
+///
+/// The breaker passes a context value from [`handle_request`](Guest::handle_request)
+/// to [`handle_response`](Guest::handle_response); plugins embedding it must forward
+/// that value unchanged.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{CircuitBreaker, CircuitBreakerConfig},
+///     register,
+/// };
+///
+/// register(CircuitBreaker::new(CircuitBreakerConfig { path_prefixes: vec!["/api/".into()], ..Default::default() }));
+/// ```
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: GuestState<CircuitState>,
+    total: SlidingWindow,
+    failures: SlidingWindow,
+}
+
+impl CircuitBreaker {
+    /// Create a new, closed circuit breaker.
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        let window = Duration::from_secs(config.window_secs as u64);
+        Self {
+            config,
+            state: GuestState::new(CircuitState::Closed),
+            total: SlidingWindow::new(window, 10),
+            failures: SlidingWindow::new(window, 10),
+        }
+    }
+
+    /// Return the current state of the circuit.
+    pub fn state(&self) -> CircuitState {
+        self.state.get()
+    }
+
+    fn matches(&self, uri: &[u8]) -> bool {
+        self.config.path_prefixes.is_empty() || self.config.path_prefixes.iter().any(|p| uri.starts_with(p.as_bytes()))
+    }
+
+    /// Decide whether a request may pass. Returns the context value on success, or the
+    /// number of seconds until the next attempt if the request must be rejected.
+    fn on_request(&self, now_ms: u64) -> Result<i32, u64> {
+        let open_ms = self.config.open_secs as u64 * 1000;
+        self.state.with_mut(|state| match *state {
+            CircuitState::Closed => Ok(CTX_TRACKED),
+            CircuitState::Open { until_ms } if now_ms < until_ms => Err((until_ms - now_ms).div_ceil(1000)),
+            // a probe that never reported back is replaced after another open period
+            CircuitState::HalfOpen { since_ms } if now_ms < since_ms + open_ms => Err(1),
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => {
+                *state = CircuitState::HalfOpen { since_ms: now_ms };
+                Ok(CTX_PROBE)
+            }
+        })
+    }
+
+    fn on_response(&self, ctx: i32, failed: bool, now_ms: u64) {
+        self.state.with_mut(|state| match ctx {
+            CTX_PROBE if failed => *state = self.open(now_ms),
+            CTX_PROBE => {
+                *state = CircuitState::Closed;
+                self.total.reset(b"");
+                self.failures.reset(b"");
+            }
+            CTX_TRACKED if *state == CircuitState::Closed => {
+                let total = self.total.add_at(b"", 1, now_ms);
+                let failures = if failed { self.failures.add_at(b"", 1, now_ms) } else { self.failures.count_at(b"", now_ms) };
+                if total >= self.config.min_requests as u64 && failures as f64 >= total as f64 * self.config.failure_ratio {
+                    *state = self.open(now_ms);
+                }
+            }
+            _ => {}
+        });
+    }
+
+    fn open(&self, now_ms: u64) -> CircuitState {
+        self.total.reset(b"");
+        self.failures.reset(b"");
+        CircuitState::Open { until_ms: now_ms + self.config.open_secs as u64 * 1000 }
+    }
+}
+
+impl Guest for CircuitBreaker {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        if !request.with_uri(|uri| self.matches(uri)) {
+            return (true, CTX_UNTRACKED);
+        }
+        match self.on_request(time::unix_millis()) {
+            Ok(ctx) => (true, ctx),
+            Err(retry_after) => {
+                response.retry_later(503, RetryAfter::Seconds(retry_after));
+                (false, CTX_UNTRACKED)
+            }
+        }
+    }
+
+    fn handle_response(&self, req_ctx: i32, _request: &Request, response: &Response, is_error: bool) {
+        if req_ctx != CTX_UNTRACKED {
+            self.on_response(req_ctx, is_error || response.status() >= 500, time::unix_millis());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig { min_requests: 4, open_secs: 30, ..Default::default() })
+    }
+
+    #[test]
+    fn stays_closed_below_min_requests() {
+        let cb = breaker();
+        for _ in 0..3 {
+            cb.on_response(CTX_TRACKED, true, 0);
+        }
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn stays_closed_below_ratio() {
+        let cb = breaker();
+        for failed in [false, false, false, true, false] {
+            cb.on_response(CTX_TRACKED, failed, 0);
+        }
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn opens_and_rejects() {
+        let cb = breaker();
+        for failed in [true, false, true, false] {
+            cb.on_response(CTX_TRACKED, failed, 0);
+        }
+        assert_eq!(cb.state(), CircuitState::Open { until_ms: 30_000 });
+        assert_eq!(cb.on_request(1_000), Err(29));
+    }
+
+    #[test]
+    fn half_open_probe_closes_on_success() {
+        let cb = breaker();
+        cb.state.set(CircuitState::Open { until_ms: 30_000 });
+        assert_eq!(cb.on_request(30_000), Ok(CTX_PROBE));
+        // only one probe at a time
+        assert_eq!(cb.on_request(30_001), Err(1));
+        cb.on_response(CTX_PROBE, false, 30_100);
+        assert_eq!(cb.state(), CircuitState::Closed);
+        assert_eq!(cb.on_request(30_200), Ok(CTX_TRACKED));
+    }
+
+    #[test]
+    fn half_open_probe_reopens_on_failure() {
+        let cb = breaker();
+        cb.state.set(CircuitState::Open { until_ms: 30_000 });
+        assert_eq!(cb.on_request(30_000), Ok(CTX_PROBE));
+        cb.on_response(CTX_PROBE, true, 31_000);
+        assert_eq!(cb.state(), CircuitState::Open { until_ms: 61_000 });
+    }
+
+    #[test]
+    fn lost_probe_is_replaced() {
+        let cb = breaker();
+        cb.state.set(CircuitState::HalfOpen { since_ms: 0 });
+        assert_eq!(cb.on_request(29_999), Err(1));
+        assert_eq!(cb.on_request(30_000), Ok(CTX_PROBE));
+    }
+
+    #[test]
+    fn late_tracked_responses_ignored_while_open() {
+        let cb = breaker();
+        cb.state.set(CircuitState::Open { until_ms: 30_000 });
+        cb.on_response(CTX_TRACKED, false, 0);
+        assert_eq!(cb.state(), CircuitState::Open { until_ms: 30_000 });
+    }
+
+    #[test]
+    fn path_prefix_matching() {
+        let cb = CircuitBreaker::new(CircuitBreakerConfig { path_prefixes: vec!["/api/".into()], ..Default::default() });
+        assert!(cb.matches(b"/api/users"));
+        assert!(!cb.matches(b"/static/app.js"));
+        assert!(breaker().matches(b"/anything"));
+    }
+
+    #[test]
+    fn guest_roundtrip() {
+        // The mock response status is 200
+        let cb = breaker();
+        let (next, ctx) = cb.handle_request(&Request::new(), &Response::new());
+        assert!(next);
+        assert_eq!(ctx, CTX_TRACKED);
+        cb.handle_response(ctx, &Request::new(), &Response::new(), false);
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    Guest, compression,
+    headers::Encoding,
+    host::{Request, Response, admin, feature},
+};
+
+use super::media_type_matches;
+
+/// Configuration for the [`Compress`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressConfig {
+    /// Responses smaller than this number of bytes are sent uncompressed.
+    pub min_size: usize,
+    /// Compression level, `0..=9`.
+    pub level: u8,
+    /// Compressible media types. An entry ending in `/` matches the whole type
+    /// (`text/`), an entry starting with `+` matches a structured syntax suffix (`+json`).
+    pub content_types: Vec<String>,
+}
+
+impl Default for CompressConfig {
+    fn default() -> Self {
+        Self {
+            min_size: 1024,
+            level: 6,
+            content_types: [
+                "text/",
+                "application/json",
+                "application/javascript",
+                "application/xml",
+                "application/wasm",
+                "image/svg+xml",
+                "+json",
+                "+xml",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+/// Codings offered to clients, in order of preference; the index plus one is passed
+/// as request context.
+const SUPPORTED: &[Encoding] = &[
+    #[cfg(feature = "brotli")]
+    Encoding::Brotli,
+    #[cfg(feature = "zstd")]
+    Encoding::Zstd,
+    Encoding::Gzip,
+];
+
+/// Headers with digests of the body, which no longer match the encoded body.
+const DIGEST_HEADERS: [&[u8]; 3] = [b"Digest", b"Content-Digest", b"Repr-Digest"];
+
+/// Response compression component.
+///
+/// Requires the `compression` feature. If the client accepts `gzip` (or `br` and `zstd`
+/// with the features of the same name, which are preferred in that order), the response is buffered and compressed in [`handle_response`](Guest::handle_response), provided it
+/// has a compressible `Content-Type`, is at least `min_size` bytes long and is not
+/// encoded already. `Content-Encoding`, `Content-Length` and `Vary` are updated
+/// accordingly; a strong `ETag` is weakened and digest headers are removed, as they
+/// describe the unencoded body.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{Compress, CompressConfig},
+///     register,
+/// };
+///
+/// register(Compress::new(CompressConfig { min_size: 256, ..Default::default() }));
+/// ```
+pub struct Compress {
+    config: CompressConfig,
+}
+
+impl Compress {
+    /// Create a new component with the given configuration.
+    pub fn new(config: CompressConfig) -> Self {
+        Self { config }
+    }
+
+    /// Compress the buffered response body with `encoding` if the response qualifies.
+    ///
+    /// Returns `true` if the body was replaced. Requires `feature::BufferResponse`.
+    pub fn compress(&self, response: &Response, encoding: Encoding) -> bool {
+        if encoding == Encoding::Identity
+            || !self.is_compressible(
+                response.status(),
+                response.header.get(b"Content-Encoding").as_deref(),
+                response.header.get(b"Content-Type").as_deref(),
+            )
+        {
+            return false;
+        }
+        let body = response.body.read();
+        if body.len() < self.config.min_size {
+            return false;
+        }
+        let Ok(encoded) = compression::encode(encoding, &body, self.config.level) else {
+            return false;
+        };
+        response.body.write(&encoded);
+        response.header.set(b"Content-Encoding", encoding.as_str().as_bytes());
+        response.header.set(b"Content-Length", encoded.len().to_string().as_bytes());
+        // A strong validator and the digests describe the unencoded representation
+        if let Some(etag) = response.header.get(b"ETag") {
+            response.header.set(b"ETag", &weak_etag(&etag));
+        }
+        for name in DIGEST_HEADERS {
+            response.header.remove(name);
+        }
+        if !response.header.has_token(b"Vary", b"Accept-Encoding") && !response.header.has_token(b"Vary", b"*") {
+            response.header.add(b"Vary", b"Accept-Encoding");
+        }
+        true
+    }
+
+    fn is_compressible(&self, status: i32, content_encoding: Option<&[u8]>, content_type: Option<&[u8]>) -> bool {
+        if matches!(status, 100..=199 | 204 | 206 | 304) || content_encoding.is_some_and(|e| !e.eq_ignore_ascii_case(b"identity"))
+        {
+            return false;
+        }
+        content_type.is_some_and(|content_type| media_type_matches(content_type, &self.config.content_types))
+    }
+}
+
+/// `etag` as weak validator (RFC 9110, section 8.8.3.3), as the encoded body is not
+/// byte-identical to the one it was computed for.
+fn weak_etag(etag: &[u8]) -> Vec<u8> {
+    match etag.starts_with(b"W/") {
+        true => etag.to_vec(),
+        false => [&b"W/"[..], etag].concat(),
+    }
+}
+
+impl Guest for Compress {
+    fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+        let Some(index) = Encoding::negotiate_request(request, SUPPORTED).and_then(|e| SUPPORTED.iter().position(|s| *s == e))
+        else {
+            return (true, 0);
+        };
+        admin::enable(feature::BufferResponse);
+        (true, index as i32 + 1)
+    }
+
+    fn handle_response(&self, req_ctx: i32, _request: &Request, response: &Response, is_error: bool) {
+        let encoding = usize::try_from(req_ctx - 1).ok().and_then(|index| SUPPORTED.get(index));
+        if let (Some(encoding), false) = (encoding, is_error) {
+            self.compress(response, *encoding);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compress() -> Compress {
+        Compress::new(CompressConfig::default())
+    }
+
+    #[test]
+    fn compressible_content_types() {
+        let c = compress();
+        assert!(c.is_compressible(200, None, Some(b"text/html; charset=utf-8")));
+        assert!(c.is_compressible(200, None, Some(b"Application/JSON")));
+        assert!(c.is_compressible(200, None, Some(b"application/problem+json")));
+        assert!(c.is_compressible(200, None, Some(b"image/svg+xml")));
+        assert!(!c.is_compressible(200, None, Some(b"image/png")));
+        assert!(!c.is_compressible(200, None, Some(b"application/octet-stream")));
+        assert!(!c.is_compressible(200, None, None));
+    }
+
+    #[test]
+    fn already_encoded_or_bodyless() {
+        let c = compress();
+        assert!(!c.is_compressible(200, Some(b"br"), Some(b"text/html")));
+        assert!(c.is_compressible(200, Some(b"identity"), Some(b"text/html")));
+        assert!(!c.is_compressible(204, None, Some(b"text/html")));
+        assert!(!c.is_compressible(304, None, Some(b"text/html")));
+        assert!(!c.is_compressible(206, None, Some(b"text/html")));
+    }
+
+    #[test]
+    fn encoded_body_gets_weak_etag() {
+        assert_eq!(weak_etag(b"\"v1\""), b"W/\"v1\"");
+        assert_eq!(weak_etag(b"W/\"v1\""), b"W/\"v1\"");
+    }
+
+    #[test]
+    fn response_without_content_type_is_untouched() {
+        // The mock response has no Content-Type header
+        assert!(!compress().compress(&Response::new(), Encoding::Gzip));
+        assert!(!compress().compress(&Response::new(), Encoding::Identity));
+    }
+
+    #[test]
+    fn request_without_accept_encoding() {
+        // The mock request has no Accept-Encoding header
+        assert_eq!(compress().handle_request(&Request::new(), &Response::new()), (true, 0));
+        compress().handle_response(0, &Request::new(), &Response::new(), false);
+    }
+}
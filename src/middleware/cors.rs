@@ -0,0 +1,246 @@
+use crate::{
+    Guest,
+    host::{Request, Response},
+};
+
+use super::{HeaderList, preflight::is_preflight};
+
+/// Configuration for the [`Cors`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// Origins allowed to access the resource, e.g. `https://app.example.com`. Use `*` to allow any origin.
+    pub allowed_origins: Vec<String>,
+    /// Methods announced in `Access-Control-Allow-Methods` for preflight requests.
+    pub allowed_methods: Vec<String>,
+    /// Request headers allowed in preflight requests. Use `*` to mirror the requested headers.
+    pub allowed_headers: Vec<String>,
+    /// Response headers exposed to the client via `Access-Control-Expose-Headers`.
+    pub exposed_headers: Vec<String>,
+    /// Whether to send `Access-Control-Allow-Credentials: true`.
+    ///
+    /// Credentials are only allowed for explicitly listed origins; origins matched by
+    /// `*` are answered with `*` and without credentials.
+    pub allow_credentials: bool,
+    /// Seconds a preflight result may be cached, sent as `Access-Control-Max-Age`.
+    pub max_age: Option<u32>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: ["GET", "HEAD", "POST"].map(String::from).to_vec(),
+            allowed_headers: Vec::new(),
+            exposed_headers: Vec::new(),
+            allow_credentials: false,
+            max_age: None,
+        }
+    }
+}
+
+/// Cross-Origin Resource Sharing (CORS) component.
+///
+/// Preflight requests (`OPTIONS` with `Access-Control-Request-Method`) are answered
+/// directly with `204 No Content` in [`handle_request`](Guest::handle_request) and never
+/// reach the upstream. For all other requests from an allowed origin, the
+/// `Access-Control-*` headers are added in [`handle_response`](Guest::handle_response).
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{Cors, CorsConfig},
+///     register,
+/// };
+///
+/// let config = CorsConfig { allowed_origins: vec!["https://app.example.com".into()], ..Default::default() };
+/// register(Cors::new(config));
+/// ```
+pub struct Cors {
+    config: CorsConfig,
+}
+
+impl Cors {
+    /// Create a new component with the given configuration.
+    pub fn new(config: CorsConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns the value for `Access-Control-Allow-Origin` if `origin` is allowed.
+    ///
+    /// Listed origins are echoed; other origins allowed by `*` are answered with `*`,
+    /// so an origin that is not listed is never reflected.
+    fn allow_origin(&self, origin: &[u8]) -> Option<Vec<u8>> {
+        if self.config.allowed_origins.iter().any(|o| o.as_bytes().eq_ignore_ascii_case(origin)) {
+            return Some(origin.to_vec());
+        }
+        self.config.allowed_origins.iter().any(|o| o == "*").then(|| b"*".to_vec())
+    }
+
+    /// Headers shared by preflight and actual responses.
+    fn common_headers(&self, origin: &[u8]) -> Option<HeaderList> {
+        let allow_origin = self.allow_origin(origin)?;
+        let mut headers = HeaderList::new();
+        let wildcard = allow_origin == b"*";
+        if !wildcard {
+            headers.push((b"Vary", b"Origin".to_vec()));
+        }
+        headers.push((b"Access-Control-Allow-Origin", allow_origin));
+        // browsers reject credentials with `*`, and reflecting any origin would allow
+        // credentialed reads from every site
+        if self.config.allow_credentials && !wildcard {
+            headers.push((b"Access-Control-Allow-Credentials", b"true".to_vec()));
+        }
+        Some(headers)
+    }
+
+    /// Headers for the answer to a preflight request, or `None` if the origin is not allowed.
+    fn preflight_headers(&self, origin: &[u8], requested_headers: Option<&[u8]>) -> Option<HeaderList> {
+        let mut headers = self.common_headers(origin)?;
+        if !self.config.allowed_methods.is_empty() {
+            headers.push((b"Access-Control-Allow-Methods", self.config.allowed_methods.join(", ").into_bytes()));
+        }
+        if self.config.allowed_headers.iter().any(|h| h == "*") {
+            if let Some(requested) = requested_headers {
+                headers.push((b"Access-Control-Allow-Headers", requested.to_vec()));
+            }
+        } else if !self.config.allowed_headers.is_empty() {
+            headers.push((b"Access-Control-Allow-Headers", self.config.allowed_headers.join(", ").into_bytes()));
+        }
+        if let Some(max_age) = self.config.max_age {
+            headers.push((b"Access-Control-Max-Age", max_age.to_string().into_bytes()));
+        }
+        Some(headers)
+    }
+
+    /// Headers for the response to an actual request, or `None` if the origin is not allowed.
+    fn response_headers(&self, origin: &[u8]) -> Option<HeaderList> {
+        let mut headers = self.common_headers(origin)?;
+        if !self.config.exposed_headers.is_empty() {
+            headers.push((b"Access-Control-Expose-Headers", self.config.exposed_headers.join(", ").into_bytes()));
+        }
+        Some(headers)
+    }
+}
+
+impl Guest for Cors {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        if !is_preflight(request) {
+            return (true, 0);
+        }
+        let origin = request.header.get(b"Origin").unwrap_or_default();
+        let requested_headers = request.header.get(b"Access-Control-Request-Headers");
+        for (name, value) in self.preflight_headers(&origin, requested_headers.as_deref()).unwrap_or_default() {
+            response.header.set(name, &value);
+        }
+        response.set_status(204);
+        (false, 0)
+    }
+
+    fn handle_response(&self, _req_ctx: i32, request: &Request, response: &Response, _is_error: bool) {
+        let Some(origin) = request.header.get(b"Origin") else {
+            return;
+        };
+        for (name, value) in self.response_headers(&origin).unwrap_or_default() {
+            if name == b"Vary" {
+                response.header.add(name, &value);
+            } else {
+                response.header.set(name, &value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cors(origins: &[&str]) -> Cors {
+        Cors::new(CorsConfig { allowed_origins: origins.iter().map(|o| o.to_string()).collect(), ..Default::default() })
+    }
+
+    fn value<'a>(headers: &'a HeaderList, name: &[u8]) -> Option<&'a [u8]> {
+        headers.iter().find(|(n, _)| *n == name).map(|(_, v)| v.as_slice())
+    }
+
+    #[test]
+    fn origin_not_allowed() {
+        let cors = cors(&["https://a.example"]);
+        assert!(cors.allow_origin(b"https://b.example").is_none());
+        assert!(cors.response_headers(b"https://b.example").is_none());
+    }
+
+    #[test]
+    fn origin_exact_match_is_echoed() {
+        let cors = cors(&["https://a.example"]);
+        let headers = cors.response_headers(b"https://A.example").unwrap();
+        assert_eq!(value(&headers, b"Access-Control-Allow-Origin"), Some(&b"https://A.example"[..]));
+        assert_eq!(value(&headers, b"Vary"), Some(&b"Origin"[..]));
+    }
+
+    #[test]
+    fn wildcard_origin() {
+        let cors = cors(&["*"]);
+        let headers = cors.response_headers(b"https://any.example").unwrap();
+        assert_eq!(value(&headers, b"Access-Control-Allow-Origin"), Some(&b"*"[..]));
+        assert_eq!(value(&headers, b"Vary"), None);
+    }
+
+    #[test]
+    fn wildcard_origin_never_allows_credentials() {
+        let cors = Cors::new(CorsConfig {
+            allowed_origins: vec!["*".into(), "https://app.example".into()],
+            allow_credentials: true,
+            ..Default::default()
+        });
+        let headers = cors.response_headers(b"https://any.example").unwrap();
+        assert_eq!(value(&headers, b"Access-Control-Allow-Origin"), Some(&b"*"[..]));
+        assert_eq!(value(&headers, b"Access-Control-Allow-Credentials"), None);
+        let headers = cors.response_headers(b"https://app.example").unwrap();
+        assert_eq!(value(&headers, b"Access-Control-Allow-Origin"), Some(&b"https://app.example"[..]));
+        assert_eq!(value(&headers, b"Access-Control-Allow-Credentials"), Some(&b"true"[..]));
+    }
+
+    #[test]
+    fn preflight_headers_from_config() {
+        let cors = Cors::new(CorsConfig {
+            allowed_origins: vec!["https://a.example".into()],
+            allowed_methods: vec!["GET".into(), "PUT".into()],
+            allowed_headers: vec!["Content-Type".into(), "Authorization".into()],
+            max_age: Some(600),
+            ..Default::default()
+        });
+        let headers = cors.preflight_headers(b"https://a.example", Some(b"X-Other")).unwrap();
+        assert_eq!(value(&headers, b"Access-Control-Allow-Methods"), Some(&b"GET, PUT"[..]));
+        assert_eq!(value(&headers, b"Access-Control-Allow-Headers"), Some(&b"Content-Type, Authorization"[..]));
+        assert_eq!(value(&headers, b"Access-Control-Max-Age"), Some(&b"600"[..]));
+    }
+
+    #[test]
+    fn preflight_mirrors_requested_headers_for_wildcard() {
+        let cors =
+            Cors::new(CorsConfig { allowed_origins: vec!["*".into()], allowed_headers: vec!["*".into()], ..Default::default() });
+        let headers = cors.preflight_headers(b"https://a.example", Some(b"X-Custom")).unwrap();
+        assert_eq!(value(&headers, b"Access-Control-Allow-Headers"), Some(&b"X-Custom"[..]));
+    }
+
+    #[test]
+    fn exposed_headers_only_on_actual_response() {
+        let cors = Cors::new(CorsConfig {
+            allowed_origins: vec!["*".into()],
+            exposed_headers: vec!["X-Request-Id".into()],
+            ..Default::default()
+        });
+        let headers = cors.response_headers(b"https://a.example").unwrap();
+        assert_eq!(value(&headers, b"Access-Control-Expose-Headers"), Some(&b"X-Request-Id"[..]));
+        let preflight = cors.preflight_headers(b"https://a.example", None).unwrap();
+        assert_eq!(value(&preflight, b"Access-Control-Expose-Headers"), None);
+    }
+
+    #[test]
+    fn non_preflight_request_continues() {
+        // The mock request is a GET without Origin header
+        let cors = cors(&["*"]);
+        let (next, _) = cors.handle_request(&Request::new(), &Response::new());
+        assert!(next);
+        cors.handle_response(0, &Request::new(), &Response::new(), false);
+    }
+}
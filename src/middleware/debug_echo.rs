@@ -0,0 +1,151 @@
+use std::fmt::Write;
+
+use crate::{
+    Guest,
+    digest::constant_time_eq,
+    host::{Request, Response},
+};
+
+/// Configuration for the [`DebugEcho`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugEchoConfig {
+    /// Header that carries the secret.
+    pub header: String,
+    /// Secret that enables the echo; an empty secret disables the component.
+    pub secret: String,
+    /// Headers whose values are replaced by `[redacted]` in the dump, compared
+    /// case-insensitively. The secret header is always redacted.
+    pub redact: Vec<String>,
+}
+
+impl Default for DebugEchoConfig {
+    fn default() -> Self {
+        Self {
+            header: "X-Debug-Echo".to_string(),
+            secret: String::new(),
+            redact: ["Authorization", "Cookie", "Proxy-Authorization"].map(String::from).to_vec(),
+        }
+    }
+}
+
+/// Diagnostic echo of the request as the guest sees it.
+///
+/// Requests carrying the configured secret header are answered with `200` and a
+/// plain text dump of the request line, source address, headers and body size,
+/// instead of being passed to the upstream. This shows what earlier proxy layers did
+/// to the request, e.g. which headers they added, merged or stripped:
+///
+/// ```text
+/// GET /api/users HTTP/1.1
+/// source: 10.0.0.7:51234
+///
+/// Host: example.com
+/// X-Forwarded-For: 203.0.113.9
+/// Authorization: [redacted]
+///
+/// body: 0 bytes
+/// ```
+///
+/// The body size is only meaningful if the plugin enabled
+/// [`BufferRequest`](crate::host::feature::BufferRequest). Without a configured secret
+/// all requests pass through.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{DebugEcho, DebugEchoConfig},
+///     register,
+/// };
+///
+/// register(DebugEcho::new(DebugEchoConfig { secret: "s3cr3t".into(), ..Default::default() }));
+/// ```
+pub struct DebugEcho {
+    config: DebugEchoConfig,
+}
+
+impl DebugEcho {
+    /// Create a new component with the given configuration.
+    pub fn new(config: DebugEchoConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns `true` if the request carries the secret header.
+    pub fn is_enabled_for(&self, request: &Request) -> bool {
+        !self.config.secret.is_empty()
+            && request
+                .header
+                .values_iter(self.config.header.as_bytes())
+                .any(|value| constant_time_eq(&value, self.config.secret.as_bytes()))
+    }
+
+    /// The dump of the request.
+    pub fn dump(&self, request: &Request) -> String {
+        let mut dump =
+            format!("{} {} {}\nsource: {}\n\n", request.method(), request.uri(), request.version(), request.source_addr());
+        for (name, values) in request.header.entries_iter() {
+            let redacted = self.is_redacted(&name);
+            for value in values {
+                match redacted {
+                    true => {
+                        let _ = writeln!(dump, "{name}: [redacted]");
+                    }
+                    false => {
+                        let _ = writeln!(dump, "{name}: {value}");
+                    }
+                }
+            }
+        }
+        let _ = writeln!(dump, "\nbody: {} bytes", request.body.read().len());
+        dump
+    }
+
+    fn is_redacted(&self, name: &[u8]) -> bool {
+        name.eq_ignore_ascii_case(self.config.header.as_bytes())
+            || self.config.redact.iter().any(|redact| name.eq_ignore_ascii_case(redact.as_bytes()))
+    }
+}
+
+impl Guest for DebugEcho {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        if !self.is_enabled_for(request) {
+            return (true, 0);
+        }
+        response.header.set(b"Cache-Control", b"no-store");
+        response.respond(200, b"text/plain; charset=utf-8", self.dump(request).as_bytes());
+        (false, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo(header: &str, secret: &str) -> DebugEcho {
+        DebugEcho::new(DebugEchoConfig { header: header.into(), secret: secret.into(), ..Default::default() })
+    }
+
+    #[test]
+    fn gated_by_secret_header() {
+        // The mock request has the headers "X-FOO: test1", "x-bar: test2, test3" and "x-baz: test4, test4"
+        assert!(echo("X-FOO", "test1").is_enabled_for(&Request::new()));
+        assert!(echo("x-bar", "test3").is_enabled_for(&Request::new()));
+        assert!(!echo("X-FOO", "test2").is_enabled_for(&Request::new()));
+        assert!(!echo("X-Debug-Echo", "test1").is_enabled_for(&Request::new()));
+        assert!(!echo("x-baz", "").is_enabled_for(&Request::new()));
+    }
+
+    #[test]
+    fn dump_redacts_secret() {
+        let dump = echo("X-FOO", "test1").dump(&Request::new());
+        assert!(dump.starts_with("GET https://test HTTP/2.0\nsource: 192.168.1.1\n\n"), "{dump}");
+        assert!(dump.contains("X-FOO: [redacted]\n"), "{dump}");
+        assert!(!dump.contains("test1"), "{dump}");
+        assert!(dump.contains("x-bar: test2\nx-bar: test3\n"), "{dump}");
+        assert!(dump.ends_with("\nbody: 0 bytes\n"), "{dump}");
+    }
+
+    #[test]
+    fn handle_request_short_circuits() {
+        assert_eq!(echo("X-FOO", "test1").handle_request(&Request::new(), &Response::new()), (false, 0));
+        assert_eq!(echo("X-FOO", "wrong").handle_request(&Request::new(), &Response::new()), (true, 0));
+    }
+}
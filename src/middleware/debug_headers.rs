@@ -0,0 +1,151 @@
+use std::cell::RefCell;
+
+use crate::{
+    Guest, GuestState,
+    host::{Request, Response, feature::Feature, time},
+};
+
+thread_local! {
+    static RULE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Report the rule that decided the current request, e.g. `waf:sql-injection`.
+///
+/// Components call this from [`handle_request`](Guest::handle_request); the rule is
+/// only shown by a surrounding [`DebugHeaders`] component.
+pub fn rule_matched(rule: &str) {
+    RULE.with(|current| *current.borrow_mut() = Some(rule.to_string()));
+}
+
+/// Configuration for the [`DebugHeaders`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugHeadersConfig {
+    /// Whether responses are annotated; when disabled the wrapped component runs unchanged.
+    pub enabled: bool,
+    /// Prefix of the header names, e.g. `X-Plugin-` for `X-Plugin-Decision`.
+    pub prefix: String,
+}
+
+impl Default for DebugHeadersConfig {
+    fn default() -> Self {
+        Self { enabled: false, prefix: "X-Plugin-".to_string() }
+    }
+}
+
+/// Troubleshooting mode that makes the decisions of a component visible in its responses.
+///
+/// Wraps another component and annotates the response with:
+///
+/// - `X-Plugin-Decision`: `continue` if the request was passed to the upstream, `stop`
+///   if the component answered it
+/// - `X-Plugin-Latency-Us`: time spent in [`handle_request`](Guest::handle_request)
+/// - `X-Plugin-Rule-Matched`: the rule reported with [`rule_matched`], if any
+///
+/// so a plain `curl -i` shows what the plugin did. Responses to requests that continue
+/// are annotated in [`handle_response`](Guest::handle_response).
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{DebugHeaders, DebugHeadersConfig, Maintenance, MaintenanceConfig},
+///     register,
+/// };
+///
+/// let maintenance = Maintenance::new(MaintenanceConfig { enabled: true, ..Default::default() });
+/// register(DebugHeaders::new(DebugHeadersConfig { enabled: true, ..Default::default() }, maintenance));
+/// ```
+pub struct DebugHeaders<G> {
+    config: DebugHeadersConfig,
+    inner: G,
+    latency_us: GuestState<u64>,
+    rule: GuestState<Option<String>>,
+}
+
+impl<G: Guest> DebugHeaders<G> {
+    /// Wrap `inner` with the given configuration.
+    pub fn new(config: DebugHeadersConfig, inner: G) -> Self {
+        Self { config, inner, latency_us: GuestState::new(0), rule: GuestState::new(None) }
+    }
+
+    fn annotate(&self, response: &Response, decision: &str) {
+        let header = |name: &str| format!("{}{name}", self.config.prefix);
+        response.header.set(header("Decision").as_bytes(), decision.as_bytes());
+        response.header.set(header("Latency-Us").as_bytes(), self.latency_us.get().to_string().as_bytes());
+        self.rule.with(|rule| {
+            if let Some(rule) = rule {
+                response.header.set(header("Rule-Matched").as_bytes(), rule.as_bytes());
+            }
+        });
+    }
+}
+
+impl<G: Guest> Guest for DebugHeaders<G> {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        if !self.config.enabled {
+            return self.inner.handle_request(request, response);
+        }
+        RULE.with(|current| current.borrow_mut().take());
+        let start = time::monotonic_nanos();
+        let (next, ctx) = self.inner.handle_request(request, response);
+        self.latency_us.set(time::monotonic_nanos().saturating_sub(start) / 1000);
+        // Header values must not contain control characters.
+        let rule = RULE.with(|current| current.borrow_mut().take()).map(|rule| rule.replace(|c: char| c.is_control(), " "));
+        self.rule.replace(rule);
+        if !next {
+            self.annotate(response, "stop");
+        }
+        (next, ctx)
+    }
+
+    fn handle_response(&self, req_ctx: i32, request: &Request, response: &Response, is_error: bool) {
+        self.inner.handle_response(req_ctx, request, response, is_error);
+        if self.config.enabled {
+            self.annotate(response, "continue");
+        }
+    }
+
+    fn features(&self) -> Feature {
+        self.inner.features()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Rule(&'static str, bool);
+
+    impl Guest for Rule {
+        fn handle_request(&self, _request: &Request, _response: &Response) -> (bool, i32) {
+            rule_matched(self.0);
+            (self.1, 7)
+        }
+    }
+
+    fn debug(enabled: bool, inner: Rule) -> DebugHeaders<Rule> {
+        DebugHeaders::new(DebugHeadersConfig { enabled, ..Default::default() }, inner)
+    }
+
+    #[test]
+    fn records_sanitized_rule() {
+        let debug = debug(true, Rule("deny\nall", false));
+        assert_eq!(debug.handle_request(&Request::new(), &Response::new()), (false, 7));
+        assert_eq!(debug.rule.with(|rule| rule.clone()).as_deref(), Some("deny all"));
+        assert!(RULE.with(|rule| rule.borrow().is_none()));
+    }
+
+    #[test]
+    fn passes_context_through() {
+        let debug = debug(true, Rule("allow", true));
+        assert_eq!(debug.handle_request(&Request::new(), &Response::new()), (true, 7));
+        debug.handle_response(7, &Request::new(), &Response::new(), false);
+        assert_eq!(debug.rule.with(|rule| rule.clone()).as_deref(), Some("allow"));
+    }
+
+    #[test]
+    fn disabled_only_delegates() {
+        let debug = debug(false, Rule("allow", false));
+        assert_eq!(debug.handle_request(&Request::new(), &Response::new()), (false, 7));
+        assert!(debug.rule.with(Option::is_none));
+        RULE.with(|rule| rule.borrow_mut().take());
+    }
+}
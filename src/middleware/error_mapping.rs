@@ -0,0 +1,194 @@
+use crate::{
+    Guest,
+    host::{Request, Response, admin, feature},
+};
+
+use super::Template;
+
+/// A rule of the [`ErrorMapping`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default, rename_all = "camelCase"))]
+pub struct ErrorRule {
+    /// Upstream status codes the rule applies to.
+    pub statuses: Vec<i32>,
+    /// Status sent to the client instead; the upstream status is kept if `None`.
+    pub status: Option<i32>,
+    /// Content type of the replacement body.
+    pub content_type: String,
+    /// Replacement body, a [`Template`] escaped according to `content_type`.
+    pub body: String,
+    /// Additional response headers.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Default for ErrorRule {
+    fn default() -> Self {
+        Self {
+            statuses: vec![502, 503, 504],
+            status: None,
+            content_type: "application/json".into(),
+            body: r#"{"status":{{status}},"error":"{{reason}}","request_id":"{{request_id}}"}"#.into(),
+            headers: Vec::new(),
+        }
+    }
+}
+
+/// Configuration for the [`ErrorMapping`] component.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default, rename_all = "camelCase"))]
+pub struct ErrorMappingConfig {
+    /// Rules in order of precedence; the first rule matching the upstream status applies.
+    pub rules: Vec<ErrorRule>,
+}
+
+/// Replaces upstream error responses with configured ones.
+///
+/// In [`handle_response`](Guest::handle_response), responses whose status matches a rule
+/// get the configured status, headers and body; the body template can use the
+/// placeholders of [`Template::render_error`] and is rendered with the final status.
+/// The component enables `feature::BufferResponse` for every request, as the upstream
+/// body has to be replaced.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{ErrorMapping, ErrorMappingConfig, ErrorRule},
+///     register,
+/// };
+///
+/// register(ErrorMapping::new(ErrorMappingConfig {
+///     rules: vec![ErrorRule { statuses: vec![502, 504], status: Some(503), ..Default::default() }],
+/// }));
+/// ```
+pub struct ErrorMapping {
+    rules: Vec<(ErrorRule, Template)>,
+}
+
+impl ErrorMapping {
+    /// Create a new component with the given configuration.
+    pub fn new(config: ErrorMappingConfig) -> Self {
+        let rules = config
+            .rules
+            .into_iter()
+            .map(|rule| {
+                let template = Template::for_content_type(&rule.body, &rule.content_type);
+                (rule, template)
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Create the component from the JSON plugin configuration read with
+    /// [`admin::config`].
+    ///
+    /// Requires the `json` feature. Members of a rule are optional and default to
+    /// [`ErrorRule::default`]; without `rules` nothing is mapped:
+    ///
+    /// ```json
+    /// {"rules": [{"statuses": [502, 504], "status": 503, "contentType": "text/plain",
+    ///             "body": "unavailable", "headers": [["Retry-After", "30"]]}]}
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_config() -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(&admin::config()).map(Self::new)
+    }
+
+    fn rule_for(&self, status: i32) -> Option<&(ErrorRule, Template)> {
+        self.rules.iter().find(|(rule, _)| rule.statuses.contains(&status))
+    }
+}
+
+impl Guest for ErrorMapping {
+    fn handle_request(&self, _request: &Request, _response: &Response) -> (bool, i32) {
+        if !self.rules.is_empty() {
+            admin::enable(feature::BufferResponse);
+        }
+        (true, 0)
+    }
+
+    fn handle_response(&self, _req_ctx: i32, request: &Request, response: &Response, _is_error: bool) {
+        let upstream_status = response.status();
+        let Some((rule, template)) = self.rule_for(upstream_status) else {
+            return;
+        };
+        let status = rule.status.unwrap_or(upstream_status);
+        let body = template.render_error(request, status);
+        response.prepare_for_body_rewrite();
+        response.set_status(status);
+        for (name, value) in &rule.headers {
+            response.header.set(name.as_bytes(), value.as_bytes());
+        }
+        response.header.set(b"Content-Type", rule.content_type.as_bytes());
+        response.header.set(b"Content-Length", body.len().to_string().as_bytes());
+        response.body.write(body.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let mapping = ErrorMapping::new(ErrorMappingConfig {
+            rules: vec![
+                ErrorRule { statuses: vec![502], status: Some(503), ..Default::default() },
+                ErrorRule { statuses: vec![502, 504], status: Some(500), ..Default::default() },
+            ],
+        });
+        assert_eq!(mapping.rule_for(502).and_then(|(r, _)| r.status), Some(503));
+        assert_eq!(mapping.rule_for(504).and_then(|(r, _)| r.status), Some(500));
+        assert!(mapping.rule_for(200).is_none());
+    }
+
+    #[test]
+    fn default_rule_renders_json() {
+        let mapping = ErrorMapping::new(ErrorMappingConfig { rules: vec![ErrorRule::default()] });
+        let (_, template) = mapping.rule_for(502).unwrap();
+        // The mock request has no X-Request-Id header
+        assert_eq!(template.render_error(&Request::new(), 502), r#"{"status":502,"error":"Bad Gateway","request_id":""}"#);
+    }
+
+    #[test]
+    fn successful_response_untouched() {
+        // The mock response status is 200
+        let mapping = ErrorMapping::new(ErrorMappingConfig { rules: vec![ErrorRule::default()] });
+        assert_eq!(mapping.handle_request(&Request::new(), &Response::new()), (true, 0));
+        mapping.handle_response(0, &Request::new(), &Response::new(), false);
+    }
+
+    #[test]
+    fn mapped_response() {
+        // The mock response status is 200
+        let mapping = ErrorMapping::new(ErrorMappingConfig {
+            rules: vec![ErrorRule { statuses: vec![200], status: Some(503), ..Default::default() }],
+        });
+        mapping.handle_response(0, &Request::new(), &Response::new(), false);
+        assert_eq!(crate::host::test::take_written_body(1), br#"{"status":503,"error":"Service Unavailable","request_id":""}"#);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn config_from_json() {
+        let config: ErrorMappingConfig = serde_json::from_slice(
+            br#"{"rules": [{"statuses": [502], "status": 503, "contentType": "text/plain", "headers": [["Retry-After", "30"]]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.rules,
+            vec![ErrorRule {
+                statuses: vec![502],
+                status: Some(503),
+                content_type: "text/plain".into(),
+                headers: vec![("Retry-After".into(), "30".into())],
+                ..Default::default()
+            }]
+        );
+        assert_eq!(
+            serde_json::from_slice::<ErrorMappingConfig>(br#"{"rules": [{}]}"#).unwrap().rules,
+            vec![ErrorRule::default()]
+        );
+        assert!(serde_json::from_slice::<ErrorMappingConfig>(br#"{"rules": {}}"#).is_err());
+        // The mock configuration {"config": "test1"} has no error mapping members
+        assert!(ErrorMapping::from_config().is_ok_and(|mapping| mapping.rules.is_empty()));
+    }
+}
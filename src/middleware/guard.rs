@@ -0,0 +1,163 @@
+//! Request guards that reject requests before they reach the upstream.
+//!
+//! Each guard returns a `(bool, i32)` pair in the shape expected from
+//! [`Guest::handle_request`](crate::Guest::handle_request): `(true, 0)` if the
+//! request may continue, `(false, 0)` after the rejection response was configured.
+//!
+//! ```no_run
+//! use http_wasm_guest::{
+//!     Guest,
+//!     host::{Method, Request, Response},
+//!     middleware::guard,
+//! };
+//!
+//! struct Plugin;
+//!
+//! impl Guest for Plugin {
+//!     fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+//!         guard::allow_methods(request, response, &[Method::Get, Method::Head])
+//!     }
+//! }
+//! ```
+use crate::host::{Method, Request, Response};
+
+/// Reject requests whose method is not in `allowed` with `405 Method Not Allowed`.
+///
+/// The rejection carries an `Allow` header listing the permitted methods, as
+/// required by RFC 9110.
+pub fn allow_methods(request: &Request, response: &Response, allowed: &[Method]) -> (bool, i32) {
+    if request.with_method(|method| is_allowed(method, allowed)) {
+        return (true, 0);
+    }
+    response.header.set(b"Allow", allow_header(allowed).as_bytes());
+    response.set_status(405);
+    (false, 0)
+}
+
+/// Reject requests whose host is not matched by one of the `allowed` patterns.
+///
+/// Patterns are exact host names (`example.com`) or wildcards matching one or more
+/// subdomain labels (`*.example.com`, which does not match `example.com` itself);
+/// both are compared case-insensitively and without port. The host is taken from the
+/// `Host` header, or from the request target in absolute form. Requests without a
+/// host are rejected with `400 Bad Request`, mismatches with `421 Misdirected Request`.
+///
+/// This protects upstreams that build URLs from the `Host` header against host
+/// header injection and DNS rebinding.
+pub fn allow_hosts(request: &Request, response: &Response, allowed: &[&str]) -> (bool, i32) {
+    let host = request.header.get(b"Host").map(|h| h.to_vec()).or_else(|| uri_authority(&request.uri()).map(<[u8]>::to_vec));
+    match host {
+        Some(host) if !host.trim_ascii().is_empty() => {
+            if host_allowed(&host, allowed) {
+                return (true, 0);
+            }
+            response.set_status(421);
+        }
+        _ => response.set_status(400),
+    }
+    (false, 0)
+}
+
+fn host_allowed(host: &[u8], allowed: &[&str]) -> bool {
+    let host = strip_port(host.trim_ascii()).to_ascii_lowercase();
+    let host = host.strip_suffix(b".").unwrap_or(&host);
+    allowed.iter().any(|pattern| {
+        let pattern = pattern.to_ascii_lowercase();
+        match pattern.strip_prefix("*.") {
+            Some(domain) => host
+                .strip_suffix(domain.as_bytes())
+                .and_then(|prefix| prefix.strip_suffix(b"."))
+                .is_some_and(|labels| !labels.is_empty()),
+            None => host == pattern.as_bytes(),
+        }
+    })
+}
+
+/// Host without port; IPv6 literals keep their brackets.
+fn strip_port(host: &[u8]) -> &[u8] {
+    if host.starts_with(b"[") {
+        let end = host.iter().position(|b| *b == b']').map_or(host.len(), |i| i + 1);
+        return host.get(..end).unwrap_or(host);
+    }
+    match host.iter().rposition(|b| *b == b':') {
+        Some(i) => host.get(..i).unwrap_or(host),
+        None => host,
+    }
+}
+
+/// Authority of an absolute-form request target, without userinfo.
+fn uri_authority(uri: &[u8]) -> Option<&[u8]> {
+    let start = uri.windows(3).position(|w| w == b"://")? + 3;
+    let rest = uri.get(start..)?;
+    let end = rest.iter().position(|b| matches!(b, b'/' | b'?' | b'#')).unwrap_or(rest.len());
+    let authority = rest.get(..end)?;
+    Some(authority.rsplit(|b| *b == b'@').next().unwrap_or(authority))
+}
+
+fn is_allowed(method: &[u8], allowed: &[Method]) -> bool {
+    Method::parse(method).is_some_and(|m| allowed.contains(&m))
+}
+
+fn allow_header(allowed: &[Method]) -> String {
+    allowed.iter().map(Method::as_str).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_allowed() {
+        assert!(is_allowed(b"GET", &[Method::Get, Method::Post]));
+        assert!(!is_allowed(b"DELETE", &[Method::Get, Method::Post]));
+        assert!(!is_allowed(b"PURGE", &[Method::Get]));
+        assert!(!is_allowed(b"GET", &[]));
+    }
+
+    #[test]
+    fn allow_header_lists_methods() {
+        assert_eq!(allow_header(&[Method::Get, Method::Head, Method::Options]), "GET, HEAD, OPTIONS");
+        assert_eq!(allow_header(&[]), "");
+    }
+
+    #[test]
+    fn host_patterns() {
+        let allowed = ["example.com", "*.example.org"];
+        assert!(host_allowed(b"example.com", &allowed));
+        assert!(host_allowed(b"Example.COM:8080", &allowed));
+        assert!(host_allowed(b"example.com.", &allowed));
+        assert!(host_allowed(b"a.example.org", &allowed));
+        assert!(host_allowed(b"a.b.example.org:443", &allowed));
+        assert!(!host_allowed(b"example.org", &allowed));
+        assert!(!host_allowed(b"badexample.org", &allowed));
+        assert!(!host_allowed(b"www.example.com", &allowed));
+        assert!(!host_allowed(b"example.com.evil", &allowed));
+    }
+
+    #[test]
+    fn host_ports_and_literals() {
+        assert_eq!(strip_port(b"[::1]:8080"), b"[::1]");
+        assert_eq!(strip_port(b"10.0.0.1:80"), b"10.0.0.1");
+        assert!(host_allowed(b"[::1]:8080", &["[::1]"]));
+        assert_eq!(uri_authority(b"https://user@host:1/path"), Some(&b"host:1"[..]));
+        assert_eq!(uri_authority(b"/path"), None);
+    }
+
+    #[test]
+    fn allow_hosts_uses_absolute_uri() {
+        // The mock request is "GET https://test" without Host header
+        assert_eq!(allow_hosts(&Request::new(), &Response::new(), &["test"]), (true, 0));
+        assert_eq!(allow_hosts(&Request::new(), &Response::new(), &["example.com"]), (false, 0));
+    }
+
+    #[test]
+    fn allow_methods_continues_for_allowed() {
+        // The mock request is a GET
+        assert_eq!(allow_methods(&Request::new(), &Response::new(), &[Method::Get]), (true, 0));
+    }
+
+    #[test]
+    fn allow_methods_rejects_other() {
+        assert_eq!(allow_methods(&Request::new(), &Response::new(), &[Method::Post]), (false, 0));
+    }
+}
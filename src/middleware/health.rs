@@ -0,0 +1,189 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    Guest, GuestState,
+    codec::escape_json,
+    host::{
+        Request, Response, admin,
+        feature::{self, Feature},
+        time,
+    },
+};
+
+use super::template::path;
+
+/// Feature names reported by the [`Health`] component.
+const FEATURES: [(&str, Feature); 3] =
+    [("buffer_request", feature::BufferRequest), ("buffer_response", feature::BufferResponse), ("trailers", feature::Trailers)];
+
+/// Configuration for the [`Health`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthConfig {
+    /// Path answered with the plugin status.
+    pub path: String,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self { path: "/_plugin/health".to_string() }
+    }
+}
+
+/// Health and readiness endpoint of the plugin.
+///
+/// Answers `GET` and `HEAD` requests for the configured path directly from
+/// [`handle_request`](Guest::handle_request) with the plugin status as JSON:
+///
+/// ```json
+/// {"status":"ok","config_loaded":true,"uptime_secs":42,
+///  "features":{"buffer_request":true,"buffer_response":false,"trailers":false},
+///  "errors":{"host":0,"upstream_timeout":3}}
+/// ```
+///
+/// The status is `ok` (`200`) while the configuration is loaded and the host granted
+/// all features requested through [`enable`](Health::enable), `degraded` (`503`)
+/// otherwise. Errors are counted with [`record_error`](Health::record_error);
+/// [`handle_response`](Guest::handle_response) counts responses the host marks as
+/// errors as `host`.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     host::feature::BufferRequest,
+///     middleware::{Health, HealthConfig},
+///     register,
+/// };
+///
+/// let health = Health::new(HealthConfig::default());
+/// health.enable(BufferRequest);
+/// register(health);
+/// ```
+pub struct Health {
+    config: HealthConfig,
+    config_loaded: GuestState<bool>,
+    requested: GuestState<i32>,
+    granted: GuestState<i32>,
+    started: u64,
+    errors: GuestState<BTreeMap<String, u64>>,
+}
+
+impl Health {
+    /// Create a new component with the given configuration.
+    ///
+    /// The configuration counts as loaded if the host provided a non-empty one.
+    pub fn new(config: HealthConfig) -> Self {
+        Self {
+            config,
+            config_loaded: GuestState::new(!admin::config().is_empty()),
+            requested: GuestState::new(0),
+            granted: GuestState::new(0),
+            started: time::unix_secs(),
+            errors: GuestState::new(BTreeMap::from([("host".to_string(), 0)])),
+        }
+    }
+
+    /// Enable host features through [`admin::enable`] and record which of them the
+    /// host reports as enabled.
+    pub fn enable(&self, features: Feature) -> i32 {
+        let granted = admin::enable(features);
+        self.requested.update(|requested| requested | i32::from(features));
+        self.granted.set(granted);
+        granted
+    }
+
+    /// Mark whether the plugin configuration was parsed successfully.
+    pub fn set_config_loaded(&self, loaded: bool) {
+        self.config_loaded.set(loaded);
+    }
+
+    /// Increment the error counter `kind`, e.g. `upstream_timeout`.
+    pub fn record_error(&self, kind: &str) {
+        self.errors.with_mut(|errors| *errors.entry(kind.to_string()).or_default() += 1);
+    }
+
+    /// Returns `true` if the configuration is loaded and all requested features are granted.
+    pub fn is_ready(&self) -> bool {
+        self.config_loaded.get() && self.granted.get() & self.requested.get() == self.requested.get()
+    }
+
+    /// The status document served by the endpoint.
+    pub fn to_json(&self) -> String {
+        let status = if self.is_ready() { "ok" } else { "degraded" };
+        let uptime = time::unix_secs().saturating_sub(self.started);
+        let mut json =
+            format!(r#"{{"status":"{status}","config_loaded":{},"uptime_secs":{uptime},"features":{{"#, self.config_loaded.get());
+        for (i, (name, feature)) in FEATURES.iter().enumerate() {
+            let granted = self.granted.get() & i32::from(*feature) != 0;
+            let _ = write!(json, r#"{}"{name}":{granted}"#, if i == 0 { "" } else { "," });
+        }
+        json.push_str(r#"},"errors":{"#);
+        self.errors.with(|errors| {
+            for (i, (kind, count)) in errors.iter().enumerate() {
+                json.push_str(if i == 0 { "\"" } else { ",\"" });
+                escape_json(kind, &mut json);
+                let _ = write!(json, "\":{count}");
+            }
+        });
+        json.push_str("}}");
+        json
+    }
+}
+
+impl Guest for Health {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        if request.with_uri(|uri| path(uri) != self.config.path)
+            || !request.with_method(|method| matches!(method, b"GET" | b"HEAD"))
+        {
+            return (true, 0);
+        }
+        let status = if self.is_ready() { 200 } else { 503 };
+        response.header.set(b"Cache-Control", b"no-store");
+        response.respond(status, b"application/json", self.to_json().as_bytes());
+        (false, 0)
+    }
+
+    fn handle_response(&self, _req_ctx: i32, _request: &Request, _response: &Response, is_error: bool) {
+        if is_error {
+            self.record_error("host");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_document() {
+        // The mock provides a non-empty configuration and reports no enabled features.
+        let health = Health::new(HealthConfig::default());
+        health.record_error("upstream \"timeout\"");
+        health.record_error("upstream \"timeout\"");
+        assert!(health.is_ready());
+        assert_eq!(
+            health.to_json(),
+            r#"{"status":"ok","config_loaded":true,"uptime_secs":0,"features":{"buffer_request":false,"buffer_response":false,"trailers":false},"errors":{"host":0,"upstream \"timeout\"":2}}"#
+        );
+    }
+
+    #[test]
+    fn degraded_without_config_or_features() {
+        let health = Health::new(HealthConfig::default());
+        health.set_config_loaded(false);
+        assert!(!health.is_ready());
+        health.set_config_loaded(true);
+        health.enable(feature::BufferRequest);
+        assert!(!health.is_ready());
+        assert!(health.to_json().starts_with(r#"{"status":"degraded","#));
+    }
+
+    #[test]
+    fn serves_configured_path() {
+        // The mock request is "GET https://test"
+        let health = Health::new(HealthConfig::default());
+        assert_eq!(health.handle_request(&Request::new(), &Response::new()), (true, 0));
+        let health = Health::new(HealthConfig { path: "https://test".into() });
+        assert_eq!(health.handle_request(&Request::new(), &Response::new()), (false, 0));
+        health.handle_response(0, &Request::new(), &Response::new(), true);
+        assert_eq!(health.errors.with(|errors| errors.get("host").copied()), Some(1));
+    }
+}
@@ -0,0 +1,327 @@
+use std::{borrow::Cow, time::Duration};
+
+use crate::{
+    Guest,
+    codec::percent_decode,
+    host::{Request, Response, log},
+};
+
+use super::{SlidingWindow, client_ip};
+
+/// Configuration for the [`Honeypot`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoneypotConfig {
+    /// Decoy paths no legitimate client requests. Entries ending in `*` match all paths
+    /// with that prefix; matching is done on the percent-decoded path and ignores the
+    /// query string and ASCII case.
+    pub paths: Vec<String>,
+    /// How long a client IP is blocked after requesting a decoy path.
+    pub block_for: Duration,
+    /// Status of the response to decoy and blocked requests.
+    pub status: i32,
+    /// Request header with the client IP appended by a trusted proxy, e.g.
+    /// `X-Forwarded-For`; the last address is used. `None` uses the source address.
+    ///
+    /// Only set it if every request passes the proxy, which must append to the header,
+    /// otherwise clients choose the address that is blocked.
+    pub client_ip_header: Option<String>,
+}
+
+impl Default for HoneypotConfig {
+    fn default() -> Self {
+        Self {
+            paths: ["/wp-login.php", "/wp-admin*", "/xmlrpc.php", "/.env", "/.git/*", "/phpmyadmin*"].map(String::from).to_vec(),
+            block_for: Duration::from_secs(600),
+            status: 403,
+            client_ip_header: None,
+        }
+    }
+}
+
+/// Number of buckets of the block window; blocks end within a tenth of `block_for`.
+const BUCKETS: usize = 10;
+
+/// Log level of security events (warn).
+const LOG_LEVEL: i32 = 1;
+
+/// Decoy path tripwire.
+///
+/// A request for one of the configured decoy paths marks the client IP (without port)
+/// as hostile: the request and all further requests from that IP are rejected with
+/// `status` for about `block_for`. Each trip is logged as a security event in
+/// `key=value` form, e.g.
+/// `event=honeypot client_ip=10.0.0.1 method=GET path=/wp-login.php block_secs=600`.
+///
+/// Blocked IPs are kept in a [`SlidingWindow`] of the guest instance, so they are not
+/// shared between instances or replicas.
+///
+/// Behind a load balancer or reverse proxy the source address is the proxy, and a trip
+/// would block every client. Set [`client_ip_header`](HoneypotConfig::client_ip_header)
+/// to the header the proxy appends the client IP to.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{Honeypot, HoneypotConfig},
+///     register,
+/// };
+///
+/// register(Honeypot::new(HoneypotConfig { paths: vec!["/admin.php".into()], ..Default::default() }));
+/// ```
+pub struct Honeypot {
+    config: HoneypotConfig,
+    blocked: SlidingWindow,
+}
+
+impl Honeypot {
+    /// Create a new component with the given configuration.
+    pub fn new(config: HoneypotConfig) -> Self {
+        let blocked = SlidingWindow::new(config.block_for, BUCKETS);
+        Self { config, blocked }
+    }
+
+    /// Create the component from the JSON plugin configuration read with
+    /// [`admin::config`](crate::host::admin::config).
+    ///
+    /// Requires the `json` feature. All members are optional and default to
+    /// [`HoneypotConfig::default`]:
+    ///
+    /// ```json
+    /// {"paths": ["/wp-login.php", "/.env"], "blockSecs": 600, "status": 403, "clientIpHeader": "X-Forwarded-For"}
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_config() -> Result<Self, serde_json::Error> {
+        parse_config(&crate::host::admin::config()).map(Self::new)
+    }
+
+    /// Whether `path` (without query string) is a decoy path.
+    pub fn is_decoy(&self, path: &[u8]) -> bool {
+        self.config.paths.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => path.get(..prefix.len()).is_some_and(|p| p.eq_ignore_ascii_case(prefix.as_bytes())),
+            None => path.eq_ignore_ascii_case(pattern.as_bytes()),
+        })
+    }
+
+    /// Whether the client IP is currently blocked.
+    pub fn is_blocked(&self, ip: &[u8]) -> bool {
+        self.blocked.count(ip) > 0
+    }
+
+    /// Block the client IP for `block_for`.
+    pub fn block(&self, ip: &[u8]) {
+        self.blocked.increment(ip);
+    }
+
+    /// Call `f` with the client IP, from `client_ip_header` if configured and present.
+    fn with_client_ip<R>(&self, request: &Request, f: impl FnOnce(&[u8]) -> R) -> R {
+        let Some(name) = &self.config.client_ip_header else {
+            return request.with_source_addr(|addr| f(client_ip(addr)));
+        };
+        request.header.with_values(name.as_bytes(), |values| match values.last().and_then(|value| forwarded_ip(value)) {
+            Some(ip) => f(ip),
+            None => request.with_source_addr(|addr| f(client_ip(addr))),
+        })
+    }
+}
+
+impl Guest for Honeypot {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        self.with_client_ip(request, |ip| {
+            if self.is_blocked(ip) {
+                return response.deny(self.config.status, "Forbidden").into();
+            }
+            request.with_uri(|uri| {
+                let path = decoded_path(uri);
+                if !self.is_decoy(&path) {
+                    return (true, 0);
+                }
+                self.block(ip);
+                if log::enabled(LOG_LEVEL) {
+                    let event = request.with_method(|method| {
+                        event(&[
+                            ("event", b"honeypot"),
+                            ("client_ip", ip),
+                            ("method", method),
+                            ("path", &path),
+                            ("block_secs", self.config.block_for.as_secs().to_string().as_bytes()),
+                        ])
+                    });
+                    log::write(LOG_LEVEL, event.as_bytes());
+                }
+                response.deny(self.config.status, "Forbidden").into()
+            })
+        })
+    }
+}
+
+/// The last address of a forwarded-for list, which the nearest proxy appended.
+fn forwarded_ip(value: &[u8]) -> Option<&[u8]> {
+    let ip = value.rsplit(|b| *b == b',').next()?.trim_ascii();
+    (!ip.is_empty()).then(|| client_ip(ip))
+}
+
+/// Percent-decoded path of the request target, so encoded decoy paths match too.
+fn decoded_path(uri: &[u8]) -> Cow<'_, [u8]> {
+    let path = path(uri);
+    match path.contains(&b'%') {
+        true => Cow::Owned(percent_decode(path, false)),
+        false => Cow::Borrowed(path),
+    }
+}
+
+#[cfg(feature = "json")]
+fn parse_config(json: &[u8]) -> Result<HoneypotConfig, serde_json::Error> {
+    use serde::de::Error;
+    use serde_json::Value;
+
+    let invalid = |member: &str| serde_json::Error::custom(format!("invalid honeypot member {member}"));
+    let Value::Object(object) = serde_json::from_slice(json)? else {
+        return Err(serde_json::Error::custom("expected an object"));
+    };
+    let mut config = HoneypotConfig::default();
+    if let Some(paths) = object.get("paths") {
+        let paths = paths.as_array().ok_or_else(|| invalid("paths"))?;
+        config.paths =
+            paths.iter().map(|path| path.as_str().map(String::from).ok_or_else(|| invalid("paths"))).collect::<Result<_, _>>()?;
+    }
+    if let Some(secs) = object.get("blockSecs") {
+        config.block_for = Duration::from_secs(secs.as_u64().ok_or_else(|| invalid("blockSecs"))?);
+    }
+    if let Some(status) = object.get("status") {
+        let status = status.as_i64().and_then(|status| i32::try_from(status).ok());
+        config.status = status.filter(|status| (100..=599).contains(status)).ok_or_else(|| invalid("status"))?;
+    }
+    if let Some(header) = object.get("clientIpHeader") {
+        config.client_ip_header = Some(header.as_str().ok_or_else(|| invalid("clientIpHeader"))?.to_string());
+    }
+    Ok(config)
+}
+
+/// Path of an origin-form or absolute-form request target, without query and fragment.
+fn path(uri: &[u8]) -> &[u8] {
+    let start = match uri.windows(3).position(|w| w == b"://") {
+        Some(scheme_end) => {
+            let authority = uri.get(scheme_end + 3..).unwrap_or_default();
+            scheme_end + 3 + authority.iter().position(|b| matches!(b, b'/' | b'?' | b'#')).unwrap_or(authority.len())
+        }
+        None => 0,
+    };
+    let rest = uri.get(start..).unwrap_or_default();
+    let end = rest.iter().position(|b| matches!(b, b'?' | b'#')).unwrap_or(rest.len());
+    rest.get(..end).unwrap_or_default()
+}
+
+/// Format fields as `key=value` pairs; values with spaces, quotes, `=` or control
+/// characters are quoted.
+fn event(fields: &[(&str, &[u8])]) -> String {
+    let mut out = String::new();
+    for (key, value) in fields {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        let value = String::from_utf8_lossy(value);
+        out.push_str(key);
+        out.push('=');
+        if value.is_empty() || value.chars().any(|c| c == ' ' || c == '"' || c == '=' || c.is_control()) {
+            out.push_str(&format!("{value:?}"));
+        } else {
+            out.push_str(&value);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoy_paths() {
+        let honeypot = Honeypot::new(HoneypotConfig::default());
+        assert!(honeypot.is_decoy(b"/wp-login.php"));
+        assert!(honeypot.is_decoy(b"/WP-Admin/setup.php"));
+        assert!(honeypot.is_decoy(b"/.git/config"));
+        assert!(!honeypot.is_decoy(b"/.git"));
+        assert!(!honeypot.is_decoy(b"/wp-login.php.bak"));
+        assert!(!honeypot.is_decoy(b"/"));
+    }
+
+    #[test]
+    fn encoded_decoy_paths() {
+        let honeypot = Honeypot::new(HoneypotConfig::default());
+        assert!(honeypot.is_decoy(&decoded_path(b"/%2eenv")));
+        assert!(honeypot.is_decoy(&decoded_path(b"https://host/%2E%67it/config?x=1")));
+        assert!(matches!(decoded_path(b"/plain?%2e"), Cow::Borrowed(b"/plain")));
+    }
+
+    #[test]
+    fn forwarded_client_ip() {
+        assert_eq!(forwarded_ip(b"203.0.113.9"), Some(&b"203.0.113.9"[..]));
+        assert_eq!(forwarded_ip(b"198.51.100.1, 203.0.113.9 "), Some(&b"203.0.113.9"[..]));
+        assert_eq!(forwarded_ip(b"[2001:db8::1]:443"), Some(&b"2001:db8::1"[..]));
+        assert_eq!(forwarded_ip(b"203.0.113.9, "), None);
+    }
+
+    #[test]
+    fn path_of_request_target() {
+        assert_eq!(path(b"/wp-login.php?redirect=1"), b"/wp-login.php");
+        assert_eq!(path(b"https://host:8080/.env#x"), b"/.env");
+        assert_eq!(path(b"https://host"), b"");
+    }
+
+    #[test]
+    fn event_fields() {
+        assert_eq!(event(&[("event", b"honeypot"), ("path", b"/a b")]), r#"event=honeypot path="/a b""#);
+        assert_eq!(event(&[("ua", b"")]), r#"ua="""#);
+    }
+
+    #[test]
+    fn block_expires() {
+        let honeypot = Honeypot::new(HoneypotConfig { block_for: Duration::from_secs(10), ..Default::default() });
+        honeypot.blocked.add_at(b"10.0.0.1", 1, 0);
+        assert_eq!(honeypot.blocked.count_at(b"10.0.0.1", 9_999), 1);
+        assert_eq!(honeypot.blocked.count_at(b"10.0.0.1", 10_000), 0);
+    }
+
+    #[test]
+    fn trip_blocks_client() {
+        // The mock request is "GET https://test" from 192.168.1.1
+        let honeypot = Honeypot::new(HoneypotConfig { paths: vec!["*".into()], ..Default::default() });
+        assert!(!honeypot.is_blocked(b"192.168.1.1"));
+        assert_eq!(honeypot.handle_request(&Request::new(), &Response::new()), (false, 0));
+        assert!(honeypot.is_blocked(b"192.168.1.1"));
+        let other = Honeypot::new(HoneypotConfig::default());
+        assert_eq!(other.handle_request(&Request::new(), &Response::new()), (true, 0));
+    }
+
+    #[test]
+    fn trip_blocks_forwarded_client() {
+        // The mock request has "x-bar: test2, test3" and no X-Forwarded-For header
+        let config = HoneypotConfig { paths: vec!["*".into()], client_ip_header: Some("x-bar".into()), ..Default::default() };
+        let honeypot = Honeypot::new(config);
+        assert_eq!(honeypot.handle_request(&Request::new(), &Response::new()), (false, 0));
+        assert!(honeypot.is_blocked(b"test3"));
+        assert!(!honeypot.is_blocked(b"192.168.1.1"));
+        let config =
+            HoneypotConfig { paths: vec!["*".into()], client_ip_header: Some("X-Forwarded-For".into()), ..Default::default() };
+        let fallback = Honeypot::new(config);
+        fallback.handle_request(&Request::new(), &Response::new());
+        assert!(fallback.is_blocked(b"192.168.1.1"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn config_from_json() {
+        let config =
+            parse_config(br#"{"paths": ["/admin.php"], "blockSecs": 60, "status": 404, "clientIpHeader": "X-Forwarded-For"}"#)
+                .unwrap();
+        assert_eq!(config.paths, ["/admin.php"]);
+        assert_eq!((config.block_for, config.status), (Duration::from_secs(60), 404));
+        assert_eq!(config.client_ip_header.as_deref(), Some("X-Forwarded-For"));
+        assert_eq!(parse_config(b"{}").unwrap(), HoneypotConfig::default());
+        assert!(parse_config(br#"{"paths": "/admin.php"}"#).is_err());
+        assert!(parse_config(br#"{"status": 42}"#).is_err());
+        assert!(parse_config(b"[]").is_err());
+        // The mock configuration {"config": "test1"} has no honeypot members
+        assert!(Honeypot::from_config().is_ok_and(|honeypot| honeypot.config == HoneypotConfig::default()));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{
+    Guest,
+    codec::percent_decode,
+    host::{Request, Response, admin, feature},
+};
+
+use super::media_type_matches;
+
+/// Configuration for the [`JsonFormat`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonFormatConfig {
+    /// Query parameter requesting pretty-printed output, e.g. `?pretty`; the values
+    /// `0` and `false` disable it.
+    pub query_param: Option<String>,
+    /// Request header requesting pretty-printed output, with the same values.
+    pub header: Option<String>,
+    /// Number of spaces per indentation level of pretty-printed output.
+    pub indent: usize,
+    /// Formatted media types. An entry ending in `/` matches the whole type, an entry
+    /// starting with `+` matches a structured syntax suffix (`+json`).
+    pub content_types: Vec<String>,
+    /// Larger bodies are passed through unchanged.
+    pub max_size: usize,
+}
+
+impl Default for JsonFormatConfig {
+    fn default() -> Self {
+        Self {
+            query_param: Some("pretty".into()),
+            header: Some("X-Pretty-Json".into()),
+            indent: 2,
+            content_types: ["application/json", "+json"].map(String::from).to_vec(),
+            max_size: 1024 * 1024,
+        }
+    }
+}
+
+/// Minifies JSON response bodies, or pretty-prints them on request.
+///
+/// Responses are minified by default, which saves bandwidth for API clients; humans
+/// browsing an endpoint add the query parameter or header to get indented output.
+/// Formatting only changes whitespace between tokens, so member order, numbers and
+/// string escapes are kept exactly. Bodies that are not valid JSON, are encoded or are
+/// larger than `max_size` are passed through unchanged.
+///
+/// [`handle_request`](Guest::handle_request) returns `1` as context if pretty output
+/// was requested and `0` otherwise.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{JsonFormat, JsonFormatConfig},
+///     register,
+/// };
+///
+/// register(JsonFormat::new(JsonFormatConfig { indent: 4, ..Default::default() }));
+/// ```
+pub struct JsonFormat {
+    config: JsonFormatConfig,
+}
+
+impl JsonFormat {
+    /// Create a new component with the given configuration.
+    pub fn new(config: JsonFormatConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether the request asks for pretty-printed output.
+    pub fn wants_pretty(&self, request: &Request) -> bool {
+        let from_query = self.config.query_param.as_ref().and_then(|name| query_value(&request.uri(), name));
+        let from_header = || self.config.header.as_ref().and_then(|name| request.header.get(name.as_bytes()).map(|v| v.to_vec()));
+        from_query.or_else(from_header).is_some_and(|value| !is_disabled(&value))
+    }
+
+    /// Format the buffered response body; returns `true` if it was changed.
+    ///
+    /// Requires `feature::BufferResponse`.
+    pub fn format(&self, response: &Response, pretty: bool) -> bool {
+        let formatted = response.header.get(b"Content-Encoding").is_none()
+            && response.header.get(b"Content-Type").is_some_and(|ct| media_type_matches(&ct, &self.config.content_types));
+        if !formatted {
+            return false;
+        }
+        let body = response.body.read();
+        if body.len() > self.config.max_size {
+            return false;
+        }
+        let Some(output) = self.format_json(&body, pretty) else {
+            return false;
+        };
+        response.prepare_for_body_rewrite();
+        response.body.write(&output);
+        true
+    }
+
+    /// The reformatted body, or `None` if it is not valid JSON or already formatted.
+    fn format_json(&self, body: &[u8], pretty: bool) -> Option<Vec<u8>> {
+        serde_json::from_slice::<serde::de::IgnoredAny>(body).ok()?;
+        let minified = minify(body);
+        let output = if pretty { indent(&minified, self.config.indent) } else { minified };
+        (output != body).then_some(output)
+    }
+}
+
+impl Guest for JsonFormat {
+    fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+        admin::enable(feature::BufferResponse);
+        (true, i32::from(self.wants_pretty(request)))
+    }
+
+    fn handle_response(&self, req_ctx: i32, _request: &Request, response: &Response, is_error: bool) {
+        if !is_error {
+            self.format(response, req_ctx == 1);
+        }
+    }
+}
+
+/// Whether a parameter or header value is `0` or `false`.
+fn is_disabled(value: &[u8]) -> bool {
+    let value = value.trim_ascii();
+    value == b"0" || value.eq_ignore_ascii_case(b"false")
+}
+
+/// Decoded value of the first query parameter `name`; empty for `?name`.
+fn query_value(uri: &[u8], name: &str) -> Option<Vec<u8>> {
+    let query = uri.iter().position(|b| *b == b'?').and_then(|i| uri.get(i + 1..))?;
+    let query = query.split(|b| *b == b'#').next().unwrap_or_default();
+    query.split(|b| *b == b'&').find_map(|pair| {
+        let (key, value) = match pair.iter().position(|b| *b == b'=') {
+            Some(eq) => (pair.get(..eq).unwrap_or_default(), pair.get(eq + 1..).unwrap_or_default()),
+            None => (pair, &b""[..]),
+        };
+        (percent_decode(key, true) == name.as_bytes()).then(|| percent_decode(value, true))
+    })
+}
+
+/// Remove all whitespace outside of strings.
+fn minify(json: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for &b in json {
+        if in_string {
+            in_string = escaped || b != b'"';
+            escaped = !escaped && b == b'\\';
+        } else if b == b'"' {
+            in_string = true;
+        } else if matches!(b, b' ' | b'\t' | b'\n' | b'\r') {
+            continue;
+        }
+        out.push(b);
+    }
+    out
+}
+
+/// Pretty-print minified JSON with `width` spaces per level; empty containers stay
+/// on one line.
+fn indent(minified: &[u8], width: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(minified.len() * 2);
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let newline = |out: &mut Vec<u8>, depth: usize| {
+        out.push(b'\n');
+        out.resize(out.len() + depth * width, b' ');
+    };
+    for (i, &b) in minified.iter().enumerate() {
+        if in_string {
+            in_string = escaped || b != b'"';
+            escaped = !escaped && b == b'\\';
+            out.push(b);
+            continue;
+        }
+        match b {
+            b'"' => {
+                in_string = true;
+                out.push(b);
+            }
+            b'{' | b'[' => {
+                out.push(b);
+                if !matches!(minified.get(i + 1), Some(b'}' | b']')) {
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            b'}' | b']' => {
+                if !matches!(minified.get(i.wrapping_sub(1)), Some(b'{' | b'[')) {
+                    depth = depth.saturating_sub(1);
+                    newline(&mut out, depth);
+                }
+                out.push(b);
+            }
+            b',' => {
+                out.push(b);
+                newline(&mut out, depth);
+            }
+            b':' => out.extend_from_slice(b": "),
+            _ => out.push(b),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minify_keeps_strings() {
+        assert_eq!(minify(b" { \"a b\" : [ 1 , \"x\\\" y\" ] ,\n\"c\":{} } "), b"{\"a b\":[1,\"x\\\" y\"],\"c\":{}}");
+        assert_eq!(minify(br#"["\\", " "]"#), br#"["\\"," "]"#);
+    }
+
+    #[test]
+    fn indent_nested() {
+        assert_eq!(
+            String::from_utf8_lossy(&indent(br#"{"a":[1,{}],"b":"x,{y}","c":[]}"#, 2)),
+            "{\n  \"a\": [\n    1,\n    {}\n  ],\n  \"b\": \"x,{y}\",\n  \"c\": []\n}"
+        );
+        assert_eq!(indent(b"3", 2), b"3");
+    }
+
+    #[test]
+    fn format_json_body() {
+        let format = JsonFormat::new(JsonFormatConfig::default());
+        assert_eq!(format.format_json(b"{ \"b\": 1, \"a\": 1.50 }", false).as_deref(), Some(&b"{\"b\":1,\"a\":1.50}"[..]));
+        assert_eq!(format.format_json(b"{\"b\":1}", false), None);
+        assert_eq!(format.format_json(b"{\"b\":1}", true).as_deref(), Some(&b"{\n  \"b\": 1\n}"[..]));
+        assert_eq!(format.format_json(b"{ not json", false), None);
+    }
+
+    #[test]
+    fn pretty_query_param() {
+        assert_eq!(query_value(b"/api?x=1&pretty", "pretty"), Some(Vec::new()));
+        assert_eq!(query_value(b"/api?pretty=false#f", "pretty"), Some(b"false".to_vec()));
+        assert_eq!(query_value(b"/api?prettyx=1", "pretty"), None);
+        assert!(is_disabled(b" 0 "));
+        assert!(!is_disabled(b""));
+    }
+
+    #[test]
+    fn mock_request_is_not_pretty() {
+        // The mock request is "GET https://test" without headers
+        let format = JsonFormat::new(JsonFormatConfig::default());
+        assert_eq!(format.handle_request(&Request::new(), &Response::new()), (true, 0));
+        // The mock response has no Content-Type header
+        assert!(!format.format(&Response::new(), true));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{
+    Guest,
+    headers::RetryAfter,
+    host::{Request, Response},
+};
+
+/// Configuration for the [`Maintenance`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default, rename_all = "camelCase"))]
+pub struct MaintenanceConfig {
+    /// Whether maintenance mode is active.
+    pub enabled: bool,
+    /// Path prefixes in maintenance; an empty list puts all requests in maintenance.
+    pub path_prefixes: Vec<String>,
+    /// Body of the maintenance response, e.g. an HTML page or a JSON document.
+    pub body: String,
+    /// Content type of `body`.
+    pub content_type: String,
+    /// Value of the `Retry-After` header in seconds, if any.
+    pub retry_after: Option<u32>,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path_prefixes: Vec::new(),
+            body: "<html><body><h1>Service under maintenance</h1></body></html>".to_string(),
+            content_type: "text/html; charset=utf-8".to_string(),
+            retry_after: None,
+        }
+    }
+}
+
+/// Maintenance mode switch.
+///
+/// When enabled, matching requests are answered directly with `503 Service Unavailable`
+/// and the configured body, bypassing the upstream entirely.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{Maintenance, MaintenanceConfig},
+///     register,
+/// };
+///
+/// register(Maintenance::new(MaintenanceConfig {
+///     enabled: true,
+///     body: r#"{"error":"maintenance"}"#.into(),
+///     content_type: "application/json".into(),
+///     retry_after: Some(600),
+///     ..Default::default()
+/// }));
+/// ```
+pub struct Maintenance {
+    config: MaintenanceConfig,
+}
+
+impl Maintenance {
+    /// Create a new component with the given configuration.
+    pub fn new(config: MaintenanceConfig) -> Self {
+        Self { config }
+    }
+
+    /// Create the component from the JSON plugin configuration read with
+    /// [`admin::config`](crate::host::admin::config).
+    ///
+    /// Requires the `json` feature. All members are optional and default to
+    /// [`MaintenanceConfig::default`]:
+    ///
+    /// ```json
+    /// {"enabled": true, "pathPrefixes": ["/shop"], "body": "{\"error\":\"maintenance\"}",
+    ///  "contentType": "application/json", "retryAfter": 600}
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_config() -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(&crate::host::admin::config()).map(Self::new)
+    }
+
+    /// Returns `true` if a request for `uri` is in maintenance.
+    pub fn is_active_for(&self, uri: &[u8]) -> bool {
+        self.config.enabled
+            && (self.config.path_prefixes.is_empty() || self.config.path_prefixes.iter().any(|p| uri.starts_with(p.as_bytes())))
+    }
+}
+
+impl Guest for Maintenance {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        if !request.with_uri(|uri| self.is_active_for(uri)) {
+            return (true, 0);
+        }
+        response.header.set(b"Content-Type", self.config.content_type.as_bytes());
+        response.header.set(b"Cache-Control", b"no-store");
+        match self.config.retry_after {
+            Some(secs) => response.retry_later(503, RetryAfter::Seconds(secs as u64)),
+            None => response.set_status(503),
+        }
+        response.body.write(self.config.body.as_bytes());
+        (false, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_passes_through() {
+        let maintenance = Maintenance::new(MaintenanceConfig::default());
+        assert!(!maintenance.is_active_for(b"/"));
+        assert_eq!(maintenance.handle_request(&Request::new(), &Response::new()), (true, 0));
+    }
+
+    #[test]
+    fn enabled_for_all_paths() {
+        let maintenance = Maintenance::new(MaintenanceConfig { enabled: true, ..Default::default() });
+        assert!(maintenance.is_active_for(b"/any"));
+        assert_eq!(maintenance.handle_request(&Request::new(), &Response::new()), (false, 0));
+    }
+
+    #[test]
+    fn enabled_for_path_prefix() {
+        let maintenance =
+            Maintenance::new(MaintenanceConfig { enabled: true, path_prefixes: vec!["/shop".into()], ..Default::default() });
+        assert!(maintenance.is_active_for(b"/shop/cart"));
+        assert!(!maintenance.is_active_for(b"/blog"));
+        // The mock URI is "https://test"
+        assert_eq!(maintenance.handle_request(&Request::new(), &Response::new()), (true, 0));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn config_from_json() {
+        let config: MaintenanceConfig =
+            serde_json::from_slice(br#"{"enabled": true, "pathPrefixes": ["/shop"], "retryAfter": 600}"#).unwrap();
+        assert_eq!(
+            config,
+            MaintenanceConfig {
+                enabled: true,
+                path_prefixes: vec!["/shop".into()],
+                retry_after: Some(600),
+                ..Default::default()
+            }
+        );
+        assert!(serde_json::from_slice::<MaintenanceConfig>(br#"{"retryAfter": -1}"#).is_err());
+        // The mock configuration {"config": "test1"} has no maintenance members
+        assert!(Maintenance::from_config().is_ok_and(|maintenance| maintenance.config == MaintenanceConfig::default()));
+    }
+}
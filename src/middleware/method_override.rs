@@ -0,0 +1,103 @@
+use crate::{
+    Guest,
+    host::{Method, Request, Response},
+};
+
+/// Configuration for the [`MethodOverride`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodOverrideConfig {
+    /// Header carrying the intended method.
+    pub header: String,
+    /// Methods a `POST` request may be turned into.
+    pub allowed: Vec<Method>,
+}
+
+impl Default for MethodOverrideConfig {
+    fn default() -> Self {
+        Self { header: "X-HTTP-Method-Override".into(), allowed: vec![Method::Put, Method::Patch, Method::Delete] }
+    }
+}
+
+/// Rewrites the method of `POST` requests from the `X-HTTP-Method-Override` header.
+///
+/// For clients behind proxies that only pass `GET` and `POST`. The override applies
+/// only to `POST` requests and only to the configured methods; other values are
+/// ignored. The header is removed in either case, so the upstream does not apply it
+/// a second time.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     host::Method,
+///     middleware::{MethodOverride, MethodOverrideConfig},
+///     register,
+/// };
+///
+/// register(MethodOverride::new(MethodOverrideConfig { allowed: vec![Method::Delete], ..Default::default() }));
+/// ```
+pub struct MethodOverride {
+    config: MethodOverrideConfig,
+}
+
+impl MethodOverride {
+    /// Create a new component with the given configuration.
+    pub fn new(config: MethodOverrideConfig) -> Self {
+        Self { config }
+    }
+
+    /// Apply the override to `request`; returns the new method if it was changed.
+    pub fn apply(&self, request: &Request) -> Option<Method> {
+        let value = request.header.get(self.config.header.as_bytes())?;
+        request.header.remove(self.config.header.as_bytes());
+        let method = self.override_method(&request.method(), &value)?;
+        request.set_method(method.as_str().as_bytes());
+        Some(method)
+    }
+
+    fn override_method(&self, method: &[u8], value: &[u8]) -> Option<Method> {
+        if method != b"POST" {
+            return None;
+        }
+        let value = value.trim_ascii().to_ascii_uppercase();
+        Method::parse(&value).filter(|m| self.config.allowed.contains(m))
+    }
+}
+
+impl Guest for MethodOverride {
+    fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+        self.apply(request);
+        (true, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn method_override() -> MethodOverride {
+        MethodOverride::new(MethodOverrideConfig::default())
+    }
+
+    #[test]
+    fn overrides_post_only() {
+        let sut = method_override();
+        assert_eq!(sut.override_method(b"POST", b"DELETE"), Some(Method::Delete));
+        assert_eq!(sut.override_method(b"POST", b" patch "), Some(Method::Patch));
+        assert_eq!(sut.override_method(b"GET", b"DELETE"), None);
+        assert_eq!(sut.override_method(b"PUT", b"DELETE"), None);
+    }
+
+    #[test]
+    fn only_configured_methods() {
+        let sut = method_override();
+        assert_eq!(sut.override_method(b"POST", b"GET"), None);
+        assert_eq!(sut.override_method(b"POST", b"CONNECT"), None);
+        assert_eq!(sut.override_method(b"POST", b"PURGE"), None);
+    }
+
+    #[test]
+    fn request_without_header() {
+        // The mock request has no X-HTTP-Method-Override header
+        assert_eq!(method_override().apply(&Request::new()), None);
+        assert_eq!(method_override().handle_request(&Request::new(), &Response::new()), (true, 0));
+    }
+}
@@ -0,0 +1,168 @@
+//! Reusable middleware components for common plugin tasks.
+//!
+//! Every component implements [`Guest`](crate::Guest), so it can either be passed
+//! to [`register`](crate::register) directly or be called from the handlers of a
+//! plugin that combines several components.
+mod ab_test;
+mod access_log;
+mod audit;
+mod body_masking;
+mod bot_detection;
+mod canary;
+mod circuit_breaker;
+#[cfg(feature = "compression")]
+mod compress;
+mod cors;
+mod debug_echo;
+mod debug_headers;
+mod error_mapping;
+mod health;
+mod honeypot;
+#[cfg(feature = "json")]
+mod json_format;
+mod maintenance;
+mod method_override;
+mod rate_limit;
+mod request_id;
+mod sticky_session;
+mod template;
+mod transcode;
+#[cfg(feature = "waf")]
+mod waf;
+mod window;
+
+pub mod guard;
+pub mod preflight;
+
+pub use ab_test::{AbTest, AbTestConfig, AbTestKey, Variant};
+pub use access_log::{AccessLog, AccessLogConfig, AccessLogFormat};
+pub use audit::{Audit, AuditConfig};
+pub use body_masking::{BodyMasking, BodyMaskingConfig};
+pub use bot_detection::{BotAction, BotDetection, BotDetectionConfig};
+pub use canary::{Canary, CanaryConfig, CanaryKey};
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+#[cfg(feature = "compression")]
+pub use compress::{Compress, CompressConfig};
+pub use cors::{Cors, CorsConfig};
+pub use debug_echo::{DebugEcho, DebugEchoConfig};
+pub use debug_headers::{DebugHeaders, DebugHeadersConfig, rule_matched};
+pub use error_mapping::{ErrorMapping, ErrorMappingConfig, ErrorRule};
+pub use health::{Health, HealthConfig};
+pub use honeypot::{Honeypot, HoneypotConfig};
+#[cfg(feature = "json")]
+pub use json_format::{JsonFormat, JsonFormatConfig};
+pub use maintenance::{Maintenance, MaintenanceConfig};
+pub use method_override::{MethodOverride, MethodOverrideConfig};
+pub use rate_limit::{RateLimitConfig, RateLimitKey, RateLimitStatus, RateLimiter};
+pub use request_id::{RequestId, RequestIdConfig, RequestIdFormat};
+pub use sticky_session::{StickySession, StickySessionConfig};
+pub use template::Template;
+pub use transcode::{Transcode, TranscodeConfig};
+#[cfg(feature = "waf")]
+pub use waf::{Waf, WafAction, WafConfig, WafError, WafMatch, WafOperator, WafPreset, WafRule, WafTarget};
+pub use window::SlidingWindow;
+
+use crate::{headers::Mime, host::Request};
+
+/// Header name/value pairs computed by a component before they are applied to the host.
+type HeaderList = Vec<(&'static [u8], Vec<u8>)>;
+
+/// Strip the port from a source address as returned by `Request::source_addr`.
+///
+/// Handles `1.2.3.4:5678`, `[::1]:5678` and addresses without a port.
+pub(crate) fn client_ip(addr: &[u8]) -> &[u8] {
+    if let Some(rest) = addr.strip_prefix(b"[") {
+        let end = rest.iter().position(|b| *b == b']').unwrap_or(rest.len());
+        return &rest[..end];
+    }
+    match addr.iter().filter(|b| **b == b':').count() {
+        1 => addr.split(|b| *b == b':').next().unwrap_or(addr),
+        _ => addr,
+    }
+}
+
+/// Return the value of the cookie `name` from the `Cookie` headers of the request.
+pub(crate) fn cookie(request: &Request, name: &str) -> Option<Vec<u8>> {
+    request.header.values_iter(b"Cookie").find_map(|header| cookie_value(&header, name.as_bytes()).map(<[u8]>::to_vec))
+}
+
+/// Find the value of the cookie `name` in a `Cookie` header value (`a=1; b=2`).
+fn cookie_value<'a>(header: &'a [u8], name: &[u8]) -> Option<&'a [u8]> {
+    header.split(|b| *b == b';').find_map(|pair| {
+        let pair = pair.trim_ascii();
+        let eq = pair.iter().position(|b| *b == b'=')?;
+        let (n, v) = pair.split_at_checked(eq)?;
+        (n.trim_ascii() == name).then(|| v.get(1..).unwrap_or_default().trim_ascii())
+    })
+}
+
+/// Whether the media type of a `Content-Type` value matches one of `patterns`.
+///
+/// Patterns are matched with [`Mime::matches`]: a pattern ending in `/` matches the
+/// whole type (`text/`), a pattern starting with `+` matches a structured syntax
+/// suffix (`+json`); others match the media type exactly. All comparisons ignore
+/// ASCII case.
+pub(crate) fn media_type_matches(content_type: &[u8], patterns: &[String]) -> bool {
+    Mime::parse(content_type).is_some_and(|mime| patterns.iter().any(|pattern| mime.matches(pattern)))
+}
+
+/// 64-bit FNV-1a hash over all parts, stable across builds and platforms.
+pub(crate) fn stable_hash(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for b in part.iter() {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        // separate parts so that ("ab", "c") and ("a", "bc") differ
+        hash ^= 0xff;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_ip_strips_port() {
+        assert_eq!(client_ip(b"1.1.1.1:12345"), b"1.1.1.1");
+        assert_eq!(client_ip(b"[fe80::90f4:16ff:fee0:24b3%enp5s0]:41236"), b"fe80::90f4:16ff:fee0:24b3%enp5s0");
+        assert_eq!(client_ip(b"192.168.1.1"), b"192.168.1.1");
+        assert_eq!(client_ip(b"::1"), b"::1");
+    }
+
+    #[test]
+    fn cookie_value_lookup() {
+        let header = b"session=abc; variant=b ;empty=; other";
+        assert_eq!(cookie_value(header, b"variant"), Some(&b"b"[..]));
+        assert_eq!(cookie_value(header, b"session"), Some(&b"abc"[..]));
+        assert_eq!(cookie_value(header, b"empty"), Some(&b""[..]));
+        assert_eq!(cookie_value(header, b"other"), None);
+        assert_eq!(cookie_value(header, b"sess"), None);
+    }
+
+    #[test]
+    fn cookie_from_request() {
+        // The mock request has no Cookie header
+        assert_eq!(cookie(&Request::new(), "session"), None);
+    }
+
+    #[test]
+    fn media_types() {
+        let patterns = ["text/".to_string(), "application/json".to_string(), "+xml".to_string()];
+        assert!(media_type_matches(b"Text/HTML; charset=utf-8", &patterns));
+        assert!(media_type_matches(b"application/json", &patterns));
+        assert!(media_type_matches(b"application/atom+xml", &patterns));
+        assert!(!media_type_matches(b"application/jsonx", &patterns));
+        assert!(!media_type_matches(b"image/png", &patterns));
+    }
+
+    #[test]
+    fn stable_hash_is_stable() {
+        assert_eq!(stable_hash(&[]), 0xcbf29ce484222325);
+        assert_eq!(stable_hash(&[b"a"]), stable_hash(&[b"a"]));
+        assert_ne!(stable_hash(&[b"ab", b"c"]), stable_hash(&[b"a", b"bc"]));
+    }
+}
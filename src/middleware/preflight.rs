@@ -0,0 +1,102 @@
+//! Minimal CORS preflight responder.
+//!
+//! For plugins that only need to answer preflight requests without the full
+//! [`Cors`](super::Cors) component. The responder is permissive: it echoes the
+//! requesting origin and the requested headers, and only restricts the methods.
+//!
+//! ```no_run
+//! use http_wasm_guest::{
+//!     Guest,
+//!     host::{Request, Response},
+//!     middleware::preflight,
+//! };
+//!
+//! struct Plugin;
+//!
+//! impl Guest for Plugin {
+//!     fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+//!         if preflight::is_preflight(request) {
+//!             return preflight::respond(request, response, &["GET", "POST"]);
+//!         }
+//!         (true, 0)
+//!     }
+//! }
+//! ```
+use crate::host::{Request, Response};
+
+use super::HeaderList;
+
+/// Returns `true` if the request is a CORS preflight request.
+///
+/// A preflight request uses the `OPTIONS` method and carries an
+/// `Access-Control-Request-Method` header.
+pub fn is_preflight(request: &Request) -> bool {
+    request.with_method(|method| method == b"OPTIONS") && request.header.get(b"Access-Control-Request-Method").is_some()
+}
+
+/// Answer a preflight request with `204 No Content` and the matching CORS headers.
+///
+/// `allowed_methods` is announced in `Access-Control-Allow-Methods`; if it is empty,
+/// the requested method is allowed. Returns `(false, 0)`, so the result can be
+/// returned from `handle_request` to stop the request from reaching the upstream.
+pub fn respond(request: &Request, response: &Response, allowed_methods: &[&str]) -> (bool, i32) {
+    let origin = request.header.get(b"Origin").unwrap_or_default();
+    let method = request.header.get(b"Access-Control-Request-Method").unwrap_or_default();
+    let requested_headers = request.header.get(b"Access-Control-Request-Headers");
+    for (name, value) in headers(&origin, &method, requested_headers.as_deref(), allowed_methods) {
+        response.header.set(name, &value);
+    }
+    response.set_status(204);
+    (false, 0)
+}
+
+fn headers(origin: &[u8], method: &[u8], requested_headers: Option<&[u8]>, allowed_methods: &[&str]) -> HeaderList {
+    let mut headers = HeaderList::new();
+    if !origin.is_empty() {
+        headers.push((b"Access-Control-Allow-Origin", origin.to_vec()));
+        headers.push((b"Vary", b"Origin".to_vec()));
+    }
+    let methods = if allowed_methods.is_empty() { method.to_vec() } else { allowed_methods.join(", ").into_bytes() };
+    headers.push((b"Access-Control-Allow-Methods", methods));
+    if let Some(requested) = requested_headers {
+        headers.push((b"Access-Control-Allow-Headers", requested.to_vec()));
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value<'a>(headers: &'a HeaderList, name: &[u8]) -> Option<&'a [u8]> {
+        headers.iter().find(|(n, _)| *n == name).map(|(_, v)| v.as_slice())
+    }
+
+    #[test]
+    fn mock_request_is_not_preflight() {
+        // The mock request is a GET
+        assert!(!is_preflight(&Request::new()));
+    }
+
+    #[test]
+    fn headers_echo_origin_and_requested_headers() {
+        let headers = headers(b"https://a.example", b"PUT", Some(b"Content-Type"), &["GET", "PUT"]);
+        assert_eq!(value(&headers, b"Access-Control-Allow-Origin"), Some(&b"https://a.example"[..]));
+        assert_eq!(value(&headers, b"Access-Control-Allow-Methods"), Some(&b"GET, PUT"[..]));
+        assert_eq!(value(&headers, b"Access-Control-Allow-Headers"), Some(&b"Content-Type"[..]));
+    }
+
+    #[test]
+    fn headers_allow_requested_method_by_default() {
+        let headers = headers(b"", b"DELETE", None, &[]);
+        assert_eq!(value(&headers, b"Access-Control-Allow-Origin"), None);
+        assert_eq!(value(&headers, b"Access-Control-Allow-Methods"), Some(&b"DELETE"[..]));
+        assert_eq!(value(&headers, b"Access-Control-Allow-Headers"), None);
+    }
+
+    #[test]
+    fn respond_short_circuits() {
+        let (next, _) = respond(&Request::new(), &Response::new(), &["GET"]);
+        assert!(!next);
+    }
+}
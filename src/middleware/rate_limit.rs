@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+
+use crate::{
+    Guest, GuestState,
+    headers::RetryAfter,
+    host::{Request, Response, time},
+};
+
+use super::client_ip;
+
+/// Source of the key a request is rate limited by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(rename_all = "camelCase"))]
+pub enum RateLimitKey {
+    /// The client IP address (without port).
+    ClientIp,
+    /// The first value of the named request header, e.g. an API key.
+    Header(String),
+}
+
+/// Configuration for the [`RateLimiter`] component.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default, rename_all = "camelCase"))]
+pub struct RateLimitConfig {
+    /// Maximum number of requests that can be made in a burst.
+    pub capacity: u32,
+    /// Tokens added to each bucket per second.
+    pub refill_per_second: f64,
+    /// How requests are grouped into buckets.
+    pub key: RateLimitKey,
+    /// Maximum number of buckets kept in memory; idle buckets are evicted first.
+    pub max_keys: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self { capacity: 60, refill_per_second: 1.0, key: RateLimitKey::ClientIp, max_keys: 10_000 }
+    }
+}
+
+/// Result of a rate limit check for a single request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Whether the request is within the limit.
+    pub allowed: bool,
+    /// Configured burst capacity.
+    pub limit: u32,
+    /// Requests left in the current bucket after this request.
+    pub remaining: u32,
+    /// Seconds until the bucket is completely refilled.
+    pub reset: u64,
+    /// Seconds until the next request would be allowed; `None` if allowed or if the
+    /// bucket never refills.
+    pub retry_after: Option<u64>,
+}
+
+impl RateLimitStatus {
+    /// Return the `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers
+    /// for this status, as defined by the IETF RateLimit header fields draft.
+    ///
+    /// `RateLimit-Reset` is given in delta-seconds until the quota is fully restored.
+    pub fn headers(&self) -> [(&'static [u8], String); 3] {
+        [
+            (b"RateLimit-Limit", self.limit.to_string()),
+            (b"RateLimit-Remaining", self.remaining.to_string()),
+            (b"RateLimit-Reset", self.reset.to_string()),
+        ]
+    }
+
+    /// Set the rate limit headers returned by [`headers`](RateLimitStatus::headers) on the response.
+    ///
+    /// The status can come from any source, so this works for custom limiters as
+    /// well as for [`RateLimiter::check`].
+    pub fn set_headers(&self, response: &Response) {
+        for (name, value) in self.headers() {
+            response.header.set(name, value.as_bytes());
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_ms: u64,
+}
+
+/// Token-bucket rate limiter keyed by client IP or a request header.
+///
+/// Each key owns a bucket holding up to `capacity` tokens which refills at
+/// `refill_per_second`. Every request takes one token; requests arriving at an empty
+/// bucket are answered with `429 Too Many Requests` and a `Retry-After` header, which is
+/// left out if `refill_per_second` is zero and the bucket never refills.
+///
+/// The state lives in the plugin instance, so limits apply per host instance and are
+/// not shared between replicas.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{RateLimitConfig, RateLimiter},
+///     register,
+/// };
+///
+/// register(RateLimiter::new(RateLimitConfig { capacity: 10, refill_per_second: 0.5, ..Default::default() }));
+/// ```
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: GuestState<HashMap<Box<[u8]>, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter with the given configuration.
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, buckets: GuestState::default() }
+    }
+
+    /// Create the rate limiter from the JSON plugin configuration read with
+    /// [`admin::config`](crate::host::admin::config).
+    ///
+    /// Requires the `json` feature. All members are optional and default to
+    /// [`RateLimitConfig::default`]; `key` is either `"clientIp"` or `{"header": "<name>"}`.
+    /// A `refillPerSecond` that is not positive is rejected:
+    ///
+    /// ```json
+    /// {"capacity": 10, "refillPerSecond": 0.5, "key": {"header": "X-Api-Key"}, "maxKeys": 1000}
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_config() -> Result<Self, serde_json::Error> {
+        parse_config(&crate::host::admin::config()).map(Self::new)
+    }
+
+    /// Take a token from the bucket of `key` and return the resulting status.
+    pub fn check(&self, key: &[u8]) -> RateLimitStatus {
+        self.check_at(key, time::unix_millis())
+    }
+
+    /// Extract the rate limit key from the request according to the configuration.
+    ///
+    /// Requests without the configured header share a single bucket.
+    pub fn key(&self, request: &Request) -> Box<[u8]> {
+        match &self.config.key {
+            RateLimitKey::ClientIp => Box::from(client_ip(&request.source_addr())),
+            RateLimitKey::Header(name) => request.header.get(name.as_bytes()).map(|v| Box::from(&*v)).unwrap_or_default(),
+        }
+    }
+
+    fn check_at(&self, key: &[u8], now_ms: u64) -> RateLimitStatus {
+        let capacity = self.config.capacity as f64;
+        let rate = self.config.refill_per_second;
+        let (allowed, tokens) = self.buckets.with_mut(|buckets| {
+            if !buckets.contains_key(key) && buckets.len() >= self.config.max_keys {
+                self.evict(buckets, now_ms);
+            }
+            let bucket = buckets.entry(Box::from(key)).or_insert(Bucket { tokens: capacity, updated_ms: now_ms });
+
+            let elapsed = now_ms.saturating_sub(bucket.updated_ms) as f64 / 1000.0;
+            bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+            bucket.updated_ms = now_ms;
+
+            let allowed = bucket.tokens >= 1.0;
+            if allowed {
+                bucket.tokens -= 1.0;
+            }
+            (allowed, bucket.tokens)
+        });
+        let seconds_until = |tokens: f64| if rate > 0.0 { (tokens.max(0.0) / rate).ceil() as u64 } else { u64::MAX };
+        RateLimitStatus {
+            allowed,
+            limit: self.config.capacity,
+            remaining: tokens.floor() as u32,
+            reset: seconds_until(capacity - tokens),
+            retry_after: (!allowed && rate > 0.0).then(|| seconds_until(1.0 - tokens)),
+        }
+    }
+
+    /// Make room for a new bucket: drop all buckets that are full again, or the
+    /// least recently used one if none is.
+    fn evict(&self, buckets: &mut HashMap<Box<[u8]>, Bucket>, now_ms: u64) {
+        let capacity = self.config.capacity as f64;
+        let rate = self.config.refill_per_second;
+        buckets.retain(|_, b| b.tokens + now_ms.saturating_sub(b.updated_ms) as f64 / 1000.0 * rate < capacity);
+        if buckets.len() >= self.config.max_keys {
+            let oldest = buckets.iter().min_by_key(|(_, b)| b.updated_ms).map(|(k, _)| k.clone());
+            if let Some(key) = oldest {
+                buckets.remove(&key);
+            }
+        }
+    }
+}
+
+impl Guest for RateLimiter {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        let status = self.check(&self.key(request));
+        if status.allowed {
+            return (true, 0);
+        }
+        status.set_headers(response);
+        match status.retry_after {
+            Some(secs) => response.retry_later(429, RetryAfter::Seconds(secs)),
+            None => response.set_status(429),
+        }
+        (false, 0)
+    }
+}
+
+#[cfg(feature = "json")]
+fn parse_config(json: &[u8]) -> Result<RateLimitConfig, serde_json::Error> {
+    use serde::de::Error;
+
+    let config: RateLimitConfig = serde_json::from_slice(json)?;
+    if !(config.refill_per_second > 0.0 && config.refill_per_second.is_finite()) {
+        return Err(serde_json::Error::custom(format!("invalid refillPerSecond {}", config.refill_per_second)));
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(capacity: u32, refill_per_second: f64) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig { capacity, refill_per_second, ..Default::default() })
+    }
+
+    #[test]
+    fn burst_up_to_capacity() {
+        let limiter = limiter(3, 1.0);
+        assert_eq!(limiter.check_at(b"a", 0).remaining, 2);
+        assert_eq!(limiter.check_at(b"a", 0).remaining, 1);
+        assert_eq!(limiter.check_at(b"a", 0).remaining, 0);
+        let status = limiter.check_at(b"a", 0);
+        assert!(!status.allowed);
+        assert_eq!(status.retry_after, Some(1));
+        assert_eq!(status.reset, 3);
+    }
+
+    #[test]
+    fn keys_are_independent() {
+        let limiter = limiter(1, 1.0);
+        assert!(limiter.check_at(b"a", 0).allowed);
+        assert!(!limiter.check_at(b"a", 0).allowed);
+        assert!(limiter.check_at(b"b", 0).allowed);
+    }
+
+    #[test]
+    fn refill_over_time() {
+        let limiter = limiter(2, 0.5);
+        assert!(limiter.check_at(b"a", 0).allowed);
+        assert!(limiter.check_at(b"a", 0).allowed);
+        let status = limiter.check_at(b"a", 1000);
+        assert!(!status.allowed);
+        assert_eq!(status.retry_after, Some(1));
+        assert!(limiter.check_at(b"a", 2000).allowed);
+    }
+
+    #[test]
+    fn refill_capped_at_capacity() {
+        let limiter = limiter(2, 10.0);
+        assert!(limiter.check_at(b"a", 0).allowed);
+        assert_eq!(limiter.check_at(b"a", 60_000).remaining, 1);
+    }
+
+    #[test]
+    fn zero_rate_never_refills() {
+        let limiter = limiter(1, 0.0);
+        assert!(limiter.check_at(b"a", 0).allowed);
+        let status = limiter.check_at(b"a", 1_000_000);
+        assert!(!status.allowed);
+        // No Retry-After is sent when waiting does not help
+        assert_eq!(status.retry_after, None);
+    }
+
+    #[test]
+    fn eviction_bounds_memory() {
+        let limiter =
+            RateLimiter::new(RateLimitConfig { capacity: 5, refill_per_second: 0.0, max_keys: 2, ..Default::default() });
+        limiter.check_at(b"a", 0);
+        limiter.check_at(b"b", 1);
+        limiter.check_at(b"c", 2);
+        limiter.buckets.with(|buckets| {
+            assert_eq!(buckets.len(), 2);
+            assert!(!buckets.contains_key(&b"a"[..]));
+        });
+    }
+
+    #[test]
+    fn eviction_prefers_full_buckets() {
+        let limiter =
+            RateLimiter::new(RateLimitConfig { capacity: 1, refill_per_second: 1.0, max_keys: 2, ..Default::default() });
+        limiter.check_at(b"a", 0);
+        limiter.check_at(b"b", 1500);
+        limiter.check_at(b"c", 1600);
+        limiter.buckets.with(|buckets| {
+            assert!(buckets.contains_key(&b"b"[..]));
+            assert!(!buckets.contains_key(&b"a"[..]));
+        });
+    }
+
+    #[test]
+    fn key_from_client_ip() {
+        // The mock source address is "192.168.1.1"
+        let limiter = limiter(1, 1.0);
+        assert_eq!(&*limiter.key(&Request::new()), b"192.168.1.1");
+    }
+
+    #[test]
+    fn key_from_header() {
+        let limiter = RateLimiter::new(RateLimitConfig { key: RateLimitKey::Header("X-FOO".into()), ..Default::default() });
+        assert_eq!(&*limiter.key(&Request::new()), b"test1");
+        let limiter = RateLimiter::new(RateLimitConfig { key: RateLimitKey::Header("X-Missing".into()), ..Default::default() });
+        assert!(limiter.key(&Request::new()).is_empty());
+    }
+
+    #[test]
+    fn status_headers() {
+        let status = RateLimitStatus { allowed: true, limit: 10, remaining: 7, reset: 3, retry_after: None };
+        let headers = status.headers();
+        assert_eq!(headers[0], (&b"RateLimit-Limit"[..], "10".to_string()));
+        assert_eq!(headers[1], (&b"RateLimit-Remaining"[..], "7".to_string()));
+        assert_eq!(headers[2], (&b"RateLimit-Reset"[..], "3".to_string()));
+        status.set_headers(&Response::new());
+    }
+
+    #[test]
+    fn handle_request_rejects_when_exhausted() {
+        let limiter = limiter(1, 0.0);
+        assert!(limiter.handle_request(&Request::new(), &Response::new()).0);
+        assert!(!limiter.handle_request(&Request::new(), &Response::new()).0);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn config_from_json() {
+        let config =
+            parse_config(br#"{"capacity": 10, "refillPerSecond": 0.5, "key": {"header": "X-Api-Key"}, "maxKeys": 1000}"#)
+                .unwrap();
+        assert_eq!(
+            config,
+            RateLimitConfig {
+                capacity: 10,
+                refill_per_second: 0.5,
+                key: RateLimitKey::Header("X-Api-Key".into()),
+                max_keys: 1000
+            }
+        );
+        assert_eq!(parse_config(br#"{"key": "clientIp"}"#).unwrap(), RateLimitConfig::default());
+        assert!(parse_config(br#"{"refillPerSecond": 0}"#).is_err());
+        assert!(parse_config(br#"{"refillPerSecond": -1.0}"#).is_err());
+        assert!(parse_config(br#"{"key": "cookie"}"#).is_err());
+        // The mock configuration {"config": "test1"} has no rate limit members
+        assert!(RateLimiter::from_config().is_ok_and(|limiter| limiter.config == RateLimitConfig::default()));
+    }
+}
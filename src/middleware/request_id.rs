@@ -0,0 +1,171 @@
+use crate::{
+    Guest,
+    codec::hex_encode,
+    host::{Request, Response, random},
+};
+
+/// Format of generated request IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestIdFormat {
+    /// Version 4 UUID, e.g. `3f2b8c1e-9d4a-4f6b-8e2c-1a7d5b9e0c34`.
+    UuidV4,
+    /// The given number of random bytes as lowercase hex.
+    Hex(usize),
+}
+
+/// Configuration for the [`RequestId`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestIdConfig {
+    /// Name of the request and response header that carries the ID.
+    pub header: String,
+    /// Format of generated IDs.
+    pub format: RequestIdFormat,
+    /// Keep an ID sent by the client or an upstream proxy instead of generating one.
+    pub trust_incoming: bool,
+    /// Incoming IDs longer than this are replaced.
+    pub max_length: usize,
+    /// Copy the ID to the response header as well.
+    pub echo: bool,
+}
+
+impl Default for RequestIdConfig {
+    fn default() -> Self {
+        Self { header: "X-Request-Id".into(), format: RequestIdFormat::UuidV4, trust_incoming: true, max_length: 128, echo: true }
+    }
+}
+
+/// Request ID propagation.
+///
+/// Makes sure every request carries an ID in the configured header (`X-Request-Id` by
+/// default), so the host, the upstream service and the plugin logs can be correlated.
+/// An incoming ID is kept if it is trusted and well-formed, i.e. at most `max_length`
+/// visible ASCII characters; otherwise a new one is generated from the host's random
+/// source. The ID is echoed on the response so clients can quote it in support requests.
+///
+/// Other components read the ID with [`get`](RequestId::get), e.g. for log lines, and
+/// the `{{request_id}}` placeholder of [`Template`](super::Template) picks it up too.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{RequestId, RequestIdConfig, RequestIdFormat},
+///     register,
+/// };
+///
+/// register(RequestId::new(RequestIdConfig { format: RequestIdFormat::Hex(16), ..Default::default() }));
+/// ```
+pub struct RequestId {
+    config: RequestIdConfig,
+}
+
+impl RequestId {
+    /// Create a new component with the given configuration.
+    pub fn new(config: RequestIdConfig) -> Self {
+        Self { config }
+    }
+
+    /// The request ID as set by [`handle_request`](Guest::handle_request), if any.
+    pub fn get(&self, request: &Request) -> Option<String> {
+        request.header.get(self.config.header.as_bytes()).map(|id| id.to_string())
+    }
+
+    /// Generate a new ID in the configured format.
+    pub fn generate(&self) -> String {
+        match self.config.format {
+            RequestIdFormat::UuidV4 => random::format_uuid_v4(random::weak_bytes()),
+            RequestIdFormat::Hex(len) => {
+                let mut id = vec![0; len];
+                random::fill(&mut id);
+                hex_encode(&id)
+            }
+        }
+    }
+
+    /// The ID to use for a request that arrived with `incoming` in the header.
+    fn resolve(&self, incoming: Option<&[u8]>) -> String {
+        match incoming {
+            Some(id) if self.config.trust_incoming && self.is_valid(id) => String::from_utf8_lossy(id).into_owned(),
+            _ => self.generate(),
+        }
+    }
+
+    /// Only short, visible ASCII IDs are accepted, so they are safe to log and echo.
+    fn is_valid(&self, id: &[u8]) -> bool {
+        !id.is_empty() && id.len() <= self.config.max_length && id.iter().all(u8::is_ascii_graphic)
+    }
+}
+
+impl Guest for RequestId {
+    fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+        let name = self.config.header.as_bytes();
+        let incoming = request.header.values(name);
+        let id = match incoming.as_slice() {
+            [single] => self.resolve(Some(single)),
+            _ => self.resolve(None),
+        };
+        if !matches!(incoming.as_slice(), [single] if *single == id.as_str()) {
+            request.header.set(name, id.as_bytes());
+        }
+        (true, 0)
+    }
+
+    fn handle_response(&self, _req_ctx: i32, request: &Request, response: &Response, _is_error: bool) {
+        if !self.config.echo {
+            return;
+        }
+        if let Some(id) = request.header.get(self.config.header.as_bytes()) {
+            response.header.set(self.config.header.as_bytes(), &id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_valid_incoming_id() {
+        let component = RequestId::new(RequestIdConfig::default());
+        assert_eq!(component.resolve(Some(b"abc-123")), "abc-123");
+    }
+
+    #[test]
+    fn replaces_invalid_incoming_id() {
+        let component = RequestId::new(RequestIdConfig { max_length: 8, ..Default::default() });
+        for id in [&b""[..], b"has space", b"line\r\nbreak", b"123456789", "\u{e4}".as_bytes()] {
+            let resolved = component.resolve(Some(id));
+            assert_eq!(resolved.len(), 36, "{resolved}");
+        }
+    }
+
+    #[test]
+    fn untrusted_incoming_id_is_replaced() {
+        let component = RequestId::new(RequestIdConfig { trust_incoming: false, ..Default::default() });
+        assert_ne!(component.resolve(Some(b"abc-123")), "abc-123");
+    }
+
+    #[test]
+    fn generated_formats() {
+        let uuid = RequestId::new(RequestIdConfig::default()).generate();
+        assert_eq!(uuid.split('-').map(str::len).collect::<Vec<_>>(), [8, 4, 4, 4, 12]);
+        let hex = RequestId::new(RequestIdConfig { format: RequestIdFormat::Hex(8), ..Default::default() });
+        let id = hex.generate();
+        assert_eq!(id.len(), 16);
+        assert!(id.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_ne!(id, hex.generate());
+    }
+
+    #[test]
+    fn handle_request_continues() {
+        let component = RequestId::new(RequestIdConfig::default());
+        assert_eq!(component.handle_request(&Request::new(), &Response::new()), (true, 0));
+        component.handle_response(0, &Request::new(), &Response::new(), false);
+    }
+
+    #[test]
+    fn get_reads_configured_header() {
+        // The mock request header X-FOO is "test1"
+        let component = RequestId::new(RequestIdConfig { header: "X-FOO".into(), ..Default::default() });
+        assert_eq!(component.get(&Request::new()).as_deref(), Some("test1"));
+        assert_eq!(RequestId::new(RequestIdConfig::default()).get(&Request::new()), None);
+    }
+}
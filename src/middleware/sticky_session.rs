@@ -0,0 +1,152 @@
+use crate::{
+    Guest,
+    host::{Request, Response},
+};
+
+use super::cookie;
+
+/// Configuration for the [`StickySession`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StickySessionConfig {
+    /// Response header in which the upstream identifies itself.
+    pub upstream_header: String,
+    /// Name of the affinity cookie.
+    pub cookie: String,
+    /// Request header the cookie value is copied into for routing.
+    pub routing_header: String,
+    /// Lifetime of the cookie in seconds; a session cookie if `None`.
+    pub max_age: Option<u32>,
+    /// Whether to add the `Secure` attribute to the cookie.
+    pub secure: bool,
+    /// Whether to remove the upstream header from the response sent to the client.
+    pub hide_upstream_header: bool,
+}
+
+impl Default for StickySessionConfig {
+    fn default() -> Self {
+        Self {
+            upstream_header: "X-Upstream-Id".into(),
+            cookie: "sticky".into(),
+            routing_header: "X-Sticky-Upstream".into(),
+            max_age: None,
+            secure: false,
+            hide_upstream_header: true,
+        }
+    }
+}
+
+/// Session stickiness through an affinity cookie.
+///
+/// On the response, the identifier the upstream reports in `upstream_header` is stored
+/// in the affinity cookie. On later requests, the cookie value is copied into
+/// `routing_header`, which the load balancer uses to route the client to the same
+/// upstream. Values that are not valid cookie values are ignored.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{StickySession, StickySessionConfig},
+///     register,
+/// };
+///
+/// register(StickySession::new(StickySessionConfig { secure: true, ..Default::default() }));
+/// ```
+pub struct StickySession {
+    config: StickySessionConfig,
+}
+
+impl StickySession {
+    /// Create a new component with the given configuration.
+    pub fn new(config: StickySessionConfig) -> Self {
+        Self { config }
+    }
+
+    /// Return the upstream the request is pinned to by its affinity cookie.
+    pub fn upstream(&self, request: &Request) -> Option<Vec<u8>> {
+        cookie(request, &self.config.cookie).filter(|v| is_cookie_value(v))
+    }
+
+    fn set_cookie_value(&self, upstream: &[u8]) -> Vec<u8> {
+        let mut value = format!("{}=", self.config.cookie).into_bytes();
+        value.extend_from_slice(upstream);
+        value.extend_from_slice(b"; Path=/; HttpOnly; SameSite=Lax");
+        if let Some(max_age) = self.config.max_age {
+            value.extend_from_slice(format!("; Max-Age={max_age}").as_bytes());
+        }
+        if self.config.secure {
+            value.extend_from_slice(b"; Secure");
+        }
+        value
+    }
+}
+
+/// Returns `true` if `value` consists of cookie-octets as defined in RFC 6265.
+fn is_cookie_value(value: &[u8]) -> bool {
+    !value.is_empty() && value.iter().all(|b| matches!(b, 0x21 | 0x23..=0x2b | 0x2d..=0x3a | 0x3c..=0x5b | 0x5d..=0x7e))
+}
+
+impl Guest for StickySession {
+    fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+        match self.upstream(request) {
+            Some(upstream) => request.header.set(self.config.routing_header.as_bytes(), &upstream),
+            // never trust a routing header sent by the client itself
+            None => request.header.remove(self.config.routing_header.as_bytes()),
+        }
+        (true, 0)
+    }
+
+    fn handle_response(&self, _req_ctx: i32, request: &Request, response: &Response, _is_error: bool) {
+        let Some(upstream) = response.header.get(self.config.upstream_header.as_bytes()) else {
+            return;
+        };
+        if self.config.hide_upstream_header {
+            response.header.remove(self.config.upstream_header.as_bytes());
+        }
+        if is_cookie_value(&upstream) && self.upstream(request).as_deref() != Some(&*upstream) {
+            response.header.add(b"Set-Cookie", &self.set_cookie_value(&upstream));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cookie_value_validation() {
+        assert!(is_cookie_value(b"backend-1"));
+        assert!(is_cookie_value(b"10.0.0.1:8080"));
+        assert!(!is_cookie_value(b""));
+        assert!(!is_cookie_value(b"a b"));
+        assert!(!is_cookie_value(b"a;b"));
+        assert!(!is_cookie_value(b"a\r\nb"));
+        assert!(!is_cookie_value(b"\"a\""));
+    }
+
+    #[test]
+    fn set_cookie_attributes() {
+        let sticky = StickySession::new(StickySessionConfig::default());
+        assert_eq!(sticky.set_cookie_value(b"b1"), b"sticky=b1; Path=/; HttpOnly; SameSite=Lax");
+        let sticky = StickySession::new(StickySessionConfig {
+            cookie: "route".into(),
+            max_age: Some(3600),
+            secure: true,
+            ..Default::default()
+        });
+        assert_eq!(sticky.set_cookie_value(b"b1"), b"route=b1; Path=/; HttpOnly; SameSite=Lax; Max-Age=3600; Secure");
+    }
+
+    #[test]
+    fn request_without_cookie() {
+        // The mock request has no Cookie header
+        let sticky = StickySession::new(StickySessionConfig::default());
+        assert_eq!(sticky.upstream(&Request::new()), None);
+        assert!(sticky.handle_request(&Request::new(), &Response::new()).0);
+    }
+
+    #[test]
+    fn response_from_upstream() {
+        // The mock response headers include X-FOO: test1
+        let sticky = StickySession::new(StickySessionConfig { upstream_header: "X-FOO".into(), ..Default::default() });
+        sticky.handle_response(0, &Request::new(), &Response::new(), false);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    codec::escape_json,
+    host::{Request, escape_html},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Var(String),
+}
+
+/// Minimal template for error pages and other generated bodies.
+///
+/// Placeholders are written as `{{name}}`; surrounding whitespace inside the braces is
+/// ignored and unknown placeholders render as empty string. HTML and JSON templates
+/// escape all substituted values for their format, text templates insert them verbatim.
+///
+/// [`render_error`](Template::render_error) provides the placeholders `status`,
+/// `reason`, `method`, `path`, `host` and `request_id` (from `X-Request-Id`).
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     host::{Request, Response},
+///     middleware::Template,
+/// };
+///
+/// fn not_found(template: &Template, request: &Request, response: &Response) -> (bool, i32) {
+///     response.respond_html(404, &template.render_error(request, 404));
+///     (false, 0)
+/// }
+///
+/// let template = Template::html("<h1>{{status}} {{reason}}</h1><p>{{path}} (request {{request_id}})</p>");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+    escape: Escape,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    Html,
+    Json,
+}
+
+impl Template {
+    /// Parse an HTML template; substituted values are HTML-escaped.
+    pub fn html(source: &str) -> Self {
+        Self { parts: parse(source), escape: Escape::Html }
+    }
+
+    /// Parse a JSON template; substituted values are escaped for use inside JSON strings,
+    /// e.g. `{"error": "{{reason}}"}`.
+    pub fn json(source: &str) -> Self {
+        Self { parts: parse(source), escape: Escape::Json }
+    }
+
+    /// Parse a plain text template; substituted values are inserted verbatim.
+    pub fn text(source: &str) -> Self {
+        Self { parts: parse(source), escape: Escape::None }
+    }
+
+    /// Parse a template for a body of the given `Content-Type`, escaping values for HTML
+    /// or JSON bodies.
+    pub fn for_content_type(source: &str, content_type: &str) -> Self {
+        let content_type = content_type.to_ascii_lowercase();
+        if content_type.contains("html") {
+            Self::html(source)
+        } else if content_type.contains("json") {
+            Self::json(source)
+        } else {
+            Self::text(source)
+        }
+    }
+
+    /// Render the template, resolving placeholders with `lookup`.
+    pub fn render(&self, lookup: impl Fn(&str) -> Option<String>) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Var(name) => {
+                    let value = lookup(name).unwrap_or_default();
+                    match self.escape {
+                        Escape::None => out.push_str(&value),
+                        Escape::Html => out.push_str(&escape_html(&value)),
+                        Escape::Json => escape_json(&value, &mut out),
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Render the template for an error response to `request` with the given status.
+    pub fn render_error(&self, request: &Request, status: i32) -> String {
+        let header = |name: &[u8]| request.header.get(name).map(|v| String::from_utf8_lossy(&v).into_owned());
+        self.render(|name| match name {
+            "status" => Some(status.to_string()),
+            "reason" => Some(reason_phrase(status).to_string()),
+            "method" => Some(String::from_utf8_lossy(&request.method()).into_owned()),
+            "path" => Some(path(&request.uri())),
+            "host" => header(b"Host"),
+            "request_id" => header(b"X-Request-Id"),
+            _ => None,
+        })
+    }
+}
+
+fn parse(source: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut rest = source;
+    while let Some((before, after)) = rest.split_once("{{") {
+        let Some((name, after)) = after.split_once("}}") else {
+            break;
+        };
+        if !before.is_empty() {
+            parts.push(Part::Text(before.to_string()));
+        }
+        parts.push(Part::Var(name.trim().to_string()));
+        rest = after;
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest.to_string()));
+    }
+    parts
+}
+
+/// Path of a request URI, without query string and fragment.
+pub(super) fn path(uri: &[u8]) -> String {
+    let end = uri.iter().position(|b| *b == b'?' || *b == b'#').unwrap_or(uri.len());
+    String::from_utf8_lossy(uri.get(..end).unwrap_or_default()).into_owned()
+}
+
+/// Reason phrase for common status codes; empty for others.
+fn reason_phrase(status: i32) -> &'static str {
+    match status {
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        410 => "Gone",
+        413 => "Content Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Content",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_placeholders() {
+        let template = Template::text("a {{ x }} b {{y}}");
+        assert_eq!(
+            template.parts,
+            vec![Part::Text("a ".into()), Part::Var("x".into()), Part::Text(" b ".into()), Part::Var("y".into())]
+        );
+    }
+
+    #[test]
+    fn unterminated_placeholder_is_text() {
+        let template = Template::text("a {{x");
+        assert_eq!(template.render(|_| Some("!".into())), "a {{x");
+    }
+
+    #[test]
+    fn unknown_placeholders_are_empty() {
+        let template = Template::text("[{{missing}}]");
+        assert_eq!(template.render(|_| None), "[]");
+    }
+
+    #[test]
+    fn html_escapes_values() {
+        let lookup = |_: &str| Some("<script>".to_string());
+        assert_eq!(Template::html("<p>{{x}}</p>").render(lookup), "<p>&lt;script&gt;</p>");
+        assert_eq!(Template::text("{{x}}").render(lookup), "<script>");
+    }
+
+    #[test]
+    fn json_escapes_values() {
+        let lookup = |_: &str| Some("a\"b\\c\n\u{1}".to_string());
+        assert_eq!(Template::json(r#"{"e":"{{x}}"}"#).render(lookup), r#"{"e":"a\"b\\c\n\u0001"}"#);
+    }
+
+    #[test]
+    fn escape_by_content_type() {
+        assert_eq!(Template::for_content_type("", "text/html; charset=utf-8").escape, Escape::Html);
+        assert_eq!(Template::for_content_type("", "application/problem+json").escape, Escape::Json);
+        assert_eq!(Template::for_content_type("", "text/plain").escape, Escape::None);
+    }
+
+    #[test]
+    fn render_error_from_request() {
+        // The mock request is "GET https://test" without X-Request-Id
+        let template = Template::text("{{status}} {{reason}} {{method}} {{path}} [{{request_id}}]");
+        assert_eq!(template.render_error(&Request::new(), 404), "404 Not Found GET https://test []");
+    }
+
+    #[test]
+    fn path_strips_query() {
+        assert_eq!(path(b"/a/b?c=1"), "/a/b");
+        assert_eq!(path(b"/a#frag"), "/a");
+        assert_eq!(path(b"/"), "/");
+    }
+}
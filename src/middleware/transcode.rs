@@ -0,0 +1,187 @@
+use crate::{
+    Guest,
+    headers::{Mime, split_params},
+    host::{Request, Response, admin, feature},
+};
+
+use super::media_type_matches;
+
+/// Characters of windows-1252 for the bytes `0x80` to `0x9F`; the other bytes map to
+/// the code point of the same value.
+const WINDOWS_1252_C1: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}', '\u{02C6}', '\u{2030}',
+    '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}', '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}',
+    '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}', '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}',
+    '\u{017E}', '\u{0178}',
+];
+
+/// Charset labels decoded as windows-1252, as in the WHATWG Encoding Standard.
+const WINDOWS_1252_LABELS: [&str; 17] = [
+    "ansi_x3.4-1968",
+    "ascii",
+    "cp1252",
+    "cp819",
+    "csisolatin1",
+    "ibm819",
+    "iso-8859-1",
+    "iso-ir-100",
+    "iso8859-1",
+    "iso88591",
+    "iso_8859-1",
+    "iso_8859-1:1987",
+    "l1",
+    "latin1",
+    "us-ascii",
+    "windows-1252",
+    "x-cp1252",
+];
+
+/// Configuration for the [`Transcode`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscodeConfig {
+    /// Converted media types. An entry ending in `/` matches the whole type (`text/`),
+    /// an entry starting with `+` matches a structured syntax suffix (`+xml`).
+    pub content_types: Vec<String>,
+    /// Larger bodies are passed through unchanged.
+    pub max_size: usize,
+}
+
+impl Default for TranscodeConfig {
+    fn default() -> Self {
+        Self {
+            content_types: ["text/", "application/javascript", "application/json", "application/xml", "+json", "+xml"]
+                .map(String::from)
+                .to_vec(),
+            max_size: 1024 * 1024,
+        }
+    }
+}
+
+/// Converts response bodies in legacy charsets to UTF-8.
+///
+/// Bodies whose `Content-Type` declares ISO-8859-1, windows-1252 or US-ASCII are
+/// decoded as windows-1252, like browsers do for all of these labels, and the charset
+/// parameter is replaced with `utf-8`. This lets legacy applications be served next to
+/// UTF-8 services and processed by UTF-8 based transforms such as
+/// [`BodyMasking`](super::BodyMasking). Only bodies with a matching `Content-Type`,
+/// without `Content-Encoding` and of at most `max_size` bytes are converted; the body
+/// metadata headers are removed as in [`Response::prepare_for_body_rewrite`].
+///
+/// A charset declared inside the document, such as an HTML `<meta charset>`, is left
+/// unchanged; the `Content-Type` header takes precedence over it.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{Transcode, TranscodeConfig},
+///     register,
+/// };
+///
+/// register(Transcode::new(TranscodeConfig::default()));
+/// ```
+pub struct Transcode {
+    config: TranscodeConfig,
+}
+
+impl Transcode {
+    /// Create a new component with the given configuration.
+    pub fn new(config: TranscodeConfig) -> Self {
+        Self { config }
+    }
+
+    /// Convert the buffered response body to UTF-8; returns `true` if it was changed.
+    ///
+    /// Requires `feature::BufferResponse`.
+    pub fn transcode(&self, response: &Response) -> bool {
+        if response.header.get(b"Content-Encoding").is_some() {
+            return false;
+        }
+        let content_type = response
+            .header
+            .get(b"Content-Type")
+            .filter(|ct| media_type_matches(ct, &self.config.content_types))
+            .and_then(|ct| utf8_content_type(&ct));
+        let Some(content_type) = content_type else {
+            return false;
+        };
+        let body = response.body.read();
+        if body.len() > self.config.max_size {
+            return false;
+        }
+        response.prepare_for_body_rewrite();
+        response.body.write(decode_windows_1252(&body).as_bytes());
+        response.header.set(b"Content-Type", content_type.as_bytes());
+        true
+    }
+}
+
+impl Guest for Transcode {
+    fn handle_request(&self, _request: &Request, _response: &Response) -> (bool, i32) {
+        admin::enable(feature::BufferResponse);
+        (true, 0)
+    }
+
+    fn handle_response(&self, _req_ctx: i32, _request: &Request, response: &Response, is_error: bool) {
+        if !is_error {
+            self.transcode(response);
+        }
+    }
+}
+
+/// `content_type` with its charset parameter set to `utf-8`, or `None` if it does not
+/// declare a charset decoded as windows-1252.
+fn utf8_content_type(content_type: &[u8]) -> Option<String> {
+    let charset = Mime::parse(content_type)?.charset()?.to_ascii_lowercase();
+    if !WINDOWS_1252_LABELS.contains(&charset.as_str()) {
+        return None;
+    }
+    // Rewrite only the charset to keep the rest of the value as sent by the upstream.
+    let content_type = std::str::from_utf8(content_type).ok()?;
+    let params: Vec<String> = split_params(content_type)
+        .into_iter()
+        .map(|param| match param.split_once('=') {
+            Some((name, _)) if name.trim().eq_ignore_ascii_case("charset") => format!(" {}=utf-8", name.trim()),
+            _ => param.to_string(),
+        })
+        .collect();
+    Some(params.join(";"))
+}
+
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => WINDOWS_1252_C1.get(usize::from(b - 0x80)).copied().unwrap_or(char::REPLACEMENT_CHARACTER),
+            _ => char::from(b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_legacy_bytes() {
+        assert_eq!(decode_windows_1252(b"Gr\xFC\xDFe \x80 \x93q\x94 \x81"), "Grüße € “q” \u{81}");
+        assert_eq!(decode_windows_1252(b"plain"), "plain");
+    }
+
+    #[test]
+    fn replace_charset() {
+        assert_eq!(utf8_content_type(b"text/html; charset=ISO-8859-1").as_deref(), Some("text/html; charset=utf-8"));
+        assert_eq!(
+            utf8_content_type(b"text/plain;Charset=\"windows-1252\"; format=flowed").as_deref(),
+            Some("text/plain; Charset=utf-8; format=flowed")
+        );
+        assert_eq!(utf8_content_type(b"text/html; charset=utf-8"), None);
+        assert_eq!(utf8_content_type(b"text/html; charset=shift_jis"), None);
+        assert_eq!(utf8_content_type(b"text/html"), None);
+    }
+
+    #[test]
+    fn mock_response_is_unchanged() {
+        // The mock response has no Content-Type header
+        let transcode = Transcode::new(TranscodeConfig::default());
+        assert!(!transcode.transcode(&Response::new()));
+    }
+}
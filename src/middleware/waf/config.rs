@@ -0,0 +1,172 @@
+use serde_json::{Map, Value};
+
+use super::{WafAction, WafConfig, WafError, WafOperator, WafPreset, WafRule, WafTarget};
+
+/// Parse the JSON configuration described at [`Waf::from_config`](super::Waf::from_config).
+pub(super) fn parse(json: &[u8]) -> Result<WafConfig, WafError> {
+    let value: Value = serde_json::from_slice(json).map_err(|e| error("", e.to_string()))?;
+    let Value::Object(object) = value else {
+        return Err(error("", "expected an object"));
+    };
+    let mut config = WafConfig::default();
+    if let Some(rules) = object.get("rules") {
+        let rules = rules.as_array().ok_or_else(|| error("", "rules must be an array"))?;
+        config.rules = rules.iter().enumerate().map(|(i, rule)| parse_rule(i, rule)).collect::<Result<_, _>>()?;
+    }
+    if let Some(presets) = object.get("presets") {
+        let presets = presets.as_array().ok_or_else(|| error("", "presets must be an array"))?;
+        config.presets = presets
+            .iter()
+            .map(|preset| {
+                let name = preset.as_str().unwrap_or_default();
+                name.parse::<WafPreset>().map_err(|()| error("", format!("unknown preset {preset}")))
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    if let Some(action) = object.get("presetAction") {
+        config.preset_action = parse_action("", action)?;
+    }
+    if let Some(status) = object.get("blockStatus") {
+        config.block_status = status
+            .as_i64()
+            .and_then(|status| i32::try_from(status).ok())
+            .filter(|status| (100..=599).contains(status))
+            .ok_or_else(|| error("", format!("invalid blockStatus {status}")))?;
+    }
+    if let Some(header) = object.get("tagHeader") {
+        config.tag_header = header.as_str().ok_or_else(|| error("", "tagHeader must be a string"))?.to_string();
+    }
+    if let Some(max) = object.get("maxBody") {
+        config.max_body = as_usize(max).ok_or_else(|| error("", format!("invalid maxBody {max}")))?;
+    }
+    Ok(config)
+}
+
+fn parse_rule(index: usize, rule: &Value) -> Result<WafRule, WafError> {
+    let Value::Object(rule) = rule else {
+        return Err(error(&format!("#{index}"), "expected an object"));
+    };
+    let id = match rule.get("id").and_then(Value::as_str) {
+        Some(id) if !id.is_empty() => id.to_string(),
+        _ => return Err(error(&format!("#{index}"), "missing id")),
+    };
+    let target = match rule.get("target").and_then(Value::as_str) {
+        Some("uri") => WafTarget::Uri,
+        Some("headers") => WafTarget::Headers,
+        Some("body") => WafTarget::Body,
+        Some(target) => match target.strip_prefix("header:") {
+            Some(name) if !name.is_empty() => WafTarget::Header(name.to_string()),
+            _ => return Err(error(&id, format!("unknown target {target}"))),
+        },
+        None => return Err(error(&id, "missing target")),
+    };
+    let operator = parse_operator(&id, rule)?;
+    let action = match rule.get("action") {
+        Some(action) => parse_action(&id, action)?,
+        None => WafAction::Block,
+    };
+    Ok(WafRule { id, target, operator, action })
+}
+
+fn parse_operator(id: &str, rule: &Map<String, Value>) -> Result<WafOperator, WafError> {
+    let operators = [
+        rule.get("regex").map(|v| v.as_str().map(|s| WafOperator::Regex(s.to_string()))),
+        rule.get("contains").map(|v| v.as_str().map(|s| WafOperator::Contains(s.to_string()))),
+        rule.get("longerThan").map(|v| as_usize(v).map(WafOperator::LongerThan)),
+    ];
+    let mut operators = operators.into_iter().flatten();
+    match (operators.next(), operators.next()) {
+        (Some(Some(operator)), None) => Ok(operator),
+        (Some(None), None) => Err(error(id, "invalid operator value")),
+        (None, _) => Err(error(id, "missing operator regex, contains or longerThan")),
+        (Some(_), Some(_)) => Err(error(id, "more than one operator")),
+    }
+}
+
+fn parse_action(id: &str, action: &Value) -> Result<WafAction, WafError> {
+    match action.as_str() {
+        Some("block") => Ok(WafAction::Block),
+        Some("log") => Ok(WafAction::Log),
+        Some("tag") => Ok(WafAction::Tag),
+        _ => Err(error(id, format!("unknown action {action}"))),
+    }
+}
+
+fn as_usize(value: &Value) -> Option<usize> {
+    value.as_u64().and_then(|n| usize::try_from(n).ok())
+}
+
+fn error(rule: &str, message: impl Into<String>) -> WafError {
+    WafError { rule: rule.to_string(), message: message.into() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::waf::Waf;
+
+    #[test]
+    fn full_config() {
+        let config = parse(
+            br#"{"rules": [
+                    {"id": "dot", "target": "uri", "regex": "/\\.(git|env)", "action": "block"},
+                    {"id": "ua", "target": "header:User-Agent", "contains": "sqlmap", "action": "tag"},
+                    {"id": "big", "target": "body", "longerThan": 10}
+                ],
+                "presets": ["sqli", "path-traversal"], "presetAction": "log",
+                "blockStatus": 406, "tagHeader": "X-Waf", "maxBody": 1024}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.rules,
+            vec![
+                WafRule {
+                    id: "dot".into(),
+                    target: WafTarget::Uri,
+                    operator: WafOperator::Regex(r"/\.(git|env)".into()),
+                    action: WafAction::Block
+                },
+                WafRule {
+                    id: "ua".into(),
+                    target: WafTarget::Header("User-Agent".into()),
+                    operator: WafOperator::Contains("sqlmap".into()),
+                    action: WafAction::Tag
+                },
+                WafRule {
+                    id: "big".into(),
+                    target: WafTarget::Body,
+                    operator: WafOperator::LongerThan(10),
+                    action: WafAction::Block
+                },
+            ]
+        );
+        assert_eq!(config.presets, vec![WafPreset::Sqli, WafPreset::PathTraversal]);
+        assert_eq!(config.preset_action, WafAction::Log);
+        assert_eq!((config.block_status, config.tag_header.as_str(), config.max_body), (406, "X-Waf", 1024));
+        assert_eq!(parse(b"{}").unwrap(), WafConfig::default());
+    }
+
+    #[test]
+    fn invalid_config() {
+        let rule_of = |json: &[u8]| parse(json).err().map(|e| e.rule);
+        assert_eq!(rule_of(b"[]"), Some(String::new()));
+        assert_eq!(rule_of(br#"{"rules": [{"target": "uri", "regex": "a"}]}"#), Some("#0".into()));
+        assert_eq!(rule_of(br#"{"rules": [{"id": "a", "target": "cookie", "regex": "a"}]}"#), Some("a".into()));
+        assert_eq!(rule_of(br#"{"rules": [{"id": "a", "target": "uri"}]}"#), Some("a".into()));
+        assert_eq!(rule_of(br#"{"rules": [{"id": "a", "target": "uri", "regex": "a", "contains": "b"}]}"#), Some("a".into()));
+        assert_eq!(rule_of(br#"{"rules": [{"id": "a", "target": "uri", "longerThan": -1}]}"#), Some("a".into()));
+        assert_eq!(rule_of(br#"{"rules": [{"id": "a", "target": "uri", "regex": "a", "action": "drop"}]}"#), Some("a".into()));
+        assert_eq!(rule_of(br#"{"presets": ["nosql"]}"#), Some(String::new()));
+        assert_eq!(rule_of(br#"{"blockStatus": 42}"#), Some(String::new()));
+        let error = parse(br#"{"maxBody": "1k"}"#).unwrap_err();
+        assert_eq!(error.to_string(), r#"invalid waf config: invalid maxBody "1k""#);
+    }
+
+    #[test]
+    fn invalid_pattern_is_reported_by_rule() {
+        let config = parse(br#"{"rules": [{"id": "bad", "target": "uri", "regex": "("}]}"#).unwrap();
+        assert_eq!(Waf::new(config).err().map(|e| e.rule), Some("bad".to_string()));
+        // The mock configuration {"config": "test1"} has no WAF members
+        assert!(Waf::from_config().is_ok_and(|waf| waf.rules.is_empty()));
+    }
+}
@@ -0,0 +1,444 @@
+#[cfg(feature = "json")]
+mod config;
+mod presets;
+
+use std::{error::Error, fmt::Display, ops::ControlFlow};
+
+use regex_lite::Regex;
+
+use crate::{
+    Guest,
+    codec::percent_decode,
+    host::{
+        Body, Request, Response,
+        feature::{self, Feature},
+        log,
+    },
+};
+
+pub use presets::WafPreset;
+
+/// Part of the request a [`WafRule`] inspects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WafTarget {
+    /// The request target, percent-decoded (`+` as space).
+    Uri,
+    /// All values of the named request header.
+    Header(String),
+    /// All values of all request headers.
+    Headers,
+    /// The request body, up to `max_body` bytes. Requires `feature::BufferRequest`, see
+    /// [`Waf::features`](crate::Guest::features).
+    Body,
+}
+
+/// Condition of a [`WafRule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WafOperator {
+    /// The value matches the regular expression (`regex-lite` syntax).
+    Regex(String),
+    /// The value contains the string, compared case-insensitively for ASCII.
+    Contains(String),
+    /// The value is longer than the given number of bytes.
+    LongerThan(usize),
+}
+
+/// Action taken when a [`WafRule`] matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WafAction {
+    /// Log the match and reject the request with `block_status`.
+    Block,
+    /// Log the match only.
+    Log,
+    /// Log the match and list the rule id in the `tag_header` request header, so the
+    /// upstream can decide.
+    Tag,
+}
+
+impl WafAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WafAction::Block => "block",
+            WafAction::Log => "log",
+            WafAction::Tag => "tag",
+        }
+    }
+}
+
+/// A rule of the [`Waf`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WafRule {
+    /// Identifier used in logs and tags.
+    pub id: String,
+    /// Inspected part of the request.
+    pub target: WafTarget,
+    /// Condition on the inspected value.
+    pub operator: WafOperator,
+    /// Action on match.
+    pub action: WafAction,
+}
+
+/// Configuration for the [`Waf`] component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WafConfig {
+    /// Rules, evaluated in order.
+    pub rules: Vec<WafRule>,
+    /// Built-in rule packs, evaluated after `rules`.
+    pub presets: Vec<WafPreset>,
+    /// Action of the preset rules.
+    pub preset_action: WafAction,
+    /// Status of blocked requests.
+    pub block_status: i32,
+    /// Request header listing the ids of matched `Tag` rules.
+    pub tag_header: String,
+    /// Number of body bytes inspected by `Body` rules.
+    pub max_body: usize,
+}
+
+impl Default for WafConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            presets: Vec::new(),
+            preset_action: WafAction::Block,
+            block_status: 403,
+            tag_header: "X-Waf-Match".into(),
+            max_body: 64 * 1024,
+        }
+    }
+}
+
+/// Error returned by [`Waf::new`] for rules that cannot be compiled, and by
+/// [`Waf::from_config`] for invalid configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WafError {
+    /// Id of the invalid rule, empty for errors outside of a rule.
+    pub rule: String,
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl Display for WafError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.rule.is_empty() {
+            true => write!(f, "invalid waf config: {}", self.message),
+            false => write!(f, "invalid waf rule {}: {}", self.rule, self.message),
+        }
+    }
+}
+
+impl Error for WafError {}
+
+/// A rule that matched a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WafMatch {
+    /// Id of the rule.
+    pub rule: String,
+    /// Action of the rule.
+    pub action: WafAction,
+    /// Inspected part of the request, e.g. `uri` or `header user-agent`.
+    pub target: String,
+    /// The matched text, shortened for logging.
+    pub excerpt: String,
+}
+
+enum Matcher {
+    Regex(Regex),
+    Contains(String),
+    LongerThan(usize),
+}
+
+struct CompiledRule {
+    id: String,
+    target: WafTarget,
+    matcher: Matcher,
+    action: WafAction,
+}
+
+/// Request values inspected by the rules.
+#[derive(Default)]
+struct Inputs {
+    uri: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+/// Longest excerpt of the matched text written to the log.
+const MAX_EXCERPT: usize = 64;
+
+/// Log level of match events (warn).
+const LOG_LEVEL: i32 = 1;
+
+/// Rules-based web application firewall.
+///
+/// Requires the `waf` feature. Rules are compiled once in [`new`](Waf::new) and
+/// evaluated in order for every request; each match is logged through the host
+/// logger with rule id, target and the matched text. `Block` rules reject the request
+/// with `block_status`, `Tag` rules add their id to the `tag_header` request header.
+/// [`WafPreset`]s add ready-made rules for common attacks.
+///
+/// `Body` rules need the host to buffer the request body, which [`Guest::features`]
+/// declares; a plugin that calls the component from its own handlers must forward it.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     middleware::{Waf, WafAction, WafConfig, WafOperator, WafRule, WafTarget},
+///     register,
+/// };
+///
+/// let waf = Waf::new(WafConfig {
+///     rules: vec![WafRule {
+///         id: "no-dotfiles".into(),
+///         target: WafTarget::Uri,
+///         operator: WafOperator::Regex(r"/\.(git|env)".into()),
+///         action: WafAction::Block,
+///     }],
+///     ..Default::default()
+/// });
+/// if let Ok(waf) = waf {
+///     register(waf);
+/// }
+/// ```
+pub struct Waf {
+    config: WafConfig,
+    rules: Vec<CompiledRule>,
+}
+
+impl Waf {
+    /// Compile the rules of `config`.
+    pub fn new(config: WafConfig) -> Result<Self, WafError> {
+        let presets = config.presets.iter().flat_map(|preset| preset.rules(config.preset_action));
+        let rules = config.rules.iter().cloned().chain(presets).map(|rule| compile(&rule)).collect::<Result<_, _>>()?;
+        Ok(Self { config, rules })
+    }
+
+    /// Compile the rules of the JSON plugin configuration read with
+    /// [`admin::config`](crate::host::admin::config).
+    ///
+    /// Requires the `json` feature. All members are optional and default to
+    /// [`WafConfig::default`]; rules take an operator member `regex`, `contains` or
+    /// `longerThan`, and a target of `uri`, `headers`, `body` or `header:<name>`:
+    ///
+    /// ```json
+    /// {
+    ///   "rules": [{"id": "no-dotfiles", "target": "uri", "regex": "/\\.(git|env)", "action": "block"}],
+    ///   "presets": ["sqli", "xss"],
+    ///   "presetAction": "log",
+    ///   "blockStatus": 403,
+    ///   "tagHeader": "X-Waf-Match",
+    ///   "maxBody": 65536
+    /// }
+    /// ```
+    ///
+    /// ```no_run
+    /// use http_wasm_guest::{middleware::Waf, register};
+    ///
+    /// match Waf::from_config() {
+    ///     Ok(waf) => register(waf),
+    ///     Err(e) => http_wasm_guest::host::log::write(2, e.to_string().as_bytes()),
+    /// }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_config() -> Result<Self, WafError> {
+        Self::new(config::parse(&crate::host::admin::config())?)
+    }
+
+    /// Evaluate all rules against `request` and return the matches.
+    pub fn evaluate(&self, request: &Request) -> Vec<WafMatch> {
+        let mut inputs = Inputs {
+            uri: request.with_uri(|uri| String::from_utf8_lossy(&percent_decode(uri, true)).into_owned()),
+            ..Default::default()
+        };
+        if self.rules.iter().any(|r| matches!(r.target, WafTarget::Header(_) | WafTarget::Headers)) {
+            for (name, values) in request.header.entries_iter() {
+                let name = String::from_utf8_lossy(&name).to_ascii_lowercase();
+                inputs.headers.extend(values.iter().map(|v| (name.clone(), String::from_utf8_lossy(v).into_owned())));
+            }
+        }
+        if self.inspects_body() {
+            inputs.body = String::from_utf8_lossy(&read_prefix(&request.body, self.config.max_body)).into_owned();
+        }
+        self.check(&inputs)
+    }
+
+    fn inspects_body(&self) -> bool {
+        self.rules.iter().any(|r| r.target == WafTarget::Body)
+    }
+
+    fn check(&self, inputs: &Inputs) -> Vec<WafMatch> {
+        let mut matches = Vec::new();
+        for rule in &self.rules {
+            let found = match &rule.target {
+                WafTarget::Uri => rule.matcher.find(&inputs.uri).map(|m| ("uri".to_string(), m)),
+                WafTarget::Body => rule.matcher.find(&inputs.body).map(|m| ("body".to_string(), m)),
+                WafTarget::Header(name) => inputs
+                    .headers
+                    .iter()
+                    .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+                    .find_map(|(n, v)| rule.matcher.find(v).map(|m| (format!("header {n}"), m))),
+                WafTarget::Headers => {
+                    inputs.headers.iter().find_map(|(n, v)| rule.matcher.find(v).map(|m| (format!("header {n}"), m)))
+                }
+            };
+            if let Some((target, excerpt)) = found {
+                matches.push(WafMatch { rule: rule.id.clone(), action: rule.action, target, excerpt });
+            }
+        }
+        matches
+    }
+}
+
+impl Matcher {
+    /// Return an excerpt of the matching text if `value` matches.
+    fn find(&self, value: &str) -> Option<String> {
+        let excerpt = match self {
+            Matcher::Regex(regex) => regex.find(value)?.as_str().to_string(),
+            Matcher::Contains(needle) => value.to_ascii_lowercase().contains(needle.as_str()).then(|| needle.clone())?,
+            Matcher::LongerThan(max) => (value.len() > *max).then(|| format!("length {}", value.len()))?,
+        };
+        Some(excerpt.chars().take(MAX_EXCERPT).collect())
+    }
+}
+
+/// The first `max` bytes of `body`; reading stops once they are collected.
+fn read_prefix(body: &Body, max: usize) -> Vec<u8> {
+    let mut prefix = Vec::new();
+    body.read_chunks(|chunk| {
+        let take = chunk.len().min(max - prefix.len());
+        prefix.extend_from_slice(chunk.get(..take).unwrap_or_default());
+        match prefix.len() < max {
+            true => ControlFlow::Continue(()),
+            false => ControlFlow::Break(()),
+        }
+    });
+    prefix
+}
+
+fn compile(rule: &WafRule) -> Result<CompiledRule, WafError> {
+    let matcher = match &rule.operator {
+        WafOperator::Regex(pattern) => {
+            Matcher::Regex(Regex::new(pattern).map_err(|e| WafError { rule: rule.id.clone(), message: e.to_string() })?)
+        }
+        WafOperator::Contains(needle) => Matcher::Contains(needle.to_ascii_lowercase()),
+        WafOperator::LongerThan(max) => Matcher::LongerThan(*max),
+    };
+    Ok(CompiledRule { id: rule.id.clone(), target: rule.target.clone(), matcher, action: rule.action })
+}
+
+impl Guest for Waf {
+    fn handle_request(&self, request: &Request, response: &Response) -> (bool, i32) {
+        let matches = self.evaluate(request);
+        if matches.is_empty() {
+            return (true, 0);
+        }
+        if log::enabled(LOG_LEVEL) {
+            for m in &matches {
+                let message = format!("waf: rule {} ({}) matched {}: {:?}", m.rule, m.action.as_str(), m.target, m.excerpt);
+                log::write(LOG_LEVEL, message.as_bytes());
+            }
+        }
+        if matches.iter().any(|m| m.action == WafAction::Block) {
+            return response.deny(self.config.block_status, "Forbidden").into();
+        }
+        let tags: Vec<&str> = matches.iter().filter(|m| m.action == WafAction::Tag).map(|m| m.rule.as_str()).collect();
+        if !tags.is_empty() {
+            request.header.set(self.config.tag_header.as_bytes(), tags.join(",").as_bytes());
+        }
+        (true, 0)
+    }
+
+    fn features(&self) -> Feature {
+        match self.inspects_body() {
+            true => feature::BufferRequest,
+            false => Feature::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, target: WafTarget, operator: WafOperator, action: WafAction) -> WafRule {
+        WafRule { id: id.into(), target, operator, action }
+    }
+
+    fn waf(rules: Vec<WafRule>) -> Waf {
+        Waf::new(WafConfig { rules, ..Default::default() }).unwrap()
+    }
+
+    fn inputs(uri: &str, headers: &[(&str, &str)], body: &str) -> Inputs {
+        Inputs {
+            uri: uri.into(),
+            headers: headers.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect(),
+            body: body.into(),
+        }
+    }
+
+    #[test]
+    fn invalid_regex_is_reported() {
+        let result = Waf::new(WafConfig {
+            rules: vec![rule("bad", WafTarget::Uri, WafOperator::Regex("(".into()), WafAction::Log)],
+            ..Default::default()
+        });
+        assert_eq!(result.err().map(|e| e.rule), Some("bad".to_string()));
+    }
+
+    #[test]
+    fn operators() {
+        let waf = waf(vec![
+            rule("re", WafTarget::Uri, WafOperator::Regex(r"\.\./".into()), WafAction::Block),
+            rule("has", WafTarget::Body, WafOperator::Contains("SeLeCt".into()), WafAction::Log),
+            rule("len", WafTarget::Header("x-long".into()), WafOperator::LongerThan(3), WafAction::Tag),
+        ]);
+        let matches = waf.check(&inputs("/a/../b", &[("x-long", "abcd")], "select 1"));
+        let found: Vec<_> = matches.iter().map(|m| (m.rule.as_str(), m.target.as_str(), m.excerpt.as_str())).collect();
+        assert_eq!(found, vec![("re", "uri", "../"), ("has", "body", "select"), ("len", "header x-long", "length 4")]);
+        assert!(waf.check(&inputs("/a/b", &[("x-long", "abc")], "")).is_empty());
+    }
+
+    #[test]
+    fn header_targets() {
+        let waf = waf(vec![
+            rule("ua", WafTarget::Header("User-Agent".into()), WafOperator::Contains("sqlmap".into()), WafAction::Block),
+            rule("any", WafTarget::Headers, WafOperator::Contains("<script".into()), WafAction::Log),
+        ]);
+        let matches = waf.check(&inputs("/", &[("user-agent", "sqlmap/1.0"), ("referer", "x<SCRIPT>")], ""));
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches.get(1).map(|m| m.target.as_str()), Some("header referer"));
+    }
+
+    #[test]
+    fn excerpt_is_shortened() {
+        let waf = waf(vec![rule("re", WafTarget::Uri, WafOperator::Regex("a+".into()), WafAction::Log)]);
+        let matches = waf.check(&inputs(&"a".repeat(200), &[], ""));
+        assert_eq!(matches.first().map(|m| m.excerpt.len()), Some(MAX_EXCERPT));
+    }
+
+    #[test]
+    fn request_decision() {
+        // The mock request is "GET https://test"
+        let blocking = waf(vec![rule("t", WafTarget::Uri, WafOperator::Contains("test".into()), WafAction::Block)]);
+        assert_eq!(blocking.handle_request(&Request::new(), &Response::new()), (false, 0));
+        let tagging = waf(vec![rule("t", WafTarget::Uri, WafOperator::Contains("test".into()), WafAction::Tag)]);
+        assert_eq!(tagging.handle_request(&Request::new(), &Response::new()), (true, 0));
+        assert!(waf(Vec::new()).evaluate(&Request::new()).is_empty());
+    }
+
+    #[test]
+    fn body_is_read_up_to_max_body() {
+        use crate::host::test::kinds::OVERSIZED_BODY;
+        // The oversized mock body delivers full chunks without end
+        assert_eq!(read_prefix(&Body::new(OVERSIZED_BODY), 100).len(), 100);
+        assert_eq!(read_prefix(&Body::new(OVERSIZED_BODY), 5000).len(), 5000);
+        assert!(read_prefix(&Body::new(OVERSIZED_BODY), 0).is_empty());
+        assert_eq!(read_prefix(&Body::new(1), 1000), b"<html><body>test</body>");
+    }
+
+    #[test]
+    fn body_rules_declare_buffering() {
+        assert_eq!(waf(Vec::new()).features(), Feature::default());
+        let body = waf(vec![rule("b", WafTarget::Body, WafOperator::Contains("x".into()), WafAction::Log)]);
+        assert_eq!(body.features(), feature::BufferRequest);
+    }
+}
@@ -0,0 +1,140 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::{WafAction, WafOperator, WafRule, WafTarget};
+
+/// Built-in rule packs for common attacks.
+///
+/// Presets inspect the percent-decoded request target, where most probing happens;
+/// they are a baseline, not a replacement for a full WAF. Enable them with
+/// [`WafConfig::presets`](super::WafConfig::presets) or parse them by name
+/// (`sqli`, `xss`, `path-traversal`) from plugin configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WafPreset {
+    /// SQL injection: `UNION SELECT`, tautologies, comments after quotes, stacked
+    /// queries and time-based functions.
+    Sqli,
+    /// Cross-site scripting: script tags, event handler attributes, `javascript:` URIs
+    /// and embedding tags.
+    Xss,
+    /// Path traversal: `..` segments (also encoded), sensitive system files and NUL bytes.
+    PathTraversal,
+}
+
+const SQLI: &[(&str, &str)] = &[
+    ("sqli-union", r"(?i)\bunion\b(\s|/\*.*?\*/)+(all\s+)?select\b"),
+    ("sqli-tautology", r#"(?i)['"]\s*(or|and)\s+['"]?\w+['"]?\s*(=|<|>|like\b)"#),
+    ("sqli-comment", r#"['"]\s*(--|#|/\*)"#),
+    ("sqli-stacked", r"(?i);\s*(drop|delete|insert|update|alter|truncate|shutdown|exec)\b"),
+    ("sqli-time", r"(?i)\b(sleep|benchmark|pg_sleep)\s*\(|\bwaitfor\s+delay\b"),
+];
+
+const XSS: &[(&str, &str)] = &[
+    ("xss-script", r"(?i)<\s*/?\s*script\b"),
+    ("xss-handler", r"(?i)\bon(error|load|click|mouseover|focus|blur|submit|toggle|animationstart)\s*="),
+    ("xss-js-uri", r"(?i)\b(javascript|vbscript)\s*:"),
+    ("xss-embed", r"(?i)<\s*(iframe|object|embed|svg|math)\b"),
+];
+
+const PATH_TRAVERSAL: &[(&str, &str)] = &[
+    ("traversal-dotdot", r"(^|[/\\])\.\.([/\\]|$)"),
+    ("traversal-encoded", r"(?i)(%2e|\.)(%2e|\.)(%2f|%5c)|(%2f|%5c)(%2e|\.)(%2e|\.)"),
+    ("traversal-files", r"(?i)(/etc/(passwd|shadow|hosts)|/proc/self/|\b(win|boot|system)\.ini\b)"),
+    ("traversal-nul", r"\x00"),
+];
+
+impl WafPreset {
+    /// Return the rules of the preset with the given action.
+    pub fn rules(&self, action: WafAction) -> Vec<WafRule> {
+        let patterns = match self {
+            WafPreset::Sqli => SQLI,
+            WafPreset::Xss => XSS,
+            WafPreset::PathTraversal => PATH_TRAVERSAL,
+        };
+        patterns
+            .iter()
+            .map(|(id, pattern)| WafRule {
+                id: id.to_string(),
+                target: WafTarget::Uri,
+                operator: WafOperator::Regex(pattern.to_string()),
+                action,
+            })
+            .collect()
+    }
+
+    /// Return the name of the preset.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WafPreset::Sqli => "sqli",
+            WafPreset::Xss => "xss",
+            WafPreset::PathTraversal => "path-traversal",
+        }
+    }
+}
+
+impl Display for WafPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WafPreset {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "sqli" => Ok(WafPreset::Sqli),
+            "xss" => Ok(WafPreset::Xss),
+            "path-traversal" | "traversal" => Ok(WafPreset::PathTraversal),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::waf::{Inputs, Waf, WafConfig};
+
+    fn matched(preset: WafPreset, uri: &str) -> Vec<String> {
+        let waf = Waf::new(WafConfig { presets: vec![preset], ..Default::default() }).unwrap();
+        waf.check(&Inputs { uri: uri.into(), ..Default::default() }).into_iter().map(|m| m.rule).collect()
+    }
+
+    #[test]
+    fn names() {
+        for preset in [WafPreset::Sqli, WafPreset::Xss, WafPreset::PathTraversal] {
+            assert_eq!(preset.as_str().parse(), Ok(preset));
+        }
+        assert_eq!(" SQLi ".parse(), Ok(WafPreset::Sqli));
+        assert_eq!("rce".parse::<WafPreset>(), Err(()));
+    }
+
+    #[test]
+    fn sqli() {
+        assert_eq!(matched(WafPreset::Sqli, "/items?id=1 UNION ALL SELECT password FROM users"), ["sqli-union"]);
+        assert_eq!(matched(WafPreset::Sqli, "/login?user=admin' OR '1'='1"), ["sqli-tautology"]);
+        assert_eq!(matched(WafPreset::Sqli, "/login?user=admin'--"), ["sqli-comment"]);
+        assert_eq!(matched(WafPreset::Sqli, "/items?id=1; DROP TABLE users"), ["sqli-stacked"]);
+        assert_eq!(matched(WafPreset::Sqli, "/items?id=1 AND SLEEP(5)"), ["sqli-time"]);
+        assert!(matched(WafPreset::Sqli, "/search?q=union station&sort=select").is_empty());
+        assert!(matched(WafPreset::Sqli, "/blog/it's-or-never").is_empty());
+    }
+
+    #[test]
+    fn xss() {
+        assert_eq!(matched(WafPreset::Xss, "/?q=<script>alert(1)</script>"), ["xss-script"]);
+        assert_eq!(matched(WafPreset::Xss, "/?q=x\" onerror=alert(1)"), ["xss-handler"]);
+        assert_eq!(matched(WafPreset::Xss, "/?next=JavaScript:alert(1)"), ["xss-js-uri"]);
+        assert_eq!(matched(WafPreset::Xss, "/?q=<iframe src=x>"), ["xss-embed"]);
+        assert!(matched(WafPreset::Xss, "/scripts/online.js?description=load").is_empty());
+    }
+
+    #[test]
+    fn path_traversal() {
+        assert_eq!(matched(WafPreset::PathTraversal, "/static/../../app.conf"), ["traversal-dotdot"]);
+        assert_eq!(matched(WafPreset::PathTraversal, "/static/%2e%2e%2fapp.conf"), ["traversal-encoded"]);
+        assert_eq!(matched(WafPreset::PathTraversal, "/download?file=/etc/passwd"), ["traversal-files"]);
+        assert_eq!(matched(WafPreset::PathTraversal, "/a.txt\0.png"), ["traversal-nul"]);
+        assert!(matched(WafPreset::PathTraversal, "/docs/v1..v2/file..txt").is_empty());
+    }
+}
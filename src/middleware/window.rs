@@ -0,0 +1,195 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::{GuestState, host::time};
+
+struct Counter {
+    counts: Box<[u32]>,
+    /// Absolute slot number of the most recent bucket.
+    head: u64,
+}
+
+/// Per-key event counter over a sliding time window.
+///
+/// The window is divided into a fixed number of buckets; events are counted in the
+/// bucket of the current time slot and expire as the window moves on. More buckets
+/// give a smoother window at the cost of memory per key. Use it for rate limiting,
+/// error-rate tracking or bot heuristics.
+///
+/// The counter keeps at most `max_keys` keys (10 000 by default); keys without
+/// events in the window are evicted first.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use http_wasm_guest::middleware::SlidingWindow;
+///
+/// let failures = SlidingWindow::new(Duration::from_secs(60), 6);
+/// if failures.increment(b"10.0.0.1") > 5 {
+///     // block the client
+/// }
+/// ```
+pub struct SlidingWindow {
+    slot_ms: u64,
+    buckets: usize,
+    max_keys: usize,
+    counters: GuestState<HashMap<Box<[u8]>, Counter>>,
+}
+
+impl SlidingWindow {
+    /// Create a counter over `window`, divided into `buckets` buckets.
+    ///
+    /// `buckets` is clamped to at least 1, the bucket width to at least 1ms.
+    pub fn new(window: Duration, buckets: usize) -> Self {
+        let buckets = buckets.max(1);
+        let slot_ms = (window.as_millis() as u64 / buckets as u64).max(1);
+        Self { slot_ms, buckets, max_keys: 10_000, counters: GuestState::default() }
+    }
+
+    /// Limit the number of keys kept in memory.
+    pub fn with_max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = max_keys;
+        self
+    }
+
+    /// Record one event for `key` and return the number of events in the window.
+    pub fn increment(&self, key: &[u8]) -> u64 {
+        self.add(key, 1)
+    }
+
+    /// Record `n` events for `key` and return the number of events in the window.
+    pub fn add(&self, key: &[u8], n: u32) -> u64 {
+        self.add_at(key, n, time::unix_millis())
+    }
+
+    /// Return the number of events recorded for `key` in the window.
+    pub fn count(&self, key: &[u8]) -> u64 {
+        self.count_at(key, time::unix_millis())
+    }
+
+    /// Forget all events recorded for `key`.
+    pub fn reset(&self, key: &[u8]) {
+        self.counters.with_mut(|counters| counters.remove(key));
+    }
+
+    pub(crate) fn add_at(&self, key: &[u8], n: u32, now_ms: u64) -> u64 {
+        let slot = now_ms / self.slot_ms;
+        self.counters.with_mut(|counters| {
+            if !counters.contains_key(key) && counters.len() >= self.max_keys {
+                self.evict(counters, slot);
+            }
+            let counter = counters
+                .entry(Box::from(key))
+                .or_insert_with(|| Counter { counts: vec![0; self.buckets].into_boxed_slice(), head: slot });
+            self.advance(counter, slot);
+            let index = (slot % self.buckets as u64) as usize;
+            if let Some(count) = counter.counts.get_mut(index) {
+                *count = count.saturating_add(n);
+            }
+            counter.counts.iter().map(|c| *c as u64).sum()
+        })
+    }
+
+    pub(crate) fn count_at(&self, key: &[u8], now_ms: u64) -> u64 {
+        let slot = now_ms / self.slot_ms;
+        self.counters.with_mut(|counters| match counters.get_mut(key) {
+            Some(counter) => {
+                self.advance(counter, slot);
+                counter.counts.iter().map(|c| *c as u64).sum()
+            }
+            None => 0,
+        })
+    }
+
+    /// Move the head of the counter to `slot`, clearing buckets that left the window.
+    fn advance(&self, counter: &mut Counter, slot: u64) {
+        if slot <= counter.head {
+            return;
+        }
+        let expired = (slot - counter.head).min(self.buckets as u64);
+        for s in (slot + 1 - expired)..=slot {
+            if let Some(count) = counter.counts.get_mut((s % self.buckets as u64) as usize) {
+                *count = 0;
+            }
+        }
+        counter.head = slot;
+    }
+
+    /// Drop counters without events in the window, or the least recently updated one.
+    fn evict(&self, counters: &mut HashMap<Box<[u8]>, Counter>, slot: u64) {
+        counters.retain(|_, c| slot.saturating_sub(c.head) < self.buckets as u64);
+        if counters.len() >= self.max_keys {
+            let oldest = counters.iter().min_by_key(|(_, c)| c.head).map(|(k, _)| k.clone());
+            if let Some(key) = oldest {
+                counters.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window() -> SlidingWindow {
+        // 10s window with 1s buckets
+        SlidingWindow::new(Duration::from_secs(10), 10)
+    }
+
+    #[test]
+    fn counts_within_window() {
+        let w = window();
+        assert_eq!(w.add_at(b"a", 1, 0), 1);
+        assert_eq!(w.add_at(b"a", 2, 500), 3);
+        assert_eq!(w.add_at(b"a", 1, 9_999), 4);
+        assert_eq!(w.count_at(b"b", 0), 0);
+    }
+
+    #[test]
+    fn events_expire_bucket_by_bucket() {
+        let w = window();
+        w.add_at(b"a", 1, 0);
+        w.add_at(b"a", 1, 5_000);
+        assert_eq!(w.count_at(b"a", 9_999), 2);
+        assert_eq!(w.count_at(b"a", 10_000), 1);
+        assert_eq!(w.count_at(b"a", 15_000), 0);
+    }
+
+    #[test]
+    fn long_gap_clears_everything() {
+        let w = window();
+        w.add_at(b"a", 5, 0);
+        assert_eq!(w.add_at(b"a", 1, 1_000_000), 1);
+    }
+
+    #[test]
+    fn reset_forgets_key() {
+        let w = window();
+        w.add_at(b"a", 3, 0);
+        w.reset(b"a");
+        assert_eq!(w.count_at(b"a", 0), 0);
+    }
+
+    #[test]
+    fn eviction_bounds_memory() {
+        let w = window().with_max_keys(2);
+        w.add_at(b"a", 1, 0);
+        w.add_at(b"b", 1, 1_000);
+        w.add_at(b"c", 1, 2_000);
+        assert_eq!(w.counters.with(|counters| counters.len()), 2);
+        assert_eq!(w.count_at(b"a", 2_000), 0);
+        assert_eq!(w.count_at(b"b", 2_000), 1);
+    }
+
+    #[test]
+    fn degenerate_configuration_is_clamped() {
+        let w = SlidingWindow::new(Duration::ZERO, 0);
+        assert_eq!(w.add_at(b"a", 1, 0), 1);
+        assert_eq!(w.count_at(b"a", 1), 0);
+    }
+
+    #[test]
+    fn increment_uses_clock() {
+        let w = window();
+        assert_eq!(w.increment(b"a"), 1);
+        assert_eq!(w.count(b"a"), 1);
+    }
+}
@@ -0,0 +1,259 @@
+//! Parsing of `multipart/form-data` bodies (RFC 7578).
+//!
+//! Parts are parsed from the buffered body with limits on their number and size, so
+//! upload validation can inspect field values and files before the request reaches
+//! the upstream. Use [`Body::read_multipart`](crate::host::Body::read_multipart) to
+//! parse a request body according to its `Content-Type`.
+//!
+//! ```
+//! use http_wasm_guest::multipart::{self, Limits};
+//!
+//! let body = b"--xyz\r\n\
+//!     Content-Disposition: form-data; name=\"avatar\"; filename=\"me.png\"\r\n\
+//!     Content-Type: image/png\r\n\
+//!     \r\n\
+//!     \x89PNG\r\n\
+//!     --xyz--\r\n";
+//! let boundary = multipart::boundary(b"multipart/form-data; boundary=xyz").unwrap();
+//! let parts = multipart::parse(body, &boundary, &Limits::default()).unwrap();
+//! assert_eq!(parts[0].filename.as_deref(), Some("me.png"));
+//! assert_eq!(parts[0].data, b"\x89PNG");
+//! ```
+use std::{error::Error, fmt::Display};
+
+use crate::headers::{Mime, split_params, unquote};
+
+/// Limits applied while parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of parts.
+    pub max_parts: usize,
+    /// Maximum size of the content of a single part.
+    pub max_part_size: usize,
+    /// Maximum size of the header block of a single part.
+    pub max_header_size: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self { max_parts: 100, max_part_size: 10 * 1024 * 1024, max_header_size: 8 * 1024 }
+    }
+}
+
+/// A field or file of a `multipart/form-data` body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    /// Form field name from `Content-Disposition`.
+    pub name: String,
+    /// File name as sent by the client, for file uploads. It is not sanitized and may
+    /// contain path separators.
+    pub filename: Option<String>,
+    /// `Content-Type` of the part, if present.
+    pub content_type: Option<String>,
+    /// Content of the part.
+    pub data: Vec<u8>,
+}
+
+impl Part {
+    /// Whether the part is a file upload.
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+
+    /// Content of the part as text, if it is valid UTF-8.
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
+/// Errors returned when parsing a multipart body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultipartError {
+    /// The `Content-Type` is not `multipart/form-data` with a valid boundary.
+    NotMultipart,
+    /// The body is not valid multipart data.
+    Malformed,
+    /// The body has more parts than allowed.
+    TooManyParts,
+    /// The header block of a part exceeds the limit.
+    HeadersTooLarge,
+    /// The content of the named part exceeds the limit.
+    PartTooLarge(String),
+}
+
+impl Display for MultipartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MultipartError::NotMultipart => write!(f, "not a multipart/form-data body"),
+            MultipartError::Malformed => write!(f, "malformed multipart body"),
+            MultipartError::TooManyParts => write!(f, "too many parts"),
+            MultipartError::HeadersTooLarge => write!(f, "part headers exceed limit"),
+            MultipartError::PartTooLarge(name) => write!(f, "part `{name}` exceeds limit"),
+        }
+    }
+}
+
+impl Error for MultipartError {}
+
+/// Boundary of a `multipart/form-data` `Content-Type` value.
+pub fn boundary(content_type: &[u8]) -> Option<String> {
+    let mime = Mime::parse(content_type).filter(|mime| mime.essence() == "multipart/form-data")?;
+    mime.param("boundary").filter(|boundary| (1..=70).contains(&boundary.len())).map(str::to_string)
+}
+
+/// Parse a multipart `body` delimited by `boundary`.
+///
+/// The preamble before the first and the epilogue after the last delimiter are ignored.
+pub fn parse(body: &[u8], boundary: &str, limits: &Limits) -> Result<Vec<Part>, MultipartError> {
+    let delimiter = [b"--", boundary.as_bytes()].concat();
+    let next_delimiter = [b"\r\n", delimiter.as_slice()].concat();
+    let start = match body.starts_with(&delimiter) {
+        true => delimiter.len(),
+        false => find(body, &next_delimiter).ok_or(MultipartError::Malformed)? + next_delimiter.len(),
+    };
+    let mut rest = body.get(start..).unwrap_or_default();
+    let mut parts = Vec::new();
+    while !rest.starts_with(b"--") {
+        // the delimiter line may end with transport padding
+        let line_end = find(rest, b"\r\n").ok_or(MultipartError::Malformed)?;
+        if !rest.get(..line_end).unwrap_or_default().iter().all(|b| matches!(b, b' ' | b'\t')) {
+            return Err(MultipartError::Malformed);
+        }
+        if parts.len() == limits.max_parts {
+            return Err(MultipartError::TooManyParts);
+        }
+        let part = rest.get(line_end + 2..).unwrap_or_default();
+        let (headers, content) = match part.starts_with(b"\r\n") {
+            true => (&b""[..], part.get(2..).unwrap_or_default()),
+            false => {
+                let window = part.get(..limits.max_header_size + 4).unwrap_or(part);
+                let end = find(window, b"\r\n\r\n").ok_or(match window.len() < part.len() {
+                    true => MultipartError::HeadersTooLarge,
+                    false => MultipartError::Malformed,
+                })?;
+                (part.get(..end).unwrap_or_default(), part.get(end + 4..).unwrap_or_default())
+            }
+        };
+        let mut parsed = parse_headers(headers)?;
+        let end = find(content, &next_delimiter).ok_or(MultipartError::Malformed)?;
+        if end > limits.max_part_size {
+            return Err(MultipartError::PartTooLarge(parsed.name));
+        }
+        parsed.data = content.get(..end).unwrap_or_default().to_vec();
+        parts.push(parsed);
+        rest = content.get(end + next_delimiter.len()..).unwrap_or_default();
+    }
+    Ok(parts)
+}
+
+/// A part without content from its header block.
+fn parse_headers(headers: &[u8]) -> Result<Part, MultipartError> {
+    let headers = String::from_utf8_lossy(headers);
+    let mut part = Part { name: String::new(), filename: None, content_type: None, data: Vec::new() };
+    let mut has_name = false;
+    for line in headers.split("\r\n") {
+        let (name, value) = line.split_once(':').ok_or(MultipartError::Malformed)?;
+        let value = value.trim();
+        if name.trim().eq_ignore_ascii_case("Content-Type") {
+            part.content_type = Some(value.to_string());
+        } else if name.trim().eq_ignore_ascii_case("Content-Disposition") {
+            let mut params = split_params(value).into_iter();
+            if !params.next().is_some_and(|d| d.trim().eq_ignore_ascii_case("form-data")) {
+                return Err(MultipartError::Malformed);
+            }
+            for param in params {
+                let Some((key, value)) = param.split_once('=') else {
+                    continue;
+                };
+                match key.trim().to_ascii_lowercase().as_str() {
+                    "name" => {
+                        part.name = unquote(value.trim());
+                        has_name = true;
+                    }
+                    "filename" => part.filename = Some(unquote(value.trim())),
+                    _ => {}
+                }
+            }
+        }
+    }
+    match has_name {
+        true => Ok(part),
+        false => Err(MultipartError::Malformed),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORM: &[u8] = b"preamble\r\n--AaB03x\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\
+        \r\n\
+        Hello\r\n\
+        --AaB03x  \r\n\
+        content-disposition: form-data; name=\"file\"; filename=\"a \\\"b\\\"; c.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        line 1\r\n--AaB\r\nline 2\r\n\
+        --AaB03x--\r\n\
+        epilogue";
+
+    #[test]
+    fn parse_fields_and_files() {
+        let parts = parse(FORM, "AaB03x", &Limits::default()).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "title");
+        assert_eq!(parts[0].text(), Some("Hello"));
+        assert!(!parts[0].is_file());
+        assert_eq!(parts[1].name, "file");
+        assert_eq!(parts[1].filename.as_deref(), Some("a \"b\"; c.txt"));
+        assert_eq!(parts[1].content_type.as_deref(), Some("text/plain"));
+        assert_eq!(parts[1].data, b"line 1\r\n--AaB\r\nline 2");
+    }
+
+    #[test]
+    fn parse_empty_parts() {
+        assert_eq!(parse(b"--b--", "b", &Limits::default()), Ok(Vec::new()));
+        let parts = parse(b"--b\r\nContent-Disposition: form-data; name=e\r\n\r\n\r\n--b--", "b", &Limits::default()).unwrap();
+        assert_eq!(parts[0].name, "e");
+        assert!(parts[0].data.is_empty());
+        // a part without Content-Disposition has no name
+        assert_eq!(parse(b"--b\r\n\r\nx\r\n--b--", "b", &Limits::default()), Err(MultipartError::Malformed));
+    }
+
+    #[test]
+    fn enforce_limits() {
+        let limits = |max_parts, max_part_size, max_header_size| Limits { max_parts, max_part_size, max_header_size };
+        assert_eq!(parse(FORM, "AaB03x", &limits(1, 100, 100)), Err(MultipartError::TooManyParts));
+        assert_eq!(parse(FORM, "AaB03x", &limits(2, 10, 100)), Err(MultipartError::PartTooLarge("file".into())));
+        assert_eq!(parse(FORM, "AaB03x", &limits(2, 100, 40)), Err(MultipartError::HeadersTooLarge));
+        assert!(parse(FORM, "AaB03x", &limits(2, 21, 96)).is_ok());
+    }
+
+    #[test]
+    fn malformed_bodies() {
+        assert_eq!(parse(b"no delimiter", "b", &Limits::default()), Err(MultipartError::Malformed));
+        assert_eq!(
+            parse(b"--b\r\nContent-Disposition: form-data; name=x\r\n\r\ntruncated", "b", &Limits::default()),
+            Err(MultipartError::Malformed)
+        );
+        assert_eq!(parse(b"--bx\r\n", "b", &Limits::default()), Err(MultipartError::Malformed));
+        assert_eq!(
+            parse(b"--b\r\nContent-Disposition: attachment; name=x\r\n\r\n\r\n--b--", "b", &Limits::default()),
+            Err(MultipartError::Malformed)
+        );
+    }
+
+    #[test]
+    fn boundary_from_content_type() {
+        assert_eq!(boundary(b"multipart/form-data; boundary=AaB03x").as_deref(), Some("AaB03x"));
+        assert_eq!(boundary(b"Multipart/Form-Data; charset=utf-8; Boundary=\"a;b c\"").as_deref(), Some("a;b c"));
+        assert_eq!(boundary(b"multipart/mixed; boundary=x"), None);
+        assert_eq!(boundary(b"multipart/form-data; boundary=\"\""), None);
+        assert_eq!(boundary(b"multipart/form-data"), None);
+    }
+}
@@ -0,0 +1,348 @@
+//! Helpers for reverse proxies that expose an internal service under a public URL.
+//!
+//! A [`UrlMapping`] translates URLs of the internal origin, e.g.
+//! `http://wiki.internal:8080`, to the public origin and path prefix, e.g.
+//! `https://example.com/wiki`, so that links in the responses of the service keep
+//! working for clients. Use it with [`html::rewrite_links`](crate::html::rewrite_links)
+//! for HTML bodies and [`rewrite_location`] for redirects. A [`CookieRewrite`] adapts
+//! the scope of the cookies the service sets.
+
+use std::fmt;
+
+use crate::host::Response;
+
+/// Headers with a URL that points back to the service.
+const LOCATION_HEADERS: [&[u8]; 2] = [b"Location", b"Content-Location"];
+
+/// Translation of URLs from an internal to a public base URL.
+///
+/// ```
+/// use http_wasm_guest::proxy::UrlMapping;
+///
+/// let mapping = UrlMapping::new("http://wiki.internal:8080", "https://example.com/wiki").with_root_relative(true);
+/// assert_eq!(mapping.map("http://wiki.internal:8080/page?x=1").as_deref(), Some("https://example.com/wiki/page?x=1"));
+/// assert_eq!(mapping.map("/static/app.css").as_deref(), Some("/wiki/static/app.css"));
+/// assert_eq!(mapping.map("https://other.example/"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlMapping {
+    internal: Base,
+    public: Base,
+    root_relative: bool,
+}
+
+/// Origin (`scheme://authority`, lowercase) and path prefix without trailing `/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Base {
+    origin: String,
+    path: String,
+}
+
+impl Base {
+    fn parse(url: &str) -> Self {
+        let url = url.trim();
+        let authority_start = url.find("://").map_or(0, |i| i + 3);
+        let path_start = url.get(authority_start..).and_then(|rest| rest.find('/')).map_or(url.len(), |i| authority_start + i);
+        let origin = url.get(..path_start).unwrap_or_default().to_ascii_lowercase();
+        let path = url.get(path_start..).unwrap_or_default().trim_end_matches('/').to_string();
+        Self { origin, path }
+    }
+
+    /// Authority of the origin, for protocol-relative URLs.
+    fn authority(&self) -> &str {
+        self.origin.find("://").and_then(|i| self.origin.get(i + 3..)).unwrap_or(&self.origin)
+    }
+}
+
+impl UrlMapping {
+    /// Create a mapping from the `internal` to the `public` base URL, each an origin
+    /// with an optional path prefix, e.g. `http://app:8080` and `https://example.com/app`.
+    pub fn new(internal: &str, public: &str) -> Self {
+        Self { internal: Base::parse(internal), public: Base::parse(public), root_relative: false }
+    }
+
+    /// Also map root-relative URLs (`/path`) from the internal to the public path
+    /// prefix, for services that are unaware of the prefix they are exposed under.
+    pub fn with_root_relative(mut self, root_relative: bool) -> Self {
+        self.root_relative = root_relative;
+        self
+    }
+
+    /// Translate `url` if it points to the internal base URL.
+    ///
+    /// Absolute and protocol-relative (`//host/path`) URLs are mapped to absolute
+    /// public URLs; root-relative ones, if enabled, keep being root-relative.
+    /// Scheme and host are compared ignoring ASCII case, paths exactly.
+    pub fn map(&self, url: &str) -> Option<String> {
+        if let Some(rest) = url.strip_prefix("//") {
+            let rest = strip_prefix_ignore_case(rest, self.internal.authority())?;
+            return self.map_path(at_boundary(rest)?).map(|path| format!("{}{path}", self.public.origin));
+        }
+        if url.starts_with('/') {
+            let mapped = self.root_relative.then(|| self.map_path(url)).flatten();
+            return mapped.map(|path| if path.is_empty() { "/".to_string() } else { path }).filter(|path| path != url);
+        }
+        let rest = strip_prefix_ignore_case(url, &self.internal.origin)?;
+        self.map_path(at_boundary(rest)?).map(|path| format!("{}{path}", self.public.origin))
+    }
+
+    /// Replace the internal path prefix of `path` (including query and fragment).
+    fn map_path(&self, path: &str) -> Option<String> {
+        let rest = at_boundary(path.strip_prefix(self.internal.path.as_str())?)?;
+        Some(format!("{}{rest}", self.public.path))
+    }
+}
+
+/// Rewrite the `Location` and `Content-Location` headers of an upstream response
+/// with `mapping`, so that redirects do not leak the internal host name; returns
+/// `true` if a header was changed.
+///
+/// ```no_run
+/// use http_wasm_guest::{
+///     Guest,
+///     host::{Request, Response},
+///     proxy::{UrlMapping, rewrite_location},
+/// };
+///
+/// struct Proxy {
+///     mapping: UrlMapping,
+/// }
+///
+/// impl Guest for Proxy {
+///     fn handle_response(&self, _req_ctx: i32, _request: &Request, response: &Response, _is_error: bool) {
+///         rewrite_location(response, &self.mapping);
+///     }
+/// }
+/// ```
+pub fn rewrite_location(response: &Response, mapping: &UrlMapping) -> bool {
+    let mut changed = false;
+    for name in LOCATION_HEADERS {
+        let mapped =
+            response.header.get(name).and_then(|value| mapping.map(String::from_utf8_lossy(value.trim_ascii()).as_ref()));
+        if let Some(mapped) = mapped {
+            response.header.set(name, mapped.as_bytes());
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// `SameSite` attribute of a cookie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    /// Sent with same-site requests only.
+    Strict,
+    /// Also sent with top-level cross-site navigations.
+    Lax,
+    /// Also sent with cross-site requests; browsers require `Secure` as well.
+    None,
+}
+
+impl SameSite {
+    /// The attribute value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Rewriting of the `Domain` and `Path` attributes of upstream `Set-Cookie` headers,
+/// for services exposed under another host name or path prefix.
+///
+/// ```
+/// use http_wasm_guest::proxy::{CookieRewrite, SameSite};
+///
+/// let rewrite = CookieRewrite {
+///     domains: vec![("wiki.internal".into(), "example.com".into())],
+///     paths: vec![("/".into(), "/wiki".into())],
+///     secure: true,
+///     same_site: Some(SameSite::Lax),
+/// };
+/// assert_eq!(
+///     rewrite.rewrite("sid=1; Domain=wiki.internal; Path=/; HttpOnly").as_deref(),
+///     Some("sid=1; Domain=example.com; Path=/wiki; HttpOnly; Secure; SameSite=Lax")
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieRewrite {
+    /// Internal and public cookie domains, compared ignoring ASCII case and a leading
+    /// dot. An empty public domain removes the attribute, making the cookie host-only.
+    pub domains: Vec<(String, String)>,
+    /// Internal and public path prefixes; the first prefix that matches whole path
+    /// segments is replaced.
+    pub paths: Vec<(String, String)>,
+    /// Add the `Secure` attribute.
+    pub secure: bool,
+    /// Set the `SameSite` attribute, replacing the upstream value.
+    pub same_site: Option<SameSite>,
+}
+
+impl CookieRewrite {
+    /// Rewrite a `Set-Cookie` value; `None` if nothing changed.
+    pub fn rewrite(&self, set_cookie: &str) -> Option<String> {
+        let mut parts = set_cookie.split(';');
+        let cookie = parts.next().unwrap_or_default().trim();
+        let mut attributes: Vec<String> = parts.map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect();
+        let mut changed = false;
+        attributes.retain_mut(|attribute| {
+            let (name, value) = attribute.split_once('=').unwrap_or((attribute.as_str(), ""));
+            let (name, value) = (name.trim(), value.trim());
+            let replacement = if name.eq_ignore_ascii_case("domain") {
+                self.map_domain(value).map(|domain| (!domain.is_empty()).then(|| format!("Domain={domain}")))
+            } else if name.eq_ignore_ascii_case("path") {
+                self.map_path(value).map(|path| Some(format!("Path={path}")))
+            } else if name.eq_ignore_ascii_case("samesite") {
+                self.same_site.filter(|s| !value.eq_ignore_ascii_case(s.as_str())).map(|s| Some(format!("SameSite={s}")))
+            } else {
+                None
+            };
+            match replacement {
+                Some(Some(new)) => {
+                    changed = true;
+                    *attribute = new;
+                    true
+                }
+                Some(None) => {
+                    changed = true;
+                    false
+                }
+                None => true,
+            }
+        });
+        let has = |attributes: &[String], name: &str| {
+            attributes.iter().any(|a| a.split('=').next().is_some_and(|n| n.trim().eq_ignore_ascii_case(name)))
+        };
+        if self.secure && !has(&attributes, "secure") {
+            attributes.push("Secure".into());
+            changed = true;
+        }
+        if let Some(same_site) = self.same_site.filter(|_| !has(&attributes, "samesite")) {
+            attributes.push(format!("SameSite={same_site}"));
+            changed = true;
+        }
+        changed.then(|| std::iter::once(cookie.to_string()).chain(attributes).collect::<Vec<_>>().join("; "))
+    }
+
+    /// Rewrite all `Set-Cookie` headers of the response; returns `true` if one changed.
+    pub fn rewrite_response(&self, response: &Response) -> bool {
+        let values = response.header.values(b"Set-Cookie");
+        let rewritten: Vec<Option<String>> = values.iter().map(|v| self.rewrite(&String::from_utf8_lossy(v))).collect();
+        if rewritten.iter().all(Option::is_none) {
+            return false;
+        }
+        response.header.remove(b"Set-Cookie");
+        for (value, rewritten) in values.iter().zip(rewritten) {
+            response.header.add(b"Set-Cookie", rewritten.as_ref().map_or(value.as_ref(), |r| r.as_bytes()));
+        }
+        true
+    }
+
+    fn map_domain(&self, domain: &str) -> Option<&str> {
+        let domain = domain.strip_prefix('.').unwrap_or(domain);
+        self.domains
+            .iter()
+            .find(|(internal, _)| internal.strip_prefix('.').unwrap_or(internal).eq_ignore_ascii_case(domain))
+            .map(|(_, public)| public.as_str())
+    }
+
+    fn map_path(&self, path: &str) -> Option<String> {
+        self.paths.iter().find_map(|(internal, public)| {
+            let internal = internal.trim_end_matches('/');
+            let rest = path.strip_prefix(internal).filter(|rest| rest.is_empty() || rest.starts_with('/'))?;
+            let public = public.trim_end_matches('/');
+            // `Path=/` scopes the cookie to the whole public prefix
+            let mapped = if rest == "/" { public.to_string() } else { format!("{public}{rest}") };
+            let mapped = if mapped.is_empty() { "/".to_string() } else { mapped };
+            (mapped != path).then_some(mapped)
+        })
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix).then(|| text.get(prefix.len()..)).flatten()
+}
+
+/// `rest` if it starts a new path segment, query or fragment.
+fn at_boundary(rest: &str) -> Option<&str> {
+    (rest.is_empty() || rest.starts_with(['/', '?', '#'])).then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_urls() {
+        let mapping = UrlMapping::new("http://App.internal:8080/", "https://example.com/app/");
+        assert_eq!(mapping.map("HTTP://app.internal:8080").as_deref(), Some("https://example.com/app"));
+        assert_eq!(mapping.map("http://app.internal:8080/a/b?c#d").as_deref(), Some("https://example.com/app/a/b?c#d"));
+        assert_eq!(mapping.map("//app.internal:8080/x").as_deref(), Some("https://example.com/app/x"));
+        assert_eq!(mapping.map("http://app.internal:80801/x"), None);
+        assert_eq!(mapping.map("http://app.internal/x"), None);
+        assert_eq!(mapping.map("mailto:a@app.internal"), None);
+    }
+
+    #[test]
+    fn internal_path_prefix() {
+        let mapping = UrlMapping::new("http://backend/legacy", "https://example.com").with_root_relative(true);
+        assert_eq!(mapping.map("http://backend/legacy/x").as_deref(), Some("https://example.com/x"));
+        assert_eq!(mapping.map("http://backend/legacy?q").as_deref(), Some("https://example.com?q"));
+        assert_eq!(mapping.map("http://backend/other"), None);
+        assert_eq!(mapping.map("/legacy/img.png").as_deref(), Some("/img.png"));
+        assert_eq!(mapping.map("/legacy").as_deref(), Some("/"));
+        assert_eq!(mapping.map("/legacyx"), None);
+    }
+
+    #[test]
+    fn root_relative_urls() {
+        let mapping = UrlMapping::new("http://backend", "https://example.com/app");
+        assert_eq!(mapping.map("/x"), None);
+        let mapping = mapping.with_root_relative(true);
+        assert_eq!(mapping.map("/x").as_deref(), Some("/app/x"));
+        assert_eq!(mapping.map("/").as_deref(), Some("/app/"));
+        let same_prefix = UrlMapping::new("http://backend/app", "https://example.com/app").with_root_relative(true);
+        assert_eq!(same_prefix.map("/app/x"), None);
+    }
+
+    #[test]
+    fn cookie_domain_and_path() {
+        let rewrite = CookieRewrite {
+            domains: vec![(".backend.internal".into(), "example.com".into()), ("legacy.internal".into(), "".into())],
+            paths: vec![("/api".into(), "/".into()), ("/".into(), "/app/".into())],
+            ..Default::default()
+        };
+        assert_eq!(rewrite.rewrite("a=1; domain=backend.internal").as_deref(), Some("a=1; Domain=example.com"));
+        assert_eq!(rewrite.rewrite("a=1;Domain=LEGACY.internal;Path=/").as_deref(), Some("a=1; Path=/app"));
+        assert_eq!(rewrite.rewrite("a=1; Path=/api/v1").as_deref(), Some("a=1; Path=/v1"));
+        assert_eq!(rewrite.rewrite("a=1; Path=/api").as_deref(), Some("a=1; Path=/"));
+        assert_eq!(rewrite.rewrite("a=1; Path=/x").as_deref(), Some("a=1; Path=/app/x"));
+        assert_eq!(rewrite.rewrite("a=1; Domain=other.example; Max-Age=60"), None);
+    }
+
+    #[test]
+    fn cookie_secure_and_same_site() {
+        let rewrite = CookieRewrite { secure: true, same_site: Some(SameSite::None), ..Default::default() };
+        assert_eq!(rewrite.rewrite("a=b=c; SameSite=Lax").as_deref(), Some("a=b=c; SameSite=None; Secure"));
+        assert_eq!(rewrite.rewrite("a=1; secure; samesite=none"), None);
+        assert_eq!(CookieRewrite::default().rewrite("a=1; Path=/"), None);
+    }
+
+    #[test]
+    fn location_without_header() {
+        // The mock response has no Location header
+        let mapping = UrlMapping::new("http://backend", "https://example.com").with_root_relative(true);
+        assert!(!rewrite_location(&Response::new(), &mapping));
+        // nor Set-Cookie headers
+        assert!(!CookieRewrite { secure: true, ..Default::default() }.rewrite_response(&Response::new()));
+    }
+}
@@ -0,0 +1,281 @@
+//! Masking of personal data and secrets in text.
+//!
+//! A [`Redactor`] replaces configured [`Pattern`]s in a string, e.g. before log
+//! messages leave the guest; install one for all host logging with
+//! [`host::log::set_redactor`](crate::host::log::set_redactor).
+//!
+//! ```
+//! use http_wasm_guest::redact::Redactor;
+//!
+//! let redactor = Redactor::default();
+//! assert_eq!(
+//!     redactor.redact("login jane@example.com with Bearer eyJhbGciOi.x.y"),
+//!     "login [redacted] with Bearer [redacted]"
+//! );
+//! ```
+use std::{borrow::Cow, ops::Range};
+
+/// Kind of sensitive data recognized by a [`Redactor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// Email addresses (`local@domain.tld`).
+    Email,
+    /// The token of `Bearer` credentials; the scheme name is kept.
+    BearerToken,
+    /// Payment card numbers: 13 to 19 digits, optionally grouped by spaces or dashes,
+    /// with a valid Luhn check digit.
+    CardNumber,
+    /// Every occurrence of the string, compared case-sensitively.
+    Literal(String),
+}
+
+impl Pattern {
+    /// Byte ranges of all matches in `text`, in order and without overlap.
+    pub fn find_all(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            Pattern::Email => emails(text),
+            Pattern::BearerToken => bearer_tokens(text),
+            Pattern::CardNumber => card_numbers(text),
+            Pattern::Literal(literal) if !literal.is_empty() => {
+                text.match_indices(literal.as_str()).map(|(start, m)| start..start + m.len()).collect()
+            }
+            Pattern::Literal(_) => Vec::new(),
+        }
+    }
+}
+
+/// Replaces sensitive data in text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redactor {
+    patterns: Vec<Pattern>,
+    replacement: Replacement,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Replacement {
+    Fixed(String),
+    Mask { keep_last: usize, mask: char },
+}
+
+impl Default for Redactor {
+    /// Redact emails, bearer tokens and card numbers with `[redacted]`.
+    fn default() -> Self {
+        Self::new(vec![Pattern::Email, Pattern::BearerToken, Pattern::CardNumber])
+    }
+}
+
+impl Redactor {
+    /// Create a redactor for the given patterns, replacing matches with `[redacted]`.
+    pub fn new(patterns: Vec<Pattern>) -> Self {
+        Self { patterns, replacement: Replacement::Fixed("[redacted]".into()) }
+    }
+
+    /// Use `replacement` instead of `[redacted]`.
+    pub fn with_replacement(mut self, replacement: &str) -> Self {
+        self.replacement = Replacement::Fixed(replacement.to_string());
+        self
+    }
+
+    /// Mask matches instead of replacing them: letters and digits become `mask`, except
+    /// the last `keep_last` ones, while separators are kept, so
+    /// `4111 1111 1111 1111` turns into `**** **** **** 1111` with `keep_last` 4.
+    pub fn with_mask(mut self, keep_last: usize, mask: char) -> Self {
+        self.replacement = Replacement::Mask { keep_last, mask };
+        self
+    }
+
+    /// Return `text` with all matches replaced; borrows `text` if nothing matched.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut ranges: Vec<Range<usize>> = self.patterns.iter().flat_map(|p| p.find_all(text)).collect();
+        if ranges.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        ranges.sort_by_key(|r| r.start);
+        let mut out = String::with_capacity(text.len());
+        let mut pos = 0;
+        for range in ranges {
+            if range.start < pos {
+                // overlaps the previous match, which is replaced already
+                pos = pos.max(range.end);
+                continue;
+            }
+            out.push_str(text.get(pos..range.start).unwrap_or_default());
+            let matched = text.get(range.clone()).unwrap_or_default();
+            match &self.replacement {
+                Replacement::Fixed(replacement) => out.push_str(replacement),
+                Replacement::Mask { keep_last, mask } => mask_into(matched, *keep_last, *mask, &mut out),
+            }
+            pos = range.end;
+        }
+        out.push_str(text.get(pos..).unwrap_or_default());
+        Cow::Owned(out)
+    }
+}
+
+fn mask_into(matched: &str, keep_last: usize, mask: char, out: &mut String) {
+    let masked = matched.chars().filter(|c| c.is_alphanumeric()).count().saturating_sub(keep_last);
+    let mut seen = 0;
+    for c in matched.chars() {
+        if c.is_alphanumeric() {
+            out.push(if seen < masked { mask } else { c });
+            seen += 1;
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+fn emails(text: &str) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let is_local = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'%' | b'+' | b'-');
+    let is_domain = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-');
+    let mut ranges = Vec::new();
+    let mut after_last = 0;
+    for (at, _) in text.match_indices('@') {
+        if at < after_last {
+            continue;
+        }
+        let local = bytes.get(after_last..at).unwrap_or_default();
+        let start = at - local.iter().rev().take_while(|b| is_local(**b)).count();
+        let domain = bytes.get(at + 1..).unwrap_or_default();
+        let mut end = at + 1 + domain.iter().take_while(|b| is_domain(**b)).count();
+        // a sentence may end right after the address
+        while end > at + 1 && matches!(bytes.get(end - 1), Some(b'.' | b'-')) {
+            end -= 1;
+        }
+        let domain = text.get(at + 1..end).unwrap_or_default();
+        if start < at && domain.contains('.') && !domain.starts_with('.') {
+            ranges.push(start..end);
+            after_last = end;
+        }
+    }
+    ranges
+}
+
+fn bearer_tokens(text: &str) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let is_token = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'+' | b'/' | b'=');
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while let Some(window) = bytes.get(i..i + 7) {
+        let word_start = i == 0 || bytes.get(i - 1).is_some_and(|b| !b.is_ascii_alphanumeric());
+        if word_start && window.eq_ignore_ascii_case(b"bearer ") {
+            let start = i + 7 + bytes.get(i + 7..).unwrap_or_default().iter().take_while(|b| **b == b' ').count();
+            let len = bytes.get(start..).unwrap_or_default().iter().take_while(|b| is_token(**b)).count();
+            if len > 0 {
+                ranges.push(start..start + len);
+                i = start + len;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    ranges
+}
+
+/// Byte ranges of payment card numbers with a valid Luhn check digit.
+fn card_numbers(text: &str) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while let Some(&b) = bytes.get(i) {
+        let boundary = i == 0 || bytes.get(i - 1).is_some_and(|p| !p.is_ascii_alphanumeric());
+        if !b.is_ascii_digit() || !boundary {
+            i += 1;
+            continue;
+        }
+        // digits, each group separated by a single space or dash
+        let mut end = i;
+        let mut digits = Vec::new();
+        while let Some(&c) = bytes.get(end) {
+            if c.is_ascii_digit() {
+                digits.push(c - b'0');
+                end += 1;
+            } else if matches!(c, b' ' | b'-') && bytes.get(end + 1).is_some_and(u8::is_ascii_digit) {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+        let at_boundary = bytes.get(end).is_none_or(|c| !c.is_ascii_alphanumeric());
+        if at_boundary && (13..=19).contains(&digits.len()) && luhn_valid(&digits) {
+            ranges.push(i..end);
+        }
+        i = end.max(i + 1);
+    }
+    ranges
+}
+
+/// Whether the digits pass the Luhn (mod 10) check.
+fn luhn_valid(digits: &[u8]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| {
+            let d = *d as u32;
+            if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d }
+        })
+        .sum();
+    !digits.is_empty() && sum % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: Pattern, text: &str) -> Vec<&str> {
+        pattern.find_all(text).into_iter().filter_map(|r| text.get(r)).collect()
+    }
+
+    #[test]
+    fn email_addresses() {
+        assert_eq!(matches(Pattern::Email, "mail jane.doe+x@mail.example.com."), ["jane.doe+x@mail.example.com"]);
+        assert_eq!(matches(Pattern::Email, "a@b.co, c@d.org"), ["a@b.co", "c@d.org"]);
+        assert!(matches(Pattern::Email, "user@localhost @handle x@.com").is_empty());
+    }
+
+    #[test]
+    fn bearer_tokens_keep_scheme() {
+        assert_eq!(matches(Pattern::BearerToken, "Authorization: Bearer abc.DEF-1_2~+/="), ["abc.DEF-1_2~+/="]);
+        assert_eq!(matches(Pattern::BearerToken, "bearer  t1 and BEARER t2"), ["t1", "t2"]);
+        assert!(matches(Pattern::BearerToken, "nobearer x, Bearer ").is_empty());
+    }
+
+    #[test]
+    fn card_numbers_with_luhn() {
+        assert_eq!(matches(Pattern::CardNumber, "card 4111 1111 1111 1111 ok"), ["4111 1111 1111 1111"]);
+        assert_eq!(matches(Pattern::CardNumber, "5500-0000-0000-0004;"), ["5500-0000-0000-0004"]);
+        assert_eq!(matches(Pattern::CardNumber, "378282246310005"), ["378282246310005"]);
+        // invalid check digit, too short, embedded in a word
+        assert!(matches(Pattern::CardNumber, "4111111111111112 411111111111 x4111111111111111").is_empty());
+    }
+
+    #[test]
+    fn luhn() {
+        assert!(luhn_valid(&[7, 9, 9, 2, 7, 3, 9, 8, 7, 1, 3]));
+        assert!(!luhn_valid(&[7, 9, 9, 2, 7, 3, 9, 8, 7, 1, 2]));
+        assert!(!luhn_valid(&[]));
+    }
+
+    #[test]
+    fn redact_replaces_all_patterns() {
+        let redactor = Redactor::new(vec![Pattern::Email, Pattern::Literal("secret".into())]).with_replacement("***");
+        assert_eq!(redactor.redact("secret for a@b.io and secret"), "*** for *** and ***");
+        assert!(matches!(redactor.redact("nothing here"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn mask_keeps_last_characters() {
+        let redactor = Redactor::new(vec![Pattern::CardNumber]).with_mask(4, '*');
+        assert_eq!(redactor.redact("pan 4111-1111-1111-1111."), "pan ****-****-****-1111.");
+        let redactor = Redactor::new(vec![Pattern::Literal("ab".into())]).with_mask(4, '#');
+        assert_eq!(redactor.redact("ab"), "ab");
+    }
+
+    #[test]
+    fn overlapping_matches_are_replaced_once() {
+        let redactor = Redactor::new(vec![Pattern::Email, Pattern::Literal("example".into())]);
+        assert_eq!(redactor.redact("to a@example.com"), "to [redacted]");
+    }
+}
@@ -0,0 +1,214 @@
+//! Pattern search over streamed bodies.
+//!
+//! A [`StreamScanner`] is fed the chunks of a body as they arrive, e.g. from
+//! [`Body::read_chunks`](crate::host::Body::read_chunks), and finds matches that
+//! span chunk boundaries by keeping a window of the last bytes of the previous chunk.
+//! Memory use is bounded by the chunk size plus the window, independent of the body size.
+//!
+//! ```no_run
+//! use std::ops::ControlFlow;
+//! use http_wasm_guest::{
+//!     host::Request,
+//!     scan::{ScanPattern, StreamScanner},
+//! };
+//!
+//! fn contains_secret(request: &Request) -> bool {
+//!     let mut scanner = StreamScanner::new(vec![ScanPattern::Literal("BEGIN PRIVATE KEY".into())], 64);
+//!     let mut found = false;
+//!     request.body.read_chunks(|chunk| {
+//!         found = !scanner.feed(chunk).is_empty();
+//!         if found { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+//!     });
+//!     found || !scanner.finish().is_empty()
+//! }
+//! ```
+use std::ops::Range;
+
+use crate::redact::Pattern;
+
+/// Pattern searched by a [`StreamScanner`].
+///
+/// Patterns match text: invalid UTF-8 sequences in the body separate the text around
+/// them and never match.
+#[derive(Debug, Clone)]
+pub enum ScanPattern {
+    /// The exact string.
+    Literal(String),
+    /// A [`redact::Pattern`](Pattern), e.g. card numbers.
+    Redact(Pattern),
+    /// A regular expression (`regex-lite` syntax); requires the `regex` feature.
+    #[cfg(feature = "regex")]
+    Regex(regex_lite::Regex),
+}
+
+impl ScanPattern {
+    fn find_all(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            ScanPattern::Literal(literal) if literal.is_empty() => Vec::new(),
+            ScanPattern::Literal(literal) => {
+                text.match_indices(literal.as_str()).map(|(start, m)| start..start + m.len()).collect()
+            }
+            ScanPattern::Redact(pattern) => pattern.find_all(text),
+            #[cfg(feature = "regex")]
+            ScanPattern::Regex(regex) => regex.find_iter(text).map(|m| m.range()).collect(),
+        }
+    }
+}
+
+/// A match found by a [`StreamScanner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanMatch {
+    /// Index of the matching pattern.
+    pub pattern: usize,
+    /// Byte offsets of the match in the whole stream.
+    pub range: Range<u64>,
+    /// The matched text.
+    pub text: String,
+}
+
+/// Chunk-boundary-safe pattern scanner.
+///
+/// Every match no longer than `max_match_len` bytes is reported exactly once, with its
+/// offsets in the whole stream, either by the [`feed`](StreamScanner::feed) call that
+/// completes it or by [`finish`](StreamScanner::finish). Longer matches may be
+/// reported truncated or not at all.
+pub struct StreamScanner {
+    patterns: Vec<ScanPattern>,
+    overlap: usize,
+    /// Unreported tail of the stream, starting at `carry_start`.
+    carry: Vec<u8>,
+    carry_start: u64,
+    /// End of the last reported match of each pattern, to skip overlapping rediscoveries.
+    reported_end: Vec<u64>,
+}
+
+impl StreamScanner {
+    /// Create a scanner for `patterns`; `max_match_len` is the longest match in bytes
+    /// that is guaranteed to be found across chunk boundaries.
+    pub fn new(patterns: Vec<ScanPattern>, max_match_len: usize) -> Self {
+        let reported_end = vec![0; patterns.len()];
+        // at least one complete UTF-8 sequence fits into the window
+        Self { patterns, overlap: max_match_len.max(4), carry: Vec::new(), carry_start: 0, reported_end }
+    }
+
+    /// Scan the next chunk of the stream; returns the matches completed by it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<ScanMatch> {
+        self.carry.extend_from_slice(chunk);
+        // matches starting before `safe` fit into the window completely
+        let safe = self.carry.len().saturating_sub(self.overlap);
+        let matches = self.scan(safe);
+        self.carry.drain(..safe);
+        self.carry_start += safe as u64;
+        matches
+    }
+
+    /// End the stream; returns the matches in the remaining window.
+    pub fn finish(&mut self) -> Vec<ScanMatch> {
+        let matches = self.scan(self.carry.len());
+        self.carry_start += self.carry.len() as u64;
+        self.carry.clear();
+        matches
+    }
+
+    /// Find matches in the window that start before `limit`.
+    fn scan(&mut self, limit: usize) -> Vec<ScanMatch> {
+        let mut matches = Vec::new();
+        for (offset, text) in valid_utf8(&self.carry) {
+            for (index, pattern) in self.patterns.iter().enumerate() {
+                for range in pattern.find_all(text) {
+                    let start = offset + range.start;
+                    let absolute = self.carry_start + start as u64..self.carry_start + (offset + range.end) as u64;
+                    let reported_end = self.reported_end.get_mut(index);
+                    if start >= limit || reported_end.as_ref().is_some_and(|end| absolute.start < **end) {
+                        continue;
+                    }
+                    if let Some(end) = reported_end {
+                        *end = absolute.end;
+                    }
+                    matches.push(ScanMatch {
+                        pattern: index,
+                        range: absolute,
+                        text: text.get(range).unwrap_or_default().to_string(),
+                    });
+                }
+            }
+        }
+        matches.sort_by_key(|m| (m.range.start, m.pattern));
+        matches
+    }
+}
+
+/// Valid UTF-8 segments of `bytes` with their offsets.
+fn valid_utf8(bytes: &[u8]) -> Vec<(usize, &str)> {
+    let mut segments = Vec::new();
+    let mut offset = 0;
+    for chunk in bytes.utf8_chunks() {
+        let valid = chunk.valid();
+        if !valid.is_empty() {
+            segments.push((offset, valid));
+        }
+        offset += valid.len() + chunk.invalid().len();
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_chunks(scanner: &mut StreamScanner, chunks: &[&[u8]]) -> Vec<(usize, Range<u64>, String)> {
+        let mut found: Vec<ScanMatch> = chunks.iter().flat_map(|chunk| scanner.feed(chunk)).collect();
+        found.extend(scanner.finish());
+        found.into_iter().map(|m| (m.pattern, m.range, m.text)).collect()
+    }
+
+    #[test]
+    fn match_across_chunk_boundary() {
+        let mut scanner = StreamScanner::new(vec![ScanPattern::Literal("secret".into())], 16);
+        assert_eq!(scan_chunks(&mut scanner, &[b"xx sec", b"ret yy"]), vec![(0, 3..9, "secret".into())]);
+    }
+
+    #[test]
+    fn every_match_reported_once() {
+        let body = b"ab secret cd secret ef secret";
+        for size in 1..body.len() {
+            let mut scanner = StreamScanner::new(vec![ScanPattern::Literal("secret".into())], 8);
+            let chunks: Vec<&[u8]> = body.chunks(size).collect();
+            let starts: Vec<u64> = scan_chunks(&mut scanner, &chunks).into_iter().map(|(_, r, _)| r.start).collect();
+            assert_eq!(starts, vec![3, 13, 23], "chunk size {size}");
+        }
+    }
+
+    #[test]
+    fn multi_byte_characters_split_across_chunks() {
+        let body = "grüße an ÄÖÜ".as_bytes();
+        let mut scanner = StreamScanner::new(vec![ScanPattern::Literal("ÄÖÜ".into())], 8);
+        let chunks: Vec<&[u8]> = body.chunks(3).collect();
+        assert_eq!(scan_chunks(&mut scanner, &chunks), vec![(0, 11..17, "ÄÖÜ".into())]);
+    }
+
+    #[test]
+    fn redact_patterns_and_invalid_bytes() {
+        let mut scanner = StreamScanner::new(vec![ScanPattern::Redact(Pattern::CardNumber)], 32);
+        let found = scan_chunks(&mut scanner, &[b"\xff pan 4111 1111 ", b"1111 1111\xfe"]);
+        assert_eq!(found, vec![(0, 6..25, "4111 1111 1111 1111".into())]);
+    }
+
+    #[test]
+    fn window_is_bounded() {
+        let mut scanner = StreamScanner::new(vec![ScanPattern::Literal("x".into())], 8);
+        for _ in 0..100 {
+            scanner.feed(&[b'a'; 100]);
+        }
+        assert_eq!(scanner.carry.len(), 8);
+        assert_eq!(scanner.carry_start, 9_992);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_pattern() {
+        let regex = regex_lite::Regex::new(r"token=[a-z0-9]+").unwrap();
+        let mut scanner = StreamScanner::new(vec![ScanPattern::Regex(regex)], 32);
+        assert_eq!(scan_chunks(&mut scanner, &[b"?token=ab", b"c123&x=1"]), vec![(0, 1..13, "token=abc123".into())]);
+    }
+}
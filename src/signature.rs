@@ -0,0 +1,431 @@
+//! HTTP Message Signatures (RFC 9421) with `hmac-sha256`.
+//!
+//! A signature covers a list of message components: header fields by their lowercase
+//! name and the derived components `@method`, `@target-uri`, `@authority`, `@scheme`,
+//! `@request-target`, `@path` and `@query` of requests and `@status` of responses.
+//! [`sign`] adds the `Signature-Input` and `Signature` headers, so an edge plugin can
+//! sign a request after modifying it and the upstream can verify that it passed the
+//! edge; [`verify`] checks such a signature with a shared secret.
+//!
+//! Component parameters such as `;req` or `;sf` and asymmetric algorithms are not
+//! supported.
+//!
+//! ```no_run
+//! use http_wasm_guest::{
+//!     host::Request,
+//!     signature::{self, HmacKey, SignatureParams},
+//! };
+//!
+//! fn sign_upstream(request: &Request, key: &HmacKey) -> Result<(), signature::SignatureError> {
+//!     let params = SignatureParams::new(&["@method", "@authority", "@path", "x-user-id"], key);
+//!     signature::sign(request, "edge", &params, key)
+//! }
+//! ```
+use std::{
+    error::Error,
+    fmt::{self, Display, Write},
+};
+
+use crate::{
+    codec,
+    digest::{constant_time_eq, hmac_sha256},
+    host::{Header, Request, Response, time},
+};
+
+/// The only supported signature algorithm.
+const HMAC_SHA256: &str = "hmac-sha256";
+
+/// Shared secret for `hmac-sha256` signatures.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HmacKey {
+    /// Key identifier sent in the `keyid` parameter.
+    pub key_id: String,
+    /// Shared secret.
+    pub secret: Vec<u8>,
+}
+
+impl fmt::Debug for HmacKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacKey").field("key_id", &self.key_id).finish_non_exhaustive()
+    }
+}
+
+/// Covered components and metadata of a signature, the value of `Signature-Input`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignatureParams {
+    /// Component identifiers in signing order, e.g. `@method` or `content-digest`.
+    pub components: Vec<String>,
+    /// Creation time in seconds since the Unix epoch.
+    pub created: Option<u64>,
+    /// Expiration time in seconds since the Unix epoch.
+    pub expires: Option<u64>,
+    /// Nonce against replay.
+    pub nonce: Option<String>,
+    /// Identifier of the key.
+    pub key_id: Option<String>,
+    /// Signature algorithm.
+    pub alg: Option<String>,
+    /// Application-specific tag.
+    pub tag: Option<String>,
+}
+
+impl SignatureParams {
+    /// Parameters covering `components`, created now and naming the key and algorithm.
+    pub fn new(components: &[&str], key: &HmacKey) -> Self {
+        Self {
+            components: components.iter().map(|c| c.to_string()).collect(),
+            created: Some(time::unix_secs()),
+            key_id: Some(key.key_id.clone()),
+            alg: Some(HMAC_SHA256.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Parse an inner list with parameters, e.g. `("@method" "host");created=1618884473`.
+    ///
+    /// Unknown parameters are ignored.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().strip_prefix('(')?;
+        let (list, params) = input.split_once(')')?;
+        let mut result = Self::default();
+        for item in list.split(' ').filter(|item| !item.is_empty()) {
+            let (name, rest) = item.strip_prefix('"')?.split_once('"')?;
+            result.components.push(format!("{name}{rest}"));
+        }
+        for param in split_outside_quotes(params, ';').into_iter().filter(|p| !p.trim().is_empty()) {
+            let (key, value) = param.split_once('=')?;
+            let string = || value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).map(str::to_string);
+            match key.trim() {
+                "created" => result.created = Some(value.parse().ok()?),
+                "expires" => result.expires = Some(value.parse().ok()?),
+                "nonce" => result.nonce = Some(string()?),
+                "keyid" => result.key_id = Some(string()?),
+                "alg" => result.alg = Some(string()?),
+                "tag" => result.tag = Some(string()?),
+                _ => {}
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Display for SignatureParams {
+    /// Serialize as inner list with parameters, the `@signature-params` value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('(')?;
+        for (i, component) in self.components.iter().enumerate() {
+            f.write_str(if i > 0 { " \"" } else { "\"" })?;
+            match component.split_once(';') {
+                Some((name, params)) => write!(f, "{name}\";{params}")?,
+                None => write!(f, "{component}\"")?,
+            }
+        }
+        f.write_char(')')?;
+        if let Some(created) = self.created {
+            write!(f, ";created={created}")?;
+        }
+        if let Some(expires) = self.expires {
+            write!(f, ";expires={expires}")?;
+        }
+        for (name, value) in [("nonce", &self.nonce), ("keyid", &self.key_id), ("alg", &self.alg), ("tag", &self.tag)] {
+            if let Some(value) = value {
+                write!(f, ";{name}=\"{value}\"")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Errors returned when signing or verifying a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The message has no signature with the requested label.
+    Missing,
+    /// `Signature-Input` or `Signature` is not valid.
+    Malformed,
+    /// A covered component is absent from the message or not supported.
+    MissingComponent(String),
+    /// No key matches the `keyid` of the signature.
+    UnknownKey,
+    /// The signature uses an algorithm other than `hmac-sha256`.
+    UnsupportedAlgorithm(String),
+    /// The `expires` time of the signature has passed.
+    Expired,
+    /// The signature does not match the message.
+    Invalid,
+}
+
+impl Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Missing => write!(f, "missing signature"),
+            SignatureError::Malformed => write!(f, "malformed signature fields"),
+            SignatureError::MissingComponent(name) => write!(f, "missing covered component `{name}`"),
+            SignatureError::UnknownKey => write!(f, "unknown signature key"),
+            SignatureError::UnsupportedAlgorithm(alg) => write!(f, "unsupported signature algorithm: {alg}"),
+            SignatureError::Expired => write!(f, "signature expired"),
+            SignatureError::Invalid => write!(f, "invalid signature"),
+        }
+    }
+}
+
+impl Error for SignatureError {}
+
+/// A request or response whose components can be signed.
+pub trait Message {
+    /// Header fields of the message.
+    fn header(&self) -> &Header;
+    /// Value of a derived component such as `@method`, or `None` if it does not apply.
+    fn derived_component(&self, name: &str) -> Option<String>;
+}
+
+impl Message for Request {
+    fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Requests in origin form are assumed to use `https`, with the authority taken
+    /// from the `Host` header.
+    fn derived_component(&self, name: &str) -> Option<String> {
+        self.with_uri(|uri| {
+            let host = || self.header.get(b"Host").map(|host| host.to_string());
+            request_component(name, &String::from_utf8_lossy(uri), || self.method().to_string(), host)
+        })
+    }
+}
+
+/// Derived component `name` of a request to `uri`; `host` is only called for the
+/// authority of an origin-form target, so other components do not need the header.
+fn request_component(
+    name: &str,
+    uri: &str,
+    method: impl FnOnce() -> String,
+    host: impl FnOnce() -> Option<String>,
+) -> Option<String> {
+    let (scheme, authority, target) = match uri.split_once("://") {
+        Some((scheme, rest)) => {
+            let end = rest.find(['/', '?']).unwrap_or(rest.len());
+            (scheme.to_ascii_lowercase(), Some(rest.get(..end)?.to_ascii_lowercase()), rest.get(end..)?)
+        }
+        None => ("https".into(), None, uri),
+    };
+    let authority = || authority.or_else(|| host().map(|host| host.to_ascii_lowercase()));
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    match name {
+        "@method" => Some(method()),
+        "@target-uri" => Some(format!("{scheme}://{}{}{target}", authority()?, if path.is_empty() { "/" } else { "" })),
+        "@authority" => authority(),
+        "@scheme" => Some(scheme),
+        "@request-target" => Some(uri.to_string()),
+        "@path" => Some(if path.is_empty() { "/".into() } else { path.to_string() }),
+        "@query" => Some(format!("?{query}")),
+        _ => None,
+    }
+}
+
+impl Message for Response {
+    fn header(&self) -> &Header {
+        &self.header
+    }
+
+    fn derived_component(&self, name: &str) -> Option<String> {
+        (name == "@status").then(|| self.status().to_string())
+    }
+}
+
+/// Sign the components of `message` listed in `params` with `key` and add the
+/// `Signature-Input` and `Signature` headers under `label`.
+pub fn sign(message: &impl Message, label: &str, params: &SignatureParams, key: &HmacKey) -> Result<(), SignatureError> {
+    let params_value = params.to_string();
+    let base = signature_base(&params.components, &params_value, |name| component_value(message, name))?;
+    let signature = codec::base64_encode(&hmac_sha256(&key.secret, base.as_bytes()), false, true);
+    let header = message.header();
+    header.add(b"Signature-Input", format!("{label}={params_value}").as_bytes());
+    header.add(b"Signature", format!("{label}=:{signature}:").as_bytes());
+    Ok(())
+}
+
+/// Verify the signature labeled `label` with the key named by its `keyid`.
+///
+/// Returns the parameters of the valid signature, so callers can check `created`,
+/// `nonce` or the covered components against their policy.
+pub fn verify(message: &impl Message, label: &str, keys: &[HmacKey]) -> Result<SignatureParams, SignatureError> {
+    let header = message.header();
+    let params_value = dictionary_member(header, b"Signature-Input", label).ok_or(SignatureError::Missing)?;
+    let signature = dictionary_member(header, b"Signature", label).ok_or(SignatureError::Missing)?;
+    let params = SignatureParams::parse(&params_value).ok_or(SignatureError::Malformed)?;
+    match params.alg.as_deref() {
+        None | Some(HMAC_SHA256) => {}
+        Some(alg) => return Err(SignatureError::UnsupportedAlgorithm(alg.into())),
+    }
+    let key = keys.iter().find(|key| params.key_id.as_deref() == Some(&key.key_id)).ok_or(SignatureError::UnknownKey)?;
+    if params.expires.is_some_and(|expires| expires < time::unix_secs()) {
+        return Err(SignatureError::Expired);
+    }
+    let signature = signature
+        .strip_prefix(':')
+        .and_then(|s| s.strip_suffix(':'))
+        .and_then(|s| codec::base64_decode(s.as_bytes(), false).ok())
+        .ok_or(SignatureError::Malformed)?;
+    let base = signature_base(&params.components, &params_value, |name| component_value(message, name))?;
+    match constant_time_eq(&hmac_sha256(&key.secret, base.as_bytes()), &signature) {
+        true => Ok(params),
+        false => Err(SignatureError::Invalid),
+    }
+}
+
+/// Value of a derived component or of a header field, with field lines combined.
+fn component_value(message: &impl Message, name: &str) -> Option<String> {
+    if name.starts_with('@') {
+        return message.derived_component(name);
+    }
+    let values = message.header().values(name.as_bytes());
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().map(|v| v.to_string().trim().to_string()).collect::<Vec<_>>().join(", "))
+}
+
+/// The signature base: one line per component, then `@signature-params`.
+fn signature_base(
+    components: &[String],
+    params_value: &str,
+    value: impl Fn(&str) -> Option<String>,
+) -> Result<String, SignatureError> {
+    let mut base = String::new();
+    for component in components {
+        let missing = || SignatureError::MissingComponent(component.clone());
+        if component.contains(';') || component == "@signature-params" {
+            return Err(missing());
+        }
+        let value = value(component).ok_or_else(missing)?;
+        let _ = writeln!(base, "\"{component}\": {value}");
+    }
+    let _ = write!(base, "\"@signature-params\": {params_value}");
+    Ok(base)
+}
+
+/// Value of the dictionary member `label` across all lines of the header `name`.
+fn dictionary_member(header: &Header, name: &[u8], label: &str) -> Option<String> {
+    let joined = header.values(name).iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+    split_outside_quotes(&joined, ',').into_iter().find_map(|member| {
+        let (key, value) = member.split_once('=')?;
+        (key.trim() == label).then(|| value.trim().to_string())
+    })
+}
+
+/// Split at `sep` outside of quoted strings and parentheses.
+fn split_outside_quotes(input: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut in_quotes, mut escaped, mut depth, mut start) = (false, false, 0usize, 0);
+    for (i, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes => depth = depth.saturating_sub(1),
+            c if c == sep && !in_quotes && depth == 0 => {
+                parts.push(input.get(start..i).unwrap_or_default());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(input.get(start..).unwrap_or_default());
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test case B.2.5 of RFC 9421.
+    #[test]
+    fn rfc9421_hmac_sha256_example() {
+        let secret = codec::base64_decode(
+            b"uzvJfB4u3N0Jy4T7NZ75MDVcr8zSTInedJtkgcu46YW4XByzNJjxBdtjUkdJPBtbmHhIDi6pcl8jsasjlTMtDQ==",
+            false,
+        )
+        .unwrap();
+        let params_value = r#"("date" "@authority" "content-type");created=1618884473;keyid="test-shared-secret""#;
+        let params = SignatureParams::parse(params_value).unwrap();
+        assert_eq!(params.to_string(), params_value);
+        let base = signature_base(&params.components, params_value, |name| match name {
+            "date" => Some("Tue, 20 Apr 2021 02:07:55 GMT".into()),
+            "@authority" => Some("example.com".into()),
+            "content-type" => Some("application/json".into()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(
+            base,
+            "\"date\": Tue, 20 Apr 2021 02:07:55 GMT\n\"@authority\": example.com\n\"content-type\": application/json\n\
+             \"@signature-params\": (\"date\" \"@authority\" \"content-type\");created=1618884473;keyid=\"test-shared-secret\""
+        );
+        let signature = codec::base64_encode(&hmac_sha256(&secret, base.as_bytes()), false, true);
+        assert_eq!(signature, "pxcQw6G3AjtMBQjwo8XzkZf/bws5LelbaMk5rGIGtE8=");
+    }
+
+    #[test]
+    fn parse_and_serialize_params() {
+        let params =
+            SignatureParams::parse(r#"("@method" "@path");created=1;expires=2;nonce="a;b";alg="hmac-sha256";x=1"#).unwrap();
+        assert_eq!(params.components, ["@method", "@path"]);
+        assert_eq!((params.created, params.expires), (Some(1), Some(2)));
+        assert_eq!(params.nonce.as_deref(), Some("a;b"));
+        assert_eq!(params.to_string(), r#"("@method" "@path");created=1;expires=2;nonce="a;b";alg="hmac-sha256""#);
+        assert_eq!(SignatureParams::parse("();created=1").map(|p| p.components.len()), Some(0));
+        assert_eq!(SignatureParams::parse(r#""@method";created=1"#), None);
+        assert_eq!(SignatureParams::parse(r#"("@method");created=x"#), None);
+    }
+
+    #[test]
+    fn unsupported_components() {
+        let value = |_: &str| Some(String::new());
+        let components = ["@query-param;name=\"id\"".to_string()];
+        assert_eq!(
+            signature_base(&components, "()", value),
+            Err(SignatureError::MissingComponent("@query-param;name=\"id\"".into()))
+        );
+        assert_eq!(
+            signature_base(&["x-missing".into()], "()", |_| None),
+            Err(SignatureError::MissingComponent("x-missing".into()))
+        );
+    }
+
+    #[test]
+    fn request_components() {
+        // The mock request is "GET https://test" without headers
+        let request = Request::new();
+        assert_eq!(component_value(&request, "@method").as_deref(), Some("GET"));
+        assert_eq!(component_value(&request, "@target-uri").as_deref(), Some("https://test/"));
+        assert_eq!(component_value(&request, "@authority").as_deref(), Some("test"));
+        assert_eq!(component_value(&request, "@path").as_deref(), Some("/"));
+        assert_eq!(component_value(&request, "@query").as_deref(), Some("?"));
+        assert_eq!(component_value(&request, "@status"), None);
+        assert_eq!(component_value(&request, "host"), None);
+        assert_eq!(verify(&request, "sig1", &[]), Err(SignatureError::Missing));
+    }
+
+    #[test]
+    fn origin_form_components_without_host() {
+        let component = |name, host: Option<&str>| request_component(name, "/a?b=1", || "POST".into(), || host.map(Into::into));
+        assert_eq!(component("@method", None).as_deref(), Some("POST"));
+        assert_eq!(component("@path", None).as_deref(), Some("/a"));
+        assert_eq!(component("@query", None).as_deref(), Some("?b=1"));
+        assert_eq!(component("@scheme", None).as_deref(), Some("https"));
+        assert_eq!(component("@request-target", None).as_deref(), Some("/a?b=1"));
+        assert_eq!(component("@authority", None), None);
+        assert_eq!(component("@target-uri", None), None);
+        assert_eq!(component("@authority", Some("Example.COM")).as_deref(), Some("example.com"));
+        assert_eq!(component("@target-uri", Some("example.com")).as_deref(), Some("https://example.com/a?b=1"));
+    }
+
+    #[test]
+    fn dictionary_members() {
+        let input = r#"sig1=("@method");keyid="a,b", sig2=("@path" "x")"#;
+        let members = split_outside_quotes(input, ',');
+        assert_eq!(members, [r#"sig1=("@method");keyid="a,b""#, r#" sig2=("@path" "x")"#]);
+    }
+}
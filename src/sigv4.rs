@@ -0,0 +1,449 @@
+//! Verification of AWS Signature Version 4 style request signatures.
+//!
+//! SigV4 signs a canonical form of the request with a key derived from a shared
+//! secret, the date, the region and the service. The canonical request builder and
+//! the key derivation are exposed separately, so plugins can also verify schemes that
+//! follow SigV4 with other names, e.g. a different algorithm label or date header.
+//! Only the `Authorization` header form is supported, not presigned URLs.
+//!
+//! ```no_run
+//! use http_wasm_guest::{
+//!     Decision,
+//!     host::{Request, Response},
+//!     sigv4::{SigV4, SigV4Config},
+//! };
+//!
+//! fn authenticate(request: &Request, response: &Response) -> Decision {
+//!     let sigv4 = SigV4::new(SigV4Config { service: "orders".into(), region: "eu-central-1".into(), ..Default::default() });
+//!     match sigv4.verify(request, |access_key_id| (access_key_id == "AKIDEXAMPLE").then(|| "secret".to_string())) {
+//!         Ok(_) => Decision::Continue(0),
+//!         Err(_) => response.deny(403, "invalid signature"),
+//!     }
+//! }
+//! ```
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    codec::{hex_encode, percent_decode, percent_encode},
+    digest::{constant_time_eq, hmac_sha256, sha256},
+    headers::days_from_civil,
+    host::{Request, time},
+};
+
+/// Configuration of the [`SigV4`] verifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigV4Config {
+    /// Service name expected in the credential scope.
+    pub service: String,
+    /// Region expected in the credential scope.
+    pub region: String,
+    /// Headers that must be signed, in lowercase.
+    pub required_headers: Vec<String>,
+    /// Maximum difference between the request date and the current time.
+    pub max_skew_secs: u64,
+    /// Algorithm label at the start of `Authorization` and the string to sign.
+    pub algorithm: String,
+    /// Prefix of the secret for the first key derivation step.
+    pub key_prefix: String,
+    /// Last element of the credential scope.
+    pub terminator: String,
+    /// Header with the request date in `YYYYMMDD'T'HHMMSS'Z'` form.
+    pub date_header: String,
+    /// Header with the hex SHA-256 of the payload or `UNSIGNED-PAYLOAD`. When present it
+    /// must be signed, and a hash is checked against the buffered body; without it the
+    /// body is hashed.
+    pub content_hash_header: Option<String>,
+    /// URI-encode the path twice, as all AWS services except S3 do.
+    pub double_encode_path: bool,
+}
+
+impl Default for SigV4Config {
+    fn default() -> Self {
+        Self {
+            service: String::new(),
+            region: String::new(),
+            required_headers: ["host", "x-amz-date"].map(String::from).to_vec(),
+            max_skew_secs: 15 * 60,
+            algorithm: "AWS4-HMAC-SHA256".into(),
+            key_prefix: "AWS4".into(),
+            terminator: "aws4_request".into(),
+            date_header: "X-Amz-Date".into(),
+            content_hash_header: Some("X-Amz-Content-Sha256".into()),
+            double_encode_path: true,
+        }
+    }
+}
+
+/// Credential of a verified request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credential {
+    /// Access key identifying the client.
+    pub access_key_id: String,
+    /// Date of the credential scope, `YYYYMMDD`.
+    pub date: String,
+    /// Region of the credential scope.
+    pub region: String,
+    /// Service of the credential scope.
+    pub service: String,
+}
+
+/// Errors returned when verifying a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SigV4Error {
+    /// The request has no `Authorization` header for the configured algorithm.
+    Missing,
+    /// The `Authorization` or date header is not valid.
+    Malformed,
+    /// The credential scope does not match the configured region and service.
+    ScopeMismatch,
+    /// A required header is not signed or a signed header is absent.
+    MissingHeader(String),
+    /// No secret is known for the access key.
+    UnknownKey,
+    /// The request date is too far from the current time.
+    ClockSkew,
+    /// The signature does not match the request.
+    Invalid,
+}
+
+impl Display for SigV4Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SigV4Error::Missing => write!(f, "missing authorization"),
+            SigV4Error::Malformed => write!(f, "malformed authorization"),
+            SigV4Error::ScopeMismatch => write!(f, "credential scope mismatch"),
+            SigV4Error::MissingHeader(name) => write!(f, "header `{name}` is not signed"),
+            SigV4Error::UnknownKey => write!(f, "unknown access key"),
+            SigV4Error::ClockSkew => write!(f, "request date out of range"),
+            SigV4Error::Invalid => write!(f, "invalid signature"),
+        }
+    }
+}
+
+impl Error for SigV4Error {}
+
+/// Build the canonical request of SigV4.
+///
+/// `uri` is the request target as received; `headers` are the signed headers with
+/// their values, with several field lines already combined by `,`.
+pub fn canonical_request(
+    method: &str,
+    uri: &str,
+    headers: &[(String, String)],
+    payload_hash: &str,
+    double_encode_path: bool,
+) -> String {
+    let target = match uri.split_once("://") {
+        Some((_, rest)) => rest.find(['/', '?']).and_then(|i| rest.get(i..)).unwrap_or_default(),
+        None => uri,
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut path = percent_encode(&percent_decode(path.as_bytes(), false), true);
+    if double_encode_path {
+        path = percent_encode(path.as_bytes(), true);
+    }
+    if path.is_empty() {
+        path.push('/');
+    }
+    let mut params: Vec<(String, String)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let encode = |s: &str| percent_encode(&percent_decode(s.as_bytes(), false), false);
+            (encode(key), encode(value))
+        })
+        .collect();
+    params.sort();
+    let query = params.iter().map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>().join("&");
+    let mut headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.split_whitespace().collect::<Vec<_>>().join(" ")))
+        .collect();
+    headers.sort();
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{name}:{value}\n")).collect();
+    let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+    format!("{method}\n{path}\n{query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}")
+}
+
+/// Payload hash of requests whose body is not signed.
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Method, target and header values of a request to verify.
+struct Target<'a> {
+    method: &'a str,
+    uri: &'a str,
+    header: &'a dyn Fn(&str) -> Vec<String>,
+}
+
+/// Verifier for SigV4 signed requests.
+pub struct SigV4 {
+    config: SigV4Config,
+}
+
+impl SigV4 {
+    /// Create a verifier with the given configuration.
+    pub fn new(config: SigV4Config) -> Self {
+        Self { config }
+    }
+
+    /// Derive the signing key for `secret` and the scope date `YYYYMMDD`.
+    pub fn signing_key(&self, secret: &str, date: &str) -> [u8; 32] {
+        let key = format!("{}{secret}", self.config.key_prefix);
+        [&self.config.region, &self.config.service, &self.config.terminator]
+            .iter()
+            .fold(hmac_sha256(key.as_bytes(), date.as_bytes()), |key, part| hmac_sha256(&key, part.as_bytes()))
+    }
+
+    /// Hex signature of `canonical_request` for the request date `YYYYMMDD'T'HHMMSS'Z'`.
+    pub fn signature(&self, secret: &str, request_date: &str, canonical_request: &str) -> String {
+        let date = request_date.get(..8).unwrap_or_default();
+        let string_to_sign = format!(
+            "{}\n{request_date}\n{}\n{}",
+            self.config.algorithm,
+            self.scope(date),
+            hex_encode(&sha256(canonical_request.as_bytes()))
+        );
+        hex_encode(&hmac_sha256(&self.signing_key(secret, date), string_to_sign.as_bytes()))
+    }
+
+    /// Verify the `Authorization` header of `request`, looking up the secret of the
+    /// access key with `secret`.
+    ///
+    /// The body is hashed unless the content hash header is `UNSIGNED-PAYLOAD`, which
+    /// requires `feature::BufferRequest`. A content hash header must be signed and
+    /// match the body.
+    pub fn verify(&self, request: &Request, secret: impl Fn(&str) -> Option<String>) -> Result<Credential, SigV4Error> {
+        let header = |name: &str| request.header.values(name.as_bytes()).iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let target = Target { method: &request.method().to_string(), uri: &request.uri().to_string(), header: &header };
+        self.verify_parts(&target, || request.body.sha256(), time::unix_secs(), secret)
+    }
+
+    fn verify_parts(
+        &self,
+        target: &Target<'_>,
+        body_sha256: impl FnOnce() -> [u8; 32],
+        now_secs: u64,
+        secret: impl Fn(&str) -> Option<String>,
+    ) -> Result<Credential, SigV4Error> {
+        let first = |name: &str| (target.header)(name).into_iter().next();
+        let authorization = first("Authorization").ok_or(SigV4Error::Missing)?;
+        let fields = authorization.strip_prefix(&self.config.algorithm).and_then(|rest| rest.strip_prefix(' '));
+        let (credential, signed_headers, signature) =
+            parse_authorization(fields.ok_or(SigV4Error::Missing)?, &self.config.terminator)?;
+        if credential.region != self.config.region || credential.service != self.config.service {
+            return Err(SigV4Error::ScopeMismatch);
+        }
+        let is_signed = |name: &str| signed_headers.iter().any(|s| s.eq_ignore_ascii_case(name));
+        if let Some(name) = self.config.required_headers.iter().find(|name| !is_signed(name)) {
+            return Err(SigV4Error::MissingHeader(name.clone()));
+        }
+        let request_date = first(&self.config.date_header).ok_or(SigV4Error::Malformed)?;
+        let timestamp = parse_request_date(&request_date).ok_or(SigV4Error::Malformed)?;
+        if !request_date.starts_with(&credential.date) {
+            return Err(SigV4Error::Malformed);
+        }
+        if timestamp.abs_diff(now_secs) > self.config.max_skew_secs {
+            return Err(SigV4Error::ClockSkew);
+        }
+        let headers = signed_headers
+            .iter()
+            .map(|name| {
+                let values = (target.header)(name);
+                match values.is_empty() {
+                    true => Err(SigV4Error::MissingHeader(name.clone())),
+                    false => Ok((name.clone(), values.iter().map(|v| v.trim()).collect::<Vec<_>>().join(","))),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let content_hash = self.config.content_hash_header.as_ref().and_then(|name| Some((name, first(name)?)));
+        let payload_hash = match content_hash {
+            Some((name, _)) if !is_signed(name) => return Err(SigV4Error::MissingHeader(name.to_ascii_lowercase())),
+            Some((_, hash)) if hash == UNSIGNED_PAYLOAD => hash,
+            Some((_, hash)) => {
+                // a signed hash only covers the body if it is checked against it
+                if !hash.eq_ignore_ascii_case(&hex_encode(&body_sha256())) {
+                    return Err(SigV4Error::Invalid);
+                }
+                hash
+            }
+            None => hex_encode(&body_sha256()),
+        };
+        let secret = secret(&credential.access_key_id).ok_or(SigV4Error::UnknownKey)?;
+        let canonical = canonical_request(target.method, target.uri, &headers, &payload_hash, self.config.double_encode_path);
+        match constant_time_eq(self.signature(&secret, &request_date, &canonical).as_bytes(), signature.as_bytes()) {
+            true => Ok(credential),
+            false => Err(SigV4Error::Invalid),
+        }
+    }
+
+    /// Credential scope for `date`.
+    fn scope(&self, date: &str) -> String {
+        format!("{date}/{}/{}/{}", self.config.region, self.config.service, self.config.terminator)
+    }
+}
+
+/// Credential, signed headers and signature of the `Authorization` fields.
+fn parse_authorization(fields: &str, terminator: &str) -> Result<(Credential, Vec<String>, String), SigV4Error> {
+    let field = |name: &str| {
+        fields
+            .split(',')
+            .find_map(|field| field.trim().strip_prefix(name).and_then(|f| f.strip_prefix('=')))
+            .ok_or(SigV4Error::Malformed)
+    };
+    let mut scope = field("Credential")?.split('/');
+    let mut part = || scope.next().map(str::to_string).ok_or(SigV4Error::Malformed);
+    let credential = Credential { access_key_id: part()?, date: part()?, region: part()?, service: part()? };
+    if part()? != terminator || scope.next().is_some() {
+        return Err(SigV4Error::ScopeMismatch);
+    }
+    let signed_headers = field("SignedHeaders")?.split(';').map(str::to_ascii_lowercase).collect();
+    Ok((credential, signed_headers, field("Signature")?.to_string()))
+}
+
+/// Seconds since the Unix epoch of a `YYYYMMDD'T'HHMMSS'Z'` date.
+fn parse_request_date(date: &str) -> Option<u64> {
+    let bytes = date.as_bytes();
+    if bytes.len() != 16 || bytes.get(8) != Some(&b'T') || bytes.get(15) != Some(&b'Z') {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| date.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (number(0..4)?, number(4..6)?, number(6..8)?);
+    let (h, m, s) = (number(9..11)?, number(11..13)?, number(13..15)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || h > 23 || m > 59 || s > 60 {
+        return None;
+    }
+    u64::try_from(days_from_civil(year, month, day) * 86400 + h * 3600 + m * 60 + s).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    /// Example of the AWS documentation for signing an IAM `ListUsers` request.
+    #[test]
+    fn aws_documentation_example() {
+        let headers = [
+            ("Content-Type".to_string(), "application/x-www-form-urlencoded;  charset=utf-8".to_string()),
+            ("Host".to_string(), "iam.amazonaws.com".to_string()),
+            ("X-Amz-Date".to_string(), "20150830T123600Z".to_string()),
+        ];
+        let canonical = canonical_request("GET", "/?Version=2010-05-08&Action=ListUsers", &headers, EMPTY_SHA256, true);
+        assert_eq!(
+            canonical,
+            "GET\n/\nAction=ListUsers&Version=2010-05-08\ncontent-type:application/x-www-form-urlencoded; charset=utf-8\n\
+             host:iam.amazonaws.com\nx-amz-date:20150830T123600Z\n\ncontent-type;host;x-amz-date\n"
+                .to_string()
+                + EMPTY_SHA256
+        );
+        let sigv4 = SigV4::new(SigV4Config { service: "iam".into(), region: "us-east-1".into(), ..Default::default() });
+        assert_eq!(
+            hex_encode(&sigv4.signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20150830")),
+            "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9"
+        );
+        assert_eq!(
+            sigv4.signature("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20150830T123600Z", &canonical),
+            "5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn canonical_path_and_query() {
+        let canonical = |uri: &str, double| canonical_request("GET", uri, &[], "-", double);
+        assert!(canonical("https://test", true).starts_with("GET\n/\n\n"));
+        assert!(canonical("/a%20b/c d?b=2&a=x%2By&a", false).starts_with("GET\n/a%20b/c%20d\na=&a=x%2By&b=2\n"));
+        assert!(canonical("/a%20b", true).starts_with("GET\n/a%2520b\n"));
+    }
+
+    #[test]
+    fn parse_authorization_fields() {
+        let (credential, signed, signature) = parse_authorization(
+            "Credential=AKID/20150830/us-east-1/iam/aws4_request, SignedHeaders=Host;x-amz-date, Signature=abc",
+            "aws4_request",
+        )
+        .unwrap();
+        assert_eq!(credential.access_key_id, "AKID");
+        assert_eq!(
+            (credential.date.as_str(), credential.region.as_str(), credential.service.as_str()),
+            ("20150830", "us-east-1", "iam")
+        );
+        assert_eq!(signed, ["host", "x-amz-date"]);
+        assert_eq!(signature, "abc");
+        let fields = |credential: &str| format!("Credential={credential}, SignedHeaders=host, Signature=a");
+        assert_eq!(parse_authorization(&fields("AKID/20150830"), "aws4_request").err(), Some(SigV4Error::Malformed));
+        assert_eq!(
+            parse_authorization(&fields("AKID/20150830/us-east-1/iam/oss4_request"), "aws4_request").err(),
+            Some(SigV4Error::ScopeMismatch)
+        );
+    }
+
+    #[test]
+    fn request_dates() {
+        assert_eq!(parse_request_date("20150830T123600Z"), Some(1440938160));
+        assert_eq!(parse_request_date("2015-08-30T12:36:00Z"), None);
+        assert_eq!(parse_request_date("20151330T123600Z"), None);
+    }
+
+    /// Sign a `PUT /upload` request with the `signed` headers, add the `extra` headers
+    /// and verify it against `received_body`.
+    fn verify_signed(signed: &[(&str, &str)], extra: &[(&str, &str)], received_body: &[u8]) -> Result<Credential, SigV4Error> {
+        let sigv4 = SigV4::new(SigV4Config { service: "svc".into(), region: "eu-1".into(), ..Default::default() });
+        let date = "20231114T221320Z";
+        let mut headers: Vec<(String, String)> = [("host", "example.com"), ("x-amz-date", date)]
+            .iter()
+            .chain(signed)
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect();
+        let payload = signed.iter().find(|(n, _)| *n == "x-amz-content-sha256").map_or(EMPTY_SHA256, |(_, v)| v);
+        let canonical = canonical_request("PUT", "/upload", &headers, payload, true);
+        let names = {
+            let mut names = headers.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>();
+            names.sort();
+            names.join(";")
+        };
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential=AKID/20231114/eu-1/svc/aws4_request, SignedHeaders={names}, Signature={}",
+            sigv4.signature("secret", date, &canonical)
+        );
+        headers.push(("authorization".into(), authorization));
+        headers.extend(extra.iter().map(|(n, v)| (n.to_string(), v.to_string())));
+        let header = |name: &str| headers.iter().filter(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.clone()).collect();
+        let target = Target { method: "PUT", uri: "/upload", header: &header };
+        sigv4.verify_parts(&target, || sha256(received_body), 1_700_000_000, |_| Some("secret".into()))
+    }
+
+    #[test]
+    fn verify_signed_payload_hash() {
+        let hash = hex_encode(&sha256(b"payload"));
+        let signed = [("x-amz-content-sha256", hash.as_str())];
+        assert_eq!(verify_signed(&signed, &[], b"payload").map(|c| c.access_key_id), Ok("AKID".into()));
+        assert_eq!(verify_signed(&signed, &[], b"tampered").err(), Some(SigV4Error::Invalid));
+    }
+
+    #[test]
+    fn verify_hashes_body_without_content_hash() {
+        assert!(verify_signed(&[], &[], b"").is_ok());
+        assert_eq!(verify_signed(&[], &[], b"tampered").err(), Some(SigV4Error::Invalid));
+    }
+
+    #[test]
+    fn verify_unsigned_payload() {
+        let signed = [("x-amz-content-sha256", UNSIGNED_PAYLOAD)];
+        assert!(verify_signed(&signed, &[], b"anything").is_ok());
+    }
+
+    #[test]
+    fn verify_requires_signed_content_hash() {
+        let unsigned = [("x-amz-content-sha256", UNSIGNED_PAYLOAD)];
+        assert_eq!(verify_signed(&[], &unsigned, b"").err(), Some(SigV4Error::MissingHeader("x-amz-content-sha256".into())));
+    }
+
+    #[test]
+    fn verify_without_authorization() {
+        // The mock request has no headers
+        let sigv4 = SigV4::new(SigV4Config::default());
+        assert_eq!(sigv4.verify(&Request::new(), |_| None).err(), Some(SigV4Error::Missing));
+    }
+}
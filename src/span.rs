@@ -0,0 +1,213 @@
+//! Lightweight spans that log begin and end events with their duration.
+//!
+//! A span measures a piece of work, e.g. an authentication step, and writes two
+//! `logfmt` lines through [`host::log::write`](crate::host::log::write):
+//!
+//! ```text
+//! span=auth event=begin id=2 parent=1 user=42
+//! span=auth event=end id=2 parent=1 duration_us=1520 user=42 outcome=ok
+//! ```
+//!
+//! Spans started while another span is entered record it as their parent. Nothing is
+//! formatted if the host has disabled the span's level.
+//!
+//! ```no_run
+//! use http_wasm_guest::span;
+//!
+//! let mut auth = span!("auth", user = 42).enter();
+//! // ... verify credentials
+//! auth.record("outcome", "ok");
+//! drop(auth); // logs the end event
+//!
+//! let valid = span!("validate").in_scope(|| true);
+//! ```
+use std::{
+    cell::{Cell, RefCell},
+    fmt::{Display, Write},
+};
+
+use crate::host::{log, time};
+
+thread_local! {
+    static NEXT_ID: Cell<u64> = const { Cell::new(1) };
+    static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Create a [`Span`] with the given name and optional `key = value` fields.
+///
+/// ```no_run
+/// use http_wasm_guest::span;
+///
+/// let user = "jane";
+/// let _span = span!("auth", user = user, method = "basic").enter();
+/// ```
+#[macro_export]
+macro_rules! span {
+    ($name:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        $crate::span::Span::new($name)$(.field(stringify!($key), $value))*
+    };
+}
+
+/// A span that has not been entered yet; collects the fields of the begin event.
+#[derive(Debug, Clone)]
+#[must_use = "a span only logs once it is entered"]
+pub struct Span {
+    name: &'static str,
+    level: i32,
+    fields: String,
+}
+
+impl Span {
+    /// Create a span logged at info level (`0`).
+    pub fn new(name: &'static str) -> Self {
+        Self { name, level: 0, fields: String::new() }
+    }
+
+    /// Set the host log level of the events: debug=−1, info=0, warn=1, error=2.
+    pub fn level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Add a field to the begin and end events.
+    pub fn field(mut self, key: &str, value: impl Display) -> Self {
+        push_field(&mut self.fields, key, value);
+        self
+    }
+
+    /// Log the begin event and start measuring.
+    ///
+    /// The end event is logged when the returned guard is dropped.
+    pub fn enter(self) -> EnteredSpan {
+        let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+        let parent = ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            let parent = entered.last().copied();
+            entered.push(id);
+            parent
+        });
+        let span = EnteredSpan { span: self, id, parent, start: time::monotonic_nanos(), recorded: String::new() };
+        span.emit("begin", None);
+        span
+    }
+
+    /// Run `f` inside the span.
+    pub fn in_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        let _entered = self.enter();
+        f()
+    }
+}
+
+/// A running span, returned by [`Span::enter`]; logs the end event when dropped.
+#[derive(Debug)]
+pub struct EnteredSpan {
+    span: Span,
+    id: u64,
+    parent: Option<u64>,
+    start: u64,
+    recorded: String,
+}
+
+impl EnteredSpan {
+    /// Add a field to the end event, e.g. the outcome of the work.
+    pub fn record(&mut self, key: &str, value: impl Display) {
+        push_field(&mut self.recorded, key, value);
+    }
+
+    /// The ID of the span, unique within the plugin instance.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Time since the span was entered.
+    pub fn elapsed_micros(&self) -> u64 {
+        time::monotonic_nanos().saturating_sub(self.start) / 1000
+    }
+
+    fn emit(&self, event: &str, duration_us: Option<u64>) {
+        if !log::enabled(self.span.level) {
+            return;
+        }
+        let mut line = format!("span={} event={event} id={}", Logfmt(self.span.name), self.id);
+        if let Some(parent) = self.parent {
+            let _ = write!(line, " parent={parent}");
+        }
+        if let Some(duration_us) = duration_us {
+            let _ = write!(line, " duration_us={duration_us}");
+        }
+        line.push_str(&self.span.fields);
+        if duration_us.is_some() {
+            line.push_str(&self.recorded);
+        }
+        log::write(self.span.level, line.as_bytes());
+    }
+}
+
+impl Drop for EnteredSpan {
+    fn drop(&mut self) {
+        ENTERED.with(|entered| entered.borrow_mut().retain(|id| *id != self.id));
+        self.emit("end", Some(self.elapsed_micros()));
+    }
+}
+
+fn push_field(fields: &mut String, key: &str, value: impl Display) {
+    let _ = write!(fields, " {}={}", Logfmt(key), Logfmt(value));
+}
+
+/// Formats a value for `logfmt`, quoting it if it is empty or contains spaces, quotes,
+/// `=` or control characters.
+pub(crate) struct Logfmt<T>(pub(crate) T);
+
+impl<T: Display> Display for Logfmt<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.0.to_string();
+        match value.is_empty() || value.chars().any(|c| c <= ' ' || c == '"' || c == '=' || c.is_control()) {
+            true => write!(f, "{value:?}"),
+            false => f.write_str(&value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn macro_collects_fields() {
+        let span = span!("auth", user = 42, name = "jane doe");
+        assert_eq!(span.name, "auth");
+        assert_eq!(span.fields, " user=42 name=\"jane doe\"");
+        assert_eq!(span!("plain").fields, "");
+    }
+
+    #[test]
+    fn nested_spans_record_parent() {
+        let outer = Span::new("outer").enter();
+        let inner = Span::new("inner").enter();
+        assert_eq!(inner.parent, Some(outer.id()));
+        assert!(inner.id() > outer.id());
+        drop(inner);
+        let sibling = Span::new("sibling").enter();
+        assert_eq!(sibling.parent, Some(outer.id()));
+        drop(sibling);
+        drop(outer);
+        assert_eq!(Span::new("root").enter().parent, None);
+    }
+
+    #[test]
+    fn record_and_in_scope() {
+        let mut span = span!("work").level(-1).enter();
+        span.record("outcome", "");
+        assert_eq!(span.recorded, " outcome=\"\"");
+        drop(span);
+        assert_eq!(Span::new("scoped").in_scope(|| 7), 7);
+        assert!(ENTERED.with(|entered| entered.borrow().is_empty()));
+    }
+
+    #[test]
+    fn logfmt_quoting() {
+        assert_eq!(Logfmt("plain").to_string(), "plain");
+        assert_eq!(Logfmt("a=b").to_string(), "\"a=b\"");
+        assert_eq!(Logfmt("line\nbreak").to_string(), "\"line\\nbreak\"");
+    }
+}
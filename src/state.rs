@@ -0,0 +1,146 @@
+//! Mutable per-plugin state.
+//!
+//! [`Guest`](crate::Guest) handlers take `&self`, so state that changes between
+//! requests needs interior mutability. A WASM guest runs on a single thread and
+//! [`register`](crate::register) keeps the guest on that thread, so no locks or atomics
+//! are needed: [`GuestState`] wraps the value in a [`RefCell`] and hands it out through
+//! closures, which keeps borrows from outliving a call.
+//!
+//! ```no_run
+//! use http_wasm_guest::{
+//!     Guest, GuestState,
+//!     host::{Request, Response},
+//!     register,
+//! };
+//!
+//! #[derive(Default)]
+//! struct Plugin {
+//!     requests: GuestState<u64>,
+//! }
+//!
+//! impl Guest for Plugin {
+//!     fn handle_request(&self, request: &Request, _response: &Response) -> (bool, i32) {
+//!         let count = self.requests.update(|n| n + 1);
+//!         request.header.set(b"X-Request-Count", count.to_string().as_bytes());
+//!         (true, 0)
+//!     }
+//! }
+//!
+//! fn main() {
+//!     register(Plugin::default());
+//! }
+//! ```
+use std::{cell::RefCell, fmt};
+
+/// Interior-mutable state owned by a guest plugin.
+///
+/// Accessing the state again from inside [`with_mut`](Self::with_mut), or mutably from
+/// inside [`with`](Self::with), panics like a [`RefCell`] does.
+#[derive(Default)]
+pub struct GuestState<T> {
+    value: RefCell<T>,
+}
+
+impl<T> GuestState<T> {
+    /// Create a new state holding `value`.
+    pub const fn new(value: T) -> Self {
+        Self { value: RefCell::new(value) }
+    }
+
+    /// Call `f` with a shared reference to the state.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.value.borrow())
+    }
+
+    /// Call `f` with a mutable reference to the state.
+    pub fn with_mut<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.value.borrow_mut())
+    }
+
+    /// Replace the state with `value` and return the previous one.
+    pub fn replace(&self, value: T) -> T {
+        self.value.replace(value)
+    }
+
+    /// Consume the wrapper and return the state.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: Copy> GuestState<T> {
+    /// Return a copy of the state.
+    pub fn get(&self) -> T {
+        *self.value.borrow()
+    }
+
+    /// Overwrite the state.
+    pub fn set(&self, value: T) {
+        *self.value.borrow_mut() = value;
+    }
+
+    /// Replace the state with `f(state)` and return the new value.
+    pub fn update(&self, f: impl FnOnce(T) -> T) -> T {
+        let mut value = self.value.borrow_mut();
+        *value = f(*value);
+        *value
+    }
+}
+
+impl<T: Default> GuestState<T> {
+    /// Take the state, leaving the default value in its place.
+    pub fn take(&self) -> T {
+        self.value.take()
+    }
+}
+
+impl<T> From<T> for GuestState<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for GuestState<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value.try_borrow() {
+            Ok(value) => f.debug_tuple("GuestState").field(&*value).finish(),
+            Err(_) => f.write_str("GuestState(<borrowed>)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_accessors() {
+        let state = GuestState::new(1);
+        assert_eq!(state.update(|n| n + 1), 2);
+        state.set(5);
+        assert_eq!(state.get(), 5);
+    }
+
+    #[test]
+    fn closure_access() {
+        let state = GuestState::new(vec![1, 2]);
+        state.with_mut(|v| v.push(3));
+        assert_eq!(state.with(|v| v.len()), 3);
+        assert_eq!(state.replace(vec![]), [1, 2, 3]);
+        assert_eq!(state.into_inner(), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn take_leaves_default() {
+        let state = GuestState::from(String::from("a"));
+        assert_eq!(state.take(), "a");
+        assert_eq!(state.with(|s| s.clone()), "");
+    }
+
+    #[test]
+    fn debug_while_borrowed() {
+        let state = GuestState::new(7);
+        assert_eq!(format!("{state:?}"), "GuestState(7)");
+        state.with_mut(|_| assert_eq!(format!("{state:?}"), "GuestState(<borrowed>)"));
+    }
+}
@@ -0,0 +1,513 @@
+//! Parsing of TLS client certificates forwarded by a proxy.
+//!
+//! When TLS is terminated in front of the plugin, proxies forward the client
+//! certificate in a request header. Traefik's `PassTLSClientCert` middleware uses
+//! either URL-encoded PEM in `X-Forwarded-Tls-Client-Cert` or a summary in
+//! `X-Forwarded-Tls-Client-Cert-Info`; nginx-style setups use `ssl-client-cert`.
+//! [`ClientCert`] parses all of these into the fields needed for mTLS authorization.
+//!
+//! ```no_run
+//! use http_wasm_guest::{host::Request, tls::ClientCert};
+//!
+//! fn authorized(request: &Request) -> bool {
+//!     let certs = ClientCert::from_request(request).unwrap_or_default();
+//!     certs.first().and_then(ClientCert::common_name) == Some("client.example.com")
+//! }
+//! ```
+use std::{
+    error::Error,
+    fmt::Display,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{
+    codec::{base64_decode, percent_decode},
+    headers::days_from_civil,
+    host::Request,
+};
+
+/// Header set by Traefik containing the URL-encoded PEM certificate chain.
+pub const HEADER_PEM: &[u8] = b"X-Forwarded-Tls-Client-Cert";
+/// Header set by Traefik containing the URL-encoded certificate summary.
+pub const HEADER_INFO: &[u8] = b"X-Forwarded-Tls-Client-Cert-Info";
+/// Header commonly used by nginx-based proxies for the URL-encoded PEM certificate.
+pub const HEADER_SSL_CLIENT_CERT: &[u8] = b"ssl-client-cert";
+
+/// Error returned when a forwarded certificate cannot be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertError {
+    /// The header value is not valid URL-encoded PEM or base64.
+    Encoding,
+    /// The decoded certificate is not a well-formed X.509 DER structure.
+    Malformed,
+}
+
+impl Display for CertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CertError::Encoding => write!(f, "invalid certificate encoding"),
+            CertError::Malformed => write!(f, "malformed certificate"),
+        }
+    }
+}
+
+impl Error for CertError {}
+
+/// Subset of an X.509 client certificate relevant for authorization decisions.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ClientCert {
+    /// Subject distinguished name in certificate order, e.g. `C=US,O=Cheese,CN=client.example.com`.
+    pub subject: String,
+    /// Issuer distinguished name in certificate order.
+    pub issuer: String,
+    /// Subject alternative names (DNS names, emails, IP addresses and URIs).
+    pub sans: Vec<String>,
+    /// Start of the validity period as unix seconds, if known.
+    pub not_before: Option<u64>,
+    /// End of the validity period as unix seconds, if known.
+    pub not_after: Option<u64>,
+}
+
+impl ClientCert {
+    /// Parse the client certificates forwarded with the request.
+    ///
+    /// The PEM headers ([`HEADER_PEM`], [`HEADER_SSL_CLIENT_CERT`]) are preferred over
+    /// [`HEADER_INFO`], because they carry the complete certificate. Returns an empty
+    /// vector if none of the headers is present.
+    pub fn from_request(request: &Request) -> Result<Vec<ClientCert>, CertError> {
+        if let Some(value) = request.header.get(HEADER_PEM).or_else(|| request.header.get(HEADER_SSL_CLIENT_CERT)) {
+            return Self::parse_pem(&value);
+        }
+        match request.header.get(HEADER_INFO) {
+            Some(value) => Self::parse_info(&value),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Parse a URL-encoded PEM header value.
+    ///
+    /// Multiple certificates are separated by `,`. The `BEGIN`/`END` markers and line
+    /// breaks are optional, so both Traefik's stripped format and full PEM are accepted.
+    pub fn parse_pem(value: &[u8]) -> Result<Vec<ClientCert>, CertError> {
+        let decoded = percent_decode(value, false);
+        decoded
+            .split(|b| *b == b',')
+            .filter(|pem| pem.iter().any(|b| !b.is_ascii_whitespace()))
+            .map(|pem| {
+                let der = base64_decode(&strip_pem_markers(pem), false).map_err(|_| CertError::Encoding)?;
+                Self::from_der(&der)
+            })
+            .collect()
+    }
+
+    /// Parse Traefik's URL-encoded certificate info format.
+    ///
+    /// Each certificate is a `;`-separated list of `Key="value"` pairs with the keys
+    /// `Subject`, `Issuer`, `NB`, `NA` and `SAN`; certificates are separated by `,`.
+    /// Unknown keys are ignored.
+    pub fn parse_info(value: &[u8]) -> Result<Vec<ClientCert>, CertError> {
+        let decoded = percent_decode(value, true);
+        let decoded = String::from_utf8(decoded).map_err(|_| CertError::Encoding)?;
+        split_unquoted(&decoded, ',')
+            .into_iter()
+            .filter(|info| !info.trim().is_empty())
+            .map(|info| {
+                let mut cert = ClientCert::default();
+                for pair in split_unquoted(info, ';') {
+                    let (key, value) = pair.split_once('=').ok_or(CertError::Malformed)?;
+                    let value = value.trim().trim_matches('"');
+                    match key.trim() {
+                        "Subject" => cert.subject = value.to_string(),
+                        "Issuer" => cert.issuer = value.to_string(),
+                        "NB" => cert.not_before = Some(value.parse().map_err(|_| CertError::Malformed)?),
+                        "NA" => cert.not_after = Some(value.parse().map_err(|_| CertError::Malformed)?),
+                        "SAN" => cert.sans = value.split(',').filter(|s| !s.is_empty()).map(str::to_string).collect(),
+                        _ => {}
+                    }
+                }
+                Ok(cert)
+            })
+            .collect()
+    }
+
+    /// Parse a DER-encoded X.509 certificate.
+    pub fn from_der(der: &[u8]) -> Result<ClientCert, CertError> {
+        let mut cert = Der(der).expect(SEQUENCE)?;
+        let mut tbs = cert.expect(SEQUENCE)?;
+        if tbs.peek() == Some(CONTEXT_0) {
+            tbs.next()?; // version
+        }
+        tbs.expect(INTEGER)?; // serial number
+        tbs.expect(SEQUENCE)?; // signature algorithm
+        let issuer = format_name(tbs.expect(SEQUENCE)?)?;
+        let mut validity = tbs.expect(SEQUENCE)?;
+        let not_before = parse_time(validity.next()?)?;
+        let not_after = parse_time(validity.next()?)?;
+        let subject = format_name(tbs.expect(SEQUENCE)?)?;
+        tbs.expect(SEQUENCE)?; // subject public key info
+
+        let mut sans = Vec::new();
+        while !tbs.is_empty() {
+            let (tag, mut content) = tbs.next()?;
+            if tag == CONTEXT_3 {
+                sans = parse_extensions(content.expect(SEQUENCE)?)?;
+            }
+        }
+        Ok(ClientCert { subject, issuer, sans, not_before: Some(not_before), not_after: Some(not_after) })
+    }
+
+    /// Return the value of the first `CN` attribute of the subject.
+    pub fn common_name(&self) -> Option<&str> {
+        self.subject.split(',').find_map(|rdn| rdn.trim().strip_prefix("CN="))
+    }
+
+    /// Returns `true` if `unix_secs` lies within the validity period.
+    ///
+    /// Missing bounds are treated as unrestricted.
+    pub fn is_valid_at(&self, unix_secs: u64) -> bool {
+        self.not_before.is_none_or(|nb| nb <= unix_secs) && self.not_after.is_none_or(|na| unix_secs <= na)
+    }
+}
+
+// --- DER decoding ---
+
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const OID: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+const BMP_STRING: u8 = 0x1E;
+const CONTEXT_0: u8 = 0xA0;
+const CONTEXT_3: u8 = 0xA3;
+
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1D, 0x11];
+
+/// Minimal reader over a sequence of DER TLV elements.
+#[derive(Clone, Copy)]
+struct Der<'a>(&'a [u8]);
+
+impl<'a> Der<'a> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    /// Read the next element, returning its tag and a reader over its content.
+    fn next(&mut self) -> Result<(u8, Der<'a>), CertError> {
+        let (&tag, rest) = self.0.split_first().ok_or(CertError::Malformed)?;
+        let (&first, mut rest) = rest.split_first().ok_or(CertError::Malformed)?;
+        let len = if first < 0x80 {
+            first as usize
+        } else {
+            let n = (first & 0x7F) as usize;
+            if n == 0 || n > 4 {
+                return Err(CertError::Malformed);
+            }
+            let (bytes, tail) = rest.split_at_checked(n).ok_or(CertError::Malformed)?;
+            rest = tail;
+            bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize)
+        };
+        let (content, rest) = rest.split_at_checked(len).ok_or(CertError::Malformed)?;
+        self.0 = rest;
+        Ok((tag, Der(content)))
+    }
+
+    /// Read the next element and verify its tag.
+    fn expect(&mut self, tag: u8) -> Result<Der<'a>, CertError> {
+        match self.next()? {
+            (t, content) if t == tag => Ok(content),
+            _ => Err(CertError::Malformed),
+        }
+    }
+}
+
+/// Format an X.501 `Name` as comma-separated `KEY=value` pairs.
+fn format_name(mut name: Der) -> Result<String, CertError> {
+    let mut out = String::new();
+    while !name.is_empty() {
+        let mut rdn = name.expect(SET)?;
+        while !rdn.is_empty() {
+            let mut attr = rdn.expect(SEQUENCE)?;
+            let oid = attr.expect(OID)?;
+            let (tag, value) = attr.next()?;
+            if !out.is_empty() {
+                out.push(',');
+            }
+            match attribute_name(oid.0) {
+                Some(key) => out.push_str(key),
+                None => out.push_str(&format_oid(oid.0)),
+            }
+            out.push('=');
+            out.push_str(&decode_string(tag, value.0));
+        }
+    }
+    Ok(out)
+}
+
+fn attribute_name(oid: &[u8]) -> Option<&'static str> {
+    match oid {
+        [0x55, 0x04, 0x03] => Some("CN"),
+        [0x55, 0x04, 0x05] => Some("SERIALNUMBER"),
+        [0x55, 0x04, 0x06] => Some("C"),
+        [0x55, 0x04, 0x07] => Some("L"),
+        [0x55, 0x04, 0x08] => Some("ST"),
+        [0x55, 0x04, 0x09] => Some("STREET"),
+        [0x55, 0x04, 0x0A] => Some("O"),
+        [0x55, 0x04, 0x0B] => Some("OU"),
+        [0x09, 0x92, 0x26, 0x89, 0x93, 0xF2, 0x2C, 0x64, 0x01, 0x19] => Some("DC"),
+        [0x09, 0x92, 0x26, 0x89, 0x93, 0xF2, 0x2C, 0x64, 0x01, 0x01] => Some("UID"),
+        [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x01] => Some("emailAddress"),
+        _ => None,
+    }
+}
+
+/// Format an OID in dotted-decimal notation.
+fn format_oid(oid: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut value: u64 = 0;
+    for b in oid {
+        value = (value << 7) | (b & 0x7F) as u64;
+        if b & 0x80 == 0 {
+            if parts.is_empty() {
+                let first = (value / 40).min(2);
+                parts.push(first);
+                parts.push(value - first * 40);
+            } else {
+                parts.push(value);
+            }
+            value = 0;
+        }
+    }
+    parts.iter().map(u64::to_string).collect::<Vec<_>>().join(".")
+}
+
+fn decode_string(tag: u8, value: &[u8]) -> String {
+    if tag == BMP_STRING {
+        let units = value.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]]));
+        return char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect();
+    }
+    String::from_utf8_lossy(value).into_owned()
+}
+
+/// Extract the subject alternative names from the extensions sequence.
+fn parse_extensions(mut extensions: Der) -> Result<Vec<String>, CertError> {
+    let mut sans = Vec::new();
+    while !extensions.is_empty() {
+        let mut ext = extensions.expect(SEQUENCE)?;
+        if ext.expect(OID)?.0 != OID_SUBJECT_ALT_NAME {
+            continue;
+        }
+        let (mut tag, mut value) = ext.next()?;
+        if tag != OCTET_STRING {
+            (tag, value) = ext.next()?; // skip the `critical` flag
+        }
+        if tag != OCTET_STRING {
+            return Err(CertError::Malformed);
+        }
+        let mut names = value.expect(SEQUENCE)?;
+        while !names.is_empty() {
+            let (tag, name) = names.next()?;
+            match tag {
+                0x81 | 0x82 | 0x86 => sans.push(String::from_utf8_lossy(name.0).into_owned()),
+                0x87 => {
+                    if let Ok(v4) = <[u8; 4]>::try_from(name.0) {
+                        sans.push(Ipv4Addr::from(v4).to_string());
+                    } else if let Ok(v6) = <[u8; 16]>::try_from(name.0) {
+                        sans.push(Ipv6Addr::from(v6).to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(sans)
+}
+
+/// Convert a DER `UTCTime` or `GeneralizedTime` to unix seconds.
+fn parse_time((tag, value): (u8, Der)) -> Result<u64, CertError> {
+    let digits = |s: &[u8]| -> Result<i64, CertError> {
+        s.iter().try_fold(0i64, |acc, b| match b {
+            b'0'..=b'9' => Ok(acc * 10 + (b - b'0') as i64),
+            _ => Err(CertError::Malformed),
+        })
+    };
+    let (year, rest) = match tag {
+        UTC_TIME => {
+            let (yy, rest) = value.0.split_at_checked(2).ok_or(CertError::Malformed)?;
+            let yy = digits(yy)?;
+            (if yy >= 50 { 1900 + yy } else { 2000 + yy }, rest)
+        }
+        GENERALIZED_TIME => {
+            let (yyyy, rest) = value.0.split_at_checked(4).ok_or(CertError::Malformed)?;
+            (digits(yyyy)?, rest)
+        }
+        _ => return Err(CertError::Malformed),
+    };
+    let field = |i: usize| rest.get(i..i + 2).ok_or(CertError::Malformed).and_then(digits);
+    let days = days_from_civil(year, field(0)?, field(2)?);
+    let secs = days * 86400 + field(4)? * 3600 + field(6)? * 60 + field(8)?;
+    u64::try_from(secs).map_err(|_| CertError::Malformed)
+}
+
+// --- Text decoding ---
+
+/// Remove `-----BEGIN ...-----` / `-----END ...-----` markers and all whitespace.
+fn strip_pem_markers(pem: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(pem.len());
+    let mut in_marker = false;
+    let mut i = 0;
+    while i < pem.len() {
+        if pem[i..].starts_with(b"-----") {
+            in_marker = !in_marker;
+            i += 5;
+            continue;
+        }
+        if !in_marker && !pem[i].is_ascii_whitespace() {
+            out.push(pem[i]);
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Split `input` at `sep`, ignoring separators inside double quotes.
+fn split_unquoted(input: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == sep && !quoted => {
+                parts.push(input.get(start..i).unwrap_or_default());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(input.get(start..).unwrap_or_default());
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT_BASE64: &str = "MIICHDCCAcOgAwIBAgIBKjAKBggqhkjOPQQDAjA7MQswCQYDVQQGEwJVUzEPMA0GA1UECgwGQ2hlZXNlMRswGQYDVQQDDBJjbGllbnQuZXhhbXBsZS5jb20wHhcNMjYxMDE1MDYzNjE5WhcNMzYxMDEyMDYzNjE5WjA7MQswCQYDVQQGEwJVUzEPMA0GA1UECgwGQ2hlZXNlMRswGQYDVQQDDBJjbGllbnQuZXhhbXBsZS5jb20wWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAAS+4m/I0guVjTZU0LvaQl2k5Pc/7YO4KlBwbIz+GuFXLfo860+R6C4JzazwF+xUoUylG0nmBx2+mioz1nCSeOmWo4G3MIG0MB0GA1UdDgQWBBRPQSOlqk6Bt7byDIbA+g6Ysr+VQzAfBgNVHSMEGDAWgBRPQSOlqk6Bt7byDIbA+g6Ysr+VQzAPBgNVHRMBAf8EBTADAQH/MGEGA1UdEQRaMFiCEmNsaWVudC5leGFtcGxlLmNvbYINKi5leGFtcGxlLm9yZ4EQdGVzdEBleGFtcGxlLm9yZ4cECgABAoYbc3BpZmZlOi8vZXhhbXBsZS5vcmcvY2xpZW50MAoGCCqGSM49BAMCA0cAMEQCIGtM9wa7UE3+6M2+nKHNkHPYfzHQYQ5a5vBaq2xk6nd4AiBa4dmS0trWv9x5PjQRZan6bL1NNnGV3Sg78Yjrq/0mPg==";
+
+    fn assert_test_cert(cert: &ClientCert) {
+        assert_eq!(cert.subject, "C=US,O=Cheese,CN=client.example.com");
+        assert_eq!(cert.issuer, "C=US,O=Cheese,CN=client.example.com");
+        assert_eq!(
+            cert.sans,
+            vec!["client.example.com", "*.example.org", "test@example.org", "10.0.1.2", "spiffe://example.org/client"]
+        );
+        assert_eq!(cert.not_before, Some(1792046179));
+        assert_eq!(cert.not_after, Some(2107406179));
+    }
+
+    fn url_encode(s: &str) -> String {
+        s.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' => (b as char).to_string(),
+                b' ' => "+".to_string(),
+                _ => format!("%{b:02X}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_pem_traefik_format() {
+        let certs = ClientCert::parse_pem(url_encode(CERT_BASE64).as_bytes()).unwrap();
+        assert_eq!(certs.len(), 1);
+        assert_test_cert(&certs[0]);
+    }
+
+    #[test]
+    fn parse_pem_with_markers() {
+        let pem =
+            format!("-----BEGIN CERTIFICATE-----\n{}\n{}\n-----END CERTIFICATE-----\n", &CERT_BASE64[..64], &CERT_BASE64[64..]);
+        let certs = ClientCert::parse_pem(url_encode(&pem).as_bytes()).unwrap();
+        assert_eq!(certs.len(), 1);
+        assert_test_cert(&certs[0]);
+    }
+
+    #[test]
+    fn parse_pem_multiple_certs() {
+        let value = format!("{0},{0}", url_encode(CERT_BASE64));
+        let certs = ClientCert::parse_pem(value.as_bytes()).unwrap();
+        assert_eq!(certs.len(), 2);
+    }
+
+    #[test]
+    fn parse_pem_invalid_base64() {
+        assert_eq!(ClientCert::parse_pem(b"not*base64"), Err(CertError::Encoding));
+    }
+
+    #[test]
+    fn parse_pem_truncated_der() {
+        let truncated = url_encode(&CERT_BASE64[..200]);
+        assert_eq!(ClientCert::parse_pem(truncated.as_bytes()), Err(CertError::Malformed));
+    }
+
+    #[test]
+    fn parse_info_traefik_format() {
+        let info = r#"Subject="DC=org,DC=cheese,C=FR,O=Cheese,CN=*.example.com";Issuer="DC=org,DC=cheese,C=FR,O=Cheese,CN=Simple Signing CA";NB="1747282528";NA="1778818528";SAN="*.example.org,test@example.org,10.0.1.0""#;
+        let certs = ClientCert::parse_info(url_encode(info).as_bytes()).unwrap();
+        assert_eq!(certs.len(), 1);
+        let cert = &certs[0];
+        assert_eq!(cert.subject, "DC=org,DC=cheese,C=FR,O=Cheese,CN=*.example.com");
+        assert_eq!(cert.issuer, "DC=org,DC=cheese,C=FR,O=Cheese,CN=Simple Signing CA");
+        assert_eq!(cert.not_before, Some(1747282528));
+        assert_eq!(cert.not_after, Some(1778818528));
+        assert_eq!(cert.sans, vec!["*.example.org", "test@example.org", "10.0.1.0"]);
+        assert_eq!(cert.common_name(), Some("*.example.com"));
+    }
+
+    #[test]
+    fn parse_info_multiple_certs_partial_fields() {
+        let info = r#"Subject="CN=leaf",Subject="CN=intermediate";Issuer="CN=root""#;
+        let certs = ClientCert::parse_info(url_encode(info).as_bytes()).unwrap();
+        assert_eq!(certs.len(), 2);
+        assert_eq!(certs[0].common_name(), Some("leaf"));
+        assert_eq!(certs[1].issuer, "CN=root");
+        assert_eq!(certs[1].not_after, None);
+    }
+
+    #[test]
+    fn parse_info_invalid_timestamp() {
+        assert_eq!(ClientCert::parse_info(b"NB=%22soon%22"), Err(CertError::Malformed));
+    }
+
+    #[test]
+    fn validity_check() {
+        let cert = ClientCert { not_before: Some(100), not_after: Some(200), ..Default::default() };
+        assert!(!cert.is_valid_at(99));
+        assert!(cert.is_valid_at(100));
+        assert!(cert.is_valid_at(200));
+        assert!(!cert.is_valid_at(201));
+        assert!(ClientCert::default().is_valid_at(0));
+    }
+
+    #[test]
+    fn from_request_without_headers() {
+        let certs = ClientCert::from_request(&Request::new()).unwrap();
+        assert!(certs.is_empty());
+    }
+
+    #[test]
+    fn generalized_time() {
+        let time = parse_time((GENERALIZED_TIME, Der(b"20500101000000Z"))).unwrap();
+        assert_eq!(time, 2524608000);
+    }
+
+    #[test]
+    fn format_unknown_oid() {
+        assert_eq!(format_oid(&[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D]), "1.2.840.113549");
+    }
+}
@@ -0,0 +1,366 @@
+//! W3C Trace Context propagation.
+//!
+//! [`TraceParent`] and [`TraceState`] parse and format the `traceparent` and
+//! `tracestate` headers. [`TraceContext::extract`] joins the trace of an incoming
+//! request or starts a new one, and [`TraceContext::inject`] forwards it to the
+//! upstream with the plugin as the parent span.
+//!
+//! ```no_run
+//! use http_wasm_guest::{host::Request, trace::TraceContext};
+//!
+//! fn forward(request: &Request) {
+//!     let context = TraceContext::extract(request, 0.1).child();
+//!     context.inject(request);
+//!     log::info!("trace_id={} span_id={}", context.parent.trace_id_hex(), context.parent.span_id_hex());
+//! }
+//! ```
+use std::fmt::Display;
+
+use crate::{
+    codec::{hex_decode, hex_encode},
+    host::{Request, random},
+};
+
+/// Name of the header carrying the trace and parent span IDs.
+pub const TRACEPARENT: &str = "traceparent";
+/// Name of the header carrying vendor-specific trace state.
+pub const TRACESTATE: &str = "tracestate";
+
+const SAMPLED: u8 = 0x01;
+const MAX_STATE_ENTRIES: usize = 32;
+
+/// Value of a `traceparent` header: trace ID, parent span ID and trace flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceParent {
+    /// The 16-byte ID of the whole trace.
+    pub trace_id: [u8; 16],
+    /// The 8-byte ID of the span that made the request.
+    pub span_id: [u8; 8],
+    /// Trace flags; bit 0 is the sampled flag.
+    pub flags: u8,
+}
+
+impl TraceParent {
+    /// Start a new trace with random IDs.
+    pub fn new(sampled: bool) -> Self {
+        let mut trace_id: [u8; 16] = random::weak_bytes();
+        while trace_id == [0; 16] {
+            trace_id = random::weak_bytes();
+        }
+        Self { trace_id, span_id: new_span_id(), flags: if sampled { SAMPLED } else { 0 } }
+    }
+
+    /// Parse a `traceparent` header value such as
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+    ///
+    /// Returns `None` for invalid values, including all-zero IDs and the forbidden
+    /// version `ff`. Values of future versions are accepted if they start with the
+    /// fields of version `00`.
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        let value = value.trim_ascii();
+        let mut fields = value.splitn(5, |b| *b == b'-');
+        let version = fixed_hex::<1>(fields.next()?)?;
+        let trace_id = fixed_hex::<16>(fields.next()?)?;
+        let span_id = fixed_hex::<8>(fields.next()?)?;
+        let [flags] = fixed_hex::<1>(fields.next()?)?;
+        let valid_rest = match (version, fields.next()) {
+            ([0xff], _) => false,
+            ([0x00], rest) => rest.is_none(),
+            (_, _) => true,
+        };
+        (valid_rest && trace_id != [0; 16] && span_id != [0; 8]).then_some(Self { trace_id, span_id, flags })
+    }
+
+    /// Whether the caller recorded this trace.
+    pub fn is_sampled(&self) -> bool {
+        self.flags & SAMPLED != 0
+    }
+
+    /// Set or clear the sampled flag.
+    pub fn set_sampled(&mut self, sampled: bool) {
+        match sampled {
+            true => self.flags |= SAMPLED,
+            false => self.flags &= !SAMPLED,
+        }
+    }
+
+    /// A new span in the same trace with the same flags, to be used as the parent of
+    /// upstream requests.
+    pub fn child(&self) -> Self {
+        Self { span_id: new_span_id(), ..*self }
+    }
+
+    /// The trace ID as 32 lowercase hex digits, e.g. for log lines.
+    pub fn trace_id_hex(&self) -> String {
+        hex_encode(&self.trace_id)
+    }
+
+    /// The span ID as 16 lowercase hex digits.
+    pub fn span_id_hex(&self) -> String {
+        hex_encode(&self.span_id)
+    }
+}
+
+/// Formats the value of version `00`.
+impl Display for TraceParent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "00-{}-{}-{:02x}", self.trace_id_hex(), self.span_id_hex(), self.flags)
+    }
+}
+
+/// Value of a `tracestate` header, an ordered list of vendor `key=value` entries.
+///
+/// The most recently updated entry comes first. At most 32 entries are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceState {
+    entries: Vec<(String, String)>,
+}
+
+impl TraceState {
+    /// Parse a `tracestate` header value such as `rojo=00f067aa0ba902b7,congo=t61rcWkgMzE`.
+    ///
+    /// Empty list members are skipped. Returns `None` if an entry is invalid, a key is
+    /// repeated or there are more than 32 entries; the whole value must then be discarded.
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        let value = std::str::from_utf8(value).ok()?;
+        let mut state = Self::default();
+        for member in value.split(',').map(|member| member.trim_matches([' ', '\t'])).filter(|m| !m.is_empty()) {
+            let (key, value) = member.split_once('=')?;
+            if !is_valid_key(key) || !is_valid_value(value) || state.get(key).is_some() {
+                return None;
+            }
+            state.entries.push((key.to_string(), value.to_string()));
+        }
+        (state.entries.len() <= MAX_STATE_ENTRIES).then_some(state)
+    }
+
+    /// The value of the entry `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Add or update the entry `key` and move it to the front.
+    ///
+    /// Returns `false` without change if the key or value is invalid. If the list is
+    /// full, the last entry is dropped.
+    pub fn insert(&mut self, key: &str, value: &str) -> bool {
+        if !is_valid_key(key) || !is_valid_value(value) {
+            return false;
+        }
+        self.remove(key);
+        self.entries.insert(0, (key.to_string(), value.to_string()));
+        self.entries.truncate(MAX_STATE_ENTRIES);
+        true
+    }
+
+    /// Remove the entry `key`.
+    pub fn remove(&mut self, key: &str) {
+        self.entries.retain(|(k, _)| k != key);
+    }
+
+    /// Iterate over the entries in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Display for TraceState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (key, value)) in self.entries.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(f, "{separator}{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Trace context of a request: the `traceparent` and the accompanying `tracestate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// Trace ID, parent span ID and flags.
+    pub parent: TraceParent,
+    /// Vendor-specific state, forwarded unchanged unless modified.
+    pub state: TraceState,
+}
+
+impl TraceContext {
+    /// The trace context sent with the request, if it has a valid `traceparent`.
+    ///
+    /// An invalid `tracestate` is dropped; repeated `tracestate` headers are combined.
+    pub fn from_request(request: &Request) -> Option<Self> {
+        let parent = match request.header.values(TRACEPARENT.as_bytes()).as_slice() {
+            [value] => TraceParent::parse(value)?,
+            _ => return None,
+        };
+        let values = request.header.values(TRACESTATE.as_bytes());
+        let combined = values.iter().map(|value| &value[..]).collect::<Vec<_>>().join(&b","[..]);
+        let state = TraceState::parse(&combined).unwrap_or_default();
+        Some(Self { parent, state })
+    }
+
+    /// Join the trace of the request or start a new one.
+    ///
+    /// A new trace is sampled with the probability `sample_rate` (`0.0..=1.0`); a
+    /// joined trace keeps the sampling decision of the caller.
+    pub fn extract(request: &Request, sample_rate: f64) -> Self {
+        Self::from_request(request).unwrap_or_else(|| Self::new(should_sample(sample_rate)))
+    }
+
+    /// Start a new trace without state.
+    pub fn new(sampled: bool) -> Self {
+        Self { parent: TraceParent::new(sampled), state: TraceState::default() }
+    }
+
+    /// The context for a new span in the same trace, see [`TraceParent::child`].
+    pub fn child(&self) -> Self {
+        Self { parent: self.parent.child(), state: self.state.clone() }
+    }
+
+    /// Set the `traceparent` and `tracestate` headers of the request.
+    ///
+    /// `tracestate` is removed if the state is empty.
+    pub fn inject(&self, request: &Request) {
+        request.header.set(TRACEPARENT.as_bytes(), self.parent.to_string().as_bytes());
+        match self.state.is_empty() {
+            true => request.header.remove(TRACESTATE.as_bytes()),
+            false => request.header.set(TRACESTATE.as_bytes(), self.state.to_string().as_bytes()),
+        }
+    }
+}
+
+fn new_span_id() -> [u8; 8] {
+    let mut span_id: [u8; 8] = random::weak_bytes();
+    while span_id == [0; 8] {
+        span_id = random::weak_bytes();
+    }
+    span_id
+}
+
+fn should_sample(rate: f64) -> bool {
+    // 53 random bits give a uniform float in [0, 1)
+    ((u64::from_le_bytes(random::weak_bytes()) >> 11) as f64 / (1u64 << 53) as f64) < rate
+}
+
+/// Exactly `2 * N` lowercase hex digits.
+fn fixed_hex<const N: usize>(field: &[u8]) -> Option<[u8; N]> {
+    if field.len() != 2 * N || field.iter().any(u8::is_ascii_uppercase) {
+        return None;
+    }
+    hex_decode(field).ok()?.try_into().ok()
+}
+
+/// `simple-key` or `tenant@system` with lowercase letters, digits and `_-*/`.
+fn is_valid_key(key: &str) -> bool {
+    let part = |s: &str, max: usize, letter_first: bool| {
+        let first_ok = s.bytes().next().is_some_and(|b| b.is_ascii_lowercase() || (!letter_first && b.is_ascii_digit()));
+        first_ok
+            && s.len() <= max
+            && s.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'_' | b'-' | b'*' | b'/'))
+    };
+    match key.split_once('@') {
+        Some((tenant, system)) => part(tenant, 241, false) && part(system, 14, true),
+        None => part(key, 256, false),
+    }
+}
+
+/// Up to 256 printable ASCII characters except `,` and `=`, not ending in a space.
+fn is_valid_value(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 256
+        && !value.ends_with(' ')
+        && value.bytes().all(|b| (0x20..=0x7e).contains(&b) && b != b',' && b != b'=')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn traceparent_round_trip() {
+        let parent = TraceParent::parse(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(parent.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(parent.span_id_hex(), "00f067aa0ba902b7");
+        assert!(parent.is_sampled());
+        assert_eq!(parent.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn traceparent_invalid() {
+        for value in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+        ] {
+            assert_eq!(TraceParent::parse(value.as_bytes()), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn traceparent_future_version() {
+        let parent = TraceParent::parse(b"cc-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-03-what-the-future").unwrap();
+        assert_eq!(parent.flags, 0x03);
+        assert_eq!(parent.to_string(), "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-03");
+    }
+
+    #[test]
+    fn traceparent_child_and_flags() {
+        let mut parent = TraceParent::parse(EXAMPLE.as_bytes()).unwrap();
+        let child = parent.child();
+        assert_eq!(child.trace_id, parent.trace_id);
+        assert_ne!(child.span_id, parent.span_id);
+        parent.set_sampled(false);
+        assert!(!parent.is_sampled());
+        assert!(TraceParent::new(true).is_sampled());
+        assert_ne!(TraceParent::new(false).trace_id, TraceParent::new(false).trace_id);
+    }
+
+    #[test]
+    fn tracestate_parse_and_update() {
+        let mut state = TraceState::parse(b"rojo=00f067aa0ba902b7, ,congo=t61rcWkgMzE").unwrap();
+        assert_eq!(state.get("congo"), Some("t61rcWkgMzE"));
+        assert!(state.insert("congo", "updated"));
+        assert!(state.insert("tenant@vendor", "x"));
+        assert!(!state.insert("Upper", "x"));
+        assert!(!state.insert("key", "a,b"));
+        assert_eq!(state.to_string(), "tenant@vendor=x,congo=updated,rojo=00f067aa0ba902b7");
+    }
+
+    #[test]
+    fn tracestate_invalid() {
+        assert_eq!(TraceState::parse(b"a=1,a=2"), None);
+        assert_eq!(TraceState::parse(b"a"), None);
+        assert_eq!(TraceState::parse(b"A=1"), None);
+        let too_many = (0..33).map(|i| format!("k{i}=v")).collect::<Vec<_>>().join(",");
+        assert_eq!(TraceState::parse(too_many.as_bytes()), None);
+    }
+
+    #[test]
+    fn tracestate_keeps_at_most_32_entries() {
+        let mut state = TraceState::default();
+        for i in 0..40 {
+            state.insert(&format!("k{i}"), "v");
+        }
+        assert_eq!(state.iter().count(), 32);
+        assert_eq!(state.iter().next(), Some(("k39", "v")));
+    }
+
+    #[test]
+    fn extract_starts_new_trace() {
+        // The mock request has no traceparent header
+        assert_eq!(TraceContext::from_request(&Request::new()), None);
+        assert!(TraceContext::extract(&Request::new(), 1.0).parent.is_sampled());
+        assert!(!TraceContext::extract(&Request::new(), 0.0).parent.is_sampled());
+    }
+}
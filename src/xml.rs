@@ -0,0 +1,314 @@
+//! Lightweight XML document model.
+//!
+//! [`Element`] is a small tree of elements, attributes and text for inspecting and
+//! changing XML messages such as SOAP envelopes without defining types for them.
+//! Typed access with serde is available through
+//! [`Body::read_xml`](crate::host::Body::read_xml) and
+//! [`Body::write_xml`](crate::host::Body::write_xml).
+//!
+//! ```
+//! use http_wasm_guest::xml::Element;
+//!
+//! let envelope = Element::parse(
+//!     br#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+//!           <soap:Body><GetQuote><Symbol>ACME &amp; Co</Symbol></GetQuote></soap:Body>
+//!         </soap:Envelope>"#,
+//! )
+//! .unwrap();
+//! let symbol = envelope.find(&["Body", "GetQuote", "Symbol"]).map(|e| e.text());
+//! assert_eq!(symbol.as_deref(), Some("ACME & Co"));
+//! ```
+use std::fmt;
+
+use quick_xml::{
+    XmlVersion,
+    escape::{escape, resolve_predefined_entity},
+    events::{BytesStart, Event},
+    reader::Reader,
+};
+
+/// Content of an [`Element`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// A child element.
+    Element(Element),
+    /// Text with references resolved; CDATA sections become text.
+    Text(String),
+}
+
+/// An XML element with its attributes and content.
+///
+/// Comments, processing instructions and text consisting only of whitespace are not
+/// kept, so serializing a parsed document yields a compact equivalent of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Element {
+    /// Qualified name, e.g. `soap:Body`.
+    pub name: String,
+    /// Attributes with qualified names and resolved values, in document order.
+    pub attributes: Vec<(String, String)>,
+    /// Child elements and text, in document order.
+    pub children: Vec<Node>,
+}
+
+/// Error returned for documents that are not well-formed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlError {
+    /// Byte offset of the error in the document.
+    pub position: u64,
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid XML at byte {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for XmlError {}
+
+impl Element {
+    /// Create an element without attributes and content.
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Default::default() }
+    }
+
+    /// Parse the root element of a UTF-8 document.
+    pub fn parse(xml: &[u8]) -> Result<Self, XmlError> {
+        let mut reader = Reader::from_reader(xml);
+        let mut stack: Vec<Element> = Vec::new();
+        let mut root = None;
+        loop {
+            let error = |reader: &Reader<&[u8]>, message: String| XmlError { position: reader.error_position(), message };
+            let event = reader.read_event().map_err(|e| error(&reader, e.to_string()))?;
+            let text = match event {
+                Event::Start(start) => {
+                    stack.push(element(&start).map_err(|m| error(&reader, m))?);
+                    continue;
+                }
+                Event::Empty(start) => {
+                    let empty = element(&start).map_err(|m| error(&reader, m))?;
+                    add(&mut stack, &mut root, Node::Element(empty)).map_err(|m| error(&reader, m))?;
+                    continue;
+                }
+                Event::End(_) => {
+                    let mut closed = stack.pop().ok_or_else(|| error(&reader, "unexpected end tag".into()))?;
+                    closed.children.retain(|node| !matches!(node, Node::Text(text) if text.trim().is_empty()));
+                    add(&mut stack, &mut root, Node::Element(closed)).map_err(|m| error(&reader, m))?;
+                    continue;
+                }
+                Event::Text(text) => text.xml10_content().map(|t| t.into_owned()).map_err(|e| e.to_string()),
+                Event::CData(cdata) => cdata.decode().map(|t| t.into_owned()).map_err(|e| e.to_string()),
+                Event::GeneralRef(reference) => match reference.resolve_char_ref() {
+                    Ok(Some(c)) => Ok(c.to_string()),
+                    Ok(None) => reference.decode().map_err(|e| e.to_string()).and_then(|name| {
+                        resolve_predefined_entity(&name).map(str::to_string).ok_or_else(|| format!("unknown entity `{name}`"))
+                    }),
+                    Err(e) => Err(e.to_string()),
+                },
+                Event::Eof => break,
+                _ => continue,
+            };
+            let text = text.map_err(|m| error(&reader, m))?;
+            match stack.last_mut() {
+                Some(parent) => match parent.children.last_mut() {
+                    // references split the text into several events
+                    Some(Node::Text(previous)) => previous.push_str(&text),
+                    _ => parent.children.push(Node::Text(text)),
+                },
+                None if text.trim().is_empty() => {}
+                None => return Err(error(&reader, "text outside of the root element".into())),
+            }
+        }
+        let position = reader.buffer_position();
+        match (stack.is_empty(), root) {
+            (true, Some(root)) => Ok(root),
+            (false, _) => Err(XmlError { position, message: "missing end tag".into() }),
+            (true, None) => Err(XmlError { position, message: "missing root element".into() }),
+        }
+    }
+
+    /// Name without namespace prefix, e.g. `Body` for `soap:Body`.
+    pub fn local_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or(&self.name)
+    }
+
+    /// Value of the attribute with the qualified `name`.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Set the attribute `name` to `value`.
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        match self.attributes.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.attributes.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Child elements in document order.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// First child element with the given local name.
+    pub fn child(&self, local_name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.local_name() == local_name)
+    }
+
+    /// First child element with the given local name, for modification.
+    pub fn child_mut(&mut self, local_name: &str) -> Option<&mut Element> {
+        self.children.iter_mut().find_map(|node| match node {
+            Node::Element(element) if element.local_name() == local_name => Some(element),
+            _ => None,
+        })
+    }
+
+    /// Descendant reached by following the first child with each local name of `path`.
+    pub fn find(&self, path: &[&str]) -> Option<&Element> {
+        path.iter().try_fold(self, |element, name| element.child(name))
+    }
+
+    /// Descendant reached by following `path`, for modification.
+    pub fn find_mut(&mut self, path: &[&str]) -> Option<&mut Element> {
+        path.iter().try_fold(self, |element, name| element.child_mut(name))
+    }
+
+    /// Text content of the element and its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.collect_text(&mut text);
+        text
+    }
+
+    /// Replace the content with `text`.
+    pub fn set_text(&mut self, text: &str) {
+        self.children = vec![Node::Text(text.to_string())];
+    }
+
+    /// Serialize the element as XML, without declaration.
+    pub fn to_xml(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out);
+        out
+    }
+
+    fn collect_text(&self, out: &mut String) {
+        for node in &self.children {
+            match node {
+                Node::Element(element) => element.collect_text(out),
+                Node::Text(text) => out.push_str(text),
+            }
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.name);
+        for (name, value) in &self.attributes {
+            out.push(' ');
+            out.push_str(name);
+            out.push_str("=\"");
+            out.push_str(&escape(value.as_str()));
+            out.push('"');
+        }
+        if self.children.is_empty() {
+            out.push_str("/>");
+            return;
+        }
+        out.push('>');
+        for node in &self.children {
+            match node {
+                Node::Element(element) => element.write(out),
+                Node::Text(text) => out.push_str(&escape(text.as_str())),
+            }
+        }
+        out.push_str("</");
+        out.push_str(&self.name);
+        out.push('>');
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_xml())
+    }
+}
+
+fn element(start: &BytesStart<'_>) -> Result<Element, String> {
+    let name = String::from_utf8(start.name().as_ref().to_vec()).map_err(|e| e.to_string())?;
+    let attributes = start
+        .attributes()
+        .map(|attribute| {
+            let attribute = attribute.map_err(|e| e.to_string())?;
+            let name = String::from_utf8(attribute.key.as_ref().to_vec()).map_err(|e| e.to_string())?;
+            let value = attribute.normalized_value(XmlVersion::Implicit1_0).map_err(|e| e.to_string())?;
+            Ok((name, value.into_owned()))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Element { name, attributes, children: Vec::new() })
+}
+
+/// Add a completed node to its parent, or make it the root element.
+fn add(stack: &mut [Element], root: &mut Option<Element>, node: Node) -> Result<(), String> {
+    match (stack.last_mut(), node) {
+        (Some(parent), node) => parent.children.push(node),
+        (None, Node::Element(element)) if root.is_none() => *root = Some(element),
+        (None, _) => return Err("multiple root elements".into()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_serialize() {
+        let xml = br#"<?xml version="1.0"?>
+            <!-- order -->
+            <order id="7" note="a &amp; b">
+              <item sku="x1">2</item>
+              <item sku="x2"/>
+              <memo><![CDATA[<fragile>]]> &#x21;</memo>
+            </order>"#;
+        let order = Element::parse(xml).unwrap();
+        assert_eq!(order.attribute("note"), Some("a & b"));
+        assert_eq!(order.elements().count(), 3);
+        assert_eq!(order.child("memo").map(Element::text).as_deref(), Some("<fragile> !"));
+        assert_eq!(
+            order.to_xml(),
+            r#"<order id="7" note="a &amp; b"><item sku="x1">2</item><item sku="x2"/><memo>&lt;fragile&gt; !</memo></order>"#
+        );
+        assert_eq!(Element::parse(order.to_xml().as_bytes()).as_ref(), Ok(&order));
+    }
+
+    #[test]
+    fn modify_by_path() {
+        let mut envelope =
+            Element::parse(b"<s:Envelope xmlns:s='urn:s'><s:Body><Login><Password>x</Password></Login></s:Body></s:Envelope>")
+                .unwrap();
+        assert_eq!(envelope.local_name(), "Envelope");
+        if let Some(password) = envelope.find_mut(&["Body", "Login", "Password"]) {
+            password.set_text("***");
+        }
+        let mut login = Element::new("Token");
+        login.set_attribute("kind", "\"bearer\"");
+        assert_eq!(login.to_xml(), r#"<Token kind="&quot;bearer&quot;"/>"#);
+        assert_eq!(envelope.find(&["Body", "Login", "Password"]).map(Element::text).as_deref(), Some("***"));
+        assert_eq!(envelope.find(&["Body", "Logout"]), None);
+    }
+
+    #[test]
+    fn malformed_documents() {
+        assert_eq!(Element::parse(b"<a><b></a>").map_err(|e| e.position > 0), Err(true));
+        assert_eq!(Element::parse(b"<a>").map_err(|e| e.message), Err("missing end tag".into()));
+        assert_eq!(Element::parse(b"  ").map_err(|e| e.message), Err("missing root element".into()));
+        assert_eq!(Element::parse(b"<a/><b/>").map_err(|e| e.message), Err("multiple root elements".into()));
+        assert!(Element::parse(b"<a>&nbsp;</a>").is_err());
+        assert!(Element::parse(b"text").is_err());
+    }
+}